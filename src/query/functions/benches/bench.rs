@@ -20,9 +20,12 @@ mod parser;
 
 use criterion::Criterion;
 use databend_common_expression::type_check;
+use databend_common_expression::types::number::UInt64Type;
 use databend_common_expression::DataBlock;
 use databend_common_expression::Evaluator;
+use databend_common_expression::FromData;
 use databend_common_expression::FunctionContext;
+use databend_common_functions::aggregates::eval_aggr;
 use databend_common_functions::BUILTIN_FUNCTIONS;
 
 fn bench(c: &mut Criterion) {
@@ -50,5 +53,21 @@ fn bench(c: &mut Criterion) {
     }
 }
 
-criterion_group!(benches, bench);
+fn bench_count(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bench_count");
+
+    let rows = 65536;
+    let values = (0..rows as u64).collect::<Vec<_>>();
+    let validity = (0..rows).map(|i| i % 3 != 0).collect::<Vec<_>>();
+    let column = UInt64Type::from_data_with_validity(values, validity);
+
+    group.bench_function("count()", |b| {
+        b.iter(|| eval_aggr("count", vec![], &[], rows))
+    });
+    group.bench_function("count(col)", |b| {
+        b.iter(|| eval_aggr("count", vec![], std::slice::from_ref(&column), rows))
+    });
+}
+
+criterion_group!(benches, bench, bench_count);
 criterion_main!(benches);
@@ -0,0 +1,148 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::any::Any;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::number::Number;
+use databend_common_expression::types::number::F64;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::NumberType;
+use databend_common_expression::types::UInt64Type;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::with_number_mapped_type;
+use databend_common_expression::Scalar;
+use num_traits::AsPrimitive;
+
+use super::aggregate_trimmed_mean::get_bounds;
+use super::aggregate_trimmed_mean::percentile;
+use super::AggregateUnaryFunction;
+use super::FunctionData;
+use super::UnaryState;
+use crate::aggregates::aggregate_function_factory::AggregateFunctionDescription;
+use crate::aggregates::assert_unary_arguments;
+use crate::aggregates::AggregateFunctionRef;
+
+struct AnomalyCountData {
+    lower: f64,
+    upper: f64,
+}
+
+impl FunctionData for AnomalyCountData {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct AnomalyCountState {
+    values: Vec<F64>,
+}
+
+impl AnomalyCountState {
+    fn anomaly_count(&mut self, lower: f64, upper: f64) -> u64 {
+        if self.values.is_empty() {
+            return 0;
+        }
+        self.values.sort_unstable();
+        let lower = percentile(&self.values, lower);
+        let upper = percentile(&self.values, upper);
+
+        self.values
+            .iter()
+            .filter(|value| !(lower..=upper).contains(&value.0))
+            .count() as u64
+    }
+}
+
+impl<T> UnaryState<T, UInt64Type> for AnomalyCountState
+where
+    T: ValueType,
+    T::Scalar: Number + AsPrimitive<f64>,
+{
+    fn add(
+        &mut self,
+        other: T::ScalarRef<'_>,
+        _function_data: Option<&dyn FunctionData>,
+    ) -> Result<()> {
+        let value: f64 = T::to_owned_scalar(other).as_();
+        self.values.push(value.into());
+        Ok(())
+    }
+
+    fn merge(&mut self, rhs: &Self) -> Result<()> {
+        self.values.extend_from_slice(&rhs.values);
+        Ok(())
+    }
+
+    fn merge_result(
+        &mut self,
+        builder: &mut Vec<u64>,
+        function_data: Option<&dyn FunctionData>,
+    ) -> Result<()> {
+        let data = unsafe {
+            function_data
+                .unwrap()
+                .as_any()
+                .downcast_ref_unchecked::<AnomalyCountData>()
+        };
+        builder.push(self.anomaly_count(data.lower, data.upper));
+        Ok(())
+    }
+}
+
+/// `anomaly_count(lower, upper)(x)` returns the number of values of `x` outside the
+/// range from the `lower` to the `upper` percentile of the group, the values equal to a
+/// percentile being inside it. The percentiles are interpolated like `quantile_cont`, the
+/// same as `trimmed_mean(lower, upper)`, which averages the values inside the range.
+///
+/// All the values of a group are buffered, as they can only be counted once the
+/// percentiles are known. NULL values are ignored, a group without values returns 0.
+pub fn try_create_aggregate_anomaly_count_function(
+    display_name: &str,
+    params: Vec<Scalar>,
+    arguments: Vec<DataType>,
+) -> Result<AggregateFunctionRef> {
+    assert_unary_arguments(display_name, arguments.len())?;
+    let (lower, upper) = get_bounds(display_name, &params)?;
+    let return_type = DataType::Number(NumberDataType::UInt64);
+
+    with_number_mapped_type!(|NUM_TYPE| match &arguments[0] {
+        DataType::Number(NumberDataType::NUM_TYPE) => {
+            let func = AggregateUnaryFunction::<
+                AnomalyCountState,
+                NumberType<NUM_TYPE>,
+                UInt64Type,
+            >::try_create(
+                display_name, return_type, params, arguments[0].clone()
+            )
+            .with_function_data(Box::new(AnomalyCountData { lower, upper }))
+            .with_need_drop(true);
+            Ok(Arc::new(func))
+        }
+        _ => Err(ErrorCode::BadDataValueType(format!(
+            "{} does not support type '{:?}'",
+            display_name, arguments[0]
+        ))),
+    })
+}
+
+pub fn aggregate_anomaly_count_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(try_create_aggregate_anomaly_count_function))
+}
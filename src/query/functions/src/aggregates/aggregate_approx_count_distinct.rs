@@ -15,9 +15,13 @@
 use std::hash::Hash;
 use std::sync::Arc;
 
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_exception::ErrorCode;
 use databend_common_exception::Result;
 use databend_common_expression::type_check::check_number;
 use databend_common_expression::types::AnyType;
+use databend_common_expression::types::BinaryType;
 use databend_common_expression::types::DataType;
 use databend_common_expression::types::DateType;
 use databend_common_expression::types::NumberDataType;
@@ -80,6 +84,25 @@ pub fn try_create_aggregate_approx_count_distinct_function(
 ) -> Result<Arc<dyn AggregateFunction>> {
     assert_unary_arguments(display_name, arguments.len())?;
 
+    match get_precision(&params)? {
+        4 => create_templated::<4>(display_name, params, arguments),
+        5 => create_templated::<5>(display_name, params, arguments),
+        6 => create_templated::<6>(display_name, params, arguments),
+        7 => create_templated::<7>(display_name, params, arguments),
+        8 => create_templated::<8>(display_name, params, arguments),
+        9 => create_templated::<9>(display_name, params, arguments),
+        10 => create_templated::<10>(display_name, params, arguments),
+        11 => create_templated::<11>(display_name, params, arguments),
+        12 => create_templated::<12>(display_name, params, arguments),
+        13 => create_templated::<13>(display_name, params, arguments),
+        14 => create_templated::<14>(display_name, params, arguments),
+        _ => unreachable!(),
+    }
+}
+
+/// The precision of the HyperLogLog, 14 by default, or the one giving the error rate
+/// of the parameter.
+fn get_precision(params: &[Scalar]) -> Result<u64> {
     let mut p = 14;
 
     if !params.is_empty() {
@@ -96,21 +119,7 @@ pub fn try_create_aggregate_approx_count_distinct_function(
         p = ((1.04f64 / *error_rate).log2() * 2.0).ceil() as u64;
         p = p.clamp(4, 14);
     }
-
-    match p {
-        4 => create_templated::<4>(display_name, params, arguments),
-        5 => create_templated::<5>(display_name, params, arguments),
-        6 => create_templated::<6>(display_name, params, arguments),
-        7 => create_templated::<7>(display_name, params, arguments),
-        8 => create_templated::<8>(display_name, params, arguments),
-        9 => create_templated::<9>(display_name, params, arguments),
-        10 => create_templated::<10>(display_name, params, arguments),
-        11 => create_templated::<11>(display_name, params, arguments),
-        12 => create_templated::<12>(display_name, params, arguments),
-        13 => create_templated::<13>(display_name, params, arguments),
-        14 => create_templated::<14>(display_name, params, arguments),
-        _ => unreachable!(),
-    }
+    Ok(p)
 }
 
 fn create_templated<const P: usize>(
@@ -181,6 +190,94 @@ fn create_templated<const P: usize>(
     })
 }
 
+/// The state of `approx_count_distinct_merge`, the union of the sketches.
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct ApproxCountDistinctMergeState<const HLL_P: usize> {
+    hll: HyperLogLog<HLL_P>,
+}
+
+impl<const HLL_P: usize> UnaryState<BinaryType, UInt64Type>
+    for ApproxCountDistinctMergeState<HLL_P>
+{
+    fn add(&mut self, other: &[u8], _function_data: Option<&dyn FunctionData>) -> Result<()> {
+        let sketch: HyperLogLog<HLL_P> = borsh::from_slice(other).map_err(|e| {
+            ErrorCode::BadBytes(format!(
+                "approx_count_distinct_merge expects sketches built by approx_count_distinct_state, but got an invalid one: {e}"
+            ))
+        })?;
+        self.hll.merge(&sketch);
+        Ok(())
+    }
+
+    fn merge(&mut self, rhs: &Self) -> Result<()> {
+        self.hll.merge(&rhs.hll);
+        Ok(())
+    }
+
+    fn merge_result(
+        &mut self,
+        builder: &mut Vec<u64>,
+        _function_data: Option<&dyn FunctionData>,
+    ) -> Result<()> {
+        builder.push(self.hll.count() as u64);
+        Ok(())
+    }
+}
+
+/// `approx_count_distinct_merge(sketch)` unions HyperLogLog sketches and returns the
+/// estimated number of distinct values of all of them.
+///
+/// The sketches are the `Binary` values of `approx_count_distinct_state(x)`, which are the
+/// borsh encoding of `simple_hll::HyperLogLog`, so they can be stored and merged later or
+/// built by other systems using the same crate. The precision is part of the format but not
+/// of the encoding: sketches built by `approx_count_distinct_state(error_rate)(x)` must be
+/// merged with the same `approx_count_distinct_merge(error_rate)(sketch)`, mixing precisions
+/// gives a wrong estimate or an error.
+pub fn try_create_aggregate_approx_count_distinct_merge_function(
+    display_name: &str,
+    params: Vec<Scalar>,
+    arguments: Vec<DataType>,
+) -> Result<Arc<dyn AggregateFunction>> {
+    assert_unary_arguments(display_name, arguments.len())?;
+    if arguments[0] != DataType::Binary {
+        return Err(ErrorCode::BadDataValueType(format!(
+            "{} expects a Binary sketch, but got '{:?}'",
+            display_name, arguments[0]
+        )));
+    }
+
+    match get_precision(&params)? {
+        4 => create_merge_templated::<4>(display_name, params, arguments),
+        5 => create_merge_templated::<5>(display_name, params, arguments),
+        6 => create_merge_templated::<6>(display_name, params, arguments),
+        7 => create_merge_templated::<7>(display_name, params, arguments),
+        8 => create_merge_templated::<8>(display_name, params, arguments),
+        9 => create_merge_templated::<9>(display_name, params, arguments),
+        10 => create_merge_templated::<10>(display_name, params, arguments),
+        11 => create_merge_templated::<11>(display_name, params, arguments),
+        12 => create_merge_templated::<12>(display_name, params, arguments),
+        13 => create_merge_templated::<13>(display_name, params, arguments),
+        14 => create_merge_templated::<14>(display_name, params, arguments),
+        _ => unreachable!(),
+    }
+}
+
+fn create_merge_templated<const P: usize>(
+    display_name: &str,
+    params: Vec<Scalar>,
+    arguments: Vec<DataType>,
+) -> Result<Arc<dyn AggregateFunction>> {
+    let return_type = DataType::Number(NumberDataType::UInt64);
+    let func = AggregateUnaryFunction::<
+        ApproxCountDistinctMergeState<P>,
+        BinaryType,
+        UInt64Type,
+    >::try_create(display_name, return_type, params, arguments[0].clone())
+    .with_need_drop(true);
+
+    Ok(Arc::new(func))
+}
+
 pub fn aggregate_approx_count_distinct_function_desc() -> AggregateFunctionDescription {
     let features = super::aggregate_function_factory::AggregateFunctionFeatures {
         returns_default_when_only_null: true,
@@ -192,3 +289,15 @@ pub fn aggregate_approx_count_distinct_function_desc() -> AggregateFunctionDescr
         features,
     )
 }
+
+pub fn aggregate_approx_count_distinct_merge_function_desc() -> AggregateFunctionDescription {
+    let features = super::aggregate_function_factory::AggregateFunctionFeatures {
+        returns_default_when_only_null: true,
+        ..Default::default()
+    };
+
+    AggregateFunctionDescription::creator_with_features(
+        Box::new(try_create_aggregate_approx_count_distinct_merge_function),
+        features,
+    )
+}
@@ -0,0 +1,269 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+use databend_common_expression::ScalarRef;
+
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::aggregate_scalar_state::TYPE_MAX;
+use super::aggregate_scalar_state::TYPE_MIN;
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::StateAddr;
+use crate::aggregates::aggregator_common::assert_arguments;
+use crate::aggregates::AggregateFunction;
+
+/// A row kept in the heap, ordered by `(order, value)` so that the top of the heap
+/// is the kept row to be evicted first: the smallest one for `arg_max_n`, the
+/// greatest one for `arg_min_n`. Comparing the values as well makes ties deterministic.
+#[derive(BorshSerialize, BorshDeserialize, Clone, PartialEq, Eq)]
+struct ArgItem<const TYPE: u8> {
+    order: Scalar,
+    value: Scalar,
+}
+
+impl<const TYPE: u8> PartialOrd for ArgItem<TYPE> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const TYPE: u8> Ord for ArgItem<TYPE> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let ordering = self
+            .order
+            .cmp(&other.order)
+            .then_with(|| self.value.cmp(&other.value));
+        match TYPE {
+            TYPE_MIN => ordering,
+            _ => ordering.reverse(),
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct ArgMinMaxNState<const TYPE: u8> {
+    n: usize,
+    heap: BinaryHeap<ArgItem<TYPE>>,
+}
+
+impl<const TYPE: u8> ArgMinMaxNState<TYPE> {
+    fn push(&mut self, item: ArgItem<TYPE>) {
+        if self.heap.len() < self.n {
+            self.heap.push(item);
+        } else if let Some(mut top) = self.heap.peek_mut() {
+            if item < *top {
+                *top = item;
+            }
+        }
+    }
+
+    fn merge(&mut self, rhs: &Self) {
+        self.n = self.n.max(rhs.n);
+        for item in rhs.heap.iter() {
+            self.push(item.clone());
+        }
+    }
+}
+
+/// `arg_max_n(value, order, n)` / `arg_min_n(value, order, n)` returns an array of the
+/// `value`s of the `n` rows with the largest / smallest `order`, sorted by `order`
+/// descending / ascending. Rows of equal `order` are sorted by `value` the same way.
+///
+/// `n` is expected to be a constant, the value of the first row is used. A group with
+/// fewer than `n` rows returns all of them. Like `arg_max`, rows whose `value` or
+/// `order` is NULL are ignored.
+#[derive(Clone)]
+pub struct AggregateArgMinMaxNFunction<const TYPE: u8> {
+    display_name: String,
+    return_type: DataType,
+}
+
+impl<const TYPE: u8> AggregateArgMinMaxNFunction<TYPE> {
+    pub fn try_create(
+        display_name: &str,
+        _params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<Arc<dyn AggregateFunction>> {
+        assert_arguments(display_name, arguments.len(), 3)?;
+        if !arguments[2].is_integer() {
+            return Err(ErrorCode::BadDataValueType(format!(
+                "The third argument of aggregate function {} must be an integer, got: {:?}",
+                display_name, arguments[2]
+            )));
+        }
+
+        Ok(Arc::new(AggregateArgMinMaxNFunction::<TYPE> {
+            display_name: display_name.to_string(),
+            return_type: DataType::Array(Box::new(arguments[0].clone())),
+        }))
+    }
+
+    fn add_row(
+        &self,
+        state: &mut ArgMinMaxNState<TYPE>,
+        columns: InputColumns,
+        row: usize,
+    ) -> Result<()> {
+        if state.n == 0 {
+            let n = match unsafe { AnyType::index_column_unchecked(&columns[2], row) } {
+                ScalarRef::Number(n) => n.integer_to_i128().unwrap(),
+                _ => unreachable!(),
+            };
+            if n < 1 {
+                return Err(ErrorCode::BadArguments(format!(
+                    "The number of rows of {} must be at least 1, but got {}",
+                    self.display_name, n
+                )));
+            }
+            state.n = n as usize;
+        }
+        let value = unsafe { AnyType::index_column_unchecked(&columns[0], row) };
+        let order = unsafe { AnyType::index_column_unchecked(&columns[1], row) };
+        state.push(ArgItem {
+            order: order.to_owned(),
+            value: value.to_owned(),
+        });
+        Ok(())
+    }
+}
+
+impl<const TYPE: u8> AggregateFunction for AggregateArgMinMaxNFunction<TYPE> {
+    fn name(&self) -> &str {
+        "AggregateArgMinMaxNFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(self.return_type.clone())
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        place.write(ArgMinMaxNState::<TYPE>::default);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<ArgMinMaxNState<TYPE>>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let state = place.get::<ArgMinMaxNState<TYPE>>();
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                self.add_row(state, columns, row)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        for (row, place) in places.iter().enumerate() {
+            let state = place.next(offset).get::<ArgMinMaxNState<TYPE>>();
+            self.add_row(state, columns, row)?;
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let state = place.get::<ArgMinMaxNState<TYPE>>();
+        self.add_row(state, columns, row)
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<ArgMinMaxNState<TYPE>>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<ArgMinMaxNState<TYPE>>();
+        let rhs: ArgMinMaxNState<TYPE> = borsh_deserialize_state(reader)?;
+        state.merge(&rhs);
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<ArgMinMaxNState<TYPE>>();
+        let other = rhs.get::<ArgMinMaxNState<TYPE>>();
+        state.merge(other);
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<ArgMinMaxNState<TYPE>>();
+        match builder {
+            ColumnBuilder::Array(box inner) => {
+                for item in state.heap.clone().into_sorted_vec() {
+                    inner.builder.push(item.value.as_ref());
+                }
+                inner.commit_row();
+            }
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+
+    fn need_manual_drop_state(&self) -> bool {
+        true
+    }
+
+    unsafe fn drop_state(&self, place: StateAddr) {
+        let state = place.get::<ArgMinMaxNState<TYPE>>();
+        std::ptr::drop_in_place(state);
+    }
+}
+
+impl<const TYPE: u8> fmt::Display for AggregateArgMinMaxNFunction<TYPE> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_arg_min_n_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(
+        AggregateArgMinMaxNFunction::<TYPE_MIN>::try_create,
+    ))
+}
+
+pub fn aggregate_arg_max_n_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(
+        AggregateArgMinMaxNFunction::<TYPE_MAX>::try_create,
+    ))
+}
@@ -0,0 +1,252 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::fmt;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::type_check::check_number;
+use databend_common_expression::types::number::F64;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::Float64Type;
+use databend_common_expression::types::NullableType;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::Expr;
+use databend_common_expression::FunctionContext;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+use databend_common_expression::ScalarRef;
+
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::StateAddr;
+use crate::aggregates::assert_binary_arguments;
+use crate::aggregates::assert_params;
+use crate::aggregates::AggregateFunction;
+use crate::BUILTIN_FUNCTIONS;
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct AutocorrState {
+    pairs: Vec<(Scalar, F64)>,
+}
+
+impl AutocorrState {
+    fn add(&mut self, columns: InputColumns, row: usize) {
+        let order = unsafe { AnyType::index_column_unchecked(&columns[0], row) };
+        let value = match unsafe { AnyType::index_column_unchecked(&columns[1], row) } {
+            ScalarRef::Number(value) => value.to_f64(),
+            _ => unreachable!(),
+        };
+        self.pairs.push((order.to_owned(), value));
+    }
+
+    fn merge(&mut self, rhs: &Self) {
+        self.pairs.extend(rhs.pairs.iter().cloned());
+    }
+
+    // Rows of the same order are sorted by value, so the result doesn't depend on
+    // the order in which the rows arrived.
+    fn autocorr(&mut self, lag: usize) -> Option<f64> {
+        if self.pairs.len() <= lag {
+            return None;
+        }
+        self.pairs.sort();
+        let values = self
+            .pairs
+            .iter()
+            .map(|(_, value)| value.0)
+            .collect::<Vec<_>>();
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let variance = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>();
+        if variance == 0.0 {
+            return None;
+        }
+        let covariance = values
+            .iter()
+            .zip(values[lag..].iter())
+            .map(|(v, lagged)| (v - mean) * (lagged - mean))
+            .sum::<f64>();
+        Some(covariance / variance)
+    }
+}
+
+/// `autocorr(k)(order, value)` returns the autocorrelation of `value` at lag `k`, when
+/// the rows are sorted by `order`.
+///
+/// It is the sum of `(x[t] - mean) * (x[t + k] - mean)` over the series divided by the
+/// sum of `(x[t] - mean)^2`, both around the mean of the whole series. Rows of the same
+/// `order` are sorted by `value`. Rows whose `order` or `value` is NULL are skipped, a
+/// series of at most `k` values, or whose values are all the same, returns NULL.
+#[derive(Clone)]
+pub struct AggregateAutocorrFunction {
+    display_name: String,
+    lag: usize,
+}
+
+impl AggregateAutocorrFunction {
+    pub fn try_create(
+        display_name: &str,
+        params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<Arc<dyn AggregateFunction>> {
+        assert_params(display_name, params.len(), 1)?;
+        assert_binary_arguments(display_name, arguments.len())?;
+
+        let lag = check_number::<_, i64>(
+            None,
+            &FunctionContext::default(),
+            &Expr::<usize>::Constant {
+                span: None,
+                scalar: params[0].clone(),
+                data_type: params[0].as_ref().infer_data_type(),
+            },
+            &BUILTIN_FUNCTIONS,
+        )?;
+        if lag < 1 {
+            return Err(ErrorCode::BadArguments(format!(
+                "{} expects k >= 1, but got {}",
+                display_name, lag
+            )));
+        }
+
+        if !arguments[0].is_numeric() && !arguments[0].is_date_or_date_time() {
+            return Err(ErrorCode::BadDataValueType(format!(
+                "{} does not support order type '{:?}'",
+                display_name, arguments[0]
+            )));
+        }
+        if !arguments[1].is_numeric() {
+            return Err(ErrorCode::BadDataValueType(format!(
+                "{} does not support value type '{:?}'",
+                display_name, arguments[1]
+            )));
+        }
+
+        Ok(Arc::new(AggregateAutocorrFunction {
+            display_name: display_name.to_string(),
+            lag: lag as usize,
+        }))
+    }
+}
+
+impl AggregateFunction for AggregateAutocorrFunction {
+    fn name(&self) -> &str {
+        "AggregateAutocorrFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(DataType::Number(NumberDataType::Float64).wrap_nullable())
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        place.write(AutocorrState::default);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<AutocorrState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let state = place.get::<AutocorrState>();
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                state.add(columns, row);
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        for (row, place) in places.iter().enumerate() {
+            let state = place.next(offset).get::<AutocorrState>();
+            state.add(columns, row);
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let state = place.get::<AutocorrState>();
+        state.add(columns, row);
+        Ok(())
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<AutocorrState>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<AutocorrState>();
+        let rhs: AutocorrState = borsh_deserialize_state(reader)?;
+        state.merge(&rhs);
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<AutocorrState>();
+        let other = rhs.get::<AutocorrState>();
+        state.merge(other);
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<AutocorrState>();
+        let builder = NullableType::<Float64Type>::try_downcast_builder(builder).unwrap();
+        match state.autocorr(self.lag) {
+            Some(autocorr) => builder.push(autocorr.into()),
+            None => builder.push_null(),
+        }
+        Ok(())
+    }
+
+    fn need_manual_drop_state(&self) -> bool {
+        true
+    }
+
+    unsafe fn drop_state(&self, place: StateAddr) {
+        let state = place.get::<AutocorrState>();
+        std::ptr::drop_in_place(state);
+    }
+}
+
+impl fmt::Display for AggregateAutocorrFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_autocorr_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(AggregateAutocorrFunction::try_create))
+}
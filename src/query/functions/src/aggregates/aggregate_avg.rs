@@ -24,6 +24,7 @@ use databend_common_expression::types::decimal::*;
 use databend_common_expression::types::*;
 use databend_common_expression::utils::arithmetics_type::ResultTypeOfUnary;
 use databend_common_expression::with_number_mapped_type;
+use databend_common_expression::ColumnBuilder;
 use databend_common_expression::Scalar;
 use num_traits::AsPrimitive;
 
@@ -33,6 +34,7 @@ use super::FunctionData;
 use super::UnaryState;
 use crate::aggregates::aggregate_function_factory::AggregateFunctionDescription;
 use crate::aggregates::aggregator_common::assert_unary_arguments;
+use crate::aggregates::aggregator_common::RoundingMode;
 use crate::aggregates::AggregateFunctionRef;
 
 #[derive(BorshSerialize, BorshDeserialize)]
@@ -98,10 +100,47 @@ where
     }
 }
 
+// `sum_count` shares the state of `avg`, and returns it as a tuple `(sum, count)`.
+impl<T, TSum> UnaryState<T, AnyType> for NumberAvgState<T, TSum>
+where
+    T: ValueType + Sync + Send,
+    TSum: ValueType,
+    T::Scalar: Number + AsPrimitive<TSum::Scalar>,
+    TSum::Scalar:
+        Number + AsPrimitive<f64> + BorshSerialize + BorshDeserialize + std::ops::AddAssign,
+{
+    fn add(
+        &mut self,
+        other: T::ScalarRef<'_>,
+        function_data: Option<&dyn FunctionData>,
+    ) -> Result<()> {
+        UnaryState::<T, Float64Type>::add(self, other, function_data)
+    }
+
+    fn merge(&mut self, rhs: &Self) -> Result<()> {
+        UnaryState::<T, Float64Type>::merge(self, rhs)
+    }
+
+    fn merge_result(
+        &mut self,
+        builder: &mut ColumnBuilder,
+        _function_data: Option<&dyn FunctionData>,
+    ) -> Result<()> {
+        let result = Scalar::Tuple(vec![
+            TSum::upcast_scalar(self.value),
+            Scalar::Number(NumberScalar::UInt64(self.count)),
+        ]);
+        builder.push(result.as_ref());
+        Ok(())
+    }
+}
+
 struct DecimalAvgData {
     // only for decimals
     // AVG：AVG(DECIMAL(a, b)) -> DECIMAL(38 or 76, max(b, 4))。
     pub scale_add: u8,
+    // the rounding of `sum / count` at the scale of the result
+    pub rounding_mode: RoundingMode,
 }
 
 impl FunctionData for DecimalAvgData {
@@ -186,8 +225,11 @@ where
         match self
             .value
             .checked_mul(T::Scalar::e(decimal_avg_data.scale_add as u32))
-            .and_then(|v| v.checked_div(T::Scalar::from_i128(self.count)))
-        {
+            .and_then(|v| {
+                decimal_avg_data
+                    .rounding_mode
+                    .div(v, T::Scalar::from_i128(self.count))
+            }) {
             Some(value) => {
                 T::push_item(builder, T::to_scalar_ref(&value));
                 Ok(())
@@ -201,12 +243,16 @@ where
     }
 }
 
+/// `avg(x)` returns the mean of the values, `avg(mode)(x)` rounds a decimal mean with
+/// the rounding mode `'half_up'`, `'half_even'` or `'truncate'`, the default. A float mean
+/// is not rounded.
 pub fn try_create_aggregate_avg_function(
     display_name: &str,
     params: Vec<Scalar>,
     arguments: Vec<DataType>,
 ) -> Result<AggregateFunctionRef> {
     assert_unary_arguments(display_name, arguments.len())?;
+    let rounding_mode = RoundingMode::try_from_params(display_name, &params)?;
 
     let data_type = if arguments[0].is_null() {
         Int8Type::data_type()
@@ -244,7 +290,10 @@ pub fn try_create_aggregate_avg_function(
                 >::try_create(
                     display_name, return_type, params, arguments[0].clone()
                 )
-                .with_function_data(Box::new(DecimalAvgData { scale_add }));
+                .with_function_data(Box::new(DecimalAvgData {
+                    scale_add,
+                    rounding_mode,
+                }));
                 Ok(Arc::new(func))
             } else {
                 let func = AggregateUnaryFunction::<
@@ -254,7 +303,10 @@ pub fn try_create_aggregate_avg_function(
                 >::try_create(
                     display_name, return_type, params, arguments[0].clone()
                 )
-                .with_function_data(Box::new(DecimalAvgData { scale_add }));
+                .with_function_data(Box::new(DecimalAvgData {
+                    scale_add,
+                    rounding_mode,
+                }));
                 Ok(Arc::new(func))
             }
         }
@@ -277,7 +329,10 @@ pub fn try_create_aggregate_avg_function(
                 >::try_create(
                     display_name, return_type, params, arguments[0].clone()
                 )
-                .with_function_data(Box::new(DecimalAvgData { scale_add }));
+                .with_function_data(Box::new(DecimalAvgData {
+                    scale_add,
+                    rounding_mode,
+                }));
                 Ok(Arc::new(func))
             } else {
                 let func = AggregateUnaryFunction::<
@@ -287,7 +342,10 @@ pub fn try_create_aggregate_avg_function(
                 >::try_create(
                     display_name, return_type, params, arguments[0].clone()
                 )
-                .with_function_data(Box::new(DecimalAvgData { scale_add }));
+                .with_function_data(Box::new(DecimalAvgData {
+                    scale_add,
+                    rounding_mode,
+                }));
                 Ok(Arc::new(func))
             }
         }
@@ -308,3 +366,45 @@ pub fn aggregate_avg_function_desc() -> AggregateFunctionDescription {
         features,
     )
 }
+
+/// `sum_count(x)` returns the tuple `(sum(x), count(x))` computed in one pass.
+///
+/// The tuple is the partial state of `avg`: partials of several views are combined
+/// by adding up the sums and the counts separately, and `sum / count` of the
+/// combined tuple is the `avg` over all of them.
+pub fn try_create_aggregate_sum_count_function(
+    display_name: &str,
+    params: Vec<Scalar>,
+    arguments: Vec<DataType>,
+) -> Result<AggregateFunctionRef> {
+    assert_unary_arguments(display_name, arguments.len())?;
+
+    let data_type = if arguments[0].is_null() {
+        Int8Type::data_type()
+    } else {
+        arguments[0].clone()
+    };
+
+    with_number_mapped_type!(|NUM| match &data_type {
+        DataType::Number(NumberDataType::NUM) => {
+            type TSum = <NUM as ResultTypeOfUnary>::Sum;
+            let return_type = DataType::Tuple(vec![
+                NumberType::<TSum>::data_type(),
+                UInt64Type::data_type(),
+            ]);
+            AggregateUnaryFunction::<
+                NumberAvgState<NumberType<NUM>, NumberType<TSum>>,
+                NumberType<NUM>,
+                AnyType,
+            >::try_create_unary(display_name, return_type, params, arguments[0].clone())
+        }
+        _ => Err(ErrorCode::BadDataValueType(format!(
+            "{} does not support type '{:?}'",
+            display_name, arguments[0]
+        ))),
+    })
+}
+
+pub fn aggregate_sum_count_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(try_create_aggregate_sum_count_function))
+}
@@ -0,0 +1,248 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::fmt;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::number::F64;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::Float64Type;
+use databend_common_expression::types::NullableType;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::Column;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+use databend_common_expression::ScalarRef;
+
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::StateAddr;
+use crate::aggregates::aggregator_common::assert_arguments;
+use crate::aggregates::AggregateFunction;
+use crate::scalars::geo_dist_init;
+use crate::scalars::sphere_distance_meters;
+
+const MICROS_PER_SECOND: f64 = 1_000_000.0;
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct AvgSpeedState {
+    points: Vec<(Scalar, F64, F64)>,
+}
+
+impl AvgSpeedState {
+    fn add(&mut self, columns: InputColumns, row: usize) {
+        let ts = unsafe { AnyType::index_column_unchecked(&columns[0], row) };
+        let lon = to_f64(&columns[1], row);
+        let lat = to_f64(&columns[2], row);
+        self.points.push((ts.to_owned(), lon.into(), lat.into()));
+    }
+
+    fn merge(&mut self, rhs: &Self) {
+        self.points.extend(rhs.points.iter().cloned());
+    }
+
+    // Points of the same `ts` are sorted by `(lon, lat)`, so the result doesn't depend
+    // on the order in which the rows arrived.
+    fn avg_speed(&mut self) -> Option<f64> {
+        if self.points.len() < 2 {
+            return None;
+        }
+        self.points.sort();
+        let distance = self
+            .points
+            .windows(2)
+            .map(|w| {
+                let ((_, lon1, lat1), (_, lon2, lat2)) = (&w[0], &w[1]);
+                sphere_distance_meters(lon1.0 as f32, lat1.0 as f32, lon2.0 as f32, lat2.0 as f32)
+                    as f64
+            })
+            .sum::<f64>();
+        let elapsed =
+            to_seconds(&self.points[self.points.len() - 1].0) - to_seconds(&self.points[0].0);
+        if elapsed == 0.0 {
+            return None;
+        }
+        Some(distance / elapsed)
+    }
+}
+
+fn to_f64(column: &Column, row: usize) -> f64 {
+    match unsafe { AnyType::index_column_unchecked(column, row) } {
+        ScalarRef::Number(number) => number.to_f64().0,
+        _ => unreachable!(),
+    }
+}
+
+fn to_seconds(ts: &Scalar) -> f64 {
+    match ts {
+        Scalar::Timestamp(micros) => *micros as f64 / MICROS_PER_SECOND,
+        Scalar::Date(days) => *days as f64 * SECONDS_PER_DAY,
+        Scalar::Number(seconds) => seconds.to_f64().0,
+        _ => unreachable!(),
+    }
+}
+
+/// `avg_speed(ts, lon, lat)` returns the average speed in meters per second along the
+/// track of the points sorted by `ts`: the sum of the great circle distances between
+/// consecutive points, measured like `great_circle_distance`, divided by the time elapsed
+/// between the first and the last point. A numeric `ts` is taken as seconds.
+///
+/// Points of the same `ts` are sorted by `(lon, lat)`. Rows with a NULL `ts`, `lon` or
+/// `lat` are skipped, a track of a single point, or whose points all have the same `ts`,
+/// returns NULL. All the points are buffered until the result is computed.
+#[derive(Clone)]
+pub struct AggregateAvgSpeedFunction {
+    display_name: String,
+}
+
+impl AggregateAvgSpeedFunction {
+    pub fn try_create(
+        display_name: &str,
+        _params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<Arc<dyn AggregateFunction>> {
+        assert_arguments(display_name, arguments.len(), 3)?;
+        if !arguments[0].is_numeric() && !arguments[0].is_date_or_date_time() {
+            return Err(ErrorCode::BadDataValueType(format!(
+                "{} does not support ts type '{:?}'",
+                display_name, arguments[0]
+            )));
+        }
+        for argument in arguments[1..].iter() {
+            if !argument.is_numeric() {
+                return Err(ErrorCode::BadDataValueType(format!(
+                    "The coordinates of aggregate function {} must be numbers, got: {:?}",
+                    display_name, argument
+                )));
+            }
+        }
+
+        geo_dist_init();
+        Ok(Arc::new(AggregateAvgSpeedFunction {
+            display_name: display_name.to_string(),
+        }))
+    }
+}
+
+impl AggregateFunction for AggregateAvgSpeedFunction {
+    fn name(&self) -> &str {
+        "AggregateAvgSpeedFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(DataType::Number(NumberDataType::Float64).wrap_nullable())
+    }
+    fn init_state(&self, place: StateAddr) {
+        place.write(AvgSpeedState::default);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<AvgSpeedState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let state = place.get::<AvgSpeedState>();
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                state.add(columns, row);
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        for (row, place) in places.iter().enumerate() {
+            let state = place.next(offset).get::<AvgSpeedState>();
+            state.add(columns, row);
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let state = place.get::<AvgSpeedState>();
+        state.add(columns, row);
+        Ok(())
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<AvgSpeedState>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<AvgSpeedState>();
+        let rhs: AvgSpeedState = borsh_deserialize_state(reader)?;
+        state.merge(&rhs);
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<AvgSpeedState>();
+        let other = rhs.get::<AvgSpeedState>();
+        state.merge(other);
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<AvgSpeedState>();
+        let builder = NullableType::<Float64Type>::try_downcast_builder(builder).unwrap();
+        match state.avg_speed() {
+            Some(speed) => builder.push(speed.into()),
+            None => builder.push_null(),
+        }
+        Ok(())
+    }
+
+    fn need_manual_drop_state(&self) -> bool {
+        true
+    }
+
+    unsafe fn drop_state(&self, place: StateAddr) {
+        let state = place.get::<AvgSpeedState>();
+        std::ptr::drop_in_place(state);
+    }
+}
+
+impl fmt::Display for AggregateAvgSpeedFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_avg_speed_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(AggregateAvgSpeedFunction::try_create))
+}
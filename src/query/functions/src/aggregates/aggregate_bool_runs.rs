@@ -0,0 +1,221 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::fmt;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::BooleanType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::StateAddr;
+use crate::aggregates::assert_binary_arguments;
+use crate::aggregates::AggregateFunction;
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct BoolRunsState {
+    events: Vec<(Scalar, bool)>,
+}
+
+impl BoolRunsState {
+    fn merge(&mut self, rhs: &Self) {
+        self.events.extend(rhs.events.iter().cloned());
+    }
+
+    // Sorted by time, then `false` before `true` for rows of the same time,
+    // so the runs don't depend on the order in which the rows arrived.
+    fn runs(&mut self) -> Vec<(Scalar, Scalar, bool)> {
+        self.events.sort();
+        let mut runs: Vec<(Scalar, Scalar, bool)> = Vec::new();
+        for (ts, flag) in self.events.iter() {
+            match runs.last_mut() {
+                Some((_, end, value)) if *value == *flag => *end = ts.clone(),
+                _ => runs.push((ts.clone(), ts.clone(), *flag)),
+            }
+        }
+        runs
+    }
+}
+
+/// `bool_runs(ts, flag)` returns the runs of rows where `flag` stayed the same,
+/// as an array of `(start_ts, end_ts, flag)` ordered by time.
+///
+/// `start_ts` and `end_ts` are the times of the first and the last rows of the run,
+/// a single row is a run starting and ending at its own time. Rows whose `ts` or
+/// `flag` is NULL are skipped and don't break a run.
+#[derive(Clone)]
+pub struct AggregateBoolRunsFunction {
+    display_name: String,
+    return_type: DataType,
+}
+
+impl AggregateBoolRunsFunction {
+    pub fn try_create(
+        display_name: &str,
+        _params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<Arc<dyn AggregateFunction>> {
+        assert_binary_arguments(display_name, arguments.len())?;
+
+        let ts_type = arguments[0].clone();
+        if !ts_type.is_numeric() && !ts_type.is_date_or_date_time() {
+            return Err(ErrorCode::BadDataValueType(format!(
+                "{} does not support time type '{:?}'",
+                display_name, arguments[0]
+            )));
+        }
+        if !arguments[1].is_boolean() {
+            return Err(ErrorCode::BadDataValueType(format!(
+                "The second argument of aggregate function {} must be boolean, got: {:?}",
+                display_name, arguments[1]
+            )));
+        }
+
+        let return_type = DataType::Array(Box::new(DataType::Tuple(vec![
+            ts_type.clone(),
+            ts_type,
+            DataType::Boolean,
+        ])));
+        Ok(Arc::new(AggregateBoolRunsFunction {
+            display_name: display_name.to_string(),
+            return_type,
+        }))
+    }
+}
+
+impl AggregateFunction for AggregateBoolRunsFunction {
+    fn name(&self) -> &str {
+        "AggregateBoolRunsFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(self.return_type.clone())
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        place.write(BoolRunsState::default);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<BoolRunsState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let flags = BooleanType::try_downcast_column(&columns[1]).unwrap();
+        let state = place.get::<BoolRunsState>();
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                let ts = unsafe { AnyType::index_column_unchecked(&columns[0], row) };
+                state.events.push((ts.to_owned(), flags.get_bit(row)));
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        let flags = BooleanType::try_downcast_column(&columns[1]).unwrap();
+        for (row, place) in places.iter().enumerate() {
+            let state = place.next(offset).get::<BoolRunsState>();
+            let ts = unsafe { AnyType::index_column_unchecked(&columns[0], row) };
+            state.events.push((ts.to_owned(), flags.get_bit(row)));
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let flags = BooleanType::try_downcast_column(&columns[1]).unwrap();
+        let state = place.get::<BoolRunsState>();
+        let ts = unsafe { AnyType::index_column_unchecked(&columns[0], row) };
+        state.events.push((ts.to_owned(), flags.get_bit(row)));
+        Ok(())
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<BoolRunsState>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<BoolRunsState>();
+        let rhs: BoolRunsState = borsh_deserialize_state(reader)?;
+        state.merge(&rhs);
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<BoolRunsState>();
+        let other = rhs.get::<BoolRunsState>();
+        state.merge(other);
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<BoolRunsState>();
+        match builder {
+            ColumnBuilder::Array(box inner) => {
+                for (start, end, flag) in state.runs() {
+                    let run = Scalar::Tuple(vec![start, end, Scalar::Boolean(flag)]);
+                    inner.builder.push(run.as_ref());
+                }
+                inner.commit_row();
+            }
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+
+    fn need_manual_drop_state(&self) -> bool {
+        true
+    }
+
+    unsafe fn drop_state(&self, place: StateAddr) {
+        let state = place.get::<BoolRunsState>();
+        std::ptr::drop_in_place(state);
+    }
+}
+
+impl fmt::Display for AggregateBoolRunsFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_bool_runs_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(AggregateBoolRunsFunction::try_create))
+}
@@ -0,0 +1,171 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::any::Any;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::type_check::check_number;
+use databend_common_expression::types::array::ArrayColumnBuilder;
+use databend_common_expression::types::number::Number;
+use databend_common_expression::types::number::F64;
+use databend_common_expression::types::ArrayType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::Float64Type;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::NumberType;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::with_number_mapped_type;
+use databend_common_expression::Expr;
+use databend_common_expression::FunctionContext;
+use databend_common_expression::Scalar;
+use num_traits::AsPrimitive;
+
+use super::AggregateUnaryFunction;
+use super::FunctionData;
+use super::UnaryState;
+use crate::aggregates::aggregate_function_factory::AggregateFunctionDescription;
+use crate::aggregates::assert_unary_arguments;
+use crate::aggregates::AggregateFunctionRef;
+use crate::BUILTIN_FUNCTIONS;
+
+struct CdfAtData {
+    thresholds: Vec<f64>,
+}
+
+impl FunctionData for CdfAtData {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct CdfAtState {
+    values: Vec<F64>,
+}
+
+impl<T> UnaryState<T, ArrayType<Float64Type>> for CdfAtState
+where
+    T: ValueType,
+    T::Scalar: Number + AsPrimitive<f64>,
+{
+    fn add(
+        &mut self,
+        other: T::ScalarRef<'_>,
+        _function_data: Option<&dyn FunctionData>,
+    ) -> Result<()> {
+        let value: f64 = T::to_owned_scalar(other).as_();
+        self.values.push(value.into());
+        Ok(())
+    }
+
+    fn merge(&mut self, rhs: &Self) -> Result<()> {
+        self.values.extend_from_slice(&rhs.values);
+        Ok(())
+    }
+
+    fn merge_result(
+        &mut self,
+        builder: &mut ArrayColumnBuilder<Float64Type>,
+        function_data: Option<&dyn FunctionData>,
+    ) -> Result<()> {
+        let data = unsafe {
+            function_data
+                .unwrap()
+                .as_any()
+                .downcast_ref_unchecked::<CdfAtData>()
+        };
+        self.values.sort_unstable();
+        let count = self.values.len();
+        for threshold in data.thresholds.iter() {
+            let less_equal = self.values.partition_point(|value| value.0 <= *threshold);
+            let fraction = if count == 0 {
+                0.0
+            } else {
+                less_equal as f64 / count as f64
+            };
+            builder.put_item(fraction.into());
+        }
+        builder.commit_row();
+        Ok(())
+    }
+}
+
+fn get_thresholds(display_name: &str, params: &[Scalar]) -> Result<Vec<f64>> {
+    if params.is_empty() {
+        return Err(ErrorCode::NumberArgumentsNotMatch(format!(
+            "{} expects at least one threshold",
+            display_name
+        )));
+    }
+    params
+        .iter()
+        .map(|param| {
+            let threshold: F64 = check_number(
+                None,
+                &FunctionContext::default(),
+                &Expr::<usize>::Constant {
+                    span: None,
+                    scalar: param.clone(),
+                    data_type: param.as_ref().infer_data_type(),
+                },
+                &BUILTIN_FUNCTIONS,
+            )?;
+            Ok(threshold.0)
+        })
+        .collect()
+}
+
+/// `cdf_at(t1, t2, ...)(x)` returns, for each threshold, the fraction of the values of `x`
+/// that are less than or equal to it, i.e. the empirical distribution function of `x` at
+/// the thresholds, in the order they are given.
+///
+/// It is `percent_rank_of(t)(x)` at several thresholds in a single pass: the values are
+/// buffered and sorted once, and each threshold is looked up with a binary search. NULL
+/// values are ignored, a group without values returns 0 for every threshold.
+pub fn try_create_aggregate_cdf_at_function(
+    display_name: &str,
+    params: Vec<Scalar>,
+    arguments: Vec<DataType>,
+) -> Result<AggregateFunctionRef> {
+    assert_unary_arguments(display_name, arguments.len())?;
+    let thresholds = get_thresholds(display_name, &params)?;
+    let return_type = DataType::Array(Box::new(DataType::Number(NumberDataType::Float64)));
+
+    with_number_mapped_type!(|NUM_TYPE| match &arguments[0] {
+        DataType::Number(NumberDataType::NUM_TYPE) => {
+            let func = AggregateUnaryFunction::<
+                CdfAtState,
+                NumberType<NUM_TYPE>,
+                ArrayType<Float64Type>,
+            >::try_create(
+                display_name, return_type, params, arguments[0].clone()
+            )
+            .with_function_data(Box::new(CdfAtData { thresholds }))
+            .with_need_drop(true);
+            Ok(Arc::new(func))
+        }
+        _ => Err(ErrorCode::BadDataValueType(format!(
+            "{} does not support type '{:?}'",
+            display_name, arguments[0]
+        ))),
+    })
+}
+
+pub fn aggregate_cdf_at_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(try_create_aggregate_cdf_at_function))
+}
@@ -27,6 +27,7 @@ use databend_common_expression::ColumnBuilder;
 use databend_common_expression::InputColumns;
 use databend_common_expression::Scalar;
 
+use super::aggregate_distinct_state::AggregateDistinctBloomState;
 use super::aggregate_distinct_state::AggregateDistinctNumberState;
 use super::aggregate_distinct_state::AggregateDistinctState;
 use super::aggregate_distinct_state::AggregateDistinctStringState;
@@ -161,6 +162,7 @@ impl<State> fmt::Display for AggregateDistinctCombinator<State> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self.nested_name.as_str() {
             "uniq" => write!(f, "uniq"),
+            "count_distinct_exact" => write!(f, "count_distinct_exact"),
             _ => write!(f, "{}_distinct", self.nested_name),
         }
     }
@@ -187,6 +189,39 @@ pub fn try_create_uniq(
     try_create(nested_name, params, arguments, &creator)
 }
 
+pub fn aggregate_count_distinct_exact_desc() -> AggregateFunctionDescription {
+    let features = super::aggregate_function_factory::AggregateFunctionFeatures {
+        returns_default_when_only_null: true,
+        ..Default::default()
+    };
+    AggregateFunctionDescription::creator_with_features(
+        Box::new(try_create_count_distinct_exact),
+        features,
+    )
+}
+
+/// Same as `count(distinct ...)`, but every argument type is kept in an exact set of
+/// serialized keys with a Bloom filter in front of it, see `AggregateDistinctBloomState`.
+pub fn try_create_count_distinct_exact(
+    nested_name: &str,
+    params: Vec<Scalar>,
+    arguments: Vec<DataType>,
+) -> Result<Arc<dyn AggregateFunction>> {
+    let name = format!("DistinctCombinator({})", nested_name);
+    assert_variadic_arguments(&name, arguments.len(), (1, 32))?;
+
+    let nested = AggregateCountFunction::try_create(nested_name, params, vec![])?;
+    Ok(Arc::new(AggregateDistinctCombinator::<
+        AggregateDistinctBloomState,
+    > {
+        nested_name: nested_name.to_owned(),
+        arguments,
+        nested,
+        name,
+        _state: PhantomData,
+    }))
+}
+
 pub fn try_create(
     nested_name: &str,
     params: Vec<Scalar>,
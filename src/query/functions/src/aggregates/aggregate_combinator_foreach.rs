@@ -0,0 +1,299 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::fmt;
+use std::sync::Arc;
+
+use bumpalo::Bump;
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::ArrayColumn;
+use databend_common_expression::types::DataType;
+use databend_common_expression::Column;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::AggregateFunctionCombinatorNull;
+use super::StateAddr;
+use crate::aggregates::aggregate_function_factory::AggregateFunctionCreator;
+use crate::aggregates::aggregate_function_factory::AggregateFunctionFeatures;
+use crate::aggregates::aggregate_function_factory::CombinatorDescription;
+use crate::aggregates::AggregateFunction;
+use crate::aggregates::AggregateFunctionRef;
+
+/// The state of `-ForEach` keeps one nested state per array index.
+/// Nested states are allocated lazily in the arena owned by the state,
+/// so the number of states grows with the longest array seen in the group.
+struct AggregateForEachState {
+    arena: Bump,
+    places: Vec<StateAddr>,
+}
+
+impl AggregateForEachState {
+    fn new() -> Self {
+        Self {
+            arena: Bump::new(),
+            places: Vec::new(),
+        }
+    }
+
+    fn grow(&mut self, nested: &AggregateFunctionRef, len: usize) {
+        let layout = nested.state_layout();
+        while self.places.len() < len {
+            let place: StateAddr = self.arena.alloc_layout(layout).into();
+            nested.init_state(place);
+            self.places.push(place);
+        }
+    }
+}
+
+/// `-ForEach` combinator applies the nested aggregate function independently
+/// to each array index, returning an array of aggregated results.
+///
+/// E.g. `sum_foreach(arr)` over `[1, 2]`, `[3, 4]` returns `[4, 6]`.
+///
+/// All array arguments of the same row must have the same size. Arrays of
+/// different rows may be ragged: index `i` is aggregated over the rows that
+/// have an element at `i`, and the result is as long as the longest array.
+#[derive(Clone)]
+pub struct AggregateForEachCombinator {
+    name: String,
+    nested_name: String,
+    nested: AggregateFunctionRef,
+}
+
+impl AggregateForEachCombinator {
+    pub fn try_create(
+        nested_name: &str,
+        params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+        nested_creator: &AggregateFunctionCreator,
+    ) -> Result<AggregateFunctionRef> {
+        let name = format!("ForEachCombinator({})", nested_name);
+
+        if arguments.is_empty() {
+            return Err(ErrorCode::NumberArgumentsNotMatch(format!(
+                "{} expect to have more than one argument",
+                name
+            )));
+        }
+
+        let mut nested_arguments = Vec::with_capacity(arguments.len());
+        for arg in arguments.iter() {
+            match arg {
+                DataType::Array(box ty) => nested_arguments.push(ty.clone()),
+                _ => {
+                    return Err(ErrorCode::BadArguments(format!(
+                        "The arguments of {} must be array type, but got {:?}",
+                        name, arg
+                    )));
+                }
+            }
+        }
+
+        let nested = if nested_arguments.iter().any(|ty| ty.is_nullable_or_null()) {
+            let nested = nested_creator(
+                nested_name,
+                AggregateFunctionCombinatorNull::transform_params(&params)?,
+                AggregateFunctionCombinatorNull::transform_arguments(&nested_arguments)?,
+            )?;
+            AggregateFunctionCombinatorNull::try_create(
+                nested_name,
+                params,
+                nested_arguments,
+                nested,
+                AggregateFunctionFeatures::default(),
+            )?
+        } else {
+            nested_creator(nested_name, params, nested_arguments)?
+        };
+
+        Ok(Arc::new(AggregateForEachCombinator {
+            name,
+            nested_name: nested_name.to_owned(),
+            nested,
+        }))
+    }
+
+    pub fn combinator_desc() -> CombinatorDescription {
+        CombinatorDescription::creator(Box::new(Self::try_create))
+    }
+
+    fn accumulate_arrays(
+        &self,
+        place: StateAddr,
+        arrays: &[&ArrayColumn<AnyType>],
+        row: usize,
+    ) -> Result<()> {
+        let values = arrays
+            .iter()
+            .map(|array| array.index(row).unwrap())
+            .collect::<Vec<_>>();
+
+        let len = values[0].len();
+        if let Some(other) = values.iter().find(|value| value.len() != len) {
+            return Err(ErrorCode::BadArguments(format!(
+                "{} requires arrays of the same size in one row, but got {} and {}",
+                self.name,
+                len,
+                other.len()
+            )));
+        }
+
+        let state = place.get::<AggregateForEachState>();
+        state.grow(&self.nested, len);
+        for (idx, nested_place) in state.places[..len].iter().enumerate() {
+            self.nested
+                .accumulate_row(*nested_place, (&values).into(), idx)?;
+        }
+        Ok(())
+    }
+}
+
+fn downcast_arrays<'a>(columns: &'a InputColumns) -> Vec<&'a ArrayColumn<AnyType>> {
+    columns
+        .iter()
+        .map(|column| match column {
+            Column::Array(box array) => array,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+impl AggregateFunction for AggregateForEachCombinator {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(DataType::Array(Box::new(self.nested.return_type()?)))
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        place.write(AggregateForEachState::new);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<AggregateForEachState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let arrays = downcast_arrays(&columns);
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                self.accumulate_arrays(place, &arrays, row)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        let arrays = downcast_arrays(&columns);
+        for (row, place) in places.iter().enumerate() {
+            self.accumulate_arrays(place.next(offset), &arrays, row)?;
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let arrays = downcast_arrays(&columns);
+        self.accumulate_arrays(place, &arrays, row)
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<AggregateForEachState>();
+        borsh_serialize_state(writer, &(state.places.len() as u64))?;
+        // Nested states are not required to be self-delimited, prefix each with its size.
+        for nested_place in state.places.iter() {
+            let mut buf = Vec::new();
+            self.nested.serialize(*nested_place, &mut buf)?;
+            borsh_serialize_state(writer, &buf)?;
+        }
+        Ok(())
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<AggregateForEachState>();
+        let len: u64 = borsh_deserialize_state(reader)?;
+        state.grow(&self.nested, len as usize);
+        for nested_place in state.places[..len as usize].iter() {
+            let buf: Vec<u8> = borsh_deserialize_state(reader)?;
+            self.nested.merge(*nested_place, &mut buf.as_slice())?;
+        }
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<AggregateForEachState>();
+        let other = rhs.get::<AggregateForEachState>();
+        state.grow(&self.nested, other.places.len());
+        for (nested_place, nested_rhs) in state.places.iter().zip(other.places.iter()) {
+            self.nested.merge_states(*nested_place, *nested_rhs)?;
+        }
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<AggregateForEachState>();
+        match builder {
+            ColumnBuilder::Array(box inner) => {
+                for nested_place in state.places.iter() {
+                    self.nested
+                        .merge_result(*nested_place, &mut inner.builder)?;
+                }
+                inner.commit_row();
+            }
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+
+    fn need_manual_drop_state(&self) -> bool {
+        true
+    }
+
+    unsafe fn drop_state(&self, place: StateAddr) {
+        let state = place.get::<AggregateForEachState>();
+        if self.nested.need_manual_drop_state() {
+            for nested_place in state.places.iter() {
+                self.nested.drop_state(*nested_place);
+            }
+        }
+        std::ptr::drop_in_place(state);
+    }
+}
+
+impl fmt::Display for AggregateForEachCombinator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}_foreach", self.nested_name)
+    }
+}
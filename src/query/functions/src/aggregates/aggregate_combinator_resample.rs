@@ -0,0 +1,298 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::fmt;
+use std::sync::Arc;
+
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::type_check::check_number;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::Expr;
+use databend_common_expression::FunctionContext;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+use databend_common_expression::ScalarRef;
+
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::StateAddr;
+use crate::aggregates::aggregate_function_factory::AggregateFunctionCreator;
+use crate::aggregates::aggregate_function_factory::CombinatorDescription;
+use crate::aggregates::AggregateFunction;
+use crate::aggregates::AggregateFunctionRef;
+use crate::BUILTIN_FUNCTIONS;
+
+/// The nested states of all the buckets are in the state, so their number is limited.
+const MAX_RESAMPLE_BUCKETS: i128 = 4096;
+
+/// `-Resample` combinator splits the rows into buckets by the range of a key and applies
+/// the nested aggregate function to each bucket, returning an array of the results of the
+/// buckets.
+///
+/// E.g. `sum_resample(0, 6, 2)(value, key)` returns the sums of `value` for `key` in
+/// `[0, 2)`, `[2, 4)` and `[4, 6)`.
+///
+/// The last three parameters are `start`, `end` and `step`, the ones before are passed
+/// to the nested function. The key is the last argument and must be an integer, rows whose
+/// key is outside `[start, end)` are skipped, so the last bucket is cut at `end`. A bucket
+/// without rows has the result of the nested function over no rows, e.g. 0 for `sum`.
+#[derive(Clone)]
+pub struct AggregateResampleCombinator {
+    name: String,
+    nested_name: String,
+    nested: AggregateFunctionRef,
+    argument_len: usize,
+    start: i128,
+    end: i128,
+    step: i128,
+    buckets: usize,
+    // The size of a nested state padded to its alignment, the offset between buckets.
+    stride: usize,
+}
+
+impl AggregateResampleCombinator {
+    pub fn try_create(
+        nested_name: &str,
+        params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+        nested_creator: &AggregateFunctionCreator,
+    ) -> Result<AggregateFunctionRef> {
+        let name = format!("ResampleCombinator({})", nested_name);
+        let argument_len = arguments.len();
+
+        if params.len() < 3 {
+            return Err(ErrorCode::NumberArgumentsNotMatch(format!(
+                "{} expect to have the parameters start, end and step",
+                name
+            )));
+        }
+        if argument_len == 0 {
+            return Err(ErrorCode::NumberArgumentsNotMatch(format!(
+                "{} expect to have more than one argument",
+                name
+            )));
+        }
+        if !arguments[argument_len - 1].is_integer() {
+            return Err(ErrorCode::BadArguments(format!(
+                "The type of the last argument for {} must be integer type, but got {:?}",
+                name,
+                &arguments[argument_len - 1]
+            )));
+        }
+
+        let nested_params = &params[0..params.len() - 3];
+        let mut range = Vec::with_capacity(3);
+        for param in &params[params.len() - 3..] {
+            let value = check_number::<_, i64>(
+                None,
+                &FunctionContext::default(),
+                &Expr::<usize>::Constant {
+                    span: None,
+                    scalar: param.clone(),
+                    data_type: param.as_ref().infer_data_type(),
+                },
+                &BUILTIN_FUNCTIONS,
+            )?;
+            range.push(value as i128);
+        }
+        let (start, end, step) = (range[0], range[1], range[2]);
+        if step <= 0 {
+            return Err(ErrorCode::BadArguments(format!(
+                "{} expects step > 0, but got {}",
+                name, step
+            )));
+        }
+        if start >= end {
+            return Err(ErrorCode::BadArguments(format!(
+                "{} expects start < end, but got start {} and end {}",
+                name, start, end
+            )));
+        }
+        let buckets = (end - start + step - 1) / step;
+        if buckets > MAX_RESAMPLE_BUCKETS {
+            return Err(ErrorCode::BadArguments(format!(
+                "{} expects at most {} buckets, but got {}",
+                name, MAX_RESAMPLE_BUCKETS, buckets
+            )));
+        }
+
+        let nested_arguments = &arguments[0..argument_len - 1];
+        let nested = nested_creator(
+            nested_name,
+            nested_params.to_vec(),
+            nested_arguments.to_vec(),
+        )?;
+        let stride = nested.state_layout().pad_to_align().size();
+
+        Ok(Arc::new(AggregateResampleCombinator {
+            name,
+            nested_name: nested_name.to_owned(),
+            nested,
+            argument_len,
+            start,
+            end,
+            step,
+            buckets: buckets as usize,
+            stride,
+        }))
+    }
+
+    pub fn combinator_desc() -> CombinatorDescription {
+        CombinatorDescription::creator(Box::new(Self::try_create))
+    }
+
+    fn bucket_place(&self, place: StateAddr, bucket: usize) -> StateAddr {
+        place.next(bucket * self.stride)
+    }
+
+    fn bucket_places(&self, place: StateAddr) -> impl Iterator<Item = StateAddr> + '_ {
+        (0..self.buckets).map(move |bucket| self.bucket_place(place, bucket))
+    }
+
+    fn accumulate_bucket_row(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        row: usize,
+    ) -> Result<()> {
+        let key = match unsafe {
+            AnyType::index_column_unchecked(&columns[self.argument_len - 1], row)
+        } {
+            ScalarRef::Number(key) => key.integer_to_i128().unwrap(),
+            _ => unreachable!(),
+        };
+        if (self.start..self.end).contains(&key) {
+            let bucket = ((key - self.start) / self.step) as usize;
+            self.nested.accumulate_row(
+                self.bucket_place(place, bucket),
+                columns.slice(0..self.argument_len - 1),
+                row,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl AggregateFunction for AggregateResampleCombinator {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(DataType::Array(Box::new(self.nested.return_type()?)))
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        for bucket_place in self.bucket_places(place) {
+            self.nested.init_state(bucket_place);
+        }
+    }
+
+    fn state_layout(&self) -> Layout {
+        let layout = self.nested.state_layout();
+        Layout::from_size_align(self.stride * self.buckets, layout.align()).unwrap()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                self.accumulate_bucket_row(place, columns, row)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        for (row, place) in places.iter().enumerate() {
+            self.accumulate_bucket_row(place.next(offset), columns, row)?;
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        self.accumulate_bucket_row(place, columns, row)
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        // Nested states are not required to be self-delimited, prefix each with its size.
+        for bucket_place in self.bucket_places(place) {
+            let mut buf = Vec::new();
+            self.nested.serialize(bucket_place, &mut buf)?;
+            borsh_serialize_state(writer, &buf)?;
+        }
+        Ok(())
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        for bucket_place in self.bucket_places(place) {
+            let buf: Vec<u8> = borsh_deserialize_state(reader)?;
+            self.nested.merge(bucket_place, &mut buf.as_slice())?;
+        }
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        for (bucket_place, bucket_rhs) in self.bucket_places(place).zip(self.bucket_places(rhs)) {
+            self.nested.merge_states(bucket_place, bucket_rhs)?;
+        }
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        match builder {
+            ColumnBuilder::Array(box inner) => {
+                for bucket_place in self.bucket_places(place) {
+                    self.nested.merge_result(bucket_place, &mut inner.builder)?;
+                }
+                inner.commit_row();
+            }
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+
+    fn need_manual_drop_state(&self) -> bool {
+        self.nested.need_manual_drop_state()
+    }
+
+    unsafe fn drop_state(&self, place: StateAddr) {
+        for bucket_place in self.bucket_places(place) {
+            self.nested.drop_state(bucket_place);
+        }
+    }
+}
+
+impl fmt::Display for AggregateResampleCombinator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}_resample", self.nested_name)
+    }
+}
@@ -84,6 +84,9 @@ impl AggregateFunction for AggregateCountFunction {
 
     // columns may be nullable
     // if not we use validity as the null signs
+    //
+    // The rows are never iterated: `count()` adds the block length, and `count(col)`
+    // subtracts the unset bits of the validity, which are counted by popcount.
     fn accumulate(
         &self,
         place: StateAddr,
@@ -92,16 +95,12 @@ impl AggregateFunction for AggregateCountFunction {
         input_rows: usize,
     ) -> Result<()> {
         let state = place.get::<AggregateCountState>();
-        let nulls = if columns.is_empty() {
-            validity.map(|v| v.unset_bits()).unwrap_or(0)
-        } else {
-            match &columns[0] {
-                Column::Nullable(c) => validity
-                    .map(|v| v & (&c.validity))
-                    .unwrap_or_else(|| c.validity.clone())
-                    .unset_bits(),
-                _ => validity.map(|v| v.unset_bits()).unwrap_or(0),
-            }
+        let nulls = match columns.iter().next() {
+            Some(Column::Nullable(c)) => match validity {
+                Some(v) => (v & (&c.validity)).unset_bits(),
+                None => c.validity.unset_bits(),
+            },
+            _ => validity.map(|v| v.unset_bits()).unwrap_or(0),
         };
         state.count += (input_rows - nulls) as u64;
         Ok(())
@@ -0,0 +1,203 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::fmt;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::UInt64Type;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::Column;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+use databend_common_expression::ScalarRef;
+
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::aggregate_function_factory::AggregateFunctionFeatures;
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::StateAddr;
+use crate::aggregates::aggregator_common::assert_arguments;
+use crate::aggregates::AggregateFunction;
+use crate::scalars::geo_dist_init;
+use crate::scalars::sphere_distance_meters;
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct CountWithinDistanceState {
+    count: u64,
+}
+
+impl CountWithinDistanceState {
+    fn add(&mut self, columns: InputColumns, row: usize) {
+        let lon = to_f64(&columns[0], row) as f32;
+        let lat = to_f64(&columns[1], row) as f32;
+        let center_lon = to_f64(&columns[2], row) as f32;
+        let center_lat = to_f64(&columns[3], row) as f32;
+        let radius = to_f64(&columns[4], row);
+        if sphere_distance_meters(center_lon, center_lat, lon, lat) as f64 <= radius {
+            self.count += 1;
+        }
+    }
+
+    fn merge(&mut self, rhs: &Self) {
+        self.count += rhs.count;
+    }
+}
+
+fn to_f64(column: &Column, row: usize) -> f64 {
+    match unsafe { AnyType::index_column_unchecked(column, row) } {
+        ScalarRef::Number(number) => number.to_f64().0,
+        _ => unreachable!(),
+    }
+}
+
+/// `count_within_distance(lon, lat, center_lon, center_lat, radius_m)` returns the number
+/// of points within `radius_m` meters of the center, the distance being measured like
+/// `great_circle_distance`.
+///
+/// The center and the radius are expected to be constants, they are read on each row.
+/// Rows with a NULL argument are skipped.
+#[derive(Clone)]
+pub struct AggregateCountWithinDistanceFunction {
+    display_name: String,
+}
+
+impl AggregateCountWithinDistanceFunction {
+    pub fn try_create(
+        display_name: &str,
+        _params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<Arc<dyn AggregateFunction>> {
+        assert_arguments(display_name, arguments.len(), 5)?;
+        for argument in arguments.iter() {
+            if !argument.is_numeric() {
+                return Err(ErrorCode::BadDataValueType(format!(
+                    "The arguments of aggregate function {} must be numbers, got: {:?}",
+                    display_name, argument
+                )));
+            }
+        }
+
+        geo_dist_init();
+        Ok(Arc::new(AggregateCountWithinDistanceFunction {
+            display_name: display_name.to_string(),
+        }))
+    }
+}
+
+impl AggregateFunction for AggregateCountWithinDistanceFunction {
+    fn name(&self) -> &str {
+        "AggregateCountWithinDistanceFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(DataType::Number(NumberDataType::UInt64))
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        place.write(CountWithinDistanceState::default);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<CountWithinDistanceState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let state = place.get::<CountWithinDistanceState>();
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                state.add(columns, row);
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        for (row, place) in places.iter().enumerate() {
+            let state = place.next(offset).get::<CountWithinDistanceState>();
+            state.add(columns, row);
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let state = place.get::<CountWithinDistanceState>();
+        state.add(columns, row);
+        Ok(())
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<CountWithinDistanceState>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<CountWithinDistanceState>();
+        let rhs: CountWithinDistanceState = borsh_deserialize_state(reader)?;
+        state.merge(&rhs);
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<CountWithinDistanceState>();
+        let other = rhs.get::<CountWithinDistanceState>();
+        state.merge(other);
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<CountWithinDistanceState>();
+        let builder = UInt64Type::try_downcast_builder(builder).unwrap();
+        builder.push(state.count);
+        Ok(())
+    }
+}
+
+impl fmt::Display for AggregateCountWithinDistanceFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_count_within_distance_function_desc() -> AggregateFunctionDescription {
+    let features = AggregateFunctionFeatures {
+        returns_default_when_only_null: true,
+        ..Default::default()
+    };
+    AggregateFunctionDescription::creator_with_features(
+        Box::new(AggregateCountWithinDistanceFunction::try_create),
+        features,
+    )
+}
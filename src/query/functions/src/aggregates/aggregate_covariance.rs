@@ -42,7 +42,7 @@ use crate::aggregates::aggregator_common::assert_binary_arguments;
 use crate::aggregates::AggregateFunction;
 use crate::aggregates::AggregateFunctionRef;
 
-#[derive(BorshSerialize, BorshDeserialize)]
+#[derive(BorshSerialize, BorshDeserialize, Default)]
 pub struct AggregateCovarianceState {
     pub count: u64,
     pub co_moments: f64,
@@ -62,7 +62,7 @@ impl AggregateCovarianceState {
     // This equals the formula in the paper. Here we take the same approach as Clickhouse
     // does. Thanks Clickhouse!
     #[inline(always)]
-    fn add(&mut self, s: f64, t: f64) {
+    pub(crate) fn add(&mut self, s: f64, t: f64) {
         let left_delta = s - self.left_mean;
         let right_delta = t - self.right_mean;
 
@@ -80,7 +80,7 @@ impl AggregateCovarianceState {
     // Clickhouse also has some optimization when two data sets are large and comparable in size.
     // Here we take the same approach as Clickhouse does. Thanks Clickhouse!
     #[inline(always)]
-    fn merge(&mut self, other: &Self) {
+    pub(crate) fn merge(&mut self, other: &Self) {
         let total = self.count + other.count;
         if total == 0 {
             return;
@@ -359,3 +359,25 @@ pub fn aggregate_covariance_population_desc() -> AggregateFunctionDescription {
         try_create_aggregate_covariance::<AggregateCovariancePopulationImpl>,
     ))
 }
+
+// Dot product function implementation
+struct AggregateDotProductImpl;
+
+impl AggregateCovariance for AggregateDotProductImpl {
+    fn name() -> &'static str {
+        "AggregateDotProductFunction"
+    }
+
+    // The sum of products is recovered from the co-moments and the means:
+    //     sum(s*t) = co-moments + n * left_mean * right_mean
+    // An empty group returns NULL by the null adaptor, as the result is nullable.
+    fn apply(state: &AggregateCovarianceState) -> f64 {
+        state.co_moments + state.count as f64 * state.left_mean * state.right_mean
+    }
+}
+
+pub fn aggregate_dot_product_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(
+        try_create_aggregate_covariance::<AggregateDotProductImpl>,
+    ))
+}
@@ -0,0 +1,234 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::cmp::Ordering;
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+use databend_common_expression::ScalarRef;
+
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::StateAddr;
+use crate::aggregates::aggregator_common::assert_arguments;
+use crate::aggregates::AggregateFunction;
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct DedupLatestState {
+    // key -> (version, value)
+    latest: BTreeMap<Scalar, (Scalar, Scalar)>,
+}
+
+impl DedupLatestState {
+    fn add(&mut self, key: Scalar, version: Scalar, value: Scalar) {
+        match self.latest.entry(key) {
+            Entry::Vacant(entry) => {
+                entry.insert((version, value));
+            }
+            Entry::Occupied(mut entry) => {
+                let (latest_version, latest_value) = entry.get();
+                // On equal versions the greatest value wins, NULL being the greatest,
+                // so the result doesn't depend on the order of rows and partial states.
+                let newer = match version.cmp(latest_version) {
+                    Ordering::Greater => true,
+                    Ordering::Equal => value.as_ref() > latest_value.as_ref(),
+                    Ordering::Less => false,
+                };
+                if newer {
+                    entry.insert((version, value));
+                }
+            }
+        }
+    }
+
+    fn merge(&mut self, rhs: &Self) {
+        for (key, (version, value)) in rhs.latest.iter() {
+            self.add(key.clone(), version.clone(), value.clone());
+        }
+    }
+}
+
+/// `dedup_latest(key, value, version)` returns an array of `(key, value)`, one per
+/// distinct `key` of the group, where `value` comes from the row with the highest
+/// `version` of that key. The array is sorted by `key`.
+///
+/// Rows whose `key` or `version` is NULL are ignored, a NULL `value` is kept.
+#[derive(Clone)]
+pub struct AggregateDedupLatestFunction {
+    display_name: String,
+    return_type: DataType,
+}
+
+impl AggregateDedupLatestFunction {
+    pub fn try_create(
+        display_name: &str,
+        _params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<Arc<dyn AggregateFunction>> {
+        assert_arguments(display_name, arguments.len(), 3)?;
+
+        let version_type = arguments[2].remove_nullable();
+        if !version_type.is_numeric()
+            && !version_type.is_decimal()
+            && !version_type.is_date_or_date_time()
+            && !matches!(version_type, DataType::String | DataType::Null)
+        {
+            return Err(ErrorCode::BadDataValueType(format!(
+                "{} does not support version type '{:?}'",
+                display_name, arguments[2]
+            )));
+        }
+
+        let return_type = DataType::Array(Box::new(DataType::Tuple(vec![
+            arguments[0].remove_nullable(),
+            arguments[1].clone(),
+        ])));
+        Ok(Arc::new(AggregateDedupLatestFunction {
+            display_name: display_name.to_string(),
+            return_type,
+        }))
+    }
+
+    fn add_row(state: &mut DedupLatestState, columns: InputColumns, row: usize) {
+        let key = unsafe { AnyType::index_column_unchecked(&columns[0], row) };
+        let version = unsafe { AnyType::index_column_unchecked(&columns[2], row) };
+        if matches!(key, ScalarRef::Null) || matches!(version, ScalarRef::Null) {
+            return;
+        }
+        let value = unsafe { AnyType::index_column_unchecked(&columns[1], row) };
+        state.add(key.to_owned(), version.to_owned(), value.to_owned());
+    }
+}
+
+impl AggregateFunction for AggregateDedupLatestFunction {
+    fn name(&self) -> &str {
+        "AggregateDedupLatestFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(self.return_type.clone())
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        place.write(DedupLatestState::default);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<DedupLatestState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let state = place.get::<DedupLatestState>();
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                Self::add_row(state, columns, row);
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        for (row, place) in places.iter().enumerate() {
+            let state = place.next(offset).get::<DedupLatestState>();
+            Self::add_row(state, columns, row);
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let state = place.get::<DedupLatestState>();
+        Self::add_row(state, columns, row);
+        Ok(())
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<DedupLatestState>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<DedupLatestState>();
+        let rhs: DedupLatestState = borsh_deserialize_state(reader)?;
+        state.merge(&rhs);
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<DedupLatestState>();
+        let other = rhs.get::<DedupLatestState>();
+        state.merge(other);
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<DedupLatestState>();
+        match builder {
+            ColumnBuilder::Array(box inner) => {
+                for (key, (_, value)) in state.latest.iter() {
+                    let pair = Scalar::Tuple(vec![key.clone(), value.clone()]);
+                    inner.builder.push(pair.as_ref());
+                }
+                inner.commit_row();
+            }
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+
+    fn need_manual_drop_state(&self) -> bool {
+        true
+    }
+
+    unsafe fn drop_state(&self, place: StateAddr) {
+        let state = place.get::<DedupLatestState>();
+        std::ptr::drop_in_place(state);
+    }
+}
+
+impl fmt::Display for AggregateDedupLatestFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_dedup_latest_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(AggregateDedupLatestFunction::try_create))
+}
@@ -408,3 +408,158 @@ impl DistinctStateFunc for AggregateUniqStringState {
         Ok(vec![])
     }
 }
+
+// Bits of the Bloom filter per distinct key, with 3 probes it gives about 3% false positives.
+const BLOOM_BITS_PER_KEY: usize = 8;
+const BLOOM_PROBES: u64 = 3;
+
+/// For count_distinct_exact.
+///
+/// The keys are kept in an exact set, the Bloom filter in front of it only decides
+/// whether a key must be looked up: a key the filter has never seen is new for sure
+/// and goes straight into the set. A false positive of the filter only costs a lookup,
+/// so the count is always exact. Keys are stored with their exact serialized size,
+/// and the scratch buffer used to serialize rows is reused, duplicated rows don't
+/// allocate at all.
+///
+/// The filter is never serialized, it is rebuilt from the set after `deserialize`.
+pub struct AggregateDistinctBloomState {
+    set: HashSet<Vec<u8>, RandomState>,
+    bloom: Vec<u64>,
+    buffer: Vec<u8>,
+}
+
+impl AggregateDistinctBloomState {
+    fn hash_key(key: &[u8]) -> u64 {
+        let mut hasher = SipHasher24::new();
+        hasher.write(key);
+        hasher.finish()
+    }
+
+    fn probes(hash: u64, bits: usize) -> impl Iterator<Item = usize> {
+        let h1 = hash;
+        let h2 = (hash >> 32) | 1;
+        (0..BLOOM_PROBES).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % bits as u64) as usize)
+    }
+
+    fn bloom_contains(&self, hash: u64) -> bool {
+        if self.bloom.is_empty() {
+            return false;
+        }
+        Self::probes(hash, self.bloom.len() * 64)
+            .all(|bit| self.bloom[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+
+    fn bloom_insert(&mut self, hash: u64) {
+        for bit in Self::probes(hash, self.bloom.len() * 64) {
+            self.bloom[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+
+    // Doubles the filter ahead of the set, so rebuilds are amortized over the inserts.
+    fn rebuild_bloom(&mut self) {
+        let words = (self.set.len() * BLOOM_BITS_PER_KEY * 2)
+            .div_ceil(64)
+            .next_power_of_two();
+        self.bloom = vec![0; words];
+        let hashes = self
+            .set
+            .iter()
+            .map(|key| Self::hash_key(key))
+            .collect::<Vec<_>>();
+        for hash in hashes {
+            self.bloom_insert(hash);
+        }
+    }
+
+    fn insert_key(&mut self, key: &[u8]) {
+        let hash = Self::hash_key(key);
+        if self.bloom_contains(hash) && self.set.contains(key) {
+            return;
+        }
+        self.set.insert(key.to_vec());
+        if self.set.len() * BLOOM_BITS_PER_KEY > self.bloom.len() * 64 {
+            self.rebuild_bloom();
+        } else {
+            self.bloom_insert(hash);
+        }
+    }
+
+    fn insert_row(&mut self, columns: InputColumns, row: usize) -> Result<()> {
+        let values = columns
+            .iter()
+            .map(|col| unsafe { AnyType::index_column_unchecked(col, row).to_owned() })
+            .collect::<Vec<_>>();
+        let mut buffer = std::mem::take(&mut self.buffer);
+        buffer.clear();
+        borsh_serialize_state(&mut buffer, &values)?;
+        self.insert_key(&buffer);
+        self.buffer = buffer;
+        Ok(())
+    }
+}
+
+impl DistinctStateFunc for AggregateDistinctBloomState {
+    fn new() -> Self {
+        AggregateDistinctBloomState {
+            set: HashSet::new(),
+            bloom: Vec::new(),
+            buffer: Vec::new(),
+        }
+    }
+
+    fn serialize(&self, writer: &mut Vec<u8>) -> Result<()> {
+        borsh_serialize_state(writer, &self.set)
+    }
+
+    fn deserialize(reader: &mut &[u8]) -> Result<Self> {
+        let set = borsh_deserialize_state(reader)?;
+        let mut state = Self {
+            set,
+            bloom: Vec::new(),
+            buffer: Vec::new(),
+        };
+        if !state.set.is_empty() {
+            state.rebuild_bloom();
+        }
+        Ok(state)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.set.is_empty()
+    }
+
+    fn len(&self) -> usize {
+        self.set.len()
+    }
+
+    fn add(&mut self, columns: InputColumns, row: usize) -> Result<()> {
+        self.insert_row(columns, row)
+    }
+
+    fn batch_add(
+        &mut self,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                self.insert_row(columns, row)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, rhs: &Self) -> Result<()> {
+        for key in rhs.set.iter() {
+            self.insert_key(key);
+        }
+        Ok(())
+    }
+
+    // This method won't be called, count_distinct_exact only reads `len`.
+    fn build_columns(&mut self, _types: &[DataType]) -> Result<Vec<Column>> {
+        Ok(vec![])
+    }
+}
@@ -0,0 +1,222 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::UInt64Type;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+use databend_common_expression::ScalarRef;
+
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::StateAddr;
+use crate::aggregates::assert_binary_arguments;
+use crate::aggregates::assert_params;
+use crate::aggregates::AggregateFunction;
+
+const MICROS_PER_MINUTE: i64 = 60_000_000;
+const MICROS_PER_HOUR: i64 = 60 * MICROS_PER_MINUTE;
+const MICROS_PER_DAY: i64 = 24 * MICROS_PER_HOUR;
+
+/// The length in microseconds of the buckets of a granularity of `'day'`, `'hour'` or
+/// `'minute'`.
+pub(super) fn get_bucket_micros(display_name: &str, granularity: &Scalar) -> Result<i64> {
+    match granularity.as_string().map(|s| s.to_lowercase()).as_deref() {
+        Some("day") => Ok(MICROS_PER_DAY),
+        Some("hour") => Ok(MICROS_PER_HOUR),
+        Some("minute") => Ok(MICROS_PER_MINUTE),
+        _ => Err(ErrorCode::BadArguments(format!(
+            "{} expects the granularity 'day', 'hour' or 'minute', but got {}",
+            display_name, granularity
+        ))),
+    }
+}
+
+/// The microseconds since the epoch of a timestamp, or of the start of the day of a date.
+pub(super) fn timestamp_micros(value: ScalarRef) -> i64 {
+    match value {
+        ScalarRef::Timestamp(ts) => ts,
+        ScalarRef::Date(days) => days as i64 * MICROS_PER_DAY,
+        _ => unreachable!(),
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct DistinctTimeBucketsState {
+    buckets: HashSet<i64>,
+}
+
+impl DistinctTimeBucketsState {
+    fn add(&mut self, columns: InputColumns, row: usize, bucket_micros: i64) {
+        let micros = timestamp_micros(unsafe { AnyType::index_column_unchecked(&columns[0], row) });
+        self.buckets.insert(micros.div_euclid(bucket_micros));
+    }
+
+    fn merge(&mut self, rhs: &Self) {
+        self.buckets.extend(rhs.buckets.iter().copied());
+    }
+}
+
+/// `distinct_time_buckets(ts, granularity)` returns the number of distinct days, hours
+/// or minutes touched by the timestamps of the group, for a granularity of `'day'`,
+/// `'hour'` or `'minute'`. The timestamps are truncated in UTC, and a date covers the
+/// first bucket of its day.
+///
+/// The granularity must be a constant, it is taken as a parameter of the function.
+/// NULL timestamps are skipped, a group without timestamps returns 0.
+#[derive(Clone)]
+pub struct AggregateDistinctTimeBucketsFunction {
+    display_name: String,
+    bucket_micros: i64,
+}
+
+impl AggregateDistinctTimeBucketsFunction {
+    pub fn try_create(
+        display_name: &str,
+        params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<Arc<dyn AggregateFunction>> {
+        assert_binary_arguments(display_name, arguments.len())?;
+        assert_params(display_name, params.len(), 1)?;
+
+        if !arguments[0].is_date_or_date_time() {
+            return Err(ErrorCode::BadDataValueType(format!(
+                "{} does not support type '{:?}'",
+                display_name, arguments[0]
+            )));
+        }
+        let bucket_micros = get_bucket_micros(display_name, &params[0])?;
+
+        Ok(Arc::new(AggregateDistinctTimeBucketsFunction {
+            display_name: display_name.to_string(),
+            bucket_micros,
+        }))
+    }
+}
+
+impl AggregateFunction for AggregateDistinctTimeBucketsFunction {
+    fn name(&self) -> &str {
+        "AggregateDistinctTimeBucketsFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(DataType::Number(NumberDataType::UInt64))
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        place.write(DistinctTimeBucketsState::default);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<DistinctTimeBucketsState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let state = place.get::<DistinctTimeBucketsState>();
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                state.add(columns, row, self.bucket_micros);
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        for (row, place) in places.iter().enumerate() {
+            let state = place.next(offset).get::<DistinctTimeBucketsState>();
+            state.add(columns, row, self.bucket_micros);
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let state = place.get::<DistinctTimeBucketsState>();
+        state.add(columns, row, self.bucket_micros);
+        Ok(())
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<DistinctTimeBucketsState>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<DistinctTimeBucketsState>();
+        let rhs: DistinctTimeBucketsState = borsh_deserialize_state(reader)?;
+        state.merge(&rhs);
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<DistinctTimeBucketsState>();
+        let other = rhs.get::<DistinctTimeBucketsState>();
+        state.merge(other);
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<DistinctTimeBucketsState>();
+        let builder = UInt64Type::try_downcast_builder(builder).unwrap();
+        builder.push(state.buckets.len() as u64);
+        Ok(())
+    }
+
+    fn need_manual_drop_state(&self) -> bool {
+        true
+    }
+
+    unsafe fn drop_state(&self, place: StateAddr) {
+        let state = place.get::<DistinctTimeBucketsState>();
+        std::ptr::drop_in_place(state);
+    }
+}
+
+impl fmt::Display for AggregateDistinctTimeBucketsFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_distinct_time_buckets_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(
+        AggregateDistinctTimeBucketsFunction::try_create,
+    ))
+}
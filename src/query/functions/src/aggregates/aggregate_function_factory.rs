@@ -46,6 +46,7 @@ static FACTORY: LazyLock<Arc<AggregateFunctionFactory>> = LazyLock::new(|| {
     let mut factory = AggregateFunctionFactory::create();
     Aggregators::register(&mut factory);
     Aggregators::register_combinator(&mut factory);
+    Aggregators::register_signatures(&mut factory);
     Arc::new(factory)
 });
 
@@ -106,6 +107,21 @@ impl AggregateFunctionDescription {
     }
 }
 
+/// The signature of a registered aggregate function, so that tooling such as autocomplete
+/// or docs generators can enumerate the aggregate functions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AggregateFunctionSignature {
+    pub name: String,
+    /// The minimal and maximal number of parameters, such as the `(2)` in
+    /// `window_funnel(2)(dt, e1, e2)`. `usize::MAX` means there is no upper bound.
+    pub params: (usize, usize),
+    /// The templates of the argument types. `T` is any type, `...` marks a variadic
+    /// argument, and an argument in `[]` is optional.
+    pub arguments: Vec<&'static str>,
+    /// How the return type is derived from the argument types.
+    pub return_type: &'static str,
+}
+
 pub struct CombinatorDescription {
     creator: AggregateFunctionCombinatorCreator,
     // TODO(Winter): function document, this is very interesting.
@@ -121,6 +137,7 @@ impl CombinatorDescription {
 pub struct AggregateFunctionFactory {
     case_insensitive_desc: HashMap<String, AggregateFunctionDescription>,
     case_insensitive_combinator_desc: Vec<(String, CombinatorDescription)>,
+    case_insensitive_signatures: HashMap<String, AggregateFunctionSignature>,
 }
 
 impl AggregateFunctionFactory {
@@ -128,6 +145,7 @@ impl AggregateFunctionFactory {
         AggregateFunctionFactory {
             case_insensitive_desc: Default::default(),
             case_insensitive_combinator_desc: Default::default(),
+            case_insensitive_signatures: Default::default(),
         }
     }
 
@@ -154,6 +172,29 @@ impl AggregateFunctionFactory {
         case_insensitive_combinator_desc.push((suffix.to_lowercase(), desc));
     }
 
+    pub fn register_signature(
+        &mut self,
+        name: &str,
+        params: (usize, usize),
+        arguments: &[&'static str],
+        return_type: &'static str,
+    ) {
+        let name = name.to_lowercase();
+        if !self.case_insensitive_desc.contains_key(&name) {
+            panic!(
+                "Logical error: signature of unknown aggregate function {}.",
+                name
+            );
+        }
+        self.case_insensitive_signatures
+            .insert(name.clone(), AggregateFunctionSignature {
+                name,
+                params,
+                arguments: arguments.to_vec(),
+                return_type,
+            });
+    }
+
     pub fn get(
         &self,
         name: impl AsRef<str>,
@@ -173,9 +214,16 @@ impl AggregateFunctionFactory {
         let name = name.as_ref();
         let mut features = AggregateFunctionFeatures::default();
         // The NULL value in the array_agg function needs to be added to the returned array column,
-        // so handled separately.
+        // so handled separately. `last_by` and `dedup_latest` keep the NULL value of the
+        // latest row as well, `value_counts_with_nulls` and `fill_rate` count the NULL values,
+        // and `rate_if` counts the rows with a NULL predicate.
         if name == "array_agg"
             || name == "list"
+            || name == "last_by"
+            || name == "dedup_latest"
+            || name == "value_counts_with_nulls"
+            || name == "fill_rate"
+            || name == "rate_if"
             || name == "json_array_agg"
             || name == "json_object_agg"
             || name == "group_array_moving_avg"
@@ -297,6 +345,18 @@ impl AggregateFunctionFactory {
         self.case_insensitive_desc.keys().cloned().collect()
     }
 
+    /// Signatures of the registered aggregate functions sorted by name,
+    /// the combinators such as `_if` or `_distinct` are not listed.
+    pub fn registered_signatures(&self) -> Vec<AggregateFunctionSignature> {
+        let mut signatures = self
+            .case_insensitive_signatures
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        signatures.sort_by(|a, b| a.name.cmp(&b.name));
+        signatures
+    }
+
     pub fn registered_features(&self) -> Vec<AggregateFunctionFeatures> {
         self.case_insensitive_desc
             .values()
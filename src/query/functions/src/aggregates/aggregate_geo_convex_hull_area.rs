@@ -0,0 +1,254 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::fmt;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::Float64Type;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::Column;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+use databend_common_expression::ScalarRef;
+
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::StateAddr;
+use crate::aggregates::assert_binary_arguments;
+use crate::aggregates::AggregateFunction;
+use crate::scalars::longitude_diff;
+use crate::scalars::spherical_triangle_area;
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct GeoConvexHullAreaState {
+    points: Vec<(f64, f64)>,
+}
+
+impl GeoConvexHullAreaState {
+    fn add(&mut self, columns: InputColumns, row: usize) {
+        let lon = to_f64(&columns[0], row);
+        let lat = to_f64(&columns[1], row);
+        self.points.push((lon, lat));
+    }
+
+    fn merge(&mut self, rhs: &Self) {
+        self.points.extend_from_slice(&rhs.points);
+    }
+
+    fn area(&self) -> f64 {
+        let Some(min_lon) = self.points.iter().map(|p| p.0).min_by(f64::total_cmp) else {
+            return 0.0;
+        };
+        // The spherical area only depends on the differences of the longitudes.
+        let points = self
+            .points
+            .iter()
+            .map(|&(lon, lat)| (longitude_diff(min_lon, lon), lat))
+            .collect::<Vec<_>>();
+        let hull = convex_hull(&points);
+        let (lon0, lat0) = hull[0];
+        hull.windows(2)
+            .skip(1)
+            .map(|w| spherical_triangle_area(lon0, lat0, w[0].0, w[0].1, w[1].0, w[1].1))
+            .sum()
+    }
+}
+
+fn to_f64(column: &Column, row: usize) -> f64 {
+    match unsafe { AnyType::index_column_unchecked(column, row) } {
+        ScalarRef::Number(number) => number.to_f64().0,
+        _ => unreachable!(),
+    }
+}
+
+fn cross(o: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+}
+
+/// The convex hull of the points with the Graham scan, counterclockwise from the lowest
+/// point, the leftmost one on ties. Duplicate and collinear points are dropped, a
+/// degenerate set of points has a hull of less than 3 vertices.
+fn convex_hull(points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let pivot = *points
+        .iter()
+        .min_by(|p, q| p.1.total_cmp(&q.1).then(p.0.total_cmp(&q.0)))
+        .unwrap();
+    let polar = |p: &(f64, f64)| {
+        let (dx, dy) = (p.0 - pivot.0, p.1 - pivot.1);
+        (dy.atan2(dx), dx * dx + dy * dy)
+    };
+    let mut others = points
+        .iter()
+        .filter(|p| **p != pivot)
+        .map(|p| (polar(p), *p))
+        .collect::<Vec<_>>();
+    others.sort_by(|(l, _), (r, _)| l.0.total_cmp(&r.0).then(l.1.total_cmp(&r.1)));
+
+    let mut hull = vec![pivot];
+    for (_, p) in others {
+        while hull.len() >= 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0.0 {
+            hull.pop();
+        }
+        hull.push(p);
+    }
+    hull
+}
+
+/// `geo_convex_hull_area(lon, lat)` returns the area in square meters of the convex hull
+/// of the points of the group, on the sphere like `geo_triangle_area`.
+///
+/// The hull is computed on the plane of the longitudes and the latitudes, with the
+/// longitudes unwrapped from the westernmost one, so a group crossing the antimeridian
+/// is handled as long as it spans less than 180 degrees. The hull is then split into
+/// triangles from its first vertex whose spherical areas are summed. All the points are
+/// buffered until the result is computed.
+///
+/// Rows with a NULL `lon` or `lat` are skipped. A group with less than 3 points, or whose
+/// points are all collinear, returns 0.
+#[derive(Clone)]
+pub struct AggregateGeoConvexHullAreaFunction {
+    display_name: String,
+}
+
+impl AggregateGeoConvexHullAreaFunction {
+    pub fn try_create(
+        display_name: &str,
+        _params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<Arc<dyn AggregateFunction>> {
+        assert_binary_arguments(display_name, arguments.len())?;
+        for argument in arguments.iter() {
+            if !argument.is_numeric() {
+                return Err(ErrorCode::BadDataValueType(format!(
+                    "The coordinates of aggregate function {} must be numbers, got: {:?}",
+                    display_name, argument
+                )));
+            }
+        }
+
+        Ok(Arc::new(AggregateGeoConvexHullAreaFunction {
+            display_name: display_name.to_string(),
+        }))
+    }
+}
+
+impl AggregateFunction for AggregateGeoConvexHullAreaFunction {
+    fn name(&self) -> &str {
+        "AggregateGeoConvexHullAreaFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(DataType::Number(NumberDataType::Float64))
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        place.write(GeoConvexHullAreaState::default);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<GeoConvexHullAreaState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let state = place.get::<GeoConvexHullAreaState>();
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                state.add(columns, row);
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        for (row, place) in places.iter().enumerate() {
+            let state = place.next(offset).get::<GeoConvexHullAreaState>();
+            state.add(columns, row);
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let state = place.get::<GeoConvexHullAreaState>();
+        state.add(columns, row);
+        Ok(())
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<GeoConvexHullAreaState>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<GeoConvexHullAreaState>();
+        let rhs: GeoConvexHullAreaState = borsh_deserialize_state(reader)?;
+        state.merge(&rhs);
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<GeoConvexHullAreaState>();
+        let other = rhs.get::<GeoConvexHullAreaState>();
+        state.merge(other);
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<GeoConvexHullAreaState>();
+        let builder = Float64Type::try_downcast_builder(builder).unwrap();
+        builder.push(state.area().into());
+        Ok(())
+    }
+
+    fn need_manual_drop_state(&self) -> bool {
+        true
+    }
+
+    unsafe fn drop_state(&self, place: StateAddr) {
+        let state = place.get::<GeoConvexHullAreaState>();
+        std::ptr::drop_in_place(state);
+    }
+}
+
+impl fmt::Display for AggregateGeoConvexHullAreaFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_geo_convex_hull_area_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(AggregateGeoConvexHullAreaFunction::try_create))
+}
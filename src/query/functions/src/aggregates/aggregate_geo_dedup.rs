@@ -0,0 +1,235 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::fmt;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::UInt64Type;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::Column;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+use databend_common_expression::ScalarRef;
+
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::aggregate_function_factory::AggregateFunctionFeatures;
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::StateAddr;
+use crate::aggregates::aggregator_common::assert_arguments;
+use crate::aggregates::AggregateFunction;
+use crate::scalars::geo_dist_init;
+use crate::scalars::sphere_distance_meters;
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct GeoDedupState {
+    epsilon: Option<f64>,
+    points: Vec<(f64, f64)>,
+}
+
+impl GeoDedupState {
+    fn merge(&mut self, rhs: &Self) {
+        if self.epsilon.is_none() {
+            self.epsilon = rhs.epsilon;
+        }
+        self.points.extend_from_slice(&rhs.points);
+    }
+
+    // The points are visited in a fixed order, so the clusters don't depend on
+    // the order in which the rows and the partial states arrived.
+    fn clusters(&mut self) -> u64 {
+        let epsilon = self.epsilon.unwrap_or_default();
+        self.points
+            .sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+
+        let mut centers: Vec<(f32, f32)> = Vec::new();
+        for (lon, lat) in self.points.iter() {
+            let (lon, lat) = (*lon as f32, *lat as f32);
+            let covered = centers.iter().any(|(center_lon, center_lat)| {
+                sphere_distance_meters(*center_lon, *center_lat, lon, lat) as f64 <= epsilon
+            });
+            if !covered {
+                centers.push((lon, lat));
+            }
+        }
+        centers.len() as u64
+    }
+}
+
+fn to_f64(column: &Column, row: usize) -> f64 {
+    match unsafe { AnyType::index_column_unchecked(column, row) } {
+        ScalarRef::Number(number) => number.to_f64().0,
+        _ => unreachable!(),
+    }
+}
+
+/// `geo_dedup(lon, lat, epsilon_m)` returns the number of clusters of the points,
+/// where a point is in a cluster if it is within `epsilon_m` meters of its center.
+///
+/// The clustering is greedy: the points are visited in order of `(lon, lat)`, each point
+/// not covered by an existing center starts a new cluster. It is an approximation, not
+/// the minimum number of clusters. `epsilon_m` is expected to be a constant, the value of
+/// the first row is used. All the points of the group are kept in the state.
+#[derive(Clone)]
+pub struct AggregateGeoDedupFunction {
+    display_name: String,
+}
+
+impl AggregateGeoDedupFunction {
+    pub fn try_create(
+        display_name: &str,
+        _params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<Arc<dyn AggregateFunction>> {
+        assert_arguments(display_name, arguments.len(), 3)?;
+        for argument in arguments.iter() {
+            if !argument.is_numeric() {
+                return Err(ErrorCode::BadDataValueType(format!(
+                    "The arguments of aggregate function {} must be numbers, got: {:?}",
+                    display_name, argument
+                )));
+            }
+        }
+
+        geo_dist_init();
+        Ok(Arc::new(AggregateGeoDedupFunction {
+            display_name: display_name.to_string(),
+        }))
+    }
+
+    fn add_row(state: &mut GeoDedupState, columns: InputColumns, row: usize) {
+        if state.epsilon.is_none() {
+            state.epsilon = Some(to_f64(&columns[2], row));
+        }
+        state
+            .points
+            .push((to_f64(&columns[0], row), to_f64(&columns[1], row)));
+    }
+}
+
+impl AggregateFunction for AggregateGeoDedupFunction {
+    fn name(&self) -> &str {
+        "AggregateGeoDedupFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(DataType::Number(NumberDataType::UInt64))
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        place.write(GeoDedupState::default);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<GeoDedupState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let state = place.get::<GeoDedupState>();
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                Self::add_row(state, columns, row);
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        for (row, place) in places.iter().enumerate() {
+            let state = place.next(offset).get::<GeoDedupState>();
+            Self::add_row(state, columns, row);
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let state = place.get::<GeoDedupState>();
+        Self::add_row(state, columns, row);
+        Ok(())
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<GeoDedupState>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<GeoDedupState>();
+        let rhs: GeoDedupState = borsh_deserialize_state(reader)?;
+        state.merge(&rhs);
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<GeoDedupState>();
+        let other = rhs.get::<GeoDedupState>();
+        state.merge(other);
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<GeoDedupState>();
+        let builder = UInt64Type::try_downcast_builder(builder).unwrap();
+        builder.push(state.clusters());
+        Ok(())
+    }
+
+    fn need_manual_drop_state(&self) -> bool {
+        true
+    }
+
+    unsafe fn drop_state(&self, place: StateAddr) {
+        let state = place.get::<GeoDedupState>();
+        std::ptr::drop_in_place(state);
+    }
+}
+
+impl fmt::Display for AggregateGeoDedupFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_geo_dedup_function_desc() -> AggregateFunctionDescription {
+    let features = AggregateFunctionFeatures {
+        returns_default_when_only_null: true,
+        ..Default::default()
+    };
+    AggregateFunctionDescription::creator_with_features(
+        Box::new(AggregateGeoDedupFunction::try_create),
+        features,
+    )
+}
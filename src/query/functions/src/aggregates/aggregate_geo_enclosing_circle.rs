@@ -0,0 +1,335 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::fmt;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::number::NumberScalar;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::Column;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+use databend_common_expression::ScalarRef;
+use rand::rngs::SmallRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::StateAddr;
+use crate::aggregates::assert_binary_arguments;
+use crate::aggregates::AggregateFunction;
+use crate::scalars::geo_dist_init;
+use crate::scalars::longitude_diff;
+use crate::scalars::sphere_distance_meters;
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct GeoEnclosingCircleState {
+    points: Vec<(f64, f64)>,
+}
+
+impl GeoEnclosingCircleState {
+    fn add(&mut self, columns: InputColumns, row: usize) {
+        let lon = to_f64(&columns[0], row);
+        let lat = to_f64(&columns[1], row);
+        self.points.push((lon, lat));
+    }
+
+    fn merge(&mut self, rhs: &Self) {
+        self.points.extend_from_slice(&rhs.points);
+    }
+
+    fn circle(&self) -> Option<(f64, f64, f64)> {
+        let mut points = self.points.clone();
+        points.sort_by(|p, q| p.0.total_cmp(&q.0).then(p.1.total_cmp(&q.1)));
+        points.dedup();
+        let min_lon = points.first()?.0;
+        let (min_lat, max_lat) = points
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), p| {
+                (min.min(p.1), max.max(p.1))
+            });
+
+        // Projected on a plane where the degrees of longitude are scaled to the degrees
+        // of latitude at the middle latitude, so the planar circles are close to the
+        // spherical ones.
+        let scale = ((min_lat + max_lat) / 2.0).to_radians().cos();
+        let mut projected = points
+            .iter()
+            .map(|&(lon, lat)| (longitude_diff(min_lon, lon) * scale, lat))
+            .collect::<Vec<_>>();
+        projected.shuffle(&mut SmallRng::seed_from_u64(0));
+        let circle = smallest_enclosing_circle(&projected);
+
+        let mut lon = min_lon + circle.center.0 / scale;
+        if lon > 180.0 {
+            lon -= 360.0;
+        } else if lon < -180.0 {
+            lon += 360.0;
+        }
+        let lat = circle.center.1;
+        let radius = points
+            .iter()
+            .map(|p| sphere_distance_meters(lon as f32, lat as f32, p.0 as f32, p.1 as f32))
+            .fold(0f32, f32::max);
+        Some((lon, lat, radius as f64))
+    }
+}
+
+fn to_f64(column: &Column, row: usize) -> f64 {
+    match unsafe { AnyType::index_column_unchecked(column, row) } {
+        ScalarRef::Number(number) => number.to_f64().0,
+        _ => unreachable!(),
+    }
+}
+
+// Tolerance of the rounding errors when checking whether a point is in a circle.
+const CIRCLE_EPSILON: f64 = 1e-12;
+
+struct Circle {
+    center: (f64, f64),
+    radius_sq: f64,
+}
+
+impl Circle {
+    fn contains(&self, p: (f64, f64)) -> bool {
+        distance_sq(self.center, p) <= self.radius_sq * (1.0 + CIRCLE_EPSILON) + CIRCLE_EPSILON
+    }
+}
+
+fn distance_sq(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - b.0) * (a.0 - b.0) + (a.1 - b.1) * (a.1 - b.1)
+}
+
+fn circle_from_2(a: (f64, f64), b: (f64, f64)) -> Circle {
+    let center = ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0);
+    Circle {
+        center,
+        radius_sq: distance_sq(center, a),
+    }
+}
+
+// The points are sorted first, so the circle doesn't depend on the order of the arguments.
+fn circle_from_3(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> Circle {
+    let mut points = [a, b, c];
+    points.sort_by(|p, q| p.0.total_cmp(&q.0).then(p.1.total_cmp(&q.1)));
+    let [a, b, c] = points;
+
+    let (bx, by) = (b.0 - a.0, b.1 - a.1);
+    let (cx, cy) = (c.0 - a.0, c.1 - a.1);
+    let d = 2.0 * (bx * cy - by * cx);
+    if d == 0.0 {
+        // Collinear, sorted the first and the last points are the farthest ones.
+        return circle_from_2(a, c);
+    }
+    let (b_sq, c_sq) = (bx * bx + by * by, cx * cx + cy * cy);
+    let ux = (cy * b_sq - by * c_sq) / d;
+    let uy = (bx * c_sq - cx * b_sq) / d;
+    Circle {
+        center: (a.0 + ux, a.1 + uy),
+        radius_sq: ux * ux + uy * uy,
+    }
+}
+
+/// The smallest circle enclosing the points with the iterative form of Welzl's
+/// algorithm, which runs in expected linear time on points in a random order.
+fn smallest_enclosing_circle(points: &[(f64, f64)]) -> Circle {
+    let mut circle = Circle {
+        center: points[0],
+        radius_sq: 0.0,
+    };
+    for (i, &p) in points.iter().enumerate().skip(1) {
+        if circle.contains(p) {
+            continue;
+        }
+        circle = Circle {
+            center: p,
+            radius_sq: 0.0,
+        };
+        for (j, &q) in points[..i].iter().enumerate() {
+            if circle.contains(q) {
+                continue;
+            }
+            circle = circle_from_2(p, q);
+            for &r in &points[..j] {
+                if !circle.contains(r) {
+                    circle = circle_from_3(p, q, r);
+                }
+            }
+        }
+    }
+    circle
+}
+
+/// `geo_enclosing_circle(lon, lat)` returns `(center_lon, center_lat, radius)` of the
+/// smallest circle enclosing the points of the group, with the radius in meters.
+///
+/// The circle is found with Welzl's algorithm on the plane of the longitudes and the
+/// latitudes, the longitudes being unwrapped from the westernmost one and scaled by the
+/// cosine of the middle latitude. The radius is then the greatest distance from the center
+/// to a point, measured like `great_circle_distance`, so all the points are within the
+/// circle. A single point gives a circle of radius 0.
+///
+/// All the points are buffered until the result is computed. Rows with a NULL `lon` or
+/// `lat` are skipped.
+#[derive(Clone)]
+pub struct AggregateGeoEnclosingCircleFunction {
+    display_name: String,
+}
+
+impl AggregateGeoEnclosingCircleFunction {
+    pub fn try_create(
+        display_name: &str,
+        _params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<Arc<dyn AggregateFunction>> {
+        assert_binary_arguments(display_name, arguments.len())?;
+        for argument in arguments.iter() {
+            if !argument.is_numeric() {
+                return Err(ErrorCode::BadDataValueType(format!(
+                    "The coordinates of aggregate function {} must be numbers, got: {:?}",
+                    display_name, argument
+                )));
+            }
+        }
+
+        geo_dist_init();
+        Ok(Arc::new(AggregateGeoEnclosingCircleFunction {
+            display_name: display_name.to_string(),
+        }))
+    }
+}
+
+impl AggregateFunction for AggregateGeoEnclosingCircleFunction {
+    fn name(&self) -> &str {
+        "AggregateGeoEnclosingCircleFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(DataType::Tuple(vec![
+            DataType::Number(NumberDataType::Float64),
+            DataType::Number(NumberDataType::Float64),
+            DataType::Number(NumberDataType::Float64),
+        ]))
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        place.write(GeoEnclosingCircleState::default);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<GeoEnclosingCircleState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let state = place.get::<GeoEnclosingCircleState>();
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                state.add(columns, row);
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        for (row, place) in places.iter().enumerate() {
+            let state = place.next(offset).get::<GeoEnclosingCircleState>();
+            state.add(columns, row);
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let state = place.get::<GeoEnclosingCircleState>();
+        state.add(columns, row);
+        Ok(())
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<GeoEnclosingCircleState>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<GeoEnclosingCircleState>();
+        let rhs: GeoEnclosingCircleState = borsh_deserialize_state(reader)?;
+        state.merge(&rhs);
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<GeoEnclosingCircleState>();
+        let other = rhs.get::<GeoEnclosingCircleState>();
+        state.merge(other);
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<GeoEnclosingCircleState>();
+        match state.circle() {
+            Some((lon, lat, radius)) => {
+                let circle = Scalar::Tuple(vec![
+                    Scalar::Number(NumberScalar::Float64(lon.into())),
+                    Scalar::Number(NumberScalar::Float64(lat.into())),
+                    Scalar::Number(NumberScalar::Float64(radius.into())),
+                ]);
+                builder.push(circle.as_ref());
+            }
+            None => builder.push_default(),
+        }
+        Ok(())
+    }
+
+    fn need_manual_drop_state(&self) -> bool {
+        true
+    }
+
+    unsafe fn drop_state(&self, place: StateAddr) {
+        let state = place.get::<GeoEnclosingCircleState>();
+        std::ptr::drop_in_place(state);
+    }
+}
+
+impl fmt::Display for AggregateGeoEnclosingCircleFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_geo_enclosing_circle_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(AggregateGeoEnclosingCircleFunction::try_create))
+}
@@ -0,0 +1,223 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::fmt;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::number::NumberScalar;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::Column;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+use databend_common_expression::ScalarRef;
+
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::StateAddr;
+use crate::aggregates::assert_binary_arguments;
+use crate::aggregates::AggregateFunction;
+
+// Below this length the sum of the unit vectors is taken as the zero vector, which
+// doesn't have a direction, the rounding errors of the sum being far smaller.
+const CENTROID_EPSILON: f64 = 1e-12;
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct GeoSphericalCentroidState {
+    // The sum of the unit vectors of the points, from the center of the earth.
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+impl GeoSphericalCentroidState {
+    fn add(&mut self, columns: InputColumns, row: usize) {
+        let lon = to_f64(&columns[0], row).to_radians();
+        let lat = to_f64(&columns[1], row).to_radians();
+        self.x += lat.cos() * lon.cos();
+        self.y += lat.cos() * lon.sin();
+        self.z += lat.sin();
+    }
+
+    fn merge(&mut self, rhs: &Self) {
+        self.x += rhs.x;
+        self.y += rhs.y;
+        self.z += rhs.z;
+    }
+
+    fn centroid(&self) -> Option<(f64, f64)> {
+        let (x, y, z) = (self.x, self.y, self.z);
+        let xy = (x * x + y * y).sqrt();
+        if (xy * xy + z * z).sqrt() < CENTROID_EPSILON {
+            return None;
+        }
+        Some((y.atan2(x).to_degrees(), z.atan2(xy).to_degrees()))
+    }
+}
+
+fn to_f64(column: &Column, row: usize) -> f64 {
+    match unsafe { AnyType::index_column_unchecked(column, row) } {
+        ScalarRef::Number(number) => number.to_f64().0,
+        _ => unreachable!(),
+    }
+}
+
+/// `geo_spherical_centroid(lon, lat)` returns `(lon, lat)` of the mean direction of the
+/// points of the group, i.e. the point of the sphere in the direction of the sum of the
+/// unit vectors of the points.
+///
+/// Unlike the mean of the longitudes and the latitudes, it is right across the
+/// antimeridian and near the poles. The longitude is in [-180, 180]. A group without any
+/// point, or whose points cancel out like two antipodal points, returns NULL. Rows with a
+/// NULL `lon` or `lat` are skipped.
+#[derive(Clone)]
+pub struct AggregateGeoSphericalCentroidFunction {
+    display_name: String,
+}
+
+impl AggregateGeoSphericalCentroidFunction {
+    pub fn try_create(
+        display_name: &str,
+        _params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<Arc<dyn AggregateFunction>> {
+        assert_binary_arguments(display_name, arguments.len())?;
+        for argument in arguments.iter() {
+            if !argument.is_numeric() {
+                return Err(ErrorCode::BadDataValueType(format!(
+                    "The coordinates of aggregate function {} must be numbers, got: {:?}",
+                    display_name, argument
+                )));
+            }
+        }
+
+        Ok(Arc::new(AggregateGeoSphericalCentroidFunction {
+            display_name: display_name.to_string(),
+        }))
+    }
+}
+
+impl AggregateFunction for AggregateGeoSphericalCentroidFunction {
+    fn name(&self) -> &str {
+        "AggregateGeoSphericalCentroidFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(DataType::Tuple(vec![
+            DataType::Number(NumberDataType::Float64),
+            DataType::Number(NumberDataType::Float64),
+        ])
+        .wrap_nullable())
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        place.write(GeoSphericalCentroidState::default);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<GeoSphericalCentroidState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let state = place.get::<GeoSphericalCentroidState>();
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                state.add(columns, row);
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        for (row, place) in places.iter().enumerate() {
+            let state = place.next(offset).get::<GeoSphericalCentroidState>();
+            state.add(columns, row);
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let state = place.get::<GeoSphericalCentroidState>();
+        state.add(columns, row);
+        Ok(())
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<GeoSphericalCentroidState>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<GeoSphericalCentroidState>();
+        let rhs: GeoSphericalCentroidState = borsh_deserialize_state(reader)?;
+        state.merge(&rhs);
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<GeoSphericalCentroidState>();
+        let other = rhs.get::<GeoSphericalCentroidState>();
+        state.merge(other);
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<GeoSphericalCentroidState>();
+        match state.centroid() {
+            Some((lon, lat)) => {
+                let centroid = Scalar::Tuple(vec![
+                    Scalar::Number(NumberScalar::Float64(lon.into())),
+                    Scalar::Number(NumberScalar::Float64(lat.into())),
+                ]);
+                builder.push(centroid.as_ref());
+            }
+            None => builder.push_default(),
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for AggregateGeoSphericalCentroidFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_geo_spherical_centroid_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(
+        AggregateGeoSphericalCentroidFunction::try_create,
+    ))
+}
@@ -0,0 +1,183 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::Result;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+use databend_common_expression::ScalarRef;
+
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::StateAddr;
+use crate::aggregates::assert_unary_arguments;
+use crate::aggregates::AggregateFunction;
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct GroupUniqArrayState {
+    values: BTreeSet<Scalar>,
+}
+
+impl GroupUniqArrayState {
+    fn add(&mut self, columns: InputColumns, row: usize) {
+        let value = unsafe { AnyType::index_column_unchecked(&columns[0], row) };
+        self.values.insert(value.to_owned());
+    }
+
+    fn merge(&mut self, rhs: &Self) {
+        self.values.extend(rhs.values.iter().cloned());
+    }
+}
+
+/// `group_uniq_array(value)` returns the distinct values of the group as an array.
+///
+/// The values are kept in an ordered set, so the array is sorted in ascending order
+/// of the values, whatever the order in which the rows and the partial states
+/// arrived. Rows whose `value` is NULL are skipped. `group_uniq_array_sorted` is an
+/// alias spelling out this order, for the queries comparing or diffing the sets.
+#[derive(Clone)]
+pub struct AggregateGroupUniqArrayFunction {
+    display_name: String,
+    return_type: DataType,
+}
+
+impl AggregateGroupUniqArrayFunction {
+    pub fn try_create(
+        display_name: &str,
+        _params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<Arc<dyn AggregateFunction>> {
+        assert_unary_arguments(display_name, arguments.len())?;
+
+        Ok(Arc::new(AggregateGroupUniqArrayFunction {
+            display_name: display_name.to_string(),
+            return_type: DataType::Array(Box::new(arguments[0].clone())),
+        }))
+    }
+}
+
+impl AggregateFunction for AggregateGroupUniqArrayFunction {
+    fn name(&self) -> &str {
+        "AggregateGroupUniqArrayFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(self.return_type.clone())
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        place.write(GroupUniqArrayState::default);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<GroupUniqArrayState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let state = place.get::<GroupUniqArrayState>();
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                state.add(columns, row);
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        for (row, place) in places.iter().enumerate() {
+            let state = place.next(offset).get::<GroupUniqArrayState>();
+            state.add(columns, row);
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let state = place.get::<GroupUniqArrayState>();
+        state.add(columns, row);
+        Ok(())
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<GroupUniqArrayState>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<GroupUniqArrayState>();
+        let rhs: GroupUniqArrayState = borsh_deserialize_state(reader)?;
+        state.merge(&rhs);
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<GroupUniqArrayState>();
+        let other = rhs.get::<GroupUniqArrayState>();
+        state.merge(other);
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<GroupUniqArrayState>();
+        let inner_type = self.return_type.as_array().unwrap();
+        let mut inner_builder = ColumnBuilder::with_capacity(inner_type, state.values.len());
+        for value in state.values.iter() {
+            inner_builder.push(value.as_ref());
+        }
+        builder.push(ScalarRef::Array(inner_builder.build()));
+        Ok(())
+    }
+
+    fn need_manual_drop_state(&self) -> bool {
+        true
+    }
+
+    unsafe fn drop_state(&self, place: StateAddr) {
+        let state = place.get::<GroupUniqArrayState>();
+        std::ptr::drop_in_place(state);
+    }
+}
+
+impl fmt::Display for AggregateGroupUniqArrayFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_group_uniq_array_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(AggregateGroupUniqArrayFunction::try_create))
+}
@@ -0,0 +1,220 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::Float64Type;
+use databend_common_expression::types::NullableType;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+use databend_common_expression::ScalarRef;
+
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::StateAddr;
+use crate::aggregates::assert_unary_arguments;
+use crate::aggregates::AggregateFunction;
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct HhiState {
+    counts: HashMap<Scalar, u64>,
+}
+
+impl HhiState {
+    fn add(&mut self, value: ScalarRef) {
+        *self.counts.entry(value.to_owned()).or_insert(0) += 1;
+    }
+
+    fn merge(&mut self, rhs: &Self) {
+        for (value, count) in rhs.counts.iter() {
+            *self.counts.entry(value.clone()).or_insert(0) += count;
+        }
+    }
+
+    fn hhi(&self) -> Option<f64> {
+        // The sum of the squared counts is exact, so the result doesn't depend on the
+        // order of the map.
+        let total: u64 = self.counts.values().sum();
+        let squares: u128 = self
+            .counts
+            .values()
+            .map(|count| *count as u128 * *count as u128)
+            .sum();
+        match total {
+            0 => None,
+            _ => Some(squares as f64 / (total as f64 * total as f64)),
+        }
+    }
+}
+
+/// `hhi(value)` returns the Herfindahl-Hirschman index of the group, the sum of the
+/// squared shares of the distinct values of `value`, the share of a value being the
+/// number of rows holding it divided by the number of rows.
+///
+/// It is between `1 / n` for `n` distinct values of the same count and 1 for a single
+/// value. NULLs are skipped, a group without any value returns NULL.
+#[derive(Clone)]
+pub struct AggregateHhiFunction {
+    display_name: String,
+}
+
+impl AggregateHhiFunction {
+    pub fn try_create(
+        display_name: &str,
+        _params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<Arc<dyn AggregateFunction>> {
+        assert_unary_arguments(display_name, arguments.len())?;
+
+        let value_type = arguments[0].remove_nullable();
+        if !value_type.is_boolean()
+            && !value_type.is_string()
+            && !value_type.is_numeric()
+            && !value_type.is_decimal()
+            && !value_type.is_date_or_date_time()
+        {
+            return Err(ErrorCode::BadDataValueType(format!(
+                "{} does not support type '{:?}'",
+                display_name, arguments[0]
+            )));
+        }
+
+        Ok(Arc::new(AggregateHhiFunction {
+            display_name: display_name.to_string(),
+        }))
+    }
+
+    fn add_row(state: &mut HhiState, columns: InputColumns, row: usize) {
+        let value = unsafe { AnyType::index_column_unchecked(&columns[0], row) };
+        if !matches!(value, ScalarRef::Null) {
+            state.add(value);
+        }
+    }
+}
+
+impl AggregateFunction for AggregateHhiFunction {
+    fn name(&self) -> &str {
+        "AggregateHhiFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(DataType::Number(NumberDataType::Float64).wrap_nullable())
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        place.write(HhiState::default);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<HhiState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let state = place.get::<HhiState>();
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                Self::add_row(state, columns, row);
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        for (row, place) in places.iter().enumerate() {
+            let state = place.next(offset).get::<HhiState>();
+            Self::add_row(state, columns, row);
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let state = place.get::<HhiState>();
+        Self::add_row(state, columns, row);
+        Ok(())
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<HhiState>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<HhiState>();
+        let rhs: HhiState = borsh_deserialize_state(reader)?;
+        state.merge(&rhs);
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<HhiState>();
+        let other = rhs.get::<HhiState>();
+        state.merge(other);
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<HhiState>();
+        let builder = NullableType::<Float64Type>::try_downcast_builder(builder).unwrap();
+        match state.hhi() {
+            Some(hhi) => builder.push(hhi.into()),
+            None => builder.push_null(),
+        }
+        Ok(())
+    }
+
+    fn need_manual_drop_state(&self) -> bool {
+        true
+    }
+
+    unsafe fn drop_state(&self, place: StateAddr) {
+        let state = place.get::<HhiState>();
+        std::ptr::drop_in_place(state);
+    }
+}
+
+impl fmt::Display for AggregateHhiFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_hhi_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(AggregateHhiFunction::try_create))
+}
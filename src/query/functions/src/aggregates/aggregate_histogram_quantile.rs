@@ -0,0 +1,332 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_base::base::OrderedFloat;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::type_check::check_number;
+use databend_common_expression::types::number::*;
+use databend_common_expression::types::*;
+use databend_common_expression::with_number_mapped_type;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::Expr;
+use databend_common_expression::FunctionContext;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+use num_traits::AsPrimitive;
+
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use crate::aggregates::aggregate_function_factory::AggregateFunctionDescription;
+use crate::aggregates::assert_binary_arguments;
+use crate::aggregates::assert_params;
+use crate::aggregates::AggregateFunction;
+use crate::aggregates::AggregateFunctionRef;
+use crate::aggregates::StateAddr;
+use crate::BUILTIN_FUNCTIONS;
+
+/// The cumulative count of each bucket, keyed by the upper bound of the bucket.
+#[derive(Default, BorshSerialize, BorshDeserialize)]
+struct HistogramQuantileState {
+    buckets: BTreeMap<OrderedFloat<f64>, f64>,
+}
+
+impl HistogramQuantileState {
+    fn add(&mut self, upper_bound: f64, count: f64) {
+        *self.buckets.entry(OrderedFloat(upper_bound)).or_default() += count;
+    }
+
+    fn merge(&mut self, rhs: &Self) {
+        for (upper_bound, count) in rhs.buckets.iter() {
+            *self.buckets.entry(*upper_bound).or_default() += count;
+        }
+    }
+
+    /// Interpolate the quantile the same way as prometheus does:
+    ///
+    /// - The highest bucket must be the `+Inf` bucket, at least two buckets are required
+    ///   and the total count must be positive, otherwise the result is NaN.
+    /// - Cumulative counts must not decrease with the upper bound. A count lower than the
+    ///   count of the previous bucket is raised to the previous count.
+    /// - If the quantile falls into the `+Inf` bucket, the upper bound of the second
+    ///   highest bucket is returned.
+    /// - If the quantile falls into the lowest bucket, and its upper bound is positive,
+    ///   the lower bound of the lowest bucket is assumed to be 0.
+    fn quantile(&self, level: f64) -> f64 {
+        if self.buckets.len() < 2 {
+            return f64::NAN;
+        }
+        let mut buckets = Vec::with_capacity(self.buckets.len());
+        let mut prev_count = 0f64;
+        for (upper_bound, count) in self.buckets.iter() {
+            prev_count = count.max(prev_count);
+            buckets.push((upper_bound.0, prev_count));
+        }
+
+        let (last_bound, observations) = buckets[buckets.len() - 1];
+        if last_bound != f64::INFINITY || observations <= 0.0 {
+            return f64::NAN;
+        }
+
+        let mut rank = level * observations;
+        let b = buckets
+            .iter()
+            .position(|(_, count)| *count >= rank)
+            .unwrap_or(buckets.len() - 1);
+        if b == buckets.len() - 1 {
+            return buckets[buckets.len() - 2].0;
+        }
+        if b == 0 && buckets[0].0 <= 0.0 {
+            return buckets[0].0;
+        }
+
+        let mut bucket_start = 0f64;
+        let bucket_end = buckets[b].0;
+        let mut count = buckets[b].1;
+        if b > 0 {
+            bucket_start = buckets[b - 1].0;
+            count -= buckets[b - 1].1;
+            rank -= buckets[b - 1].1;
+        }
+        bucket_start + (bucket_end - bucket_start) * (rank / count)
+    }
+}
+
+/// `histogram_quantile(level)(bucket_upper_bound, cumulative_count)` computes the quantile
+/// from prometheus-style cumulative histogram buckets.
+///
+/// The counts of the same bucket are summed across the group, so the buckets of several
+/// series can be aggregated together.
+#[derive(Clone)]
+pub struct AggregateHistogramQuantileFunction<T0, T1> {
+    display_name: String,
+    level: f64,
+    _t0: PhantomData<T0>,
+    _t1: PhantomData<T1>,
+}
+
+impl<T0, T1> Display for AggregateHistogramQuantileFunction<T0, T1>
+where
+    T0: Number + AsPrimitive<f64>,
+    T1: Number + AsPrimitive<f64>,
+{
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+impl<T0, T1> AggregateFunction for AggregateHistogramQuantileFunction<T0, T1>
+where
+    T0: Number + AsPrimitive<f64>,
+    T1: Number + AsPrimitive<f64>,
+{
+    fn name(&self) -> &str {
+        "AggregateHistogramQuantileFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(DataType::Number(NumberDataType::Float64))
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        place.write(HistogramQuantileState::default)
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<HistogramQuantileState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        _input_rows: usize,
+    ) -> Result<()> {
+        let upper_bounds = NumberType::<T0>::try_downcast_column(&columns[0]).unwrap();
+        let counts = NumberType::<T1>::try_downcast_column(&columns[1]).unwrap();
+        let state = place.get::<HistogramQuantileState>();
+        match validity {
+            Some(bitmap) => {
+                for ((upper_bound, count), is_valid) in
+                    upper_bounds.iter().zip(counts.iter()).zip(bitmap.iter())
+                {
+                    if is_valid {
+                        state.add(upper_bound.as_(), count.as_());
+                    }
+                }
+            }
+            None => {
+                for (upper_bound, count) in upper_bounds.iter().zip(counts.iter()) {
+                    state.add(upper_bound.as_(), count.as_());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let upper_bounds = NumberType::<T0>::try_downcast_column(&columns[0]).unwrap();
+        let counts = NumberType::<T1>::try_downcast_column(&columns[1]).unwrap();
+        let upper_bound = unsafe { upper_bounds.get_unchecked(row) };
+        let count = unsafe { counts.get_unchecked(row) };
+
+        let state = place.get::<HistogramQuantileState>();
+        state.add(upper_bound.as_(), count.as_());
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        let upper_bounds = NumberType::<T0>::try_downcast_column(&columns[0]).unwrap();
+        let counts = NumberType::<T1>::try_downcast_column(&columns[1]).unwrap();
+        upper_bounds
+            .iter()
+            .zip(counts.iter())
+            .zip(places.iter())
+            .for_each(|((upper_bound, count), place)| {
+                let addr = place.next(offset);
+                let state = addr.get::<HistogramQuantileState>();
+                state.add(upper_bound.as_(), count.as_())
+            });
+        Ok(())
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<HistogramQuantileState>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<HistogramQuantileState>();
+        let rhs: HistogramQuantileState = borsh_deserialize_state(reader)?;
+        state.merge(&rhs);
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<HistogramQuantileState>();
+        let other = rhs.get::<HistogramQuantileState>();
+        state.merge(other);
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<HistogramQuantileState>();
+        let builder = NumberType::<F64>::try_downcast_builder(builder).unwrap();
+        builder.push(state.quantile(self.level).into());
+        Ok(())
+    }
+
+    fn need_manual_drop_state(&self) -> bool {
+        true
+    }
+
+    unsafe fn drop_state(&self, place: StateAddr) {
+        let state = place.get::<HistogramQuantileState>();
+        std::ptr::drop_in_place(state);
+    }
+}
+
+impl<T0, T1> AggregateHistogramQuantileFunction<T0, T1>
+where
+    T0: Number + AsPrimitive<f64>,
+    T1: Number + AsPrimitive<f64>,
+{
+    fn try_create(display_name: &str, params: Vec<Scalar>) -> Result<AggregateFunctionRef> {
+        let level: F64 = check_number(
+            None,
+            &FunctionContext::default(),
+            &Expr::<usize>::Cast {
+                span: None,
+                is_try: false,
+                expr: Box::new(Expr::Constant {
+                    span: None,
+                    scalar: params[0].clone(),
+                    data_type: params[0].as_ref().infer_data_type(),
+                }),
+                dest_type: DataType::Number(NumberDataType::Float64),
+            },
+            &BUILTIN_FUNCTIONS,
+        )?;
+        let level = level.0;
+        if !(0.0..=1.0).contains(&level) {
+            return Err(ErrorCode::BadDataValueType(format!(
+                "level range between [0, 1], got: {:?}",
+                level
+            )));
+        }
+
+        let func = AggregateHistogramQuantileFunction::<T0, T1> {
+            display_name: display_name.to_string(),
+            level,
+            _t0: PhantomData,
+            _t1: PhantomData,
+        };
+        Ok(Arc::new(func))
+    }
+}
+
+pub fn try_create_aggregate_histogram_quantile_function(
+    display_name: &str,
+    params: Vec<Scalar>,
+    arguments: Vec<DataType>,
+) -> Result<AggregateFunctionRef> {
+    assert_params(display_name, params.len(), 1)?;
+    assert_binary_arguments(display_name, arguments.len())?;
+
+    with_number_mapped_type!(|NUM_TYPE_0| match &arguments[0] {
+        DataType::Number(NumberDataType::NUM_TYPE_0) => {
+            with_number_mapped_type!(|NUM_TYPE_1| match &arguments[1] {
+                DataType::Number(NumberDataType::NUM_TYPE_1) => {
+                    AggregateHistogramQuantileFunction::<NUM_TYPE_0, NUM_TYPE_1>::try_create(
+                        display_name,
+                        params,
+                    )
+                }
+                _ => Err(ErrorCode::BadDataValueType(format!(
+                    "cumulative count just support numeric type, but got '{:?}'",
+                    arguments[1]
+                ))),
+            })
+        }
+
+        _ => Err(ErrorCode::BadDataValueType(format!(
+            "{} just support numeric type, but got '{:?}'",
+            display_name, arguments[0]
+        ))),
+    })
+}
+
+pub fn aggregate_histogram_quantile_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(
+        try_create_aggregate_histogram_quantile_function,
+    ))
+}
@@ -0,0 +1,229 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::fmt;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::Int8Type;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::StateAddr;
+use crate::aggregates::assert_binary_arguments;
+use crate::aggregates::AggregateFunction;
+
+const NON_DECREASING: i8 = 1;
+const NON_INCREASING: i8 = -1;
+const NOT_MONOTONIC: i8 = 0;
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct IsMonotonicState {
+    pairs: Vec<(Scalar, Scalar)>,
+}
+
+impl IsMonotonicState {
+    fn add(&mut self, columns: InputColumns, row: usize) {
+        let order = unsafe { AnyType::index_column_unchecked(&columns[0], row) };
+        let value = unsafe { AnyType::index_column_unchecked(&columns[1], row) };
+        self.pairs.push((order.to_owned(), value.to_owned()));
+    }
+
+    fn merge(&mut self, rhs: &Self) {
+        self.pairs.extend(rhs.pairs.iter().cloned());
+    }
+
+    // Sorted by order then value, the first and the last pairs of an order key hold
+    // the smallest and the greatest values of the key.
+    fn direction(&mut self) -> i8 {
+        self.pairs.sort();
+        let mut non_decreasing = true;
+        let mut non_increasing = true;
+        let mut prev: Option<(&Scalar, &Scalar)> = None;
+        for rows in self.pairs.chunk_by(|l, r| l.0 == r.0) {
+            let min = &rows[0].1;
+            let max = &rows[rows.len() - 1].1;
+            if let Some((prev_min, prev_max)) = prev {
+                non_decreasing &= prev_max <= min;
+                non_increasing &= prev_min >= max;
+            }
+            prev = Some((min, max));
+        }
+        if non_decreasing {
+            NON_DECREASING
+        } else if non_increasing {
+            NON_INCREASING
+        } else {
+            NOT_MONOTONIC
+        }
+    }
+}
+
+/// `is_monotonic(order, value)` tells the direction of `value` when the rows are
+/// sorted by `order`: `1` if it is non-decreasing, `-1` if it is non-increasing and
+/// `0` if it is neither. A series that is both, a constant one or a single row,
+/// returns `1`.
+///
+/// Rows with the same `order` have no order between them, the series is
+/// non-decreasing if every value of a key is not greater than the values of the
+/// later keys, and the other way round for non-increasing. Rows whose `order` or
+/// `value` is NULL are skipped.
+#[derive(Clone)]
+pub struct AggregateIsMonotonicFunction {
+    display_name: String,
+}
+
+impl AggregateIsMonotonicFunction {
+    pub fn try_create(
+        display_name: &str,
+        _params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<Arc<dyn AggregateFunction>> {
+        assert_binary_arguments(display_name, arguments.len())?;
+
+        if !arguments[0].is_numeric() && !arguments[0].is_date_or_date_time() {
+            return Err(ErrorCode::BadDataValueType(format!(
+                "{} does not support order type '{:?}'",
+                display_name, arguments[0]
+            )));
+        }
+        let value_type = &arguments[1];
+        if !value_type.is_numeric()
+            && !value_type.is_decimal()
+            && !value_type.is_date_or_date_time()
+            && !matches!(value_type, DataType::String)
+        {
+            return Err(ErrorCode::BadDataValueType(format!(
+                "{} does not support value type '{:?}'",
+                display_name, arguments[1]
+            )));
+        }
+
+        Ok(Arc::new(AggregateIsMonotonicFunction {
+            display_name: display_name.to_string(),
+        }))
+    }
+}
+
+impl AggregateFunction for AggregateIsMonotonicFunction {
+    fn name(&self) -> &str {
+        "AggregateIsMonotonicFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(DataType::Number(NumberDataType::Int8))
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        place.write(IsMonotonicState::default);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<IsMonotonicState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let state = place.get::<IsMonotonicState>();
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                state.add(columns, row);
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        for (row, place) in places.iter().enumerate() {
+            let state = place.next(offset).get::<IsMonotonicState>();
+            state.add(columns, row);
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let state = place.get::<IsMonotonicState>();
+        state.add(columns, row);
+        Ok(())
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<IsMonotonicState>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<IsMonotonicState>();
+        let rhs: IsMonotonicState = borsh_deserialize_state(reader)?;
+        state.merge(&rhs);
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<IsMonotonicState>();
+        let other = rhs.get::<IsMonotonicState>();
+        state.merge(other);
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<IsMonotonicState>();
+        let builder = Int8Type::try_downcast_builder(builder).unwrap();
+        builder.push(state.direction());
+        Ok(())
+    }
+
+    fn need_manual_drop_state(&self) -> bool {
+        true
+    }
+
+    unsafe fn drop_state(&self, place: StateAddr) {
+        let state = place.get::<IsMonotonicState>();
+        std::ptr::drop_in_place(state);
+    }
+}
+
+impl fmt::Display for AggregateIsMonotonicFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_is_monotonic_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(AggregateIsMonotonicFunction::try_create))
+}
@@ -0,0 +1,224 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::fmt;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::Result;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::NumberScalar;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+use databend_common_expression::ScalarRef;
+use simple_hll::HyperLogLog;
+
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::StateAddr;
+use crate::aggregates::assert_binary_arguments;
+use crate::aggregates::AggregateFunction;
+use crate::aggregates::AggregateFunctionRef;
+
+/// The same precision as the default of `approx_count_distinct`.
+const JACCARD_HLL_P: usize = 14;
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct JaccardApproxState {
+    a: HyperLogLog<JACCARD_HLL_P>,
+    b: HyperLogLog<JACCARD_HLL_P>,
+}
+
+impl JaccardApproxState {
+    fn add(&mut self, a: ScalarRef, b: ScalarRef) {
+        if !matches!(a, ScalarRef::Null) {
+            self.a.add_object(&a);
+        }
+        if !matches!(b, ScalarRef::Null) {
+            self.b.add_object(&b);
+        }
+    }
+
+    fn merge(&mut self, rhs: &Self) {
+        self.a.merge(&rhs.a);
+        self.b.merge(&rhs.b);
+    }
+
+    // The intersection is estimated by `|A| + |B| - |A ∪ B|`, it may be slightly out of
+    // range because of the errors of the estimates.
+    fn similarity(&self) -> Option<f64> {
+        let mut union = self.a.clone();
+        union.merge(&self.b);
+        let union = union.count() as f64;
+        if union == 0.0 {
+            return None;
+        }
+        let intersection = self.a.count() as f64 + self.b.count() as f64 - union;
+        Some((intersection / union).clamp(0.0, 1.0))
+    }
+}
+
+/// `jaccard_approx(a, b)` estimates the Jaccard similarity `|A ∩ B| / |A ∪ B|` of the set
+/// `A` of the distinct values of `a` and the set `B` of the distinct values of `b`.
+///
+/// Both sets are kept in HyperLogLog sketches, so the state has a fixed size. The
+/// cardinalities have a relative error of about 1%, the error of the similarity is
+/// larger when the intersection is small compared to the union, so the result is not
+/// reliable to tell apart low similarities. NULL values are not a part of the sets,
+/// a group whose sets are both empty returns NULL.
+#[derive(Clone)]
+pub struct AggregateJaccardApproxFunction {
+    display_name: String,
+}
+
+impl AggregateJaccardApproxFunction {
+    pub fn try_create(
+        display_name: &str,
+        _params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<AggregateFunctionRef> {
+        assert_binary_arguments(display_name, arguments.len())?;
+        Ok(Arc::new(AggregateJaccardApproxFunction {
+            display_name: display_name.to_string(),
+        }))
+    }
+
+    fn add_row(state: &mut JaccardApproxState, columns: InputColumns, row: usize) {
+        let a = unsafe { AnyType::index_column_unchecked(&columns[0], row) };
+        let b = unsafe { AnyType::index_column_unchecked(&columns[1], row) };
+        state.add(a, b);
+    }
+}
+
+impl AggregateFunction for AggregateJaccardApproxFunction {
+    fn name(&self) -> &str {
+        "AggregateJaccardApproxFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(DataType::Number(NumberDataType::Float64).wrap_nullable())
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        place.write(JaccardApproxState::default);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<JaccardApproxState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let state = place.get::<JaccardApproxState>();
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                Self::add_row(state, columns, row);
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        for (row, place) in places.iter().enumerate() {
+            let state = place.next(offset).get::<JaccardApproxState>();
+            Self::add_row(state, columns, row);
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let state = place.get::<JaccardApproxState>();
+        Self::add_row(state, columns, row);
+        Ok(())
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<JaccardApproxState>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<JaccardApproxState>();
+        let rhs: JaccardApproxState = borsh_deserialize_state(reader)?;
+        state.merge(&rhs);
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<JaccardApproxState>();
+        let other = rhs.get::<JaccardApproxState>();
+        state.merge(other);
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<JaccardApproxState>();
+        match state.similarity() {
+            Some(similarity) => {
+                builder.push(ScalarRef::Number(NumberScalar::Float64(similarity.into())))
+            }
+            None => builder.push_default(),
+        }
+        Ok(())
+    }
+
+    fn need_manual_drop_state(&self) -> bool {
+        true
+    }
+
+    unsafe fn drop_state(&self, place: StateAddr) {
+        let state = place.get::<JaccardApproxState>();
+        std::ptr::drop_in_place(state);
+    }
+
+    // A NULL value of `a` doesn't remove the value of `b` of the same row from its set,
+    // the rows must not be filtered out by the Null combinator.
+    fn get_own_null_adaptor(
+        &self,
+        _nested_function: AggregateFunctionRef,
+        _params: Vec<Scalar>,
+        _arguments: Vec<DataType>,
+    ) -> Result<Option<AggregateFunctionRef>> {
+        Ok(Some(Arc::new(self.clone())))
+    }
+}
+
+impl fmt::Display for AggregateJaccardApproxFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_jaccard_approx_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(AggregateJaccardApproxFunction::try_create))
+}
@@ -0,0 +1,249 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::fmt;
+use std::sync::Arc;
+
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::Result;
+use databend_common_expression::types::DataType;
+use databend_common_expression::utils::column_merge_validity;
+use databend_common_expression::Column;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+use databend_common_io::prelude::BinaryWrite;
+
+use super::aggregate_arg_min_max::try_create_aggregate_arg_minmax_function;
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::aggregate_scalar_state::TYPE_MAX;
+use super::AggregateNullResultFunction;
+use super::StateAddr;
+use crate::aggregates::assert_binary_arguments;
+use crate::aggregates::AggregateFunction;
+use crate::aggregates::AggregateFunctionRef;
+
+/// `last_by(value, ts)` returns `value` from the row with the maximum `ts`.
+///
+/// It is `arg_max` with explicit NULL semantics:
+/// - rows whose `ts` is NULL are ignored;
+/// - `value` is returned as is, so a NULL `value` on the latest row yields NULL,
+///   instead of falling back to an older row as `arg_max` does;
+/// - a group without any non-NULL `ts` yields NULL.
+///
+/// On ties the first row seen with the maximum `ts` wins. Across partial states
+/// the state merged first wins, so the result of ties is not deterministic in
+/// parallel execution.
+#[derive(Clone)]
+pub struct AggregateLastByFunction {
+    display_name: String,
+    nested: AggregateFunctionRef,
+    nullable_value: bool,
+    size_of_data: usize,
+}
+
+impl AggregateLastByFunction {
+    pub fn try_create(
+        display_name: &str,
+        params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<AggregateFunctionRef> {
+        assert_binary_arguments(display_name, arguments.len())?;
+        let value_type = arguments[0].clone();
+        if arguments[1] == DataType::Null {
+            return AggregateNullResultFunction::try_create(value_type.wrap_nullable());
+        }
+
+        // The value keeps its nullability, only the NULL timestamps are filtered out.
+        let nested =
+            try_create_aggregate_arg_minmax_function::<TYPE_MAX>(display_name, params, vec![
+                value_type.clone(),
+                arguments[1].remove_nullable(),
+            ])?;
+        let size_of_data = nested.state_layout().size();
+        Ok(Arc::new(AggregateLastByFunction {
+            display_name: display_name.to_owned(),
+            nested,
+            nullable_value: value_type.is_nullable_or_null(),
+            size_of_data,
+        }))
+    }
+
+    #[inline]
+    fn set_flag(&self, place: StateAddr, flag: u8) {
+        let c = place.next(self.size_of_data).get::<u8>();
+        *c = flag;
+    }
+
+    #[inline]
+    fn get_flag(&self, place: StateAddr) -> u8 {
+        let c = place.next(self.size_of_data).get::<u8>();
+        *c
+    }
+
+    fn split_timestamp(
+        columns: InputColumns,
+        validity: Option<Bitmap>,
+    ) -> (Vec<Column>, Option<Bitmap>) {
+        let validity = column_merge_validity(&columns[1], validity);
+        let columns = vec![columns[0].clone(), columns[1].remove_nullable()];
+        (columns, validity)
+    }
+}
+
+impl AggregateFunction for AggregateLastByFunction {
+    fn name(&self) -> &str {
+        "AggregateLastByFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(self.nested.return_type()?.wrap_nullable())
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        self.set_flag(place, 0);
+        self.nested.init_state(place);
+    }
+
+    fn serialize_size_per_row(&self) -> Option<usize> {
+        self.nested.serialize_size_per_row().map(|row| row + 1)
+    }
+
+    fn state_layout(&self) -> Layout {
+        let layout = self.nested.state_layout();
+        Layout::from_size_align(layout.size() + layout.align(), layout.align()).unwrap()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let (columns, validity) = Self::split_timestamp(columns, validity.cloned());
+        if validity
+            .as_ref()
+            .map(|v| v.unset_bits() == input_rows)
+            .unwrap_or(input_rows == 0)
+        {
+            return Ok(());
+        }
+        self.nested
+            .accumulate(place, (&columns).into(), validity.as_ref(), input_rows)?;
+        self.set_flag(place, 1);
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        input_rows: usize,
+    ) -> Result<()> {
+        let (columns, validity) = Self::split_timestamp(columns, None);
+        let columns = (&columns).into();
+        match validity {
+            Some(v) if v.unset_bits() > 0 => {
+                for (valid, (row, place)) in v.iter().zip(places.iter().enumerate()) {
+                    if valid {
+                        self.set_flag(place.next(offset), 1);
+                        self.nested
+                            .accumulate_row(place.next(offset), columns, row)?;
+                    }
+                }
+            }
+            _ => {
+                self.nested
+                    .accumulate_keys(places, offset, columns, input_rows)?;
+                places
+                    .iter()
+                    .for_each(|place| self.set_flag(place.next(offset), 1));
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let (columns, validity) = Self::split_timestamp(columns, None);
+        if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+            self.nested.accumulate_row(place, (&columns).into(), row)?;
+            self.set_flag(place, 1);
+        }
+        Ok(())
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        self.nested.serialize(place, writer)?;
+        let flag: u8 = self.get_flag(place);
+        writer.write_scalar(&flag)?;
+        Ok(())
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let flag = reader[reader.len() - 1];
+        if flag == 1 {
+            self.set_flag(place, 1);
+            self.nested.merge(place, &mut &reader[..reader.len() - 1])?;
+        }
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        if self.get_flag(rhs) == 1 {
+            self.set_flag(place, 1);
+            self.nested.merge_states(place, rhs)?;
+        }
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        if self.get_flag(place) == 0 {
+            builder.push_default();
+            return Ok(());
+        }
+        // A nullable value is pushed as is, NULL included.
+        if self.nullable_value {
+            return self.nested.merge_result(place, builder);
+        }
+        match builder {
+            ColumnBuilder::Nullable(ref mut inner) => {
+                self.nested.merge_result(place, &mut inner.builder)?;
+                inner.validity.push(true);
+            }
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+
+    fn need_manual_drop_state(&self) -> bool {
+        self.nested.need_manual_drop_state()
+    }
+
+    unsafe fn drop_state(&self, place: StateAddr) {
+        self.nested.drop_state(place)
+    }
+}
+
+impl fmt::Display for AggregateLastByFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_last_by_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(AggregateLastByFunction::try_create))
+}
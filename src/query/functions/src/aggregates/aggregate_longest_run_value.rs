@@ -0,0 +1,200 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::fmt;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::StateAddr;
+use crate::aggregates::assert_binary_arguments;
+use crate::aggregates::AggregateFunction;
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct LongestRunValueState {
+    pairs: Vec<(Scalar, Scalar)>,
+}
+
+impl LongestRunValueState {
+    fn add(&mut self, columns: InputColumns, row: usize) {
+        let order = unsafe { AnyType::index_column_unchecked(&columns[0], row) };
+        let value = unsafe { AnyType::index_column_unchecked(&columns[1], row) };
+        self.pairs.push((order.to_owned(), value.to_owned()));
+    }
+
+    fn merge(&mut self, rhs: &Self) {
+        self.pairs.extend(rhs.pairs.iter().cloned());
+    }
+
+    // Rows of the same order are sorted by value, so the result doesn't depend on
+    // the order in which the rows arrived.
+    fn longest_run_value(&mut self) -> Option<&Scalar> {
+        self.pairs.sort();
+        let mut longest: Option<(&Scalar, usize)> = None;
+        for run in self.pairs.chunk_by(|(_, l), (_, r)| l == r) {
+            // Only a strictly longer run replaces the longest one, the earliest wins ties.
+            if longest.map_or(true, |(_, len)| run.len() > len) {
+                longest = Some((&run[0].1, run.len()));
+            }
+        }
+        longest.map(|(value, _)| value)
+    }
+}
+
+/// `longest_run_value(order, value)` returns the value of the longest run of consecutive
+/// equal values, when the rows are sorted by `order`.
+///
+/// Of runs of the same length, the one starting first wins. Rows of the same `order` are
+/// sorted by `value`. Rows whose `order` or `value` is NULL are skipped, a group without
+/// rows returns NULL. All the rows are buffered until the result is computed.
+#[derive(Clone)]
+pub struct AggregateLongestRunValueFunction {
+    display_name: String,
+    return_type: DataType,
+}
+
+impl AggregateLongestRunValueFunction {
+    pub fn try_create(
+        display_name: &str,
+        _params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<Arc<dyn AggregateFunction>> {
+        assert_binary_arguments(display_name, arguments.len())?;
+        if !arguments[0].is_numeric() && !arguments[0].is_date_or_date_time() {
+            return Err(ErrorCode::BadDataValueType(format!(
+                "{} does not support order type '{:?}'",
+                display_name, arguments[0]
+            )));
+        }
+
+        Ok(Arc::new(AggregateLongestRunValueFunction {
+            display_name: display_name.to_string(),
+            return_type: arguments[1].wrap_nullable(),
+        }))
+    }
+}
+
+impl AggregateFunction for AggregateLongestRunValueFunction {
+    fn name(&self) -> &str {
+        "AggregateLongestRunValueFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(self.return_type.clone())
+    }
+    fn init_state(&self, place: StateAddr) {
+        place.write(LongestRunValueState::default);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<LongestRunValueState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let state = place.get::<LongestRunValueState>();
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                state.add(columns, row);
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        for (row, place) in places.iter().enumerate() {
+            let state = place.next(offset).get::<LongestRunValueState>();
+            state.add(columns, row);
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let state = place.get::<LongestRunValueState>();
+        state.add(columns, row);
+        Ok(())
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<LongestRunValueState>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<LongestRunValueState>();
+        let rhs: LongestRunValueState = borsh_deserialize_state(reader)?;
+        state.merge(&rhs);
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<LongestRunValueState>();
+        let other = rhs.get::<LongestRunValueState>();
+        state.merge(other);
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<LongestRunValueState>();
+        match state.longest_run_value() {
+            Some(value) => builder.push(value.as_ref()),
+            None => builder.push_default(),
+        }
+        Ok(())
+    }
+
+    fn need_manual_drop_state(&self) -> bool {
+        true
+    }
+
+    unsafe fn drop_state(&self, place: StateAddr) {
+        let state = place.get::<LongestRunValueState>();
+        std::ptr::drop_in_place(state);
+    }
+}
+
+impl fmt::Display for AggregateLongestRunValueFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_longest_run_value_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(AggregateLongestRunValueFunction::try_create))
+}
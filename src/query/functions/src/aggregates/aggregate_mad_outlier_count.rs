@@ -0,0 +1,186 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::any::Any;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::type_check::check_number;
+use databend_common_expression::types::number::Number;
+use databend_common_expression::types::number::F64;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::NumberType;
+use databend_common_expression::types::UInt64Type;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::with_number_mapped_type;
+use databend_common_expression::Expr;
+use databend_common_expression::FunctionContext;
+use databend_common_expression::Scalar;
+use num_traits::AsPrimitive;
+
+use super::AggregateUnaryFunction;
+use super::FunctionData;
+use super::UnaryState;
+use crate::aggregates::aggregate_function_factory::AggregateFunctionDescription;
+use crate::aggregates::assert_params;
+use crate::aggregates::assert_unary_arguments;
+use crate::aggregates::AggregateFunctionRef;
+use crate::BUILTIN_FUNCTIONS;
+
+struct MadOutlierCountData {
+    threshold: f64,
+}
+
+impl FunctionData for MadOutlierCountData {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct MadOutlierCountState {
+    values: Vec<F64>,
+}
+
+impl MadOutlierCountState {
+    // The median is found first, then the median of the deviations from it.
+    fn outlier_count(&mut self, threshold: f64) -> u64 {
+        if self.values.is_empty() {
+            return 0;
+        }
+        self.values.sort_unstable();
+        let center = median(&self.values);
+
+        let mut deviations = self
+            .values
+            .iter()
+            .map(|value| F64::from((value.0 - center).abs()))
+            .collect::<Vec<_>>();
+        deviations.sort_unstable();
+        let limit = threshold * median(&deviations);
+
+        deviations.iter().filter(|d| d.0 > limit).count() as u64
+    }
+}
+
+// Interpolated between the two middle values like `median`.
+fn median(sorted: &[F64]) -> f64 {
+    let mid = sorted.len() / 2;
+    if sorted.len() % 2 == 0 {
+        (sorted[mid - 1].0 + sorted[mid].0) / 2.0
+    } else {
+        sorted[mid].0
+    }
+}
+
+impl<T> UnaryState<T, UInt64Type> for MadOutlierCountState
+where
+    T: ValueType,
+    T::Scalar: Number + AsPrimitive<f64>,
+{
+    fn add(
+        &mut self,
+        other: T::ScalarRef<'_>,
+        _function_data: Option<&dyn FunctionData>,
+    ) -> Result<()> {
+        let value: f64 = T::to_owned_scalar(other).as_();
+        self.values.push(value.into());
+        Ok(())
+    }
+
+    fn merge(&mut self, rhs: &Self) -> Result<()> {
+        self.values.extend_from_slice(&rhs.values);
+        Ok(())
+    }
+
+    fn merge_result(
+        &mut self,
+        builder: &mut Vec<u64>,
+        function_data: Option<&dyn FunctionData>,
+    ) -> Result<()> {
+        let data = unsafe {
+            function_data
+                .unwrap()
+                .as_any()
+                .downcast_ref_unchecked::<MadOutlierCountData>()
+        };
+        builder.push(self.outlier_count(data.threshold));
+        Ok(())
+    }
+}
+
+fn get_threshold(display_name: &str, params: &[Scalar]) -> Result<f64> {
+    assert_params(display_name, params.len(), 1)?;
+    let threshold: F64 = check_number(
+        None,
+        &FunctionContext::default(),
+        &Expr::<usize>::Constant {
+            span: None,
+            scalar: params[0].clone(),
+            data_type: params[0].as_ref().infer_data_type(),
+        },
+        &BUILTIN_FUNCTIONS,
+    )?;
+    let threshold = threshold.0;
+    if !(threshold > 0.0 && threshold.is_finite()) {
+        return Err(ErrorCode::BadArguments(format!(
+            "{} expects threshold > 0, but got {}",
+            display_name, threshold
+        )));
+    }
+    Ok(threshold)
+}
+
+/// `mad_outlier_count(threshold)(x)` returns the number of values of `x` whose absolute
+/// deviation from the median is greater than `threshold` times the median absolute
+/// deviation (MAD) of the values. The medians are interpolated like `median`.
+///
+/// All the values of a group are buffered, as the deviations can only be computed
+/// once the median is known. NULL values are ignored, a group without values returns 0.
+pub fn try_create_aggregate_mad_outlier_count_function(
+    display_name: &str,
+    params: Vec<Scalar>,
+    arguments: Vec<DataType>,
+) -> Result<AggregateFunctionRef> {
+    assert_unary_arguments(display_name, arguments.len())?;
+    let threshold = get_threshold(display_name, &params)?;
+    let return_type = DataType::Number(NumberDataType::UInt64);
+
+    with_number_mapped_type!(|NUM_TYPE| match &arguments[0] {
+        DataType::Number(NumberDataType::NUM_TYPE) => {
+            let func = AggregateUnaryFunction::<
+                MadOutlierCountState,
+                NumberType<NUM_TYPE>,
+                UInt64Type,
+            >::try_create(
+                display_name, return_type, params, arguments[0].clone()
+            )
+            .with_function_data(Box::new(MadOutlierCountData { threshold }))
+            .with_need_drop(true);
+            Ok(Arc::new(func))
+        }
+        _ => Err(ErrorCode::BadDataValueType(format!(
+            "{} does not support type '{:?}'",
+            display_name, arguments[0]
+        ))),
+    })
+}
+
+pub fn aggregate_mad_outlier_count_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(try_create_aggregate_mad_outlier_count_function))
+}
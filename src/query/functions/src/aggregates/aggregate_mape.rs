@@ -0,0 +1,203 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::fmt;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::Float64Type;
+use databend_common_expression::types::NullableType;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+use databend_common_expression::ScalarRef;
+
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::StateAddr;
+use crate::aggregates::assert_binary_arguments;
+use crate::aggregates::AggregateFunction;
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct MapeState {
+    // The sum of the absolute percentage errors of the counted rows.
+    sum: f64,
+    count: u64,
+}
+
+impl MapeState {
+    fn add(&mut self, columns: InputColumns, row: usize) {
+        let actual = match unsafe { AnyType::index_column_unchecked(&columns[0], row) } {
+            ScalarRef::Number(actual) => actual.to_f64().0,
+            _ => unreachable!(),
+        };
+        let forecast = match unsafe { AnyType::index_column_unchecked(&columns[1], row) } {
+            ScalarRef::Number(forecast) => forecast.to_f64().0,
+            _ => unreachable!(),
+        };
+        if actual != 0.0 {
+            self.sum += ((actual - forecast) / actual).abs();
+            self.count += 1;
+        }
+    }
+
+    fn merge(&mut self, rhs: &Self) {
+        self.sum += rhs.sum;
+        self.count += rhs.count;
+    }
+
+    fn mape(&self) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+        Some(self.sum / self.count as f64)
+    }
+}
+
+/// `mape(actual, forecast)` returns the mean absolute percentage error of the forecast,
+/// i.e. the mean of `abs((actual - forecast) / actual)`, as a fraction rather than a
+/// percentage: 0 for a perfect forecast, 0.1 for a forecast 10% off on average.
+///
+/// Rows whose `actual` or `forecast` is NULL are skipped, and so are the rows whose
+/// `actual` is 0, as their percentage error is undefined. A group without any other row
+/// returns NULL.
+#[derive(Clone)]
+pub struct AggregateMapeFunction {
+    display_name: String,
+}
+
+impl AggregateMapeFunction {
+    pub fn try_create(
+        display_name: &str,
+        _params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<Arc<dyn AggregateFunction>> {
+        assert_binary_arguments(display_name, arguments.len())?;
+        for argument in arguments.iter() {
+            if !argument.is_numeric() {
+                return Err(ErrorCode::BadDataValueType(format!(
+                    "{} does not support type '{:?}'",
+                    display_name, argument
+                )));
+            }
+        }
+
+        Ok(Arc::new(AggregateMapeFunction {
+            display_name: display_name.to_string(),
+        }))
+    }
+}
+
+impl AggregateFunction for AggregateMapeFunction {
+    fn name(&self) -> &str {
+        "AggregateMapeFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(DataType::Number(NumberDataType::Float64).wrap_nullable())
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        place.write(MapeState::default);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<MapeState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let state = place.get::<MapeState>();
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                state.add(columns, row);
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        for (row, place) in places.iter().enumerate() {
+            let state = place.next(offset).get::<MapeState>();
+            state.add(columns, row);
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let state = place.get::<MapeState>();
+        state.add(columns, row);
+        Ok(())
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<MapeState>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<MapeState>();
+        let rhs: MapeState = borsh_deserialize_state(reader)?;
+        state.merge(&rhs);
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<MapeState>();
+        let other = rhs.get::<MapeState>();
+        state.merge(other);
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<MapeState>();
+        let builder = NullableType::<Float64Type>::try_downcast_builder(builder).unwrap();
+        match state.mape() {
+            Some(mape) => builder.push(mape.into()),
+            None => builder.push_null(),
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for AggregateMapeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_mape_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(AggregateMapeFunction::try_create))
+}
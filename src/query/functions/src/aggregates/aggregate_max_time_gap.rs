@@ -0,0 +1,132 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::DateType;
+use databend_common_expression::types::NullableColumnBuilder;
+use databend_common_expression::types::NullableType;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::NumberType;
+use databend_common_expression::types::TimestampType;
+use databend_common_expression::types::UInt64Type;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::with_integer_mapped_type;
+use databend_common_expression::Scalar;
+use num_traits::AsPrimitive;
+
+use super::AggregateUnaryFunction;
+use super::FunctionData;
+use super::UnaryState;
+use crate::aggregates::aggregate_function_factory::AggregateFunctionDescription;
+use crate::aggregates::assert_unary_arguments;
+use crate::aggregates::AggregateFunctionRef;
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct MaxTimeGapState {
+    times: Vec<i64>,
+}
+
+impl MaxTimeGapState {
+    fn max_gap(&mut self) -> Option<u64> {
+        self.times.sort_unstable();
+        self.times
+            .windows(2)
+            .map(|pair| pair[1].abs_diff(pair[0]))
+            .max()
+    }
+}
+
+impl<T> UnaryState<T, NullableType<UInt64Type>> for MaxTimeGapState
+where
+    T: ValueType,
+    T::Scalar: AsPrimitive<i64>,
+{
+    fn add(
+        &mut self,
+        other: T::ScalarRef<'_>,
+        _function_data: Option<&dyn FunctionData>,
+    ) -> Result<()> {
+        self.times.push(T::to_owned_scalar(other).as_());
+        Ok(())
+    }
+
+    fn merge(&mut self, rhs: &Self) -> Result<()> {
+        self.times.extend_from_slice(&rhs.times);
+        Ok(())
+    }
+
+    fn merge_result(
+        &mut self,
+        builder: &mut NullableColumnBuilder<UInt64Type>,
+        _function_data: Option<&dyn FunctionData>,
+    ) -> Result<()> {
+        match self.max_gap() {
+            Some(gap) => builder.push(gap),
+            None => builder.push_null(),
+        }
+        Ok(())
+    }
+}
+
+fn create_max_time_gap<T>(display_name: &str, argument: &DataType) -> Result<AggregateFunctionRef>
+where
+    T: ValueType + Send + Sync,
+    T::Scalar: AsPrimitive<i64>,
+{
+    let return_type = DataType::Number(NumberDataType::UInt64).wrap_nullable();
+    let func = AggregateUnaryFunction::<MaxTimeGapState, T, NullableType<UInt64Type>>::try_create(
+        display_name,
+        return_type,
+        vec![],
+        argument.clone(),
+    )
+    .with_need_drop(true);
+    Ok(Arc::new(func))
+}
+
+/// `max_time_gap(ts)` returns the largest interval between two consecutive timestamps of
+/// the group once sorted, in the unit of `ts`, i.e. microseconds for a timestamp and days
+/// for a date. A long gap reveals an outage of the data source.
+///
+/// All the timestamps of a group are buffered to sort them. NULL timestamps are ignored,
+/// a group of less than two timestamps returns NULL.
+pub fn try_create_aggregate_max_time_gap_function(
+    display_name: &str,
+    _params: Vec<Scalar>,
+    arguments: Vec<DataType>,
+) -> Result<AggregateFunctionRef> {
+    assert_unary_arguments(display_name, arguments.len())?;
+
+    with_integer_mapped_type!(|NUM_TYPE| match &arguments[0] {
+        DataType::Number(NumberDataType::NUM_TYPE) => {
+            create_max_time_gap::<NumberType<NUM_TYPE>>(display_name, &arguments[0])
+        }
+        DataType::Date => create_max_time_gap::<DateType>(display_name, &arguments[0]),
+        DataType::Timestamp => create_max_time_gap::<TimestampType>(display_name, &arguments[0]),
+        _ => Err(ErrorCode::BadDataValueType(format!(
+            "{} does not support type '{:?}'",
+            display_name, arguments[0]
+        ))),
+    })
+}
+
+pub fn aggregate_max_time_gap_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(try_create_aggregate_max_time_gap_function))
+}
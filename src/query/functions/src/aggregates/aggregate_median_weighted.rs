@@ -0,0 +1,220 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::fmt;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::Float64Type;
+use databend_common_expression::types::NullableType;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::Column;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+use databend_common_expression::ScalarRef;
+
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::StateAddr;
+use crate::aggregates::assert_binary_arguments;
+use crate::aggregates::AggregateFunction;
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct MedianWeightedState {
+    pairs: Vec<(f64, f64)>,
+}
+
+impl MedianWeightedState {
+    fn add(&mut self, value: f64, weight: f64) {
+        // Also skips a NaN weight.
+        if weight > 0.0 {
+            self.pairs.push((value, weight));
+        }
+    }
+
+    fn merge(&mut self, rhs: &Self) {
+        self.pairs.extend_from_slice(&rhs.pairs);
+    }
+
+    fn median(&mut self) -> Option<f64> {
+        self.pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let half = self.pairs.iter().map(|(_, weight)| weight).sum::<f64>() / 2.0;
+        let mut cumulative = 0.0;
+        for (value, weight) in self.pairs.iter() {
+            cumulative += weight;
+            if cumulative >= half {
+                return Some(*value);
+            }
+        }
+        None
+    }
+}
+
+fn to_f64(column: &Column, row: usize) -> f64 {
+    match unsafe { AnyType::index_column_unchecked(column, row) } {
+        ScalarRef::Number(number) => number.to_f64().0,
+        _ => unreachable!(),
+    }
+}
+
+/// `median_weighted(value, weight)` returns the weighted median of `value`: sorted by
+/// `value`, the first value where the cumulative weight reaches half of the total weight.
+/// With an even split it is the lower of the two middle values, e.g. the median of 1 and
+/// 2 with the same weight is 1.
+///
+/// Rows whose `weight` is zero or negative don't carry any weight and are skipped, like
+/// the rows whose `value` or `weight` is NULL. All the pairs of a group are buffered and
+/// sorted on finalize, a group without any pair returns NULL.
+#[derive(Clone)]
+pub struct AggregateMedianWeightedFunction {
+    display_name: String,
+}
+
+impl AggregateMedianWeightedFunction {
+    pub fn try_create(
+        display_name: &str,
+        _params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<Arc<dyn AggregateFunction>> {
+        assert_binary_arguments(display_name, arguments.len())?;
+        for argument in arguments.iter() {
+            if !argument.is_numeric() {
+                return Err(ErrorCode::BadDataValueType(format!(
+                    "The arguments of aggregate function {} must be numbers, got: {:?}",
+                    display_name, argument
+                )));
+            }
+        }
+
+        Ok(Arc::new(AggregateMedianWeightedFunction {
+            display_name: display_name.to_string(),
+        }))
+    }
+
+    fn add_row(state: &mut MedianWeightedState, columns: InputColumns, row: usize) {
+        state.add(to_f64(&columns[0], row), to_f64(&columns[1], row));
+    }
+}
+
+impl AggregateFunction for AggregateMedianWeightedFunction {
+    fn name(&self) -> &str {
+        "AggregateMedianWeightedFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(DataType::Number(NumberDataType::Float64).wrap_nullable())
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        place.write(MedianWeightedState::default);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<MedianWeightedState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let state = place.get::<MedianWeightedState>();
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                Self::add_row(state, columns, row);
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        for (row, place) in places.iter().enumerate() {
+            let state = place.next(offset).get::<MedianWeightedState>();
+            Self::add_row(state, columns, row);
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let state = place.get::<MedianWeightedState>();
+        Self::add_row(state, columns, row);
+        Ok(())
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<MedianWeightedState>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<MedianWeightedState>();
+        let rhs: MedianWeightedState = borsh_deserialize_state(reader)?;
+        state.merge(&rhs);
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<MedianWeightedState>();
+        let other = rhs.get::<MedianWeightedState>();
+        state.merge(other);
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<MedianWeightedState>();
+        let builder = NullableType::<Float64Type>::try_downcast_builder(builder).unwrap();
+        match state.median() {
+            Some(median) => builder.push(median.into()),
+            None => builder.push_null(),
+        }
+        Ok(())
+    }
+
+    fn need_manual_drop_state(&self) -> bool {
+        true
+    }
+
+    unsafe fn drop_state(&self, place: StateAddr) {
+        let state = place.get::<MedianWeightedState>();
+        std::ptr::drop_in_place(state);
+    }
+}
+
+impl fmt::Display for AggregateMedianWeightedFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_median_weighted_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(AggregateMedianWeightedFunction::try_create))
+}
@@ -0,0 +1,254 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::any::Any;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::array::ArrayColumnBuilder;
+use databend_common_expression::types::decimal::*;
+use databend_common_expression::types::number::*;
+use databend_common_expression::types::*;
+use databend_common_expression::with_number_mapped_type;
+use databend_common_expression::Scalar;
+use ethnum::i256;
+
+use super::aggregate_scalar_state::TYPE_MAX;
+use super::aggregate_scalar_state::TYPE_MIN;
+use super::AggregateUnaryFunction;
+use super::FunctionData;
+use super::UnaryState;
+use crate::aggregates::aggregate_function_factory::AggregateFunctionDescription;
+use crate::aggregates::assert_params;
+use crate::aggregates::assert_unary_arguments;
+use crate::aggregates::AggregateFunctionRef;
+use crate::with_simple_no_number_mapped_type;
+
+struct TopKData {
+    k: usize,
+}
+
+impl FunctionData for TopKData {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// An item of the heap, ordered so that the top of the heap is the kept value
+/// to be evicted first: the greatest one for `min_k`, the smallest one for `max_k`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, PartialEq, Eq)]
+struct TopKItem<S, const TYPE: u8>(S);
+
+impl<S: Ord, const TYPE: u8> PartialOrd for TopKItem<S, TYPE> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<S: Ord, const TYPE: u8> Ord for TopKItem<S, TYPE> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match TYPE {
+            TYPE_MIN => self.0.cmp(&other.0),
+            _ => other.0.cmp(&self.0),
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct TopKState<T, const TYPE: u8>
+where
+    T: ValueType,
+    T::Scalar: BorshSerialize + BorshDeserialize + Ord,
+{
+    // `k` is kept in the state, `merge` has no access to the function data.
+    k: usize,
+    heap: BinaryHeap<TopKItem<T::Scalar, TYPE>>,
+}
+
+impl<T, const TYPE: u8> Default for TopKState<T, TYPE>
+where
+    T: ValueType,
+    T::Scalar: BorshSerialize + BorshDeserialize + Ord,
+{
+    fn default() -> Self {
+        Self {
+            k: 0,
+            heap: BinaryHeap::new(),
+        }
+    }
+}
+
+impl<T, const TYPE: u8> TopKState<T, TYPE>
+where
+    T: ValueType,
+    T::Scalar: BorshSerialize + BorshDeserialize + Ord,
+{
+    fn push(&mut self, item: TopKItem<T::Scalar, TYPE>) {
+        if self.heap.len() < self.k {
+            self.heap.push(item);
+        } else if let Some(mut top) = self.heap.peek_mut() {
+            if item < *top {
+                *top = item;
+            }
+        }
+    }
+}
+
+impl<T, const TYPE: u8> UnaryState<T, ArrayType<T>> for TopKState<T, TYPE>
+where
+    T: ValueType + Sync + Send,
+    T::Scalar: BorshSerialize + BorshDeserialize + Sync + Send + Ord,
+{
+    fn add(
+        &mut self,
+        other: T::ScalarRef<'_>,
+        function_data: Option<&dyn FunctionData>,
+    ) -> Result<()> {
+        let top_k_data = unsafe {
+            function_data
+                .unwrap()
+                .as_any()
+                .downcast_ref_unchecked::<TopKData>()
+        };
+        self.k = top_k_data.k;
+        self.push(TopKItem(T::to_owned_scalar(other)));
+        Ok(())
+    }
+
+    fn merge(&mut self, rhs: &Self) -> Result<()> {
+        self.k = self.k.max(rhs.k);
+        for item in rhs.heap.iter() {
+            self.push(item.clone());
+        }
+        Ok(())
+    }
+
+    fn merge_result(
+        &mut self,
+        builder: &mut ArrayColumnBuilder<T>,
+        _function_data: Option<&dyn FunctionData>,
+    ) -> Result<()> {
+        // Ascending for `min_k`, descending for `max_k`.
+        for item in self.heap.clone().into_sorted_vec() {
+            builder.put_item(T::to_scalar_ref(&item.0));
+        }
+        builder.commit_row();
+        Ok(())
+    }
+}
+
+fn get_k(display_name: &str, params: &[Scalar]) -> Result<usize> {
+    assert_params(display_name, params.len(), 1)?;
+    if let Scalar::Number(number) = params[0] {
+        if let Some(k) = number.integer_to_i128() {
+            if k > 0 {
+                return Ok(k as usize);
+            }
+        }
+    }
+    Err(ErrorCode::BadDataValueType(format!(
+        "The parameter of aggregate function {} must be a positive integer",
+        display_name
+    )))
+}
+
+/// `min_k(k)(x)` / `max_k(k)(x)` returns the `k` smallest / largest values of `x`,
+/// ascending for `min_k` and descending for `max_k`.
+///
+/// The state is a heap bounded to `k` values, so memory doesn't grow with the group.
+/// A group with fewer than `k` values returns all of them.
+pub fn try_create_aggregate_min_max_k_function<const TYPE: u8>(
+    display_name: &str,
+    params: Vec<Scalar>,
+    arguments: Vec<DataType>,
+) -> Result<AggregateFunctionRef> {
+    assert_unary_arguments(display_name, arguments.len())?;
+    let k = get_k(display_name, &params)?;
+    let data_type = arguments[0].clone();
+    let return_type = DataType::Array(Box::new(data_type.clone()));
+    with_simple_no_number_mapped_type!(|T| match data_type {
+        DataType::T => {
+            let func = AggregateUnaryFunction::<TopKState<T, TYPE>, T, ArrayType<T>>::try_create(
+                display_name,
+                return_type,
+                params,
+                arguments[0].clone(),
+            )
+            .with_function_data(Box::new(TopKData { k }))
+            .with_need_drop(true);
+            Ok(Arc::new(func))
+        }
+        DataType::Number(num_type) => {
+            with_number_mapped_type!(|NUM_TYPE| match num_type {
+                NumberDataType::NUM_TYPE => {
+                    let func = AggregateUnaryFunction::<
+                        TopKState<NumberType<NUM_TYPE>, TYPE>,
+                        NumberType<NUM_TYPE>,
+                        ArrayType<NumberType<NUM_TYPE>>,
+                    >::try_create(
+                        display_name, return_type, params, arguments[0].clone()
+                    )
+                    .with_function_data(Box::new(TopKData { k }))
+                    .with_need_drop(true);
+                    Ok(Arc::new(func))
+                }
+            })
+        }
+        DataType::Decimal(DecimalDataType::Decimal128(_)) => {
+            let func = AggregateUnaryFunction::<
+                TopKState<DecimalType<i128>, TYPE>,
+                DecimalType<i128>,
+                ArrayType<DecimalType<i128>>,
+            >::try_create(
+                display_name, return_type, params, arguments[0].clone()
+            )
+            .with_function_data(Box::new(TopKData { k }))
+            .with_need_drop(true);
+            Ok(Arc::new(func))
+        }
+        DataType::Decimal(DecimalDataType::Decimal256(_)) => {
+            let func = AggregateUnaryFunction::<
+                TopKState<DecimalType<i256>, TYPE>,
+                DecimalType<i256>,
+                ArrayType<DecimalType<i256>>,
+            >::try_create(
+                display_name, return_type, params, arguments[0].clone()
+            )
+            .with_function_data(Box::new(TopKData { k }))
+            .with_need_drop(true);
+            Ok(Arc::new(func))
+        }
+        _ => Err(ErrorCode::BadDataValueType(format!(
+            "{} does not support type '{:?}'",
+            display_name, data_type
+        ))),
+    })
+}
+
+pub fn aggregate_min_k_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(
+        try_create_aggregate_min_max_k_function::<TYPE_MIN>,
+    ))
+}
+
+pub fn aggregate_max_k_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(
+        try_create_aggregate_min_max_k_function::<TYPE_MAX>,
+    ))
+}
@@ -21,9 +21,11 @@ use std::sync::Arc;
 use borsh::BorshDeserialize;
 use borsh::BorshSerialize;
 use databend_common_exception::Result;
+use databend_common_expression::types::number::NumberScalar;
 use databend_common_expression::types::*;
 use databend_common_expression::with_number_mapped_type;
 use databend_common_expression::AggregateFunctionRef;
+use databend_common_expression::ColumnBuilder;
 use databend_common_expression::Scalar;
 
 use super::FunctionData;
@@ -107,6 +109,73 @@ where
     }
 }
 
+/// The state of `mode_with_count`, which returns the most frequent value together with
+/// its frequency. Of the values with the same frequency, the smallest one wins.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct ModeWithCountState<T>
+where
+    T: ValueType,
+    T::Scalar: Ord + Hash + BorshSerialize + BorshDeserialize,
+{
+    pub mode: ModeState<T>,
+}
+
+impl<T> Default for ModeWithCountState<T>
+where
+    T: ValueType,
+    T::Scalar: Ord + Hash + BorshSerialize + BorshDeserialize,
+{
+    fn default() -> Self {
+        ModeWithCountState::<T> {
+            mode: ModeState::default(),
+        }
+    }
+}
+
+impl<T> UnaryState<T, AnyType> for ModeWithCountState<T>
+where
+    T: ValueType + Sync + Send,
+    T::Scalar: Ord + Hash + Sync + Send + BorshSerialize + BorshDeserialize,
+{
+    fn add(
+        &mut self,
+        other: T::ScalarRef<'_>,
+        function_data: Option<&dyn FunctionData>,
+    ) -> Result<()> {
+        UnaryState::<T, T>::add(&mut self.mode, other, function_data)
+    }
+
+    fn merge(&mut self, rhs: &Self) -> Result<()> {
+        UnaryState::<T, T>::merge(&mut self.mode, &rhs.mode)
+    }
+
+    fn merge_result(
+        &mut self,
+        builder: &mut ColumnBuilder,
+        _function_data: Option<&dyn FunctionData>,
+    ) -> Result<()> {
+        let mode = self
+            .mode
+            .frequency_map
+            .iter()
+            .max_by(|(lhs, lhs_count), (rhs, rhs_count)| {
+                lhs_count.cmp(rhs_count).then_with(|| rhs.cmp(lhs))
+            });
+        match mode {
+            Some((value, count)) => {
+                let result = Scalar::Tuple(vec![
+                    T::upcast_scalar(value.clone()),
+                    Scalar::Number(NumberScalar::UInt64(*count)),
+                ]);
+                builder.push(result.as_ref());
+            }
+            None => builder.push_default(),
+        }
+
+        Ok(())
+    }
+}
+
 pub fn try_create_aggregate_mode_function(
     display_name: &str,
     params: Vec<Scalar>,
@@ -165,3 +234,66 @@ pub fn try_create_aggregate_mode_function(
 pub fn aggregate_mode_function_desc() -> AggregateFunctionDescription {
     AggregateFunctionDescription::creator(Box::new(try_create_aggregate_mode_function))
 }
+
+pub fn try_create_aggregate_mode_with_count_function(
+    display_name: &str,
+    params: Vec<Scalar>,
+    arguments: Vec<DataType>,
+) -> Result<AggregateFunctionRef> {
+    assert_unary_arguments(display_name, arguments.len())?;
+
+    let data_type = arguments[0].clone();
+    let return_type = DataType::Tuple(vec![
+        data_type.clone(),
+        DataType::Number(NumberDataType::UInt64),
+    ]);
+    with_number_mapped_type!(|NUM| match &data_type {
+        DataType::Number(NumberDataType::NUM) => {
+            let func =
+                AggregateUnaryFunction::<
+                    ModeWithCountState<NumberType<NUM>>,
+                    NumberType<NUM>,
+                    AnyType,
+                >::try_create(display_name, return_type, params, data_type.clone())
+                .with_need_drop(true);
+            Ok(Arc::new(func))
+        }
+        DataType::Decimal(DecimalDataType::Decimal128(_)) => {
+            let func = AggregateUnaryFunction::<
+                ModeWithCountState<Decimal128Type>,
+                Decimal128Type,
+                AnyType,
+            >::try_create(
+                display_name, return_type, params, data_type.clone()
+            )
+            .with_need_drop(true);
+            Ok(Arc::new(func))
+        }
+        DataType::Decimal(DecimalDataType::Decimal256(_)) => {
+            let func = AggregateUnaryFunction::<
+                ModeWithCountState<Decimal256Type>,
+                Decimal256Type,
+                AnyType,
+            >::try_create(
+                display_name, return_type, params, data_type.clone()
+            )
+            .with_need_drop(true);
+            Ok(Arc::new(func))
+        }
+        _ => {
+            let func =
+                AggregateUnaryFunction::<ModeWithCountState<AnyType>, AnyType, AnyType>::try_create(
+                    display_name,
+                    return_type,
+                    params,
+                    data_type.clone(),
+                )
+                .with_need_drop(true);
+            Ok(Arc::new(func))
+        }
+    })
+}
+
+pub fn aggregate_mode_with_count_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(try_create_aggregate_mode_with_count_function))
+}
@@ -0,0 +1,184 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::any::Any;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::array::ArrayColumnBuilder;
+use databend_common_expression::types::number::Number;
+use databend_common_expression::types::number::F64;
+use databend_common_expression::types::ArrayType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::NumberType;
+use databend_common_expression::types::UInt64Type;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::with_number_mapped_type;
+use databend_common_expression::Scalar;
+use num_traits::AsPrimitive;
+
+use super::AggregateUnaryFunction;
+use super::FunctionData;
+use super::UnaryState;
+use crate::aggregates::aggregate_function_factory::AggregateFunctionDescription;
+use crate::aggregates::assert_params;
+use crate::aggregates::assert_unary_arguments;
+use crate::aggregates::AggregateFunctionRef;
+
+/// The counts of all the buckets are built for every group, so their number is limited.
+const MAX_NTILE_BUCKETS: i128 = 4096;
+
+struct NtileCountsData {
+    buckets: usize,
+}
+
+impl FunctionData for NtileCountsData {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct NtileCountsState {
+    values: Vec<F64>,
+}
+
+impl NtileCountsState {
+    // The edges between the buckets are the quantiles at 1/n, 2/n, ... (n-1)/n, picked
+    // like `quantile_disc`, a value falls into the first bucket whose upper edge it
+    // doesn't exceed.
+    fn counts(&mut self, buckets: usize) -> Vec<u64> {
+        let mut counts = vec![0; buckets];
+        if self.values.is_empty() {
+            return counts;
+        }
+        self.values.sort_unstable();
+        let last = (self.values.len() - 1) as f64;
+        let edges = (1..buckets)
+            .map(|k| self.values[(last * k as f64 / buckets as f64).floor() as usize])
+            .collect::<Vec<_>>();
+        for value in self.values.iter() {
+            counts[edges.partition_point(|edge| edge < value)] += 1;
+        }
+        counts
+    }
+}
+
+impl<T> UnaryState<T, ArrayType<UInt64Type>> for NtileCountsState
+where
+    T: ValueType,
+    T::Scalar: Number + AsPrimitive<f64>,
+{
+    fn add(
+        &mut self,
+        other: T::ScalarRef<'_>,
+        _function_data: Option<&dyn FunctionData>,
+    ) -> Result<()> {
+        let value: f64 = T::to_owned_scalar(other).as_();
+        self.values.push(value.into());
+        Ok(())
+    }
+
+    fn merge(&mut self, rhs: &Self) -> Result<()> {
+        self.values.extend_from_slice(&rhs.values);
+        Ok(())
+    }
+
+    fn merge_result(
+        &mut self,
+        builder: &mut ArrayColumnBuilder<UInt64Type>,
+        function_data: Option<&dyn FunctionData>,
+    ) -> Result<()> {
+        let data = unsafe {
+            function_data
+                .unwrap()
+                .as_any()
+                .downcast_ref_unchecked::<NtileCountsData>()
+        };
+        for count in self.counts(data.buckets) {
+            builder.put_item(count);
+        }
+        builder.commit_row();
+        Ok(())
+    }
+}
+
+fn get_buckets(display_name: &str, params: &[Scalar]) -> Result<usize> {
+    assert_params(display_name, params.len(), 1)?;
+    if let Scalar::Number(number) = params[0] {
+        if let Some(buckets) = number.integer_to_i128() {
+            if buckets > MAX_NTILE_BUCKETS {
+                return Err(ErrorCode::BadArguments(format!(
+                    "{} expects n <= {}, but got {}",
+                    display_name, MAX_NTILE_BUCKETS, buckets
+                )));
+            }
+            if buckets >= 1 {
+                return Ok(buckets as usize);
+            }
+            return Err(ErrorCode::BadArguments(format!(
+                "{} expects n >= 1, but got {}",
+                display_name, buckets
+            )));
+        }
+    }
+    Err(ErrorCode::BadDataValueType(format!(
+        "The parameter of aggregate function {} must be an integer",
+        display_name
+    )))
+}
+
+/// `ntile_counts(n)(x)` splits the values of `x` into `n` equal-frequency buckets by the
+/// quantiles of the group itself, and returns the number of values in each bucket.
+///
+/// The edges between the buckets are values of the group, so the values equal to an edge
+/// all fall into the same bucket, and the buckets are only roughly equal when there are
+/// many duplicates. All the values of a group are buffered to find the quantiles. NULL
+/// values are ignored, a group without values returns `n` zeros. `n` is at most 4096.
+pub fn try_create_aggregate_ntile_counts_function(
+    display_name: &str,
+    params: Vec<Scalar>,
+    arguments: Vec<DataType>,
+) -> Result<AggregateFunctionRef> {
+    assert_unary_arguments(display_name, arguments.len())?;
+    let buckets = get_buckets(display_name, &params)?;
+    let return_type = DataType::Array(Box::new(DataType::Number(NumberDataType::UInt64)));
+
+    with_number_mapped_type!(|NUM_TYPE| match &arguments[0] {
+        DataType::Number(NumberDataType::NUM_TYPE) => {
+            let func = AggregateUnaryFunction::<
+                NtileCountsState,
+                NumberType<NUM_TYPE>,
+                ArrayType<UInt64Type>,
+            >::try_create(
+                display_name, return_type, params, arguments[0].clone()
+            )
+            .with_function_data(Box::new(NtileCountsData { buckets }))
+            .with_need_drop(true);
+            Ok(Arc::new(func))
+        }
+        _ => Err(ErrorCode::BadDataValueType(format!(
+            "{} does not support type '{:?}'",
+            display_name, arguments[0]
+        ))),
+    })
+}
+
+pub fn aggregate_ntile_counts_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(try_create_aggregate_ntile_counts_function))
+}
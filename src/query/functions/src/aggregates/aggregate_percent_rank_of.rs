@@ -0,0 +1,157 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::any::Any;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::type_check::check_number;
+use databend_common_expression::types::number::Number;
+use databend_common_expression::types::number::F64;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::Float64Type;
+use databend_common_expression::types::NullableColumnBuilder;
+use databend_common_expression::types::NullableType;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::NumberType;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::with_number_mapped_type;
+use databend_common_expression::Expr;
+use databend_common_expression::FunctionContext;
+use databend_common_expression::Scalar;
+use num_traits::AsPrimitive;
+
+use super::AggregateUnaryFunction;
+use super::FunctionData;
+use super::UnaryState;
+use crate::aggregates::aggregate_function_factory::AggregateFunctionDescription;
+use crate::aggregates::assert_params;
+use crate::aggregates::assert_unary_arguments;
+use crate::aggregates::AggregateFunctionRef;
+use crate::BUILTIN_FUNCTIONS;
+
+struct PercentRankOfData {
+    target: f64,
+}
+
+impl FunctionData for PercentRankOfData {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct PercentRankOfState {
+    // The number of values less than or equal to the target.
+    less_equal: u64,
+    count: u64,
+}
+
+impl<T> UnaryState<T, NullableType<Float64Type>> for PercentRankOfState
+where
+    T: ValueType,
+    T::Scalar: Number + AsPrimitive<f64>,
+{
+    fn add(
+        &mut self,
+        other: T::ScalarRef<'_>,
+        function_data: Option<&dyn FunctionData>,
+    ) -> Result<()> {
+        let percent_rank_of_data = unsafe {
+            function_data
+                .unwrap()
+                .as_any()
+                .downcast_ref_unchecked::<PercentRankOfData>()
+        };
+        let value: f64 = T::to_owned_scalar(other).as_();
+        if value <= percent_rank_of_data.target {
+            self.less_equal += 1;
+        }
+        self.count += 1;
+        Ok(())
+    }
+
+    fn merge(&mut self, rhs: &Self) -> Result<()> {
+        self.less_equal += rhs.less_equal;
+        self.count += rhs.count;
+        Ok(())
+    }
+
+    fn merge_result(
+        &mut self,
+        builder: &mut NullableColumnBuilder<Float64Type>,
+        _function_data: Option<&dyn FunctionData>,
+    ) -> Result<()> {
+        if self.count == 0 {
+            builder.push_null();
+        } else {
+            builder.push((self.less_equal as f64 / self.count as f64).into());
+        }
+        Ok(())
+    }
+}
+
+fn get_target(display_name: &str, params: &[Scalar]) -> Result<f64> {
+    assert_params(display_name, params.len(), 1)?;
+    let target: F64 = check_number(
+        None,
+        &FunctionContext::default(),
+        &Expr::<usize>::Constant {
+            span: None,
+            scalar: params[0].clone(),
+            data_type: params[0].as_ref().infer_data_type(),
+        },
+        &BUILTIN_FUNCTIONS,
+    )?;
+    Ok(target.0)
+}
+
+/// `percent_rank_of(target)(x)` returns the fraction of the values of `x` that are
+/// less than or equal to `target`, the inverse of `quantile`.
+///
+/// NULL values are ignored, a group without values returns NULL.
+pub fn try_create_aggregate_percent_rank_of_function(
+    display_name: &str,
+    params: Vec<Scalar>,
+    arguments: Vec<DataType>,
+) -> Result<AggregateFunctionRef> {
+    assert_unary_arguments(display_name, arguments.len())?;
+    let target = get_target(display_name, &params)?;
+    let return_type = DataType::Number(NumberDataType::Float64).wrap_nullable();
+
+    with_number_mapped_type!(|NUM_TYPE| match &arguments[0] {
+        DataType::Number(NumberDataType::NUM_TYPE) => {
+            let func = AggregateUnaryFunction::<
+                PercentRankOfState,
+                NumberType<NUM_TYPE>,
+                NullableType<Float64Type>,
+            >::try_create(
+                display_name, return_type, params, arguments[0].clone()
+            )
+            .with_function_data(Box::new(PercentRankOfData { target }));
+            Ok(Arc::new(func))
+        }
+        _ => Err(ErrorCode::BadDataValueType(format!(
+            "{} does not support type '{:?}'",
+            display_name, arguments[0]
+        ))),
+    })
+}
+
+pub fn aggregate_percent_rank_of_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(try_create_aggregate_percent_rank_of_function))
+}
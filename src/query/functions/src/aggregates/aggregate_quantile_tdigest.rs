@@ -77,8 +77,8 @@ impl QuantileTDigestState {
             unmerged_total_weight: 0f64,
             unmerged_weights: vec![],
             unmerged_means: vec![],
-            min: 0f64,
-            max: 0f64,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
         }
     }
 
@@ -101,8 +101,10 @@ impl QuantileTDigestState {
 
         self.unmerged_weights.extend_from_slice(&rhs.weights);
         self.unmerged_means.extend_from_slice(&rhs.means);
-        self.unmerged_total_weight = rhs.weights.iter().sum();
+        self.unmerged_total_weight += rhs.weights.iter().sum::<f64>();
         self.compress();
+        self.min = f64::min(self.min, rhs.min);
+        self.max = f64::max(self.max, rhs.max);
 
         Ok(())
     }
@@ -201,7 +203,56 @@ impl QuantileTDigestState {
         QuantileTDigestState::weighted_average(self.means[mean_last], z1, self.max, z2)
     }
 
-    fn len(&self) -> usize {
+    /// The fraction of the values not greater than `value`. A centroid of a single value
+    /// is a step at its mean, the weight of a larger one is spread evenly from the
+    /// midpoint with the previous centroid to the midpoint with the next one, or to the
+    /// min and the max for the first and the last.
+    pub(crate) fn cdf(&mut self, value: f64) -> f64 {
+        self.compress();
+        if self.weights.is_empty() || value < self.min {
+            return 0f64;
+        }
+        if value >= self.max {
+            return 1f64;
+        }
+
+        let last = self.weights.len() - 1;
+        let mut weight_below = 0f64;
+        for (i, (&weight, &mean)) in self.weights.iter().zip(self.means.iter()).enumerate() {
+            if weight == 1f64 {
+                if mean <= value {
+                    weight_below += 1f64;
+                }
+                continue;
+            }
+            let lower = if i == 0 {
+                self.min
+            } else {
+                (self.means[i - 1] + mean) / 2f64
+            };
+            let upper = if i == last {
+                self.max
+            } else {
+                (mean + self.means[i + 1]) / 2f64
+            };
+            if value >= upper {
+                weight_below += weight;
+            } else if value > lower {
+                weight_below += weight * (value - lower) / (upper - lower);
+            }
+        }
+        weight_below / self.total_weight
+    }
+
+    /// Whether a deserialized state is a compressed digest, as built by `rank_digest`.
+    pub(crate) fn is_valid_digest(&self) -> bool {
+        self.weights.len() == self.means.len()
+            && self.unmerged_total_weight == 0f64
+            && self.unmerged_weights.is_empty()
+            && self.unmerged_means.is_empty()
+    }
+
+    pub(crate) fn len(&self) -> usize {
         (self.total_weight + self.unmerged_total_weight) as usize
     }
 
@@ -213,7 +264,7 @@ impl QuantileTDigestState {
         f64::max(a, f64::min(b, x))
     }
 
-    fn compress(&mut self) {
+    pub(crate) fn compress(&mut self) {
         if self.unmerged_total_weight > 0f64 {
             self.merge_centroid(self.unmerged_weights.clone(), self.unmerged_means.clone());
             self.unmerged_weights.clear();
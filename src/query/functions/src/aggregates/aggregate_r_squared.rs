@@ -0,0 +1,207 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::fmt;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::Float64Type;
+use databend_common_expression::types::NullableType;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+use databend_common_expression::ScalarRef;
+
+use super::aggregate_covariance::AggregateCovarianceState;
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::StateAddr;
+use crate::aggregates::assert_binary_arguments;
+use crate::aggregates::AggregateFunction;
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct RSquaredState {
+    // The co-moments of x and y.
+    x_y: AggregateCovarianceState,
+    // The co-moments of x with itself, i.e. the sum of the squared deviations of x.
+    x_x: AggregateCovarianceState,
+    // The co-moments of y with itself, i.e. the sum of the squared deviations of y.
+    y_y: AggregateCovarianceState,
+}
+
+impl RSquaredState {
+    fn add(&mut self, columns: InputColumns, row: usize) {
+        let y = match unsafe { AnyType::index_column_unchecked(&columns[0], row) } {
+            ScalarRef::Number(y) => y.to_f64().0,
+            _ => unreachable!(),
+        };
+        let x = match unsafe { AnyType::index_column_unchecked(&columns[1], row) } {
+            ScalarRef::Number(x) => x.to_f64().0,
+            _ => unreachable!(),
+        };
+        self.x_y.add(x, y);
+        self.x_x.add(x, x);
+        self.y_y.add(y, y);
+    }
+
+    fn merge(&mut self, rhs: &Self) {
+        self.x_y.merge(&rhs.x_y);
+        self.x_x.merge(&rhs.x_x);
+        self.y_y.merge(&rhs.y_y);
+    }
+
+    fn r_squared(&self) -> Option<f64> {
+        if self.x_x.co_moments == 0.0 || self.y_y.co_moments == 0.0 {
+            return None;
+        }
+        let co_moments = self.x_y.co_moments;
+        Some(co_moments * co_moments / (self.x_x.co_moments * self.y_y.co_moments))
+    }
+}
+
+/// `r_squared(y, x)` returns the coefficient of determination of the least-squares fit
+/// of `y` on `x`, i.e. the fraction of the variance of `y` explained by a linear function
+/// of `x`. It equals the square of the correlation of `x` and `y`, 1 for a perfect fit.
+///
+/// Rows whose `y` or `x` is NULL are skipped. A group whose `x` doesn't vary, including a
+/// group of less than two rows, returns NULL, and so does a group whose `y` doesn't vary.
+#[derive(Clone)]
+pub struct AggregateRSquaredFunction {
+    display_name: String,
+}
+
+impl AggregateRSquaredFunction {
+    pub fn try_create(
+        display_name: &str,
+        _params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<Arc<dyn AggregateFunction>> {
+        assert_binary_arguments(display_name, arguments.len())?;
+        for argument in arguments.iter() {
+            if !argument.is_numeric() {
+                return Err(ErrorCode::BadDataValueType(format!(
+                    "{} does not support type '{:?}'",
+                    display_name, argument
+                )));
+            }
+        }
+
+        Ok(Arc::new(AggregateRSquaredFunction {
+            display_name: display_name.to_string(),
+        }))
+    }
+}
+
+impl AggregateFunction for AggregateRSquaredFunction {
+    fn name(&self) -> &str {
+        "AggregateRSquaredFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(DataType::Number(NumberDataType::Float64).wrap_nullable())
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        place.write(RSquaredState::default);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<RSquaredState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let state = place.get::<RSquaredState>();
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                state.add(columns, row);
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        for (row, place) in places.iter().enumerate() {
+            let state = place.next(offset).get::<RSquaredState>();
+            state.add(columns, row);
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let state = place.get::<RSquaredState>();
+        state.add(columns, row);
+        Ok(())
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<RSquaredState>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<RSquaredState>();
+        let rhs: RSquaredState = borsh_deserialize_state(reader)?;
+        state.merge(&rhs);
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<RSquaredState>();
+        let other = rhs.get::<RSquaredState>();
+        state.merge(other);
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<RSquaredState>();
+        let builder = NullableType::<Float64Type>::try_downcast_builder(builder).unwrap();
+        match state.r_squared() {
+            Some(r_squared) => builder.push(r_squared.into()),
+            None => builder.push_null(),
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for AggregateRSquaredFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_r_squared_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(AggregateRSquaredFunction::try_create))
+}
@@ -0,0 +1,195 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::fmt;
+use std::sync::Arc;
+
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::BinaryType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::NullableType;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::Column;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+use databend_common_expression::ScalarRef;
+
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::QuantileTDigestState;
+use super::StateAddr;
+use crate::aggregates::assert_unary_arguments;
+use crate::aggregates::AggregateFunction;
+
+fn to_f64(column: &Column, row: usize) -> f64 {
+    match unsafe { AnyType::index_column_unchecked(column, row) } {
+        ScalarRef::Number(number) => number.to_f64().0,
+        _ => unreachable!(),
+    }
+}
+
+/// `rank_digest(x)` returns a t-digest of the values of the group, serialized into a
+/// Binary, so that any quantile can be queried later with
+/// `rank_digest_quantile(digest, level)` and the rank of any value with
+/// `rank_digest_cdf(digest, value)`, without keeping the values.
+///
+/// The digest is the borsh encoding of the state of `quantile_tdigest`, in little endian:
+/// the compression `epsilon` as a u32 (100), the max number of centroids as a u64 (2048),
+/// the total weight as a f64, the weights then the means of the centroids sorted by mean,
+/// each as a u32 length followed by the f64 values, the weight and the weights and means
+/// of the unmerged values (always 0 and empty), then the min and the max values.
+///
+/// Neighbouring values are merged into a centroid as long as its weight stays below
+/// `pi * n * sqrt(q * (1 - q)) / 100`, for `n` values and `q` the rank of the centroid,
+/// i.e. 1.6% of the values around the median and far less towards the min and the max.
+/// A group of less than 128 values is therefore kept exactly. The quantiles and ranks
+/// are interpolated inside a centroid, which bounds their error by its weight.
+/// `rank_digest_quantile(rank_digest(x), level)` returns the same as
+/// `quantile_tdigest(level)(x)`.
+///
+/// NULL values are ignored, a group without values returns NULL.
+#[derive(Clone)]
+pub struct AggregateRankDigestFunction {
+    display_name: String,
+}
+
+impl AggregateRankDigestFunction {
+    pub fn try_create(
+        display_name: &str,
+        _params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<Arc<dyn AggregateFunction>> {
+        assert_unary_arguments(display_name, arguments.len())?;
+        if !arguments[0].is_numeric() {
+            return Err(ErrorCode::BadDataValueType(format!(
+                "{} does not support type '{:?}'",
+                display_name, arguments[0]
+            )));
+        }
+
+        Ok(Arc::new(AggregateRankDigestFunction {
+            display_name: display_name.to_string(),
+        }))
+    }
+}
+
+impl AggregateFunction for AggregateRankDigestFunction {
+    fn name(&self) -> &str {
+        "AggregateRankDigestFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(DataType::Binary.wrap_nullable())
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        place.write(QuantileTDigestState::new);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<QuantileTDigestState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let state = place.get::<QuantileTDigestState>();
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                state.add(to_f64(&columns[0], row), None);
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        for (row, place) in places.iter().enumerate() {
+            let state = place.next(offset).get::<QuantileTDigestState>();
+            state.add(to_f64(&columns[0], row), None);
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let state = place.get::<QuantileTDigestState>();
+        state.add(to_f64(&columns[0], row), None);
+        Ok(())
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<QuantileTDigestState>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<QuantileTDigestState>();
+        let mut rhs: QuantileTDigestState = borsh_deserialize_state(reader)?;
+        state.merge(&mut rhs)
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<QuantileTDigestState>();
+        let other = rhs.get::<QuantileTDigestState>();
+        state.merge(other)
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<QuantileTDigestState>();
+        state.compress();
+        let builder = NullableType::<BinaryType>::try_downcast_builder(builder).unwrap();
+        if state.len() == 0 {
+            builder.push_null();
+        } else {
+            let mut digest = Vec::new();
+            borsh_serialize_state(&mut digest, state)?;
+            builder.push(&digest);
+        }
+        Ok(())
+    }
+
+    fn need_manual_drop_state(&self) -> bool {
+        true
+    }
+
+    unsafe fn drop_state(&self, place: StateAddr) {
+        let state = place.get::<QuantileTDigestState>();
+        std::ptr::drop_in_place(state);
+    }
+}
+
+impl fmt::Display for AggregateRankDigestFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_rank_digest_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(AggregateRankDigestFunction::try_create))
+}
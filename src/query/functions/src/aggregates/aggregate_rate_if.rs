@@ -0,0 +1,189 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::fmt;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::Float64Type;
+use databend_common_expression::types::NullableType;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+use databend_common_expression::ScalarRef;
+
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::StateAddr;
+use crate::aggregates::assert_unary_arguments;
+use crate::aggregates::AggregateFunction;
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct RateIfState {
+    rows: u64,
+    matches: u64,
+}
+
+impl RateIfState {
+    fn add(&mut self, columns: InputColumns, row: usize) {
+        self.rows += 1;
+        let predicate = unsafe { AnyType::index_column_unchecked(&columns[0], row) };
+        if matches!(predicate, ScalarRef::Boolean(true)) {
+            self.matches += 1;
+        }
+    }
+
+    fn merge(&mut self, rhs: &Self) {
+        self.rows += rhs.rows;
+        self.matches += rhs.matches;
+    }
+}
+
+/// `rate_if(predicate)` returns the fraction of the rows of the group where `predicate`
+/// is true, i.e. `count_if(predicate) / count(*)`, as a Float64 between 0 and 1, counting
+/// both in a single pass.
+///
+/// A row whose `predicate` is NULL is counted in the rows but not in the matches, the
+/// same as a false one, and an empty group returns NULL.
+#[derive(Clone)]
+pub struct AggregateRateIfFunction {
+    display_name: String,
+}
+
+impl AggregateRateIfFunction {
+    pub fn try_create(
+        display_name: &str,
+        _params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<Arc<dyn AggregateFunction>> {
+        assert_unary_arguments(display_name, arguments.len())?;
+        if !matches!(
+            arguments[0].remove_nullable(),
+            DataType::Boolean | DataType::Null
+        ) {
+            return Err(ErrorCode::BadArguments(format!(
+                "The predicate of aggregate function {} must be a boolean, got: {:?}",
+                display_name, arguments[0]
+            )));
+        }
+
+        Ok(Arc::new(AggregateRateIfFunction {
+            display_name: display_name.to_string(),
+        }))
+    }
+}
+
+impl AggregateFunction for AggregateRateIfFunction {
+    fn name(&self) -> &str {
+        "AggregateRateIfFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(DataType::Number(NumberDataType::Float64).wrap_nullable())
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        place.write(RateIfState::default);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<RateIfState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let state = place.get::<RateIfState>();
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                state.add(columns, row);
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        for (row, place) in places.iter().enumerate() {
+            let state = place.next(offset).get::<RateIfState>();
+            state.add(columns, row);
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let state = place.get::<RateIfState>();
+        state.add(columns, row);
+        Ok(())
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<RateIfState>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<RateIfState>();
+        let rhs: RateIfState = borsh_deserialize_state(reader)?;
+        state.merge(&rhs);
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<RateIfState>();
+        let other = rhs.get::<RateIfState>();
+        state.merge(other);
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<RateIfState>();
+        let builder = NullableType::<Float64Type>::try_downcast_builder(builder).unwrap();
+        if state.rows == 0 {
+            builder.push_null();
+        } else {
+            builder.push((state.matches as f64 / state.rows as f64).into());
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for AggregateRateIfFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_rate_if_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(AggregateRateIfFunction::try_create))
+}
@@ -21,6 +21,8 @@ use borsh::BorshSerialize;
 use databend_common_arrow::arrow::bitmap::Bitmap;
 use databend_common_exception::ErrorCode;
 use databend_common_exception::Result;
+use databend_common_expression::types::number::NumberScalar;
+use databend_common_expression::types::number::F64;
 use databend_common_expression::types::BooleanType;
 use databend_common_expression::types::DataType;
 use databend_common_expression::types::NumberDataType;
@@ -28,6 +30,7 @@ use databend_common_expression::types::ValueType;
 use databend_common_expression::ColumnBuilder;
 use databend_common_expression::InputColumns;
 use databend_common_expression::Scalar;
+use databend_common_expression::ScalarRef;
 
 use super::aggregate_function::AggregateFunction;
 use super::aggregate_function::AggregateFunctionRef;
@@ -53,18 +56,36 @@ impl AggregateRetentionState {
     }
 }
 
+/// `retention(cond0, cond1, ...)` returns for each condition `1` if the group met it
+/// along with `cond0`, `0` otherwise.
+///
+/// `churn(cond0, cond1, ...)` shares the state and returns the inverse as `Float64`:
+/// for each condition `0` if the group met it along with `cond0`, `1` otherwise. A group
+/// that never met `cond0` is not part of any step, all its values are NULL, so that an
+/// `avg` of a step over the groups gives the fraction of the groups that met `cond0`
+/// but not the step.
 #[derive(Clone)]
 pub struct AggregateRetentionFunction {
     display_name: String,
     events_size: u8,
+    churn: bool,
 }
 
 impl AggregateFunction for AggregateRetentionFunction {
     fn name(&self) -> &str {
-        "AggregateRetentionFunction"
+        if self.churn {
+            "AggregateChurnFunction"
+        } else {
+            "AggregateRetentionFunction"
+        }
     }
 
     fn return_type(&self) -> Result<DataType> {
+        if self.churn {
+            return Ok(DataType::Array(Box::new(DataType::Nullable(Box::new(
+                DataType::Number(NumberDataType::Float64),
+            )))));
+        }
         Ok(DataType::Array(Box::new(DataType::Number(
             NumberDataType::UInt8,
         ))))
@@ -160,6 +181,21 @@ impl AggregateFunction for AggregateRetentionFunction {
     fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
         let state = place.get::<AggregateRetentionState>();
         let builder = builder.as_array_mut().unwrap();
+        if self.churn {
+            for i in 0..self.events_size {
+                let value = if state.events & 1 == 0 {
+                    ScalarRef::Null
+                } else if state.events & (1 << i) != 0 {
+                    ScalarRef::Number(NumberScalar::Float64(F64::from(0.0)))
+                } else {
+                    ScalarRef::Number(NumberScalar::Float64(F64::from(1.0)))
+                };
+                builder.builder.push(value);
+            }
+            builder.offsets.push(builder.builder.len() as u64);
+            return Ok(());
+        }
+
         let inner = builder
             .builder
             .as_number_mut()
@@ -197,19 +233,17 @@ impl AggregateRetentionFunction {
     pub fn try_create(
         display_name: &str,
         arguments: Vec<DataType>,
+        churn: bool,
     ) -> Result<AggregateFunctionRef> {
         Ok(Arc::new(Self {
             display_name: display_name.to_owned(),
             events_size: arguments.len() as u8,
+            churn,
         }))
     }
 }
 
-pub fn try_create_aggregate_retention_function(
-    display_name: &str,
-    _params: Vec<Scalar>,
-    arguments: Vec<DataType>,
-) -> Result<AggregateFunctionRef> {
+fn check_retention_arguments(display_name: &str, arguments: &[DataType]) -> Result<()> {
     assert_variadic_arguments(display_name, arguments.len(), (1, 32))?;
 
     for argument in arguments.iter() {
@@ -219,10 +253,31 @@ pub fn try_create_aggregate_retention_function(
             ));
         }
     }
+    Ok(())
+}
 
-    AggregateRetentionFunction::try_create(display_name, arguments)
+pub fn try_create_aggregate_retention_function(
+    display_name: &str,
+    _params: Vec<Scalar>,
+    arguments: Vec<DataType>,
+) -> Result<AggregateFunctionRef> {
+    check_retention_arguments(display_name, &arguments)?;
+    AggregateRetentionFunction::try_create(display_name, arguments, false)
+}
+
+pub fn try_create_aggregate_churn_function(
+    display_name: &str,
+    _params: Vec<Scalar>,
+    arguments: Vec<DataType>,
+) -> Result<AggregateFunctionRef> {
+    check_retention_arguments(display_name, &arguments)?;
+    AggregateRetentionFunction::try_create(display_name, arguments, true)
 }
 
 pub fn aggregate_retention_function_desc() -> AggregateFunctionDescription {
     AggregateFunctionDescription::creator(Box::new(try_create_aggregate_retention_function))
 }
+
+pub fn aggregate_churn_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(try_create_aggregate_churn_function))
+}
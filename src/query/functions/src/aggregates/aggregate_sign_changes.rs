@@ -0,0 +1,214 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::fmt;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::number::F64;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::UInt64Type;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+use databend_common_expression::ScalarRef;
+
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::StateAddr;
+use crate::aggregates::assert_binary_arguments;
+use crate::aggregates::AggregateFunction;
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct SignChangesState {
+    pairs: Vec<(Scalar, F64)>,
+}
+
+impl SignChangesState {
+    fn add(&mut self, columns: InputColumns, row: usize) {
+        let order = unsafe { AnyType::index_column_unchecked(&columns[0], row) };
+        let value = match unsafe { AnyType::index_column_unchecked(&columns[1], row) } {
+            ScalarRef::Number(value) => value.to_f64(),
+            _ => unreachable!(),
+        };
+        self.pairs.push((order.to_owned(), value));
+    }
+
+    fn merge(&mut self, rhs: &Self) {
+        self.pairs.extend(rhs.pairs.iter().cloned());
+    }
+
+    // Rows of the same order are sorted by value, so the result doesn't depend on
+    // the order in which the rows arrived.
+    fn sign_changes(&mut self) -> u64 {
+        self.pairs.sort();
+        let mut changes = 0;
+        let mut last_positive = None;
+        for (_, value) in self.pairs.iter() {
+            if value.0 == 0.0 || value.0.is_nan() {
+                continue;
+            }
+            let positive = value.0 > 0.0;
+            if last_positive.is_some_and(|last| last != positive) {
+                changes += 1;
+            }
+            last_positive = Some(positive);
+        }
+        changes
+    }
+}
+
+/// `sign_changes(order, value)` returns the number of times the sign of `value` flips
+/// between consecutive rows, when the rows are sorted by `order`.
+///
+/// Zeros have no sign and are skipped, so `1, 0, -1` counts one change and `1, 0, 1` none,
+/// NaNs are skipped as well. Rows of the same `order` are sorted by `value`. Rows whose
+/// `order` or `value` is NULL are skipped, a group without rows returns 0.
+#[derive(Clone)]
+pub struct AggregateSignChangesFunction {
+    display_name: String,
+}
+
+impl AggregateSignChangesFunction {
+    pub fn try_create(
+        display_name: &str,
+        _params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<Arc<dyn AggregateFunction>> {
+        assert_binary_arguments(display_name, arguments.len())?;
+        if !arguments[0].is_numeric() && !arguments[0].is_date_or_date_time() {
+            return Err(ErrorCode::BadDataValueType(format!(
+                "{} does not support order type '{:?}'",
+                display_name, arguments[0]
+            )));
+        }
+        if !arguments[1].is_numeric() {
+            return Err(ErrorCode::BadDataValueType(format!(
+                "{} does not support value type '{:?}'",
+                display_name, arguments[1]
+            )));
+        }
+
+        Ok(Arc::new(AggregateSignChangesFunction {
+            display_name: display_name.to_string(),
+        }))
+    }
+}
+
+impl AggregateFunction for AggregateSignChangesFunction {
+    fn name(&self) -> &str {
+        "AggregateSignChangesFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(DataType::Number(NumberDataType::UInt64))
+    }
+    fn init_state(&self, place: StateAddr) {
+        place.write(SignChangesState::default);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<SignChangesState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let state = place.get::<SignChangesState>();
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                state.add(columns, row);
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        for (row, place) in places.iter().enumerate() {
+            let state = place.next(offset).get::<SignChangesState>();
+            state.add(columns, row);
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let state = place.get::<SignChangesState>();
+        state.add(columns, row);
+        Ok(())
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<SignChangesState>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<SignChangesState>();
+        let rhs: SignChangesState = borsh_deserialize_state(reader)?;
+        state.merge(&rhs);
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<SignChangesState>();
+        let other = rhs.get::<SignChangesState>();
+        state.merge(other);
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<SignChangesState>();
+        let builder = UInt64Type::try_downcast_builder(builder).unwrap();
+        builder.push(state.sign_changes());
+        Ok(())
+    }
+
+    fn need_manual_drop_state(&self) -> bool {
+        true
+    }
+
+    unsafe fn drop_state(&self, place: StateAddr) {
+        let state = place.get::<SignChangesState>();
+        std::ptr::drop_in_place(state);
+    }
+}
+
+impl fmt::Display for AggregateSignChangesFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_sign_changes_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(AggregateSignChangesFunction::try_create))
+}
@@ -27,6 +27,8 @@ use databend_common_expression::types::number::F64;
 use databend_common_expression::types::DataType;
 use databend_common_expression::types::DecimalDataType;
 use databend_common_expression::types::Float64Type;
+use databend_common_expression::types::NullableColumnBuilder;
+use databend_common_expression::types::NullableType;
 use databend_common_expression::types::NumberDataType;
 use databend_common_expression::types::NumberType;
 use databend_common_expression::types::ValueType;
@@ -45,6 +47,9 @@ const STD_POP: u8 = 0;
 const STD_SAMP: u8 = 1;
 const VAR_POP: u8 = 2;
 const VAR_SAMP: u8 = 3;
+const COEF_VAR_POP: u8 = 4;
+const COEF_VAR_SAMP: u8 = 5;
+const STD_ERR: u8 = 6;
 
 // Streaming approximate standard deviation using Welford's
 // method, DOI: 10.2307/1266577
@@ -92,8 +97,8 @@ impl<const TYPE: u8> StddevState<TYPE> {
         Ok(())
     }
 
-    fn state_merge_result(&mut self, builder: &mut Vec<F64>) -> Result<()> {
-        let result = if self.count <= 1 {
+    fn result(&self) -> f64 {
+        if self.count <= 1 {
             0f64
         } else {
             match TYPE {
@@ -101,12 +106,36 @@ impl<const TYPE: u8> StddevState<TYPE> {
                 STD_SAMP => (self.dsquared / (self.count - 1) as f64).sqrt(),
                 VAR_POP => self.dsquared / self.count as f64,
                 VAR_SAMP => self.dsquared / (self.count - 1) as f64,
+                COEF_VAR_POP => (self.dsquared / self.count as f64).sqrt() / self.mean,
+                COEF_VAR_SAMP => (self.dsquared / (self.count - 1) as f64).sqrt() / self.mean,
+                STD_ERR => {
+                    (self.dsquared / (self.count - 1) as f64).sqrt() / (self.count as f64).sqrt()
+                }
                 _ => unreachable!(),
             }
-        };
+        }
+    }
 
-        builder.push(result.into());
+    fn state_merge_result(&mut self, builder: &mut Vec<F64>) -> Result<()> {
+        builder.push(self.result().into());
+        Ok(())
+    }
 
+    // The coefficient of variation is undefined if the mean is zero,
+    // the standard error is undefined for less than two values.
+    fn state_merge_nullable_result(
+        &mut self,
+        builder: &mut NullableColumnBuilder<Float64Type>,
+    ) -> Result<()> {
+        let undefined = match TYPE {
+            STD_ERR => self.count < 2,
+            _ => self.count == 0 || self.mean == 0f64,
+        };
+        if undefined {
+            builder.push_null();
+        } else {
+            builder.push(self.result().into());
+        }
         Ok(())
     }
 }
@@ -143,6 +172,34 @@ where
     }
 }
 
+impl<T, const TYPE: u8> UnaryState<T, NullableType<Float64Type>>
+    for NumberAggregateStddevState<TYPE>
+where
+    T: ValueType,
+    T::Scalar: Number + AsPrimitive<f64>,
+{
+    fn add(
+        &mut self,
+        other: T::ScalarRef<'_>,
+        _function_data: Option<&dyn FunctionData>,
+    ) -> Result<()> {
+        let value = T::to_owned_scalar(other).as_();
+        self.state.state_add(value)
+    }
+
+    fn merge(&mut self, other: &Self) -> Result<()> {
+        self.state.state_merge(&other.state)
+    }
+
+    fn merge_result(
+        &mut self,
+        builder: &mut NullableColumnBuilder<Float64Type>,
+        _function_data: Option<&dyn FunctionData>,
+    ) -> Result<()> {
+        self.state.state_merge_nullable_result(builder)
+    }
+}
+
 struct DecimalFuncData {
     pub scale: u8,
 }
@@ -191,6 +248,40 @@ where
     }
 }
 
+impl<T, const TYPE: u8> UnaryState<T, NullableType<Float64Type>>
+    for DecimalNumberAggregateStddevState<TYPE>
+where
+    T: ValueType,
+    T::Scalar: Decimal + BorshSerialize + BorshDeserialize,
+{
+    fn add(
+        &mut self,
+        other: T::ScalarRef<'_>,
+        function_data: Option<&dyn FunctionData>,
+    ) -> Result<()> {
+        let stddev_func_data = unsafe {
+            function_data
+                .unwrap()
+                .as_any()
+                .downcast_ref_unchecked::<DecimalFuncData>()
+        };
+        let value = T::to_owned_scalar(other).to_float64(stddev_func_data.scale);
+        self.state.state_add(value)
+    }
+
+    fn merge(&mut self, other: &Self) -> Result<()> {
+        self.state.state_merge(&other.state)
+    }
+
+    fn merge_result(
+        &mut self,
+        builder: &mut NullableColumnBuilder<Float64Type>,
+        _function_data: Option<&dyn FunctionData>,
+    ) -> Result<()> {
+        self.state.state_merge_nullable_result(builder)
+    }
+}
+
 pub fn try_create_aggregate_stddev_pop_function<const TYPE: u8>(
     display_name: &str,
     params: Vec<Scalar>,
@@ -248,3 +339,66 @@ pub fn aggregate_stddev_samp_function_desc() -> AggregateFunctionDescription {
         try_create_aggregate_stddev_pop_function::<STD_SAMP>,
     ))
 }
+
+pub fn try_create_aggregate_coef_variation_function<const TYPE: u8>(
+    display_name: &str,
+    params: Vec<Scalar>,
+    arguments: Vec<DataType>,
+) -> Result<Arc<dyn AggregateFunction>> {
+    assert_unary_arguments(display_name, arguments.len())?;
+    let return_type = DataType::Number(NumberDataType::Float64).wrap_nullable();
+    with_number_mapped_type!(|NUM_TYPE| match &arguments[0] {
+        DataType::Number(NumberDataType::NUM_TYPE) => {
+            AggregateUnaryFunction::<
+                NumberAggregateStddevState<TYPE>,
+                NumberType<NUM_TYPE>,
+                NullableType<Float64Type>,
+            >::try_create_unary(display_name, return_type, params, arguments[0].clone())
+        }
+        DataType::Decimal(DecimalDataType::Decimal128(s)) => {
+            let func = AggregateUnaryFunction::<
+                DecimalNumberAggregateStddevState<TYPE>,
+                Decimal128Type,
+                NullableType<Float64Type>,
+            >::try_create(
+                display_name, return_type, params, arguments[0].clone()
+            )
+            .with_function_data(Box::new(DecimalFuncData { scale: s.scale }));
+            Ok(Arc::new(func))
+        }
+        DataType::Decimal(DecimalDataType::Decimal256(s)) => {
+            let func = AggregateUnaryFunction::<
+                DecimalNumberAggregateStddevState<TYPE>,
+                Decimal256Type,
+                NullableType<Float64Type>,
+            >::try_create(
+                display_name, return_type, params, arguments[0].clone()
+            )
+            .with_function_data(Box::new(DecimalFuncData { scale: s.scale }));
+            Ok(Arc::new(func))
+        }
+        _ => Err(ErrorCode::BadDataValueType(format!(
+            "{} does not support type '{:?}'",
+            display_name, arguments[0]
+        ))),
+    })
+}
+
+pub fn aggregate_coef_variation_pop_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(
+        try_create_aggregate_coef_variation_function::<COEF_VAR_POP>,
+    ))
+}
+
+pub fn aggregate_coef_variation_samp_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(
+        try_create_aggregate_coef_variation_function::<COEF_VAR_SAMP>,
+    ))
+}
+
+/// `stderr(x)` returns the standard error of the mean, `stddev_samp(x) / sqrt(count(x))`.
+pub fn aggregate_stderr_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(
+        try_create_aggregate_coef_variation_function::<STD_ERR>,
+    ))
+}
@@ -291,6 +291,8 @@ pub fn try_create_aggregate_sum_function(
                 )
             }
         }
+        // There is no interval or duration type to carry the unit of a total,
+        // so `Date` and `Timestamp` are rejected instead of summed as integers.
         _ => Err(ErrorCode::BadDataValueType(format!(
             "{} does not support type '{:?}'",
             display_name, arguments[0]
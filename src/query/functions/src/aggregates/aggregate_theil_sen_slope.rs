@@ -0,0 +1,240 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::fmt;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::number::F64;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::Float64Type;
+use databend_common_expression::types::NullableType;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+use databend_common_expression::ScalarRef;
+
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::StateAddr;
+use crate::aggregates::assert_binary_arguments;
+use crate::aggregates::AggregateFunction;
+
+// A group of more points is sampled down to this many, which bounds the pairwise
+// slopes to about half a million.
+const THEIL_SEN_MAX_POINTS: usize = 1000;
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct TheilSenState {
+    // The (x, y) points.
+    points: Vec<(F64, F64)>,
+}
+
+impl TheilSenState {
+    fn add(&mut self, columns: InputColumns, row: usize) {
+        let y = match unsafe { AnyType::index_column_unchecked(&columns[0], row) } {
+            ScalarRef::Number(y) => y.to_f64(),
+            _ => unreachable!(),
+        };
+        let x = match unsafe { AnyType::index_column_unchecked(&columns[1], row) } {
+            ScalarRef::Number(x) => x.to_f64(),
+            _ => unreachable!(),
+        };
+        self.points.push((x, y));
+    }
+
+    fn merge(&mut self, rhs: &Self) {
+        self.points.extend_from_slice(&rhs.points);
+    }
+
+    // The points are sorted before the sampling, so the result doesn't depend on the
+    // order in which the rows arrived.
+    fn slope(&mut self) -> Option<f64> {
+        self.points.sort();
+        let len = self.points.len();
+        let points = if len > THEIL_SEN_MAX_POINTS {
+            (0..THEIL_SEN_MAX_POINTS)
+                .map(|i| self.points[i * len / THEIL_SEN_MAX_POINTS])
+                .collect::<Vec<_>>()
+        } else {
+            self.points.clone()
+        };
+
+        let mut slopes = Vec::new();
+        for (i, (x1, y1)) in points.iter().enumerate() {
+            for (x2, y2) in points[i + 1..].iter() {
+                if x2 != x1 {
+                    slopes.push((y2.0 - y1.0) / (x2.0 - x1.0));
+                }
+            }
+        }
+        if slopes.is_empty() {
+            return None;
+        }
+        slopes.sort_by(f64::total_cmp);
+        let mid = slopes.len() / 2;
+        if slopes.len() % 2 == 1 {
+            Some(slopes[mid])
+        } else {
+            Some((slopes[mid - 1] + slopes[mid]) / 2.0)
+        }
+    }
+}
+
+/// `theil_sen_slope(y, x)` returns the Theil-Sen estimate of the slope of `y` on `x`,
+/// the median of the slopes between all the pairs of points with different `x`.
+///
+/// Unlike the least-squares slope, it is barely moved by outliers: up to about 29% of
+/// the points can be arbitrarily wrong. The points are buffered and the slopes of all
+/// the pairs are computed at the end, which is quadratic in the size of the group, so a
+/// group of more than 1000 points is sampled down to 1000 points evenly spread in the
+/// order of `x`. Rows whose `y` or `x` is NULL are skipped, a group whose `x` doesn't
+/// vary, including a group of less than two rows, returns NULL.
+#[derive(Clone)]
+pub struct AggregateTheilSenSlopeFunction {
+    display_name: String,
+}
+
+impl AggregateTheilSenSlopeFunction {
+    pub fn try_create(
+        display_name: &str,
+        _params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<Arc<dyn AggregateFunction>> {
+        assert_binary_arguments(display_name, arguments.len())?;
+        for argument in arguments.iter() {
+            if !argument.is_numeric() {
+                return Err(ErrorCode::BadDataValueType(format!(
+                    "{} does not support type '{:?}'",
+                    display_name, argument
+                )));
+            }
+        }
+
+        Ok(Arc::new(AggregateTheilSenSlopeFunction {
+            display_name: display_name.to_string(),
+        }))
+    }
+}
+
+impl AggregateFunction for AggregateTheilSenSlopeFunction {
+    fn name(&self) -> &str {
+        "AggregateTheilSenSlopeFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(DataType::Number(NumberDataType::Float64).wrap_nullable())
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        place.write(TheilSenState::default);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<TheilSenState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let state = place.get::<TheilSenState>();
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                state.add(columns, row);
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        for (row, place) in places.iter().enumerate() {
+            let state = place.next(offset).get::<TheilSenState>();
+            state.add(columns, row);
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let state = place.get::<TheilSenState>();
+        state.add(columns, row);
+        Ok(())
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<TheilSenState>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<TheilSenState>();
+        let rhs: TheilSenState = borsh_deserialize_state(reader)?;
+        state.merge(&rhs);
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<TheilSenState>();
+        let other = rhs.get::<TheilSenState>();
+        state.merge(other);
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<TheilSenState>();
+        let builder = NullableType::<Float64Type>::try_downcast_builder(builder).unwrap();
+        match state.slope() {
+            Some(slope) => builder.push(slope.into()),
+            None => builder.push_null(),
+        }
+        Ok(())
+    }
+
+    fn need_manual_drop_state(&self) -> bool {
+        true
+    }
+
+    unsafe fn drop_state(&self, place: StateAddr) {
+        let state = place.get::<TheilSenState>();
+        std::ptr::drop_in_place(state);
+    }
+}
+
+impl fmt::Display for AggregateTheilSenSlopeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_theil_sen_slope_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(AggregateTheilSenSlopeFunction::try_create))
+}
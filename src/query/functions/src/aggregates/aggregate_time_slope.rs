@@ -0,0 +1,211 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::fmt;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::Float64Type;
+use databend_common_expression::types::NullableType;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+use databend_common_expression::ScalarRef;
+
+use super::aggregate_covariance::AggregateCovarianceState;
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::StateAddr;
+use crate::aggregates::assert_binary_arguments;
+use crate::aggregates::AggregateFunction;
+
+const MICROS_PER_SECOND: f64 = 1_000_000.0;
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct TimeSlopeState {
+    // The co-moments of the time and the value.
+    time_value: AggregateCovarianceState,
+    // The co-moments of the time with itself, i.e. the sum of the squared deviations.
+    time_time: AggregateCovarianceState,
+}
+
+impl TimeSlopeState {
+    fn add(&mut self, columns: InputColumns, row: usize) {
+        let seconds = match unsafe { AnyType::index_column_unchecked(&columns[0], row) } {
+            ScalarRef::Timestamp(ts) => ts as f64 / MICROS_PER_SECOND,
+            ScalarRef::Date(date) => date as f64 * SECONDS_PER_DAY,
+            ScalarRef::Number(seconds) => seconds.to_f64().0,
+            _ => unreachable!(),
+        };
+        let value = match unsafe { AnyType::index_column_unchecked(&columns[1], row) } {
+            ScalarRef::Number(value) => value.to_f64().0,
+            _ => unreachable!(),
+        };
+        self.time_value.add(seconds, value);
+        self.time_time.add(seconds, seconds);
+    }
+
+    fn merge(&mut self, rhs: &Self) {
+        self.time_value.merge(&rhs.time_value);
+        self.time_time.merge(&rhs.time_time);
+    }
+
+    fn slope(&self) -> Option<f64> {
+        if self.time_time.co_moments == 0.0 {
+            return None;
+        }
+        Some(self.time_value.co_moments / self.time_time.co_moments)
+    }
+}
+
+/// `time_slope(ts, value)` returns the least-squares slope of `value` regressed on `ts`,
+/// i.e. the rate of change of `value` per second.
+///
+/// Timestamps and dates are converted to seconds, a number `ts` is taken as seconds.
+/// Rows whose `ts` or `value` is NULL are skipped, a group whose `ts` doesn't vary,
+/// including a group of less than two rows, returns NULL.
+#[derive(Clone)]
+pub struct AggregateTimeSlopeFunction {
+    display_name: String,
+}
+
+impl AggregateTimeSlopeFunction {
+    pub fn try_create(
+        display_name: &str,
+        _params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<Arc<dyn AggregateFunction>> {
+        assert_binary_arguments(display_name, arguments.len())?;
+        if !arguments[0].is_numeric() && !arguments[0].is_date_or_date_time() {
+            return Err(ErrorCode::BadDataValueType(format!(
+                "{} does not support time type '{:?}'",
+                display_name, arguments[0]
+            )));
+        }
+        if !arguments[1].is_numeric() {
+            return Err(ErrorCode::BadDataValueType(format!(
+                "{} does not support value type '{:?}'",
+                display_name, arguments[1]
+            )));
+        }
+
+        Ok(Arc::new(AggregateTimeSlopeFunction {
+            display_name: display_name.to_string(),
+        }))
+    }
+}
+
+impl AggregateFunction for AggregateTimeSlopeFunction {
+    fn name(&self) -> &str {
+        "AggregateTimeSlopeFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(DataType::Number(NumberDataType::Float64).wrap_nullable())
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        place.write(TimeSlopeState::default);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<TimeSlopeState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let state = place.get::<TimeSlopeState>();
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                state.add(columns, row);
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        for (row, place) in places.iter().enumerate() {
+            let state = place.next(offset).get::<TimeSlopeState>();
+            state.add(columns, row);
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let state = place.get::<TimeSlopeState>();
+        state.add(columns, row);
+        Ok(())
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<TimeSlopeState>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<TimeSlopeState>();
+        let rhs: TimeSlopeState = borsh_deserialize_state(reader)?;
+        state.merge(&rhs);
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<TimeSlopeState>();
+        let other = rhs.get::<TimeSlopeState>();
+        state.merge(other);
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<TimeSlopeState>();
+        let builder = NullableType::<Float64Type>::try_downcast_builder(builder).unwrap();
+        match state.slope() {
+            Some(slope) => builder.push(slope.into()),
+            None => builder.push_null(),
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for AggregateTimeSlopeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_time_slope_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(AggregateTimeSlopeFunction::try_create))
+}
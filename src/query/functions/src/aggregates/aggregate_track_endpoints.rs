@@ -0,0 +1,279 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::cmp::Ordering;
+use std::fmt;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::number::NumberScalar;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::Column;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+use databend_common_expression::ScalarRef;
+
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::StateAddr;
+use crate::aggregates::aggregator_common::assert_arguments;
+use crate::aggregates::AggregateFunction;
+use crate::scalars::geo_dist_init;
+use crate::scalars::sphere_distance_meters;
+
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+struct TrackPoint {
+    ts: Scalar,
+    lon: f64,
+    lat: f64,
+}
+
+impl TrackPoint {
+    fn order(&self, other: &Self) -> Ordering {
+        self.ts
+            .cmp(&other.ts)
+            .then(self.lon.total_cmp(&other.lon))
+            .then(self.lat.total_cmp(&other.lat))
+    }
+
+    fn to_scalar(&self) -> Scalar {
+        Scalar::Tuple(vec![
+            Scalar::Number(NumberScalar::Float64(self.lon.into())),
+            Scalar::Number(NumberScalar::Float64(self.lat.into())),
+        ])
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct TrackEndpointsState {
+    first: Option<TrackPoint>,
+    last: Option<TrackPoint>,
+}
+
+impl TrackEndpointsState {
+    fn add(&mut self, point: TrackPoint) {
+        if self
+            .first
+            .as_ref()
+            .map_or(true, |first| point.order(first).is_lt())
+        {
+            self.first = Some(point.clone());
+        }
+        if self
+            .last
+            .as_ref()
+            .map_or(true, |last| point.order(last).is_gt())
+        {
+            self.last = Some(point);
+        }
+    }
+
+    fn merge(&mut self, rhs: &Self) {
+        if let Some(first) = &rhs.first {
+            self.add(first.clone());
+        }
+        if let Some(last) = &rhs.last {
+            self.add(last.clone());
+        }
+    }
+}
+
+fn to_f64(column: &Column, row: usize) -> f64 {
+    match unsafe { AnyType::index_column_unchecked(column, row) } {
+        ScalarRef::Number(number) => number.to_f64().0,
+        _ => unreachable!(),
+    }
+}
+
+/// `track_endpoints(ts, lon, lat)` returns `((lon, lat), (lon, lat), distance)`, the point
+/// of the earliest `ts`, the point of the latest `ts` and the great circle distance in
+/// meters between them, measured like `great_circle_distance`.
+///
+/// Points with the same `ts` are ordered by `(lon, lat)`, the earliest takes the smallest
+/// one and the latest the greatest one, so the result doesn't depend on the order of the
+/// rows. Rows with a NULL `ts`, `lon` or `lat` are skipped.
+#[derive(Clone)]
+pub struct AggregateTrackEndpointsFunction {
+    display_name: String,
+}
+
+impl AggregateTrackEndpointsFunction {
+    pub fn try_create(
+        display_name: &str,
+        _params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<Arc<dyn AggregateFunction>> {
+        assert_arguments(display_name, arguments.len(), 3)?;
+        if !arguments[0].is_numeric() && !arguments[0].is_date_or_date_time() {
+            return Err(ErrorCode::BadDataValueType(format!(
+                "{} does not support ts type '{:?}'",
+                display_name, arguments[0]
+            )));
+        }
+        for argument in arguments[1..].iter() {
+            if !argument.is_numeric() {
+                return Err(ErrorCode::BadDataValueType(format!(
+                    "The coordinates of aggregate function {} must be numbers, got: {:?}",
+                    display_name, argument
+                )));
+            }
+        }
+
+        geo_dist_init();
+        Ok(Arc::new(AggregateTrackEndpointsFunction {
+            display_name: display_name.to_string(),
+        }))
+    }
+
+    fn add_row(state: &mut TrackEndpointsState, columns: InputColumns, row: usize) {
+        let ts = unsafe { AnyType::index_column_unchecked(&columns[0], row) };
+        state.add(TrackPoint {
+            ts: ts.to_owned(),
+            lon: to_f64(&columns[1], row),
+            lat: to_f64(&columns[2], row),
+        });
+    }
+}
+
+impl AggregateFunction for AggregateTrackEndpointsFunction {
+    fn name(&self) -> &str {
+        "AggregateTrackEndpointsFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        let point = DataType::Tuple(vec![
+            DataType::Number(NumberDataType::Float64),
+            DataType::Number(NumberDataType::Float64),
+        ]);
+        Ok(DataType::Tuple(vec![
+            point.clone(),
+            point,
+            DataType::Number(NumberDataType::Float64),
+        ]))
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        place.write(TrackEndpointsState::default);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<TrackEndpointsState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let state = place.get::<TrackEndpointsState>();
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                Self::add_row(state, columns, row);
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        for (row, place) in places.iter().enumerate() {
+            let state = place.next(offset).get::<TrackEndpointsState>();
+            Self::add_row(state, columns, row);
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let state = place.get::<TrackEndpointsState>();
+        Self::add_row(state, columns, row);
+        Ok(())
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<TrackEndpointsState>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<TrackEndpointsState>();
+        let rhs: TrackEndpointsState = borsh_deserialize_state(reader)?;
+        state.merge(&rhs);
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<TrackEndpointsState>();
+        let other = rhs.get::<TrackEndpointsState>();
+        state.merge(other);
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<TrackEndpointsState>();
+        match (&state.first, &state.last) {
+            (Some(first), Some(last)) => {
+                let distance = sphere_distance_meters(
+                    first.lon as f32,
+                    first.lat as f32,
+                    last.lon as f32,
+                    last.lat as f32,
+                );
+                let endpoints = Scalar::Tuple(vec![
+                    first.to_scalar(),
+                    last.to_scalar(),
+                    Scalar::Number(NumberScalar::Float64((distance as f64).into())),
+                ]);
+                builder.push(endpoints.as_ref());
+            }
+            _ => builder.push_default(),
+        }
+        Ok(())
+    }
+
+    fn need_manual_drop_state(&self) -> bool {
+        true
+    }
+
+    unsafe fn drop_state(&self, place: StateAddr) {
+        let state = place.get::<TrackEndpointsState>();
+        std::ptr::drop_in_place(state);
+    }
+}
+
+impl fmt::Display for AggregateTrackEndpointsFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_track_endpoints_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(AggregateTrackEndpointsFunction::try_create))
+}
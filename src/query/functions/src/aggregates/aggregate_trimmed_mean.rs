@@ -0,0 +1,197 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::any::Any;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::type_check::check_number;
+use databend_common_expression::types::number::Number;
+use databend_common_expression::types::number::F64;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::Float64Type;
+use databend_common_expression::types::NullableColumnBuilder;
+use databend_common_expression::types::NullableType;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::NumberType;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::with_number_mapped_type;
+use databend_common_expression::Expr;
+use databend_common_expression::FunctionContext;
+use databend_common_expression::Scalar;
+use num_traits::AsPrimitive;
+
+use super::AggregateUnaryFunction;
+use super::FunctionData;
+use super::UnaryState;
+use crate::aggregates::aggregate_function_factory::AggregateFunctionDescription;
+use crate::aggregates::assert_params;
+use crate::aggregates::assert_unary_arguments;
+use crate::aggregates::AggregateFunctionRef;
+use crate::BUILTIN_FUNCTIONS;
+
+struct TrimmedMeanData {
+    lower: f64,
+    upper: f64,
+}
+
+impl FunctionData for TrimmedMeanData {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct TrimmedMeanState {
+    values: Vec<F64>,
+}
+
+impl TrimmedMeanState {
+    fn trimmed_mean(&mut self, lower: f64, upper: f64) -> Option<f64> {
+        if self.values.is_empty() {
+            return None;
+        }
+        self.values.sort_unstable();
+        let lower = percentile(&self.values, lower);
+        let upper = percentile(&self.values, upper);
+
+        let (sum, count) = self
+            .values
+            .iter()
+            .map(|value| value.0)
+            .filter(|value| (lower..=upper).contains(value))
+            .fold((0.0, 0), |(sum, count), value| (sum + value, count + 1));
+        if count == 0 {
+            return None;
+        }
+        Some(sum / count as f64)
+    }
+}
+
+// Interpolated between the closest values like `quantile_cont`.
+pub(super) fn percentile(sorted: &[F64], level: f64) -> f64 {
+    let (frac, whole) = libm::modf((sorted.len() - 1) as f64 * level);
+    let whole = whole as usize;
+    let value = sorted[whole].0;
+    let value1 = sorted.get(whole + 1).map(|v| v.0).unwrap_or(value);
+    value + (value1 - value) * frac
+}
+
+impl<T> UnaryState<T, NullableType<Float64Type>> for TrimmedMeanState
+where
+    T: ValueType,
+    T::Scalar: Number + AsPrimitive<f64>,
+{
+    fn add(
+        &mut self,
+        other: T::ScalarRef<'_>,
+        _function_data: Option<&dyn FunctionData>,
+    ) -> Result<()> {
+        let value: f64 = T::to_owned_scalar(other).as_();
+        self.values.push(value.into());
+        Ok(())
+    }
+
+    fn merge(&mut self, rhs: &Self) -> Result<()> {
+        self.values.extend_from_slice(&rhs.values);
+        Ok(())
+    }
+
+    fn merge_result(
+        &mut self,
+        builder: &mut NullableColumnBuilder<Float64Type>,
+        function_data: Option<&dyn FunctionData>,
+    ) -> Result<()> {
+        let trimmed_mean_data = unsafe {
+            function_data
+                .unwrap()
+                .as_any()
+                .downcast_ref_unchecked::<TrimmedMeanData>()
+        };
+        match self.trimmed_mean(trimmed_mean_data.lower, trimmed_mean_data.upper) {
+            Some(mean) => builder.push(mean.into()),
+            None => builder.push_null(),
+        }
+        Ok(())
+    }
+}
+
+pub(super) fn get_bounds(display_name: &str, params: &[Scalar]) -> Result<(f64, f64)> {
+    assert_params(display_name, params.len(), 2)?;
+    let mut bounds = Vec::with_capacity(2);
+    for param in params {
+        let bound: F64 = check_number(
+            None,
+            &FunctionContext::default(),
+            &Expr::<usize>::Constant {
+                span: None,
+                scalar: param.clone(),
+                data_type: param.as_ref().infer_data_type(),
+            },
+            &BUILTIN_FUNCTIONS,
+        )?;
+        bounds.push(bound.0);
+    }
+    let (lower, upper) = (bounds[0], bounds[1]);
+    if !(0.0 <= lower && lower < upper && upper <= 1.0) {
+        return Err(ErrorCode::BadArguments(format!(
+            "{} expects 0 <= lower < upper <= 1, but got lower {} and upper {}",
+            display_name, lower, upper
+        )));
+    }
+    Ok((lower, upper))
+}
+
+/// `trimmed_mean(lower, upper)(x)` returns the mean of the values of `x` between the
+/// `lower` and the `upper` percentiles, both included. The percentiles are interpolated
+/// like `quantile_cont`, so `trimmed_mean(0, 1)` is the mean of all the values.
+///
+/// All the values of a group are buffered and sorted to find the percentiles. NULL values
+/// are ignored, a group without values, or without values between the percentiles, returns
+/// NULL.
+pub fn try_create_aggregate_trimmed_mean_function(
+    display_name: &str,
+    params: Vec<Scalar>,
+    arguments: Vec<DataType>,
+) -> Result<AggregateFunctionRef> {
+    assert_unary_arguments(display_name, arguments.len())?;
+    let (lower, upper) = get_bounds(display_name, &params)?;
+    let return_type = DataType::Number(NumberDataType::Float64).wrap_nullable();
+
+    with_number_mapped_type!(|NUM_TYPE| match &arguments[0] {
+        DataType::Number(NumberDataType::NUM_TYPE) => {
+            let func = AggregateUnaryFunction::<
+                TrimmedMeanState,
+                NumberType<NUM_TYPE>,
+                NullableType<Float64Type>,
+            >::try_create(
+                display_name, return_type, params, arguments[0].clone()
+            )
+            .with_function_data(Box::new(TrimmedMeanData { lower, upper }))
+            .with_need_drop(true);
+            Ok(Arc::new(func))
+        }
+        _ => Err(ErrorCode::BadDataValueType(format!(
+            "{} does not support type '{:?}'",
+            display_name, arguments[0]
+        ))),
+    })
+}
+
+pub fn aggregate_trimmed_mean_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(try_create_aggregate_trimmed_mean_function))
+}
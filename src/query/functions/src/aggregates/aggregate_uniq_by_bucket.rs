@@ -0,0 +1,222 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::number::NumberScalar;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+use simple_hll::HyperLogLog;
+
+use super::aggregate_distinct_time_buckets::get_bucket_micros;
+use super::aggregate_distinct_time_buckets::timestamp_micros;
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::StateAddr;
+use crate::aggregates::assert_arguments;
+use crate::aggregates::assert_params;
+use crate::aggregates::AggregateFunction;
+
+/// Lower than the default of `approx_count_distinct`, as there is a sketch per bucket.
+const UNIQ_BY_BUCKET_HLL_P: usize = 12;
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct UniqByBucketState {
+    // The sketch of the values of each bucket, by the start of the bucket in microseconds.
+    buckets: BTreeMap<i64, HyperLogLog<UNIQ_BY_BUCKET_HLL_P>>,
+}
+
+impl UniqByBucketState {
+    fn add(&mut self, columns: InputColumns, row: usize, bucket_micros: i64) {
+        let micros = timestamp_micros(unsafe { AnyType::index_column_unchecked(&columns[0], row) });
+        let bucket = micros.div_euclid(bucket_micros) * bucket_micros;
+        let value = unsafe { AnyType::index_column_unchecked(&columns[2], row) };
+        self.buckets.entry(bucket).or_default().add_object(&value);
+    }
+
+    fn merge(&mut self, rhs: &Self) {
+        for (bucket, sketch) in rhs.buckets.iter() {
+            self.buckets.entry(*bucket).or_default().merge(sketch);
+        }
+    }
+}
+
+/// `uniq_by_bucket(ts, granularity, value)` returns a map of the start of each day, hour
+/// or minute touched by the timestamps of the group to the approximate number of distinct
+/// `value` in it, for a granularity of `'day'`, `'hour'` or `'minute'`. The timestamps are
+/// truncated in UTC like in `distinct_time_buckets`.
+///
+/// Each bucket has its own HyperLogLog of 4096 registers, so the state takes about 4 KiB
+/// per bucket and grows with the number of buckets of the group, a fine granularity over a
+/// long time range takes a lot of memory. The sketches of a bucket are merged across the
+/// partial states, so the counts are the same as with a single pass.
+///
+/// The granularity must be a constant, it is taken as a parameter of the function.
+/// Rows whose `ts` or `value` is NULL are skipped.
+#[derive(Clone)]
+pub struct AggregateUniqByBucketFunction {
+    display_name: String,
+    bucket_micros: i64,
+}
+
+impl AggregateUniqByBucketFunction {
+    pub fn try_create(
+        display_name: &str,
+        params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<Arc<dyn AggregateFunction>> {
+        assert_arguments(display_name, arguments.len(), 3)?;
+        assert_params(display_name, params.len(), 1)?;
+
+        if !arguments[0].is_date_or_date_time() {
+            return Err(ErrorCode::BadDataValueType(format!(
+                "{} does not support type '{:?}'",
+                display_name, arguments[0]
+            )));
+        }
+        let bucket_micros = get_bucket_micros(display_name, &params[0])?;
+
+        Ok(Arc::new(AggregateUniqByBucketFunction {
+            display_name: display_name.to_string(),
+            bucket_micros,
+        }))
+    }
+}
+
+impl AggregateFunction for AggregateUniqByBucketFunction {
+    fn name(&self) -> &str {
+        "AggregateUniqByBucketFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(DataType::Map(Box::new(DataType::Tuple(vec![
+            DataType::Timestamp,
+            DataType::Number(NumberDataType::UInt64),
+        ]))))
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        place.write(UniqByBucketState::default);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<UniqByBucketState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let state = place.get::<UniqByBucketState>();
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                state.add(columns, row, self.bucket_micros);
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        for (row, place) in places.iter().enumerate() {
+            let state = place.next(offset).get::<UniqByBucketState>();
+            state.add(columns, row, self.bucket_micros);
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let state = place.get::<UniqByBucketState>();
+        state.add(columns, row, self.bucket_micros);
+        Ok(())
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<UniqByBucketState>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<UniqByBucketState>();
+        let rhs: UniqByBucketState = borsh_deserialize_state(reader)?;
+        state.merge(&rhs);
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<UniqByBucketState>();
+        let other = rhs.get::<UniqByBucketState>();
+        state.merge(other);
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<UniqByBucketState>();
+        match builder {
+            ColumnBuilder::Map(box inner) => {
+                for (bucket, sketch) in state.buckets.iter() {
+                    let pair = Scalar::Tuple(vec![
+                        Scalar::Timestamp(*bucket),
+                        Scalar::Number(NumberScalar::UInt64(sketch.count() as u64)),
+                    ]);
+                    inner.builder.push(pair.as_ref());
+                }
+                inner.commit_row();
+            }
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+
+    fn need_manual_drop_state(&self) -> bool {
+        true
+    }
+
+    unsafe fn drop_state(&self, place: StateAddr) {
+        let state = place.get::<UniqByBucketState>();
+        std::ptr::drop_in_place(state);
+    }
+}
+
+impl fmt::Display for AggregateUniqByBucketFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_uniq_by_bucket_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(AggregateUniqByBucketFunction::try_create))
+}
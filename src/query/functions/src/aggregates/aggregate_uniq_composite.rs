@@ -0,0 +1,217 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::fmt;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::sync::Arc;
+
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::Result;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::UInt64Type;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+use databend_common_expression::ScalarRef;
+use simple_hll::HyperLogLog;
+
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::StateAddr;
+use crate::aggregates::aggregator_common::assert_variadic_arguments;
+use crate::aggregates::AggregateFunction;
+use crate::aggregates::AggregateFunctionRef;
+
+/// The same precision as the default of `approx_count_distinct`.
+const UNIQ_COMPOSITE_HLL_P: usize = 14;
+
+type UniqCompositeState = HyperLogLog<UNIQ_COMPOSITE_HLL_P>;
+
+/// The key of a row, hashed component by component.
+///
+/// `ScalarRef::Null` hashes nothing, so every component is prefixed with whether it
+/// is NULL, then `(1, NULL)` and `(NULL, 1)` are different keys.
+struct CompositeKey<'a>(Vec<ScalarRef<'a>>);
+
+impl Hash for CompositeKey<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for value in self.0.iter() {
+            match value {
+                ScalarRef::Null => false.hash(state),
+                value => {
+                    true.hash(state);
+                    value.hash(state);
+                }
+            }
+        }
+    }
+}
+
+/// `uniq_combined(a, b, ...)` estimates the number of distinct tuples of its arguments
+/// with a single HyperLogLog of the combined keys, so the state has a fixed size, where
+/// `uniq(a, b, ...)` keeps an exact set.
+///
+/// Rows with some NULL components are counted, NULL is a value of the key, but rows
+/// where every component is NULL are skipped, as `uniq(a)` skips NULL.
+#[derive(Clone)]
+pub struct AggregateUniqCompositeFunction {
+    display_name: String,
+}
+
+impl AggregateUniqCompositeFunction {
+    pub fn try_create(
+        display_name: &str,
+        _params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<AggregateFunctionRef> {
+        assert_variadic_arguments(display_name, arguments.len(), (1, 32))?;
+        Ok(Arc::new(AggregateUniqCompositeFunction {
+            display_name: display_name.to_string(),
+        }))
+    }
+
+    fn add_row(state: &mut UniqCompositeState, columns: InputColumns, row: usize) {
+        let key: Vec<ScalarRef> = columns
+            .iter()
+            .map(|column| unsafe { AnyType::index_column_unchecked(column, row) })
+            .collect();
+        if key.iter().all(|value| matches!(value, ScalarRef::Null)) {
+            return;
+        }
+        state.add_object(&CompositeKey(key));
+    }
+}
+
+impl AggregateFunction for AggregateUniqCompositeFunction {
+    fn name(&self) -> &str {
+        "AggregateUniqCompositeFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(DataType::Number(NumberDataType::UInt64))
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        place.write(UniqCompositeState::default);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<UniqCompositeState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let state = place.get::<UniqCompositeState>();
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                Self::add_row(state, columns, row);
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        for (row, place) in places.iter().enumerate() {
+            let state = place.next(offset).get::<UniqCompositeState>();
+            Self::add_row(state, columns, row);
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let state = place.get::<UniqCompositeState>();
+        Self::add_row(state, columns, row);
+        Ok(())
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<UniqCompositeState>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<UniqCompositeState>();
+        let rhs: UniqCompositeState = borsh_deserialize_state(reader)?;
+        state.merge(&rhs);
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<UniqCompositeState>();
+        let other = rhs.get::<UniqCompositeState>();
+        state.merge(other);
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<UniqCompositeState>();
+        let builder = UInt64Type::try_downcast_builder(builder).unwrap();
+        builder.push(state.count() as u64);
+        Ok(())
+    }
+
+    fn need_manual_drop_state(&self) -> bool {
+        true
+    }
+
+    unsafe fn drop_state(&self, place: StateAddr) {
+        let state = place.get::<UniqCompositeState>();
+        std::ptr::drop_in_place(state);
+    }
+
+    // The NULL components are a part of the key, the rows must not be filtered out
+    // by the Null combinator, only the rows where every component is NULL are skipped.
+    fn get_own_null_adaptor(
+        &self,
+        _nested_function: AggregateFunctionRef,
+        _params: Vec<Scalar>,
+        _arguments: Vec<DataType>,
+    ) -> Result<Option<AggregateFunctionRef>> {
+        Ok(Some(Arc::new(self.clone())))
+    }
+}
+
+impl fmt::Display for AggregateUniqCompositeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_uniq_combined_function_desc() -> AggregateFunctionDescription {
+    let features = super::aggregate_function_factory::AggregateFunctionFeatures {
+        returns_default_when_only_null: true,
+        ..Default::default()
+    };
+    AggregateFunctionDescription::creator_with_features(
+        Box::new(AggregateUniqCompositeFunction::try_create),
+        features,
+    )
+}
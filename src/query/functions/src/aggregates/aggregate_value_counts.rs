@@ -0,0 +1,242 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::number::NumberScalar;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+use databend_common_expression::ScalarRef;
+
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::StateAddr;
+use crate::aggregates::assert_unary_arguments;
+use crate::aggregates::AggregateFunction;
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct ValueCountsState {
+    counts: HashMap<Scalar, u64>,
+    nulls: u64,
+}
+
+impl ValueCountsState {
+    fn add(&mut self, value: ScalarRef) {
+        match value {
+            ScalarRef::Null => self.nulls += 1,
+            value => *self.counts.entry(value.to_owned()).or_insert(0) += 1,
+        }
+    }
+
+    fn merge(&mut self, rhs: &Self) {
+        for (value, count) in rhs.counts.iter() {
+            *self.counts.entry(value.clone()).or_insert(0) += count;
+        }
+        self.nulls += rhs.nulls;
+    }
+}
+
+/// `value_counts(value)` returns a map of each distinct `value` of the group to the
+/// number of rows holding it. NULLs are skipped.
+///
+/// `value_counts_with_nulls(value)` counts the NULLs as well, under a NULL key placed
+/// after the other keys. The keys of the map are sorted.
+#[derive(Clone)]
+pub struct AggregateValueCountsFunction<const COUNT_NULLS: bool> {
+    display_name: String,
+    return_type: DataType,
+}
+
+impl<const COUNT_NULLS: bool> AggregateValueCountsFunction<COUNT_NULLS> {
+    pub fn try_create(
+        display_name: &str,
+        _params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<Arc<dyn AggregateFunction>> {
+        assert_unary_arguments(display_name, arguments.len())?;
+
+        let key_type = arguments[0].remove_nullable();
+        if !key_type.is_boolean()
+            && !key_type.is_string()
+            && !key_type.is_numeric()
+            && !key_type.is_decimal()
+            && !key_type.is_date_or_date_time()
+        {
+            return Err(ErrorCode::BadDataValueType(format!(
+                "{} does not support type '{:?}'",
+                display_name, arguments[0]
+            )));
+        }
+
+        let key_type = if COUNT_NULLS {
+            key_type.wrap_nullable()
+        } else {
+            key_type
+        };
+        let return_type = DataType::Map(Box::new(DataType::Tuple(vec![
+            key_type,
+            DataType::Number(NumberDataType::UInt64),
+        ])));
+        Ok(Arc::new(AggregateValueCountsFunction {
+            display_name: display_name.to_string(),
+            return_type,
+        }))
+    }
+
+    fn add_row(state: &mut ValueCountsState, columns: InputColumns, row: usize) {
+        let value = unsafe { AnyType::index_column_unchecked(&columns[0], row) };
+        if COUNT_NULLS || !matches!(value, ScalarRef::Null) {
+            state.add(value);
+        }
+    }
+}
+
+impl<const COUNT_NULLS: bool> AggregateFunction for AggregateValueCountsFunction<COUNT_NULLS> {
+    fn name(&self) -> &str {
+        "AggregateValueCountsFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        Ok(self.return_type.clone())
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        place.write(ValueCountsState::default);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<ValueCountsState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let state = place.get::<ValueCountsState>();
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                Self::add_row(state, columns, row);
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        for (row, place) in places.iter().enumerate() {
+            let state = place.next(offset).get::<ValueCountsState>();
+            Self::add_row(state, columns, row);
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let state = place.get::<ValueCountsState>();
+        Self::add_row(state, columns, row);
+        Ok(())
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<ValueCountsState>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<ValueCountsState>();
+        let rhs: ValueCountsState = borsh_deserialize_state(reader)?;
+        state.merge(&rhs);
+        Ok(())
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<ValueCountsState>();
+        let other = rhs.get::<ValueCountsState>();
+        state.merge(other);
+        Ok(())
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<ValueCountsState>();
+        let mut entries = state
+            .counts
+            .iter()
+            .map(|(value, count)| (value.clone(), *count))
+            .collect::<Vec<_>>();
+        entries.sort();
+        if state.nulls > 0 {
+            entries.push((Scalar::Null, state.nulls));
+        }
+        match builder {
+            ColumnBuilder::Map(box inner) => {
+                for (value, count) in entries {
+                    let pair =
+                        Scalar::Tuple(vec![value, Scalar::Number(NumberScalar::UInt64(count))]);
+                    inner.builder.push(pair.as_ref());
+                }
+                inner.commit_row();
+            }
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+
+    fn need_manual_drop_state(&self) -> bool {
+        true
+    }
+
+    unsafe fn drop_state(&self, place: StateAddr) {
+        let state = place.get::<ValueCountsState>();
+        std::ptr::drop_in_place(state);
+    }
+}
+
+impl<const COUNT_NULLS: bool> fmt::Display for AggregateValueCountsFunction<COUNT_NULLS> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_value_counts_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(
+        AggregateValueCountsFunction::<false>::try_create,
+    ))
+}
+
+pub fn aggregate_value_counts_with_nulls_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(
+        AggregateValueCountsFunction::<true>::try_create,
+    ))
+}
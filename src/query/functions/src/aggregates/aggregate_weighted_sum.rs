@@ -0,0 +1,249 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::alloc::Layout;
+use std::fmt;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use databend_common_arrow::arrow::bitmap::Bitmap;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_expression::types::decimal::Decimal;
+use databend_common_expression::types::decimal::Decimal128Type;
+use databend_common_expression::types::decimal::DecimalDataType;
+use databend_common_expression::types::decimal::DecimalSize;
+use databend_common_expression::types::decimal::MAX_DECIMAL128_PRECISION;
+use databend_common_expression::types::AnyType;
+use databend_common_expression::types::DataType;
+use databend_common_expression::types::Float64Type;
+use databend_common_expression::types::NullableType;
+use databend_common_expression::types::NumberDataType;
+use databend_common_expression::types::ValueType;
+use databend_common_expression::ColumnBuilder;
+use databend_common_expression::InputColumns;
+use databend_common_expression::Scalar;
+use databend_common_expression::ScalarRef;
+
+use super::aggregate_function_factory::AggregateFunctionDescription;
+use super::borsh_deserialize_state;
+use super::borsh_serialize_state;
+use super::StateAddr;
+use crate::aggregates::assert_binary_arguments;
+use crate::aggregates::AggregateFunction;
+
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+struct WeightedSumState {
+    // The exact sum of the products of integer arguments, kept in the range of a
+    // Decimal(38, 0).
+    int_sum: i128,
+    float_sum: f64,
+    count: u64,
+}
+
+impl WeightedSumState {
+    fn add_int(&mut self, value: i128, weight: i128) -> Result<()> {
+        // Only the product of two large UInt64 doesn't fit in an i128.
+        self.add_int_sum(value.checked_mul(weight), 1)
+    }
+
+    fn add_float(&mut self, value: f64, weight: f64) {
+        self.float_sum += value * weight;
+        self.count += 1;
+    }
+
+    fn add_int_sum(&mut self, sum: Option<i128>, count: u64) -> Result<()> {
+        self.int_sum = sum
+            .and_then(|sum| self.int_sum.checked_add(sum))
+            .filter(|sum| (<i128 as Decimal>::MIN..=<i128 as Decimal>::MAX).contains(sum))
+            .ok_or_else(|| {
+                ErrorCode::Overflow(format!(
+                    "Decimal overflow: weighted_sum not in [{}, {}]",
+                    <i128 as Decimal>::MIN,
+                    <i128 as Decimal>::MAX,
+                ))
+            })?;
+        self.count += count;
+        Ok(())
+    }
+
+    fn merge(&mut self, rhs: &Self) -> Result<()> {
+        self.float_sum += rhs.float_sum;
+        self.add_int_sum(Some(rhs.int_sum), rhs.count)
+    }
+}
+
+/// `weighted_sum(value, weight)` returns the sum of `value * weight` over the group.
+///
+/// With integer arguments each product is computed exactly in an i128 and the sum is
+/// returned as a `Decimal(38, 0)`, so that it doesn't overflow where `sum(value * weight)`
+/// on 64 bits integers would, a sum beyond the range of the decimal is an error. With a
+/// float argument the sum is a Float64. Rows whose `value` or `weight` is NULL are
+/// skipped, a group without any row returns NULL.
+#[derive(Clone)]
+pub struct AggregateWeightedSumFunction {
+    display_name: String,
+    is_float: bool,
+}
+
+impl AggregateWeightedSumFunction {
+    pub fn try_create(
+        display_name: &str,
+        _params: Vec<Scalar>,
+        arguments: Vec<DataType>,
+    ) -> Result<Arc<dyn AggregateFunction>> {
+        assert_binary_arguments(display_name, arguments.len())?;
+        for argument in arguments.iter() {
+            if !argument.is_numeric() {
+                return Err(ErrorCode::BadDataValueType(format!(
+                    "The arguments of aggregate function {} must be numbers, got: {:?}",
+                    display_name, argument
+                )));
+            }
+        }
+
+        Ok(Arc::new(AggregateWeightedSumFunction {
+            display_name: display_name.to_string(),
+            is_float: arguments.iter().any(|argument| argument.is_floating()),
+        }))
+    }
+
+    fn add_row(
+        &self,
+        state: &mut WeightedSumState,
+        columns: InputColumns,
+        row: usize,
+    ) -> Result<()> {
+        let value = unsafe { AnyType::index_column_unchecked(&columns[0], row) };
+        let weight = unsafe { AnyType::index_column_unchecked(&columns[1], row) };
+        match (value, weight) {
+            (ScalarRef::Number(value), ScalarRef::Number(weight)) if self.is_float => {
+                state.add_float(value.to_f64().0, weight.to_f64().0);
+                Ok(())
+            }
+            (ScalarRef::Number(value), ScalarRef::Number(weight)) => state.add_int(
+                value.integer_to_i128().unwrap(),
+                weight.integer_to_i128().unwrap(),
+            ),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl AggregateFunction for AggregateWeightedSumFunction {
+    fn name(&self) -> &str {
+        "AggregateWeightedSumFunction"
+    }
+
+    fn return_type(&self) -> Result<DataType> {
+        let data_type = if self.is_float {
+            DataType::Number(NumberDataType::Float64)
+        } else {
+            DataType::Decimal(DecimalDataType::Decimal128(DecimalSize {
+                precision: MAX_DECIMAL128_PRECISION,
+                scale: 0,
+            }))
+        };
+        Ok(data_type.wrap_nullable())
+    }
+
+    fn init_state(&self, place: StateAddr) {
+        place.write(WeightedSumState::default);
+    }
+
+    fn state_layout(&self) -> Layout {
+        Layout::new::<WeightedSumState>()
+    }
+
+    fn accumulate(
+        &self,
+        place: StateAddr,
+        columns: InputColumns,
+        validity: Option<&Bitmap>,
+        input_rows: usize,
+    ) -> Result<()> {
+        let state = place.get::<WeightedSumState>();
+        for row in 0..input_rows {
+            if validity.map(|v| v.get_bit(row)).unwrap_or(true) {
+                self.add_row(state, columns, row)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn accumulate_keys(
+        &self,
+        places: &[StateAddr],
+        offset: usize,
+        columns: InputColumns,
+        _input_rows: usize,
+    ) -> Result<()> {
+        for (row, place) in places.iter().enumerate() {
+            let state = place.next(offset).get::<WeightedSumState>();
+            self.add_row(state, columns, row)?;
+        }
+        Ok(())
+    }
+
+    fn accumulate_row(&self, place: StateAddr, columns: InputColumns, row: usize) -> Result<()> {
+        let state = place.get::<WeightedSumState>();
+        self.add_row(state, columns, row)
+    }
+
+    fn serialize(&self, place: StateAddr, writer: &mut Vec<u8>) -> Result<()> {
+        let state = place.get::<WeightedSumState>();
+        borsh_serialize_state(writer, state)
+    }
+
+    fn merge(&self, place: StateAddr, reader: &mut &[u8]) -> Result<()> {
+        let state = place.get::<WeightedSumState>();
+        let rhs: WeightedSumState = borsh_deserialize_state(reader)?;
+        state.merge(&rhs)
+    }
+
+    fn merge_states(&self, place: StateAddr, rhs: StateAddr) -> Result<()> {
+        let state = place.get::<WeightedSumState>();
+        let other = rhs.get::<WeightedSumState>();
+        state.merge(other)
+    }
+
+    fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
+        let state = place.get::<WeightedSumState>();
+        if self.is_float {
+            let builder = NullableType::<Float64Type>::try_downcast_builder(builder).unwrap();
+            match state.count {
+                0 => builder.push_null(),
+                _ => builder.push(state.float_sum.into()),
+            }
+        } else {
+            let builder = NullableType::<Decimal128Type>::try_downcast_builder(builder).unwrap();
+            match state.count {
+                0 => builder.push_null(),
+                _ => builder.push(state.int_sum),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for AggregateWeightedSumFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+pub fn aggregate_weighted_sum_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(AggregateWeightedSumFunction::try_create))
+}
@@ -26,6 +26,7 @@ use databend_common_exception::ErrorCode;
 use databend_common_exception::Result;
 use databend_common_expression::type_check::check_number;
 use databend_common_expression::types::number::Number;
+use databend_common_expression::types::number::NumberScalar;
 use databend_common_expression::types::number::UInt8Type;
 use databend_common_expression::types::ArgType;
 use databend_common_expression::types::BooleanType;
@@ -41,6 +42,7 @@ use databend_common_expression::Expr;
 use databend_common_expression::FunctionContext;
 use databend_common_expression::InputColumns;
 use databend_common_expression::Scalar;
+use databend_common_expression::ScalarRef;
 use num_traits::AsPrimitive;
 
 use super::borsh_deserialize_state;
@@ -148,12 +150,23 @@ where T: Ord
     }
 }
 
+/// The maximum distances between the events of a funnel path.
+#[derive(Clone)]
+enum FunnelWindow {
+    /// `window_funnel(window)`, from the first event of the path to each of the others.
+    Total(u64),
+    /// `window_funnel([window1, ..., windowN-1])`, from each event of the path to the next one.
+    Steps(Vec<u64>),
+}
+
 #[derive(Clone)]
 pub struct AggregateWindowFunnelFunction<T> {
     display_name: String,
     _arguments: Vec<DataType>,
     event_size: usize,
-    window: u64,
+    window: FunnelWindow,
+    // Returns the time to complete the funnel instead of the event level.
+    completion_time: bool,
     t: PhantomData<T>,
 }
 
@@ -174,7 +187,11 @@ where
     }
 
     fn return_type(&self) -> Result<DataType> {
-        Ok(DataType::Number(NumberDataType::UInt8))
+        if self.completion_time {
+            Ok(DataType::Number(NumberDataType::UInt64).wrap_nullable())
+        } else {
+            Ok(DataType::Number(NumberDataType::UInt8))
+        }
     }
 
     fn init_state(&self, place: StateAddr) {
@@ -295,9 +312,16 @@ where
 
     #[allow(unused_mut)]
     fn merge_result(&self, place: StateAddr, builder: &mut ColumnBuilder) -> Result<()> {
-        let builder = UInt8Type::try_downcast_builder(builder).unwrap();
-        let result = self.get_event_level(place);
-        builder.push(result);
+        let (level, duration) = self.get_event_level(place);
+        if self.completion_time {
+            match duration {
+                Some(duration) => builder.push(ScalarRef::Number(NumberScalar::UInt64(duration))),
+                None => builder.push_default(),
+            }
+        } else {
+            let builder = UInt8Type::try_downcast_builder(builder).unwrap();
+            builder.push(level);
+        }
         Ok(())
     }
 
@@ -344,72 +368,112 @@ where
         display_name: &str,
         params: Vec<Scalar>,
         arguments: Vec<DataType>,
+        completion_time: bool,
     ) -> Result<AggregateFunctionRef> {
         let event_size = arguments.len() - 1;
-        let window = check_number::<_, u64>(
-            None,
-            &FunctionContext::default(),
-            &Expr::<usize>::Constant {
-                span: None,
-                scalar: params[0].clone(),
-                data_type: params[0].as_ref().infer_data_type(),
-            },
-            &BUILTIN_FUNCTIONS,
-        )?;
+        let window = match &params[0] {
+            Scalar::Array(windows) => {
+                if windows.len() + 1 != event_size {
+                    return Err(ErrorCode::BadArguments(format!(
+                        "{} expects {} windows, one for each gap between the events, but got {}",
+                        display_name,
+                        event_size.saturating_sub(1),
+                        windows.len()
+                    )));
+                }
+                let windows = windows
+                    .iter()
+                    .map(|window| get_window(window.to_owned()))
+                    .collect::<Result<Vec<_>>>()?;
+                FunnelWindow::Steps(windows)
+            }
+            window => FunnelWindow::Total(get_window(window.clone())?),
+        };
 
         Ok(Arc::new(Self {
             display_name: display_name.to_owned(),
             _arguments: arguments,
             event_size,
             window,
+            completion_time,
             t: PhantomData,
         }))
     }
 
     /// Loop through the entire events_list, update the event timestamp value
     /// The level path must be 1---2---3---...---check_events_size, find the max event level that satisfied the path in the sliding window.
+    /// With per-step windows, each event of the path must be within its window of the previous one instead.
     /// If found, returns the max event level, else return 0.
+    /// Along with the level, returns the shortest duration between the first and the last event
+    /// of the paths that reach the last level, or None if the funnel is not fully converted.
     /// The Algorithm complexity is O(n).
-    fn get_event_level(&self, place: StateAddr) -> u8 {
+    fn get_event_level(&self, place: StateAddr) -> (u8, Option<u64>) {
         let state = place.get::<AggregateWindowFunnelState<T::Scalar>>();
         if state.events_list.is_empty() {
-            return 0;
+            return (0, None);
         }
         if self.event_size == 1 {
-            return 1;
+            return (1, Some(0));
         }
 
         state.sort();
 
-        let mut events_timestamp: Vec<Option<T::Scalar>> = Vec::with_capacity(self.event_size);
+        // The start of the path and the time of the event, of each level.
+        let mut events_timestamp: Vec<Option<(T::Scalar, T::Scalar)>> =
+            Vec::with_capacity(self.event_size);
         for _i in 0..self.event_size {
             events_timestamp.push(None);
         }
+        let mut duration: Option<u64> = None;
         for (timestamp, event) in state.events_list.iter() {
             let event_idx = (event - 1) as usize;
 
             if event_idx == 0 {
-                events_timestamp[event_idx] = Some(timestamp.to_owned());
-            } else if let Some(v) = events_timestamp[event_idx - 1] {
+                events_timestamp[event_idx] = Some((timestamp.to_owned(), timestamp.to_owned()));
+            } else if let Some((start, last)) = events_timestamp[event_idx - 1] {
                 // we already sort the events_list
-                let window: u64 = timestamp.sub(v).as_();
-                if window <= self.window {
-                    events_timestamp[event_idx] = events_timestamp[event_idx - 1];
+                let window: u64 = timestamp.sub(start).as_();
+                let within = match &self.window {
+                    FunnelWindow::Total(max_window) => window <= *max_window,
+                    FunnelWindow::Steps(max_windows) => {
+                        let gap: u64 = timestamp.sub(last).as_();
+                        gap <= max_windows[event_idx - 1]
+                    }
+                };
+                if within {
+                    events_timestamp[event_idx] = Some((start, timestamp.to_owned()));
+                    // the timestamp of a level is the start of its path
+                    if event_idx + 1 == self.event_size {
+                        duration = Some(duration.map_or(window, |d| d.min(window)));
+                    }
                 }
             }
         }
 
         for i in (0..self.event_size).rev() {
             if events_timestamp[i].is_some() {
-                return i as u8 + 1;
+                return (i as u8 + 1, duration);
             }
         }
 
-        0
+        (0, None)
     }
 }
 
-pub fn try_create_aggregate_window_funnel_function(
+fn get_window(window: Scalar) -> Result<u64> {
+    check_number::<_, u64>(
+        None,
+        &FunctionContext::default(),
+        &Expr::<usize>::Constant {
+            span: None,
+            data_type: window.as_ref().infer_data_type(),
+            scalar: window,
+        },
+        &BUILTIN_FUNCTIONS,
+    )
+}
+
+pub fn try_create_aggregate_window_funnel_function<const COMPLETION_TIME: bool>(
     display_name: &str,
     params: Vec<Scalar>,
     arguments: Vec<DataType>,
@@ -428,17 +492,24 @@ pub fn try_create_aggregate_window_funnel_function(
     }
 
     with_integer_mapped_type!(|NUM_TYPE| match &arguments[0] {
-        DataType::Number(NumberDataType::NUM_TYPE) => AggregateWindowFunnelFunction::<
-            NumberType<NUM_TYPE>,
-        >::try_create(
-            display_name, params, arguments
+        DataType::Number(NumberDataType::NUM_TYPE) =>
+            AggregateWindowFunnelFunction::<NumberType<NUM_TYPE>>::try_create(
+                display_name,
+                params,
+                arguments,
+                COMPLETION_TIME
+            ),
+        DataType::Date => AggregateWindowFunnelFunction::<DateType>::try_create(
+            display_name,
+            params,
+            arguments,
+            COMPLETION_TIME
         ),
-        DataType::Date =>
-            AggregateWindowFunnelFunction::<DateType>::try_create(display_name, params, arguments),
         DataType::Timestamp => AggregateWindowFunnelFunction::<TimestampType>::try_create(
             display_name,
             params,
-            arguments
+            arguments,
+            COMPLETION_TIME
         ),
         _ => Err(ErrorCode::BadDataValueType(format!(
             "AggregateWindowFunnelFunction does not support type '{:?}'",
@@ -447,6 +518,21 @@ pub fn try_create_aggregate_window_funnel_function(
     })
 }
 
+/// `window_funnel(window)(timestamp, cond1, ..., condN)` returns the max level of the funnel
+/// reached within the window. `window` may also be an array of the N-1 windows between
+/// each two consecutive events, e.g. `window_funnel([86400, 600])`.
 pub fn aggregate_window_funnel_function_desc() -> AggregateFunctionDescription {
-    AggregateFunctionDescription::creator(Box::new(try_create_aggregate_window_funnel_function))
+    AggregateFunctionDescription::creator(Box::new(
+        try_create_aggregate_window_funnel_function::<false>,
+    ))
+}
+
+/// `funnel_time(window)(timestamp, cond1, ..., condN)` returns the duration between the first
+/// and the last event of the funnel, in the unit of `timestamp`, for the entities that fully
+/// converted within the window, and NULL otherwise. Aggregate it with `avg` or `quantile`
+/// to get the time to completion of all entities.
+pub fn aggregate_funnel_time_function_desc() -> AggregateFunctionDescription {
+    AggregateFunctionDescription::creator(Box::new(
+        try_create_aggregate_window_funnel_function::<true>,
+    ))
 }
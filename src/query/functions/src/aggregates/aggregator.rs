@@ -13,9 +13,13 @@
 // limitations under the License.
 
 use super::aggregate_approx_count_distinct::aggregate_approx_count_distinct_function_desc;
+use super::aggregate_approx_count_distinct::aggregate_approx_count_distinct_merge_function_desc;
 use super::aggregate_arg_min_max::aggregate_arg_max_function_desc;
 use super::aggregate_arg_min_max::aggregate_arg_min_function_desc;
+use super::aggregate_arg_min_max_n::aggregate_arg_max_n_function_desc;
+use super::aggregate_arg_min_max_n::aggregate_arg_min_n_function_desc;
 use super::aggregate_avg::aggregate_avg_function_desc;
+use super::aggregate_avg::aggregate_sum_count_function_desc;
 use super::aggregate_bitmap::aggregate_bitmap_and_count_function_desc;
 use super::aggregate_bitmap::aggregate_bitmap_intersect_count_function_desc;
 use super::aggregate_bitmap::aggregate_bitmap_intersect_function_desc;
@@ -23,39 +27,93 @@ use super::aggregate_bitmap::aggregate_bitmap_not_count_function_desc;
 use super::aggregate_bitmap::aggregate_bitmap_or_count_function_desc;
 use super::aggregate_bitmap::aggregate_bitmap_union_function_desc;
 use super::aggregate_bitmap::aggregate_bitmap_xor_count_function_desc;
+use super::aggregate_bool_runs::aggregate_bool_runs_function_desc;
 use super::aggregate_combinator_distinct::aggregate_combinator_distinct_desc;
 use super::aggregate_combinator_distinct::aggregate_combinator_uniq_desc;
+use super::aggregate_combinator_distinct::aggregate_count_distinct_exact_desc;
 use super::aggregate_combinator_state::AggregateStateCombinator;
 use super::aggregate_covariance::aggregate_covariance_population_desc;
 use super::aggregate_covariance::aggregate_covariance_sample_desc;
+use super::aggregate_covariance::aggregate_dot_product_desc;
 use super::aggregate_min_max_any::aggregate_any_function_desc;
 use super::aggregate_min_max_any::aggregate_max_function_desc;
 use super::aggregate_min_max_any::aggregate_min_function_desc;
 use super::aggregate_mode::aggregate_mode_function_desc;
+use super::aggregate_mode::aggregate_mode_with_count_function_desc;
+use super::aggregate_stddev::aggregate_coef_variation_pop_function_desc;
+use super::aggregate_stddev::aggregate_coef_variation_samp_function_desc;
 use super::aggregate_stddev::aggregate_stddev_pop_function_desc;
 use super::aggregate_stddev::aggregate_stddev_samp_function_desc;
+use super::aggregate_stddev::aggregate_stderr_function_desc;
+use super::aggregate_window_funnel::aggregate_funnel_time_function_desc;
 use super::aggregate_window_funnel::aggregate_window_funnel_function_desc;
 use super::AggregateCountFunction;
+use super::AggregateForEachCombinator;
 use super::AggregateFunctionFactory;
 use super::AggregateIfCombinator;
+use super::AggregateResampleCombinator;
+use crate::aggregates::aggregate_anomaly_count_function_desc;
 use crate::aggregates::aggregate_array_agg_function_desc;
 use crate::aggregates::aggregate_array_moving_avg_function_desc;
 use crate::aggregates::aggregate_array_moving_sum_function_desc;
+use crate::aggregates::aggregate_autocorr_function_desc;
+use crate::aggregates::aggregate_avg_speed_function_desc;
+use crate::aggregates::aggregate_cdf_at_function_desc;
+use crate::aggregates::aggregate_churn_function_desc;
+use crate::aggregates::aggregate_count_within_distance_function_desc;
+use crate::aggregates::aggregate_dedup_latest_function_desc;
+use crate::aggregates::aggregate_distinct_time_buckets_function_desc;
+use crate::aggregates::aggregate_ema_function_desc;
+use crate::aggregates::aggregate_fill_rate_function_desc;
+use crate::aggregates::aggregate_geo_convex_hull_area_function_desc;
+use crate::aggregates::aggregate_geo_dedup_function_desc;
+use crate::aggregates::aggregate_geo_enclosing_circle_function_desc;
+use crate::aggregates::aggregate_geo_spherical_centroid_function_desc;
+use crate::aggregates::aggregate_group_uniq_array_function_desc;
+use crate::aggregates::aggregate_hhi_function_desc;
 use crate::aggregates::aggregate_histogram_function_desc;
+use crate::aggregates::aggregate_histogram_quantile_function_desc;
+use crate::aggregates::aggregate_is_monotonic_function_desc;
+use crate::aggregates::aggregate_jaccard_approx_function_desc;
 use crate::aggregates::aggregate_json_array_agg_function_desc;
 use crate::aggregates::aggregate_json_object_agg_function_desc;
 use crate::aggregates::aggregate_kurtosis_function_desc;
+use crate::aggregates::aggregate_last_by_function_desc;
+use crate::aggregates::aggregate_lis_length_function_desc;
+use crate::aggregates::aggregate_longest_run_value_function_desc;
+use crate::aggregates::aggregate_mad_outlier_count_function_desc;
+use crate::aggregates::aggregate_mape_function_desc;
+use crate::aggregates::aggregate_max_k_function_desc;
+use crate::aggregates::aggregate_max_time_gap_function_desc;
 use crate::aggregates::aggregate_median_function_desc;
 use crate::aggregates::aggregate_median_tdigest_function_desc;
 use crate::aggregates::aggregate_median_tdigest_weighted_function_desc;
+use crate::aggregates::aggregate_median_weighted_function_desc;
+use crate::aggregates::aggregate_min_k_function_desc;
+use crate::aggregates::aggregate_ntile_counts_function_desc;
+use crate::aggregates::aggregate_percent_rank_of_function_desc;
 use crate::aggregates::aggregate_quantile_cont_function_desc;
 use crate::aggregates::aggregate_quantile_disc_function_desc;
 use crate::aggregates::aggregate_quantile_tdigest_function_desc;
 use crate::aggregates::aggregate_quantile_tdigest_weighted_function_desc;
+use crate::aggregates::aggregate_r_squared_function_desc;
+use crate::aggregates::aggregate_rank_digest_function_desc;
+use crate::aggregates::aggregate_rate_if_function_desc;
 use crate::aggregates::aggregate_retention_function_desc;
+use crate::aggregates::aggregate_sign_changes_function_desc;
 use crate::aggregates::aggregate_skewness_function_desc;
 use crate::aggregates::aggregate_string_agg_function_desc;
 use crate::aggregates::aggregate_sum_function_desc;
+use crate::aggregates::aggregate_theil_sen_slope_function_desc;
+use crate::aggregates::aggregate_time_slope_function_desc;
+use crate::aggregates::aggregate_track_endpoints_function_desc;
+use crate::aggregates::aggregate_transition_count_function_desc;
+use crate::aggregates::aggregate_trimmed_mean_function_desc;
+use crate::aggregates::aggregate_uniq_by_bucket_function_desc;
+use crate::aggregates::aggregate_uniq_combined_function_desc;
+use crate::aggregates::aggregate_value_counts_function_desc;
+use crate::aggregates::aggregate_value_counts_with_nulls_function_desc;
+use crate::aggregates::aggregate_weighted_sum_function_desc;
 
 pub struct Aggregators;
 
@@ -65,20 +123,42 @@ impl Aggregators {
         factory.register("sum", aggregate_sum_function_desc());
         factory.register("count", AggregateCountFunction::desc());
         factory.register("avg", aggregate_avg_function_desc());
+        factory.register("sum_count", aggregate_sum_count_function_desc());
         factory.register("uniq", aggregate_combinator_uniq_desc());
+        factory.register(
+            "count_distinct_exact",
+            aggregate_count_distinct_exact_desc(),
+        );
+        factory.register("uniq_combined", aggregate_uniq_combined_function_desc());
 
         factory.register("min", aggregate_min_function_desc());
         factory.register("max", aggregate_max_function_desc());
+        factory.register("min_k", aggregate_min_k_function_desc());
+        factory.register("max_k", aggregate_max_k_function_desc());
         factory.register("any", aggregate_any_function_desc());
         factory.register("arg_min", aggregate_arg_min_function_desc());
         factory.register("arg_max", aggregate_arg_max_function_desc());
+        factory.register("arg_min_n", aggregate_arg_min_n_function_desc());
+        factory.register("arg_max_n", aggregate_arg_max_n_function_desc());
+        factory.register("last_by", aggregate_last_by_function_desc());
+        factory.register("dedup_latest", aggregate_dedup_latest_function_desc());
 
         factory.register("covar_samp", aggregate_covariance_sample_desc());
         factory.register("covar_pop", aggregate_covariance_population_desc());
+        factory.register("dot_product", aggregate_dot_product_desc());
         factory.register("stddev_samp", aggregate_stddev_samp_function_desc());
         factory.register("stddev_pop", aggregate_stddev_pop_function_desc());
         factory.register("stddev", aggregate_stddev_samp_function_desc());
         factory.register("std", aggregate_stddev_pop_function_desc());
+        factory.register(
+            "coef_variation",
+            aggregate_coef_variation_pop_function_desc(),
+        );
+        factory.register(
+            "coef_variation_samp",
+            aggregate_coef_variation_samp_function_desc(),
+        );
+        factory.register("stderr", aggregate_stderr_function_desc());
         factory.register("quantile", aggregate_quantile_disc_function_desc());
         factory.register("quantile_disc", aggregate_quantile_disc_function_desc());
         factory.register("quantile_cont", aggregate_quantile_cont_function_desc());
@@ -90,20 +170,89 @@ impl Aggregators {
             "quantile_tdigest_weighted",
             aggregate_quantile_tdigest_weighted_function_desc(),
         );
+        factory.register("percent_rank_of", aggregate_percent_rank_of_function_desc());
+        factory.register("cdf_at", aggregate_cdf_at_function_desc());
+        factory.register("trimmed_mean", aggregate_trimmed_mean_function_desc());
+        factory.register(
+            "mad_outlier_count",
+            aggregate_mad_outlier_count_function_desc(),
+        );
+        factory.register("anomaly_count", aggregate_anomaly_count_function_desc());
+        factory.register("ntile_counts", aggregate_ntile_counts_function_desc());
+        factory.register("max_time_gap", aggregate_max_time_gap_function_desc());
         factory.register("median", aggregate_median_function_desc());
         factory.register("median_tdigest", aggregate_median_tdigest_function_desc());
         factory.register(
             "median_tdigest_weighted",
             aggregate_median_tdigest_weighted_function_desc(),
         );
+        factory.register("median_weighted", aggregate_median_weighted_function_desc());
+        factory.register("weighted_sum", aggregate_weighted_sum_function_desc());
         factory.register("window_funnel", aggregate_window_funnel_function_desc());
+        factory.register("funnel_time", aggregate_funnel_time_function_desc());
+        factory.register("bool_runs", aggregate_bool_runs_function_desc());
+        factory.register("is_monotonic", aggregate_is_monotonic_function_desc());
+        factory.register(
+            "distinct_time_buckets",
+            aggregate_distinct_time_buckets_function_desc(),
+        );
+        factory.register("uniq_by_bucket", aggregate_uniq_by_bucket_function_desc());
+        factory.register("ema", aggregate_ema_function_desc());
+        factory.register("autocorr", aggregate_autocorr_function_desc());
+        factory.register("time_slope", aggregate_time_slope_function_desc());
+        factory.register("r_squared", aggregate_r_squared_function_desc());
+        factory.register("mape", aggregate_mape_function_desc());
+        factory.register("theil_sen_slope", aggregate_theil_sen_slope_function_desc());
+        factory.register(
+            "longest_run_value",
+            aggregate_longest_run_value_function_desc(),
+        );
+        factory.register("sign_changes", aggregate_sign_changes_function_desc());
+        factory.register("lis_length", aggregate_lis_length_function_desc());
+        factory.register(
+            "transition_count",
+            aggregate_transition_count_function_desc(),
+        );
+        factory.register("geo_dedup", aggregate_geo_dedup_function_desc());
+        factory.register("track_endpoints", aggregate_track_endpoints_function_desc());
+        factory.register("avg_speed", aggregate_avg_speed_function_desc());
+        factory.register(
+            "geo_convex_hull_area",
+            aggregate_geo_convex_hull_area_function_desc(),
+        );
+        factory.register(
+            "geo_enclosing_circle",
+            aggregate_geo_enclosing_circle_function_desc(),
+        );
+        factory.register(
+            "geo_spherical_centroid",
+            aggregate_geo_spherical_centroid_function_desc(),
+        );
+        factory.register(
+            "count_within_distance",
+            aggregate_count_within_distance_function_desc(),
+        );
         factory.register(
             "approx_count_distinct",
             aggregate_approx_count_distinct_function_desc(),
         );
+        factory.register(
+            "approx_count_distinct_merge",
+            aggregate_approx_count_distinct_merge_function_desc(),
+        );
+        factory.register("jaccard_approx", aggregate_jaccard_approx_function_desc());
         factory.register("retention", aggregate_retention_function_desc());
+        factory.register("churn", aggregate_churn_function_desc());
         factory.register("array_agg", aggregate_array_agg_function_desc());
         factory.register("list", aggregate_array_agg_function_desc());
+        factory.register(
+            "group_uniq_array",
+            aggregate_group_uniq_array_function_desc(),
+        );
+        factory.register(
+            "group_uniq_array_sorted",
+            aggregate_group_uniq_array_function_desc(),
+        );
         factory.register(
             "group_array_moving_avg",
             aggregate_array_moving_avg_function_desc(),
@@ -142,13 +291,358 @@ impl Aggregators {
         );
 
         factory.register("histogram", aggregate_histogram_function_desc());
+        factory.register(
+            "histogram_quantile",
+            aggregate_histogram_quantile_function_desc(),
+        );
 
         factory.register("mode", aggregate_mode_function_desc());
+        factory.register("mode_with_count", aggregate_mode_with_count_function_desc());
+        factory.register("value_counts", aggregate_value_counts_function_desc());
+        factory.register(
+            "value_counts_with_nulls",
+            aggregate_value_counts_with_nulls_function_desc(),
+        );
+        factory.register("hhi", aggregate_hhi_function_desc());
+        factory.register("fill_rate", aggregate_fill_rate_function_desc());
+        factory.register("rate_if", aggregate_rate_if_function_desc());
+        factory.register("rank_digest", aggregate_rank_digest_function_desc());
     }
 
     pub fn register_combinator(factory: &mut AggregateFunctionFactory) {
         factory.register_combinator("_if", AggregateIfCombinator::combinator_desc());
         factory.register_combinator("_distinct", aggregate_combinator_distinct_desc());
         factory.register_combinator("_state", AggregateStateCombinator::combinator_desc());
+        factory.register_combinator("_foreach", AggregateForEachCombinator::combinator_desc());
+        factory.register_combinator("_resample", AggregateResampleCombinator::combinator_desc());
+    }
+
+    /// Signatures listed by `AggregateFunctionFactory::registered_signatures`,
+    /// every function registered above must have one.
+    pub fn register_signatures(factory: &mut AggregateFunctionFactory) {
+        factory.register_signature(
+            "sum",
+            (0, 0),
+            &["T: Number | Decimal"],
+            "Int64 | UInt64 | Float64 by the sign, Decimal of the max precision",
+        );
+        factory.register_signature("count", (0, 0), &["[T]"], "UInt64");
+        factory.register_signature(
+            "avg",
+            (0, 1),
+            &["T: Number | Decimal"],
+            "Float64, or Decimal with a larger scale",
+        );
+        factory.register_signature("sum_count", (0, 0), &["T: Number"], "Tuple(sum(T), UInt64)");
+        factory.register_signature("uniq", (0, 0), &["T..."], "UInt64");
+        factory.register_signature("count_distinct_exact", (0, 0), &["T..."], "UInt64");
+        factory.register_signature("uniq_combined", (0, 0), &["T..."], "UInt64");
+        factory.register_signature("min", (0, 0), &["T"], "T");
+        factory.register_signature("max", (0, 0), &["T"], "T");
+        factory.register_signature("min_k", (1, 1), &["T"], "Array(T)");
+        factory.register_signature("max_k", (1, 1), &["T"], "Array(T)");
+        factory.register_signature("any", (0, 0), &["T"], "T");
+        factory.register_signature("arg_min", (0, 0), &["T", "U"], "T");
+        factory.register_signature("arg_max", (0, 0), &["T", "U"], "T");
+        factory.register_signature("arg_min_n", (0, 0), &["T", "U", "Integer"], "Array(T)");
+        factory.register_signature("arg_max_n", (0, 0), &["T", "U", "Integer"], "Array(T)");
+        factory.register_signature("last_by", (0, 0), &["T", "U"], "T NULL");
+        factory.register_signature(
+            "dedup_latest",
+            (0, 0),
+            &["K", "T", "V"],
+            "Array(Tuple(K, T))",
+        );
+        factory.register_signature(
+            "covar_samp",
+            (0, 0),
+            &["T: Number | Decimal", "U: Number | Decimal"],
+            "Float64",
+        );
+        factory.register_signature(
+            "covar_pop",
+            (0, 0),
+            &["T: Number | Decimal", "U: Number | Decimal"],
+            "Float64",
+        );
+        factory.register_signature(
+            "dot_product",
+            (0, 0),
+            &["T: Number | Decimal", "U: Number | Decimal"],
+            "Float64",
+        );
+        factory.register_signature("stddev_samp", (0, 0), &["T: Number | Decimal"], "Float64");
+        factory.register_signature("stddev_pop", (0, 0), &["T: Number | Decimal"], "Float64");
+        factory.register_signature("stddev", (0, 0), &["T: Number | Decimal"], "Float64");
+        factory.register_signature("std", (0, 0), &["T: Number | Decimal"], "Float64");
+        factory.register_signature(
+            "coef_variation",
+            (0, 0),
+            &["T: Number | Decimal"],
+            "Float64 NULL",
+        );
+        factory.register_signature(
+            "coef_variation_samp",
+            (0, 0),
+            &["T: Number | Decimal"],
+            "Float64 NULL",
+        );
+        factory.register_signature("stderr", (0, 0), &["T: Number | Decimal"], "Float64 NULL");
+        factory.register_signature(
+            "quantile",
+            (0, usize::MAX),
+            &["T: Number | Decimal"],
+            "T, or Array(T) with more than one level",
+        );
+        factory.register_signature(
+            "quantile_disc",
+            (0, usize::MAX),
+            &["T: Number | Decimal"],
+            "T, or Array(T) with more than one level",
+        );
+        factory.register_signature(
+            "quantile_cont",
+            (0, usize::MAX),
+            &["T: Number | Decimal"],
+            "Float64 | Decimal, or an Array of it with more than one level",
+        );
+        factory.register_signature(
+            "quantile_tdigest",
+            (0, usize::MAX),
+            &["T: Number | Decimal"],
+            "Float64, or Array(Float64) with more than one level",
+        );
+        factory.register_signature(
+            "quantile_tdigest_weighted",
+            (0, usize::MAX),
+            &["T: Number | Decimal", "W: Number"],
+            "Float64, or Array(Float64) with more than one level",
+        );
+        factory.register_signature("percent_rank_of", (1, 1), &["T: Number"], "Float64 NULL");
+        factory.register_signature("cdf_at", (1, usize::MAX), &["T: Number"], "Array(Float64)");
+        factory.register_signature("trimmed_mean", (2, 2), &["T: Number"], "Float64 NULL");
+        factory.register_signature("mad_outlier_count", (1, 1), &["T: Number"], "UInt64");
+        factory.register_signature("anomaly_count", (2, 2), &["T: Number"], "UInt64");
+        factory.register_signature("ntile_counts", (1, 1), &["T: Number"], "Array(UInt64)");
+        factory.register_signature(
+            "max_time_gap",
+            (0, 0),
+            &["T: Integer | Date | Timestamp"],
+            "UInt64 NULL",
+        );
+        factory.register_signature(
+            "median",
+            (0, 0),
+            &["T: Number | Decimal"],
+            "Float64 | Decimal",
+        );
+        factory.register_signature(
+            "median_tdigest",
+            (0, 0),
+            &["T: Number | Decimal"],
+            "Float64",
+        );
+        factory.register_signature(
+            "median_tdigest_weighted",
+            (0, 0),
+            &["T: Number | Decimal", "W: Number"],
+            "Float64",
+        );
+        factory.register_signature(
+            "median_weighted",
+            (0, 0),
+            &["T: Number", "W: Number"],
+            "Float64 NULL",
+        );
+        factory.register_signature(
+            "weighted_sum",
+            (0, 0),
+            &["T: Number", "W: Number"],
+            "Decimal(38, 0) NULL, or Float64 NULL with a float argument",
+        );
+        factory.register_signature(
+            "window_funnel",
+            (1, 1),
+            &["T: Integer | Date | Timestamp", "Boolean..."],
+            "UInt8",
+        );
+        factory.register_signature(
+            "funnel_time",
+            (1, 1),
+            &["T: Integer | Date | Timestamp", "Boolean..."],
+            "UInt64 NULL",
+        );
+        factory.register_signature(
+            "bool_runs",
+            (0, 0),
+            &["T: Number | Date | Timestamp", "Boolean"],
+            "Array(Tuple(T, T, Boolean))",
+        );
+        factory.register_signature(
+            "is_monotonic",
+            (0, 0),
+            &[
+                "O: Number | Date | Timestamp",
+                "T: Number | Decimal | Date | Timestamp | String",
+            ],
+            "Int8",
+        );
+        factory.register_signature(
+            "distinct_time_buckets",
+            (1, 1),
+            &["T: Date | Timestamp", "String"],
+            "UInt64",
+        );
+        factory.register_signature(
+            "uniq_by_bucket",
+            (1, 1),
+            &["T: Date | Timestamp", "String", "V"],
+            "Map(Timestamp, UInt64)",
+        );
+        factory.register_signature(
+            "ema",
+            (1, 1),
+            &["O: Number | Date | Timestamp", "T: Number"],
+            "Float64",
+        );
+        factory.register_signature(
+            "autocorr",
+            (1, 1),
+            &["O: Number | Date | Timestamp", "T: Number"],
+            "Float64 NULL",
+        );
+        factory.register_signature(
+            "time_slope",
+            (0, 0),
+            &["T: Number | Date | Timestamp", "U: Number"],
+            "Float64 NULL",
+        );
+        factory.register_signature("r_squared", (0, 0), &["Number", "Number"], "Float64 NULL");
+        factory.register_signature("mape", (0, 0), &["Number", "Number"], "Float64 NULL");
+        factory.register_signature(
+            "theil_sen_slope",
+            (0, 0),
+            &["Number", "Number"],
+            "Float64 NULL",
+        );
+        factory.register_signature(
+            "longest_run_value",
+            (0, 0),
+            &["O: Number | Date | Timestamp", "T"],
+            "T NULL",
+        );
+        factory.register_signature(
+            "sign_changes",
+            (0, 0),
+            &["O: Number | Date | Timestamp", "T: Number"],
+            "UInt64",
+        );
+        factory.register_signature(
+            "lis_length",
+            (0, 0),
+            &["O: Number | Date | Timestamp", "T: Number"],
+            "UInt64",
+        );
+        factory.register_signature(
+            "transition_count",
+            (0, 0),
+            &["O: Number | Date | Timestamp", "T"],
+            "UInt64",
+        );
+        factory.register_signature(
+            "geo_dedup",
+            (0, 0),
+            &["Number", "Number", "Number"],
+            "UInt64",
+        );
+        factory.register_signature(
+            "track_endpoints",
+            (0, 0),
+            &["T: Number | Date | Timestamp", "Number", "Number"],
+            "Tuple(Tuple(Float64, Float64), Tuple(Float64, Float64), Float64)",
+        );
+        factory.register_signature(
+            "avg_speed",
+            (0, 0),
+            &["T: Number | Date | Timestamp", "Number", "Number"],
+            "Float64 NULL",
+        );
+        factory.register_signature(
+            "geo_convex_hull_area",
+            (0, 0),
+            &["Number", "Number"],
+            "Float64",
+        );
+        factory.register_signature(
+            "geo_enclosing_circle",
+            (0, 0),
+            &["Number", "Number"],
+            "Tuple(Float64, Float64, Float64)",
+        );
+        factory.register_signature(
+            "geo_spherical_centroid",
+            (0, 0),
+            &["Number", "Number"],
+            "Tuple(Float64, Float64) NULL",
+        );
+        factory.register_signature(
+            "count_within_distance",
+            (0, 0),
+            &["Number", "Number", "Number", "Number", "Number"],
+            "UInt64",
+        );
+        factory.register_signature("approx_count_distinct", (0, 1), &["T"], "UInt64");
+        factory.register_signature("approx_count_distinct_merge", (0, 1), &["Binary"], "UInt64");
+        factory.register_signature("jaccard_approx", (0, 0), &["T", "T"], "Float64 NULL");
+        factory.register_signature("retention", (0, 0), &["Boolean..."], "Array(UInt8)");
+        factory.register_signature("churn", (0, 0), &["Boolean..."], "Array(Float64 NULL)");
+        factory.register_signature("array_agg", (0, 0), &["T"], "Array(T)");
+        factory.register_signature("list", (0, 0), &["T"], "Array(T)");
+        factory.register_signature("group_uniq_array", (0, 0), &["T"], "Array(T)");
+        factory.register_signature("group_uniq_array_sorted", (0, 0), &["T"], "Array(T)");
+        factory.register_signature(
+            "group_array_moving_avg",
+            (0, 1),
+            &["T: Number | Decimal"],
+            "Array(Float64) | Array(Decimal)",
+        );
+        factory.register_signature(
+            "group_array_moving_sum",
+            (0, 1),
+            &["T: Number | Decimal"],
+            "Array(sum(T))",
+        );
+        factory.register_signature("json_array_agg", (0, 0), &["T"], "Variant");
+        factory.register_signature("json_object_agg", (0, 0), &["String", "T"], "Variant");
+        factory.register_signature("kurtosis", (0, 0), &["T: Number | Decimal"], "Float64");
+        factory.register_signature("skewness", (0, 0), &["T: Number | Decimal"], "Float64");
+        factory.register_signature("string_agg", (0, 1), &["String", "[String]"], "String");
+        factory.register_signature("bitmap_and_count", (0, 0), &["Bitmap"], "UInt64");
+        factory.register_signature("bitmap_not_count", (0, 0), &["Bitmap"], "UInt64");
+        factory.register_signature("bitmap_or_count", (0, 0), &["Bitmap"], "UInt64");
+        factory.register_signature("bitmap_xor_count", (0, 0), &["Bitmap"], "UInt64");
+        factory.register_signature("bitmap_union", (0, 0), &["Bitmap"], "Bitmap");
+        factory.register_signature("bitmap_intersect", (0, 0), &["Bitmap"], "Bitmap");
+        factory.register_signature("intersect_count", (1, 32), &["Bitmap", "T"], "UInt64");
+        factory.register_signature("histogram", (0, 1), &["T", "[UInt64]"], "String");
+        factory.register_signature(
+            "histogram_quantile",
+            (1, 1),
+            &["T: Number", "U: Number"],
+            "Float64",
+        );
+        factory.register_signature("mode", (0, 0), &["T"], "T");
+        factory.register_signature("mode_with_count", (0, 0), &["T"], "Tuple(T, UInt64)");
+        factory.register_signature("value_counts", (0, 0), &["T"], "Map(T, UInt64)");
+        factory.register_signature(
+            "value_counts_with_nulls",
+            (0, 0),
+            &["T"],
+            "Map(T NULL, UInt64)",
+        );
+        factory.register_signature("hhi", (0, 0), &["T"], "Float64 NULL");
+        factory.register_signature("fill_rate", (0, 0), &["T"], "Float64 NULL");
+        factory.register_signature("rate_if", (0, 0), &["Boolean"], "Float64 NULL");
+        factory.register_signature("rank_digest", (0, 0), &["T: Number"], "Binary NULL");
     }
 }
@@ -20,6 +20,7 @@ use bumpalo::Bump;
 use databend_common_base::runtime::drop_guard;
 use databend_common_exception::ErrorCode;
 use databend_common_exception::Result;
+use databend_common_expression::types::decimal::Decimal;
 use databend_common_expression::types::DataType;
 use databend_common_expression::Column;
 use databend_common_expression::ColumnBuilder;
@@ -107,6 +108,74 @@ pub fn assert_variadic_arguments<D: Display>(
     Ok(())
 }
 
+/// How an aggregate rounds a decimal result on finalize, given as an optional string
+/// parameter, e.g. `avg('half_even')(x)`. Only `avg` takes it: the decimal quantiles
+/// return one of the input values, there is no division to round.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RoundingMode {
+    /// Rounds half away from zero.
+    HalfUp,
+    /// Rounds half to the even neighbour, also known as the banker's rounding.
+    HalfEven,
+    /// Drops the extra digits, which is the default.
+    #[default]
+    Truncate,
+}
+
+impl RoundingMode {
+    /// Parses the rounding mode from the params of the function, without params the
+    /// result is truncated.
+    pub fn try_from_params<D: Display>(name: D, params: &[Scalar]) -> Result<Self> {
+        assert_variadic_params(&name, params.len(), (0, 1))?;
+        let Some(param) = params.first() else {
+            return Ok(RoundingMode::default());
+        };
+        match param.as_string().map(|s| s.to_lowercase()).as_deref() {
+            Some("half_up") => Ok(RoundingMode::HalfUp),
+            Some("half_even") => Ok(RoundingMode::HalfEven),
+            Some("truncate") => Ok(RoundingMode::Truncate),
+            _ => Err(ErrorCode::BadArguments(format!(
+                "{} expects the rounding mode 'half_up', 'half_even' or 'truncate', but got {}",
+                name, param
+            ))),
+        }
+    }
+
+    /// Divides `value` by the positive `divisor`, rounding the quotient with this mode.
+    /// Returns `None` on overflow.
+    pub fn div<T: Decimal>(self, value: T, divisor: T) -> Option<T> {
+        let quotient = value.checked_div(divisor)?;
+        if self == RoundingMode::Truncate {
+            return Some(quotient);
+        }
+
+        // The remainder has the sign of `value`, twice its magnitude is compared to the
+        // divisor to know on which side of the half the quotient is.
+        let remainder = value.checked_rem(divisor)?;
+        let (twice, step) = if remainder < T::zero() {
+            (
+                T::zero().checked_sub(remainder.checked_add(remainder)?)?,
+                T::minus_one(),
+            )
+        } else {
+            (remainder.checked_add(remainder)?, T::one())
+        };
+        let away_from_zero = match self {
+            RoundingMode::HalfUp => twice >= divisor,
+            RoundingMode::HalfEven => {
+                let odd = quotient.checked_rem(T::from_i128(2))? != T::zero();
+                twice > divisor || (twice == divisor && odd)
+            }
+            RoundingMode::Truncate => false,
+        };
+        if away_from_zero {
+            quotient.checked_add(step)
+        } else {
+            Some(quotient)
+        }
+    }
+}
+
 struct EvalAggr {
     addr: StateAddr,
     _arena: Bump,
@@ -17,65 +17,155 @@ mod aggregate_count;
 mod aggregate_function_factory;
 
 mod adaptors;
+mod aggregate_anomaly_count;
 mod aggregate_approx_count_distinct;
 mod aggregate_arg_min_max;
+mod aggregate_arg_min_max_n;
 mod aggregate_array_agg;
 mod aggregate_array_moving;
+mod aggregate_autocorr;
 mod aggregate_avg;
+mod aggregate_avg_speed;
 mod aggregate_bitmap;
+mod aggregate_bool_runs;
+mod aggregate_cdf_at;
 mod aggregate_combinator_distinct;
+mod aggregate_combinator_foreach;
 mod aggregate_combinator_if;
+mod aggregate_combinator_resample;
 mod aggregate_combinator_state;
+mod aggregate_count_within_distance;
 mod aggregate_covariance;
+mod aggregate_dedup_latest;
 mod aggregate_distinct_state;
+mod aggregate_distinct_time_buckets;
+mod aggregate_ema;
+mod aggregate_fill_rate;
+mod aggregate_geo_convex_hull_area;
+mod aggregate_geo_dedup;
+mod aggregate_geo_enclosing_circle;
+mod aggregate_geo_spherical_centroid;
+mod aggregate_group_uniq_array;
+mod aggregate_hhi;
 mod aggregate_histogram;
+mod aggregate_histogram_quantile;
+mod aggregate_is_monotonic;
+mod aggregate_jaccard_approx;
 mod aggregate_json_array_agg;
 mod aggregate_json_object_agg;
 mod aggregate_kurtosis;
+mod aggregate_last_by;
+mod aggregate_lis_length;
+mod aggregate_longest_run_value;
+mod aggregate_mad_outlier_count;
+mod aggregate_mape;
+mod aggregate_max_time_gap;
+mod aggregate_median_weighted;
 mod aggregate_min_max_any;
+mod aggregate_min_max_k;
 mod aggregate_mode;
+mod aggregate_ntile_counts;
 mod aggregate_null_result;
+mod aggregate_percent_rank_of;
 mod aggregate_quantile_cont;
 mod aggregate_quantile_disc;
 mod aggregate_quantile_tdigest;
 mod aggregate_quantile_tdigest_weighted;
+mod aggregate_r_squared;
+mod aggregate_rank_digest;
+mod aggregate_rate_if;
 mod aggregate_retention;
 mod aggregate_scalar_state;
+mod aggregate_sign_changes;
 mod aggregate_skewness;
 mod aggregate_stddev;
 mod aggregate_string_agg;
 mod aggregate_sum;
+mod aggregate_theil_sen_slope;
+mod aggregate_time_slope;
+mod aggregate_track_endpoints;
+mod aggregate_transition_count;
+mod aggregate_trimmed_mean;
 mod aggregate_unary;
+mod aggregate_uniq_by_bucket;
+mod aggregate_uniq_composite;
+mod aggregate_value_counts;
+mod aggregate_weighted_sum;
 mod aggregate_window_funnel;
 mod aggregator;
 mod aggregator_common;
 
 pub use adaptors::*;
+pub use aggregate_anomaly_count::*;
 pub use aggregate_arg_min_max::AggregateArgMinMaxFunction;
+pub use aggregate_arg_min_max_n::*;
 pub use aggregate_array_agg::*;
 pub use aggregate_array_moving::*;
+pub use aggregate_autocorr::*;
+pub use aggregate_avg_speed::*;
+pub use aggregate_cdf_at::*;
 pub use aggregate_combinator_distinct::AggregateDistinctCombinator;
+pub use aggregate_combinator_foreach::AggregateForEachCombinator;
 pub use aggregate_combinator_if::AggregateIfCombinator;
+pub use aggregate_combinator_resample::AggregateResampleCombinator;
 pub use aggregate_count::AggregateCountFunction;
+pub use aggregate_count_within_distance::*;
 pub use aggregate_covariance::AggregateCovarianceFunction;
+pub use aggregate_dedup_latest::*;
+pub use aggregate_distinct_time_buckets::*;
+pub use aggregate_ema::*;
+pub use aggregate_fill_rate::*;
 pub use aggregate_function::*;
 pub use aggregate_function_factory::AggregateFunctionFactory;
+pub use aggregate_function_factory::AggregateFunctionSignature;
+pub use aggregate_geo_convex_hull_area::*;
+pub use aggregate_geo_dedup::*;
+pub use aggregate_geo_enclosing_circle::*;
+pub use aggregate_geo_spherical_centroid::*;
+pub use aggregate_group_uniq_array::*;
+pub use aggregate_hhi::*;
 pub use aggregate_histogram::*;
+pub use aggregate_histogram_quantile::*;
+pub use aggregate_is_monotonic::*;
+pub use aggregate_jaccard_approx::*;
 pub use aggregate_json_array_agg::*;
 pub use aggregate_json_object_agg::*;
 pub use aggregate_kurtosis::*;
+pub use aggregate_last_by::*;
+pub use aggregate_lis_length::*;
+pub use aggregate_longest_run_value::*;
+pub use aggregate_mad_outlier_count::*;
+pub use aggregate_mape::*;
+pub use aggregate_max_time_gap::*;
+pub use aggregate_median_weighted::*;
 pub use aggregate_min_max_any::*;
+pub use aggregate_min_max_k::*;
 pub use aggregate_mode::*;
+pub use aggregate_ntile_counts::*;
 pub use aggregate_null_result::AggregateNullResultFunction;
+pub use aggregate_percent_rank_of::*;
 pub use aggregate_quantile_cont::*;
 pub use aggregate_quantile_disc::*;
 pub use aggregate_quantile_tdigest::*;
 pub use aggregate_quantile_tdigest_weighted::*;
+pub use aggregate_r_squared::*;
+pub use aggregate_rank_digest::*;
+pub use aggregate_rate_if::*;
 pub use aggregate_retention::*;
+pub use aggregate_sign_changes::*;
 pub use aggregate_skewness::*;
 pub use aggregate_string_agg::*;
 pub use aggregate_sum::*;
+pub use aggregate_theil_sen_slope::*;
+pub use aggregate_time_slope::*;
+pub use aggregate_track_endpoints::*;
+pub use aggregate_transition_count::*;
+pub use aggregate_trimmed_mean::*;
 pub use aggregate_unary::*;
+pub use aggregate_uniq_by_bucket::*;
+pub use aggregate_uniq_composite::*;
+pub use aggregate_value_counts::*;
+pub use aggregate_weighted_sum::*;
 pub use aggregator::Aggregators;
 pub use aggregator_common::*;
 pub use databend_common_expression::aggregate as aggregate_function;
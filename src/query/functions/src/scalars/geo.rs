@@ -17,22 +17,30 @@ use std::num::Wrapping;
 use std::sync::Arc;
 use std::sync::Once;
 
+use databend_common_arrow::arrow::bitmap::MutableBitmap;
 use databend_common_expression::types::map::KvPair;
 use databend_common_expression::types::number::Float64Type;
 use databend_common_expression::types::number::NumberColumnBuilder;
 use databend_common_expression::types::number::NumberScalar;
 use databend_common_expression::types::number::F32;
 use databend_common_expression::types::number::F64;
+use databend_common_expression::types::string::StringColumnBuilder;
 use databend_common_expression::types::AnyType;
+use databend_common_expression::types::ArrayType;
+use databend_common_expression::types::BooleanType;
 use databend_common_expression::types::DataType;
+use databend_common_expression::types::Int64Type;
+use databend_common_expression::types::NullableType;
 use databend_common_expression::types::NumberDataType;
 use databend_common_expression::types::NumberType;
 use databend_common_expression::types::StringType;
+use databend_common_expression::types::UInt64Type;
 use databend_common_expression::types::UInt8Type;
 use databend_common_expression::types::ValueType;
 use databend_common_expression::vectorize_with_builder_1_arg;
 use databend_common_expression::vectorize_with_builder_2_arg;
 use databend_common_expression::vectorize_with_builder_3_arg;
+use databend_common_expression::vectorize_with_builder_5_arg;
 use databend_common_expression::Column;
 use databend_common_expression::EvalContext;
 use databend_common_expression::Function;
@@ -49,6 +57,9 @@ use geo::Contains;
 use geo::Coord;
 use geo::LineString;
 use geo::Polygon;
+use geo::Winding;
+use geozero::wkt::Wkt;
+use geozero::ToGeo;
 use h3o::LatLng;
 use h3o::Resolution;
 use once_cell::sync::OnceCell;
@@ -67,8 +78,44 @@ const METRIC_LUT_SIZE: usize = 1024;
 /// Earth radius in meters using WGS84 authalic radius.
 /// We use this value to be consistent with Uber H3 library.
 const EARTH_RADIUS: f32 = 6371007.180918475f32;
+const EARTH_RADIUS_F64: f64 = 6371007.180918475f64;
 const EARTH_DIAMETER: f32 = 2f32 * EARTH_RADIUS;
 
+/// Finer than a micrometer, more decimals don't help the compression.
+const MAX_GEO_ROUND_DECIMALS: i64 = 12;
+
+/// Rings with a smaller area in square degrees, about a square of 10 cm, have no centroid.
+const MIN_CENTROID_RING_AREA: f64 = 1e-12;
+
+/// Each coordinate of a Morton key is quantized into 2^32 cells over its range.
+const MORTON_CELLS: f64 = 4294967296f64;
+
+/// The points of the compass rose clockwise from the north, 8 points take every other one.
+const COMPASS_POINTS: [&str; 16] = [
+    "N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE", "S", "SSW", "SW", "WSW", "W", "WNW", "NW",
+    "NNW",
+];
+
+/// Web Mercator uses the equatorial radius of WGS84 as the radius of the sphere.
+const WEB_MERCATOR_RADIUS: f64 = 6378137f64;
+/// The latitude where the projected world is a square, `atan(sinh(PI))` in degrees.
+const WEB_MERCATOR_MAX_LATITUDE: f64 = 85.05112877980659f64;
+
+/// The semi-major axis in meters and the flattening of the WGS84 ellipsoid.
+const WGS84_A: f64 = 6378137f64;
+const WGS84_F: f64 = 1f64 / 298.257223563f64;
+/// Vincenty's formulae converge within a few iterations, except for nearly antipodal
+/// points where they may not converge at all.
+const VINCENTY_MAX_ITERATIONS: usize = 200;
+const VINCENTY_EPSILON: f64 = 1e-12;
+
+/// Below this difference of the Mercator latitudes, two points are taken as being on the
+/// same parallel by the rhumb line functions.
+const RHUMB_MIN_MERCATOR_DIFF: f64 = 1e-12;
+
+/// The most points geo_interpolate and geo_densify return for a path.
+const MAX_GEO_PATH_POINTS: usize = 1000000;
+
 static COS_LUT: OnceCell<[f32; COS_LUT_SIZE + 1]> = OnceCell::new();
 static ASIN_SQRT_LUT: OnceCell<[f32; ASIN_SQRT_LUT_SIZE + 1]> = OnceCell::new();
 
@@ -132,6 +179,15 @@ pub fn register(registry: &mut FunctionRegistry) {
         },
     );
 
+    // great circle angle of two (lon, lat) points
+    registry.register_2_arg::<KvPair<Float64Type, Float64Type>, KvPair<Float64Type, Float64Type>, NumberType<F32>, _, _>(
+        "great_circle_angle",
+        |_, _, _| FunctionDomain::Full,
+        |(lon1, lat1), (lon2, lat2), _| {
+            F32::from(distance(lon1.0 as f32, lat1.0 as f32, lon2.0 as f32, lat2.0 as f32, GeoMethod::SphereDegrees))
+        },
+    );
+
     // great circle distance
     registry.register_4_arg::<NumberType<F64>, NumberType<F64>, NumberType<F64>, NumberType<F64>,NumberType<F32>,_, _>(
         "great_circle_distance",
@@ -141,6 +197,232 @@ pub fn register(registry: &mut FunctionRegistry) {
         },
     );
 
+    // great circle distance of two (lon, lat) points
+    registry.register_2_arg::<KvPair<Float64Type, Float64Type>, KvPair<Float64Type, Float64Type>, NumberType<F32>, _, _>(
+        "great_circle_distance",
+        |_, _, _| FunctionDomain::Full,
+        |(lon1, lat1), (lon2, lat2), _| {
+            F32::from(distance(lon1.0 as f32, lat1.0 as f32, lon2.0 as f32, lat2.0 as f32, GeoMethod::SphereMeters))
+        },
+    );
+
+    // great circle distance without the rounding of the result to f32
+    registry.register_4_arg::<NumberType<F64>, NumberType<F64>, NumberType<F64>, NumberType<F64>,NumberType<F64>,_, _>(
+        "great_circle_distance_f64",
+        |_, _, _, _, _| FunctionDomain::Full,
+        |lon1:F64,lat1:F64,lon2:F64,lat2:F64,_| {
+            F64::from(distance_f64(lon1.0 as f32, lat1.0 as f32, lon2.0 as f32, lat2.0 as f32, GeoMethod::SphereMeters))
+        },
+    );
+
+    // great circle distance of two (lon, lat) points without the rounding of the result to f32
+    registry.register_2_arg::<KvPair<Float64Type, Float64Type>, KvPair<Float64Type, Float64Type>, NumberType<F64>, _, _>(
+        "great_circle_distance_f64",
+        |_, _, _| FunctionDomain::Full,
+        |(lon1, lat1), (lon2, lat2), _| {
+            F64::from(distance_f64(lon1.0 as f32, lat1.0 as f32, lon2.0 as f32, lat2.0 as f32, GeoMethod::SphereMeters))
+        },
+    );
+
+    // whether a point is within radius_m meters of the center, measured like great_circle_distance
+    registry.register_5_arg::<NumberType<F64>, NumberType<F64>, NumberType<F64>, NumberType<F64>, NumberType<F64>, BooleanType, _, _>(
+        "geo_in_circle",
+        |_, _, _, _, _, _| FunctionDomain::Full,
+        |lon: F64, lat: F64, center_lon: F64, center_lat: F64, radius: F64, _| {
+            distance(center_lon.0 as f32, center_lat.0 as f32, lon.0 as f32, lat.0 as f32, GeoMethod::SphereMeters) as f64 <= radius.0
+        },
+    );
+
+    // equirectangular approximation of the distance in meters, for points close together
+    registry.register_4_arg::<NumberType<F64>, NumberType<F64>, NumberType<F64>, NumberType<F64>,NumberType<F64>,_, _>(
+        "equirect_distance",
+        |_, _, _, _, _| FunctionDomain::Full,
+        |lon1:F64,lat1:F64,lon2:F64,lat2:F64,_| {
+            F64::from(equirect_distance(lon1.0, lat1.0, lon2.0, lat2.0))
+        },
+    );
+
+    // rhumb line (loxodrome) distance in meters, along the constant bearing
+    registry.register_4_arg::<NumberType<F64>, NumberType<F64>, NumberType<F64>, NumberType<F64>,NumberType<F64>,_, _>(
+        "rhumb_distance",
+        |_, _, _, _, _| FunctionDomain::Full,
+        |lon1:F64,lat1:F64,lon2:F64,lat2:F64,_| {
+            F64::from(rhumb_distance(lon1.0, lat1.0, lon2.0, lat2.0))
+        },
+    );
+
+    // constant bearing in degrees of the rhumb line, clockwise from the north
+    registry.register_4_arg::<NumberType<F64>, NumberType<F64>, NumberType<F64>, NumberType<F64>,NumberType<F64>,_, _>(
+        "rhumb_bearing",
+        |_, _, _, _, _| FunctionDomain::Full,
+        |lon1:F64,lat1:F64,lon2:F64,lat2:F64,_| {
+            F64::from(rhumb_bearing(lon1.0, lat1.0, lon2.0, lat2.0))
+        },
+    );
+
+    // meters per degree of longitude at a latitude on the WGS84 ellipsoid
+    registry.register_1_arg::<NumberType<F64>, NumberType<F64>, _, _>(
+        "meters_per_degree_lon",
+        |_, _| FunctionDomain::Full,
+        |lat: F64, _| F64::from(wgs84_meters_per_degree(lat.0.clamp(-90.0, 90.0)).1),
+    );
+
+    // meters per degree of latitude at a latitude on the WGS84 ellipsoid
+    registry.register_1_arg::<NumberType<F64>, NumberType<F64>, _, _>(
+        "meters_per_degree_lat",
+        |_, _| FunctionDomain::Full,
+        |lat: F64, _| F64::from(wgs84_meters_per_degree(lat.0.clamp(-90.0, 90.0)).0),
+    );
+
+    // geo_distance_method(lon1, lat1, lon2, lat2, method)
+    registry.register_passthrough_nullable_5_arg::<Float64Type, Float64Type, Float64Type, Float64Type, StringType, Float64Type, _, _>(
+        "geo_distance_method",
+        |_, _, _, _, _, _| FunctionDomain::Full,
+        vectorize_with_builder_5_arg::<Float64Type, Float64Type, Float64Type, Float64Type, StringType, Float64Type>(
+            |lon1, lat1, lon2, lat2, method, builder, ctx| {
+                match distance_by_method(lon1.0, lat1.0, lon2.0, lat2.0, method) {
+                    Ok(distance) => builder.push(distance.into()),
+                    Err(e) => {
+                        ctx.set_error(builder.len(), e);
+                        builder.push(F64::from(0.0));
+                    }
+                }
+            },
+        ),
+    );
+
+    // total great circle length in meters of a WKT LINESTRING
+    registry.register_passthrough_nullable_1_arg::<StringType, Float64Type, _, _>(
+        "st_length",
+        |_, _| FunctionDomain::Full,
+        vectorize_with_builder_1_arg::<StringType, Float64Type>(|wkt, builder, ctx| {
+            match linestring_length(wkt) {
+                Ok(length) => builder.push(length.into()),
+                Err(e) => {
+                    ctx.set_error(builder.len(), e);
+                    builder.push(F64::from(0.0));
+                }
+            }
+        }),
+    );
+
+    // whether a WKT POINT, LINESTRING or POLYGON is well formed
+    registry.register_passthrough_nullable_1_arg::<StringType, BooleanType, _, _>(
+        "st_is_valid",
+        |_, _| FunctionDomain::Full,
+        vectorize_with_builder_1_arg::<StringType, BooleanType>(|wkt, builder, _| {
+            builder.push(parse_wkt(wkt).is_ok());
+        }),
+    );
+
+    // the canonical WKT of a POINT, LINESTRING or POLYGON
+    registry.register_passthrough_nullable_1_arg::<StringType, StringType, _, _>(
+        "st_normalize",
+        |_, _| FunctionDomain::Full,
+        vectorize_with_builder_1_arg::<StringType, StringType>(|wkt, builder, ctx| {
+            match parse_wkt(wkt) {
+                Ok(geometry) => builder.put_str(&normalized_wkt(geometry)),
+                Err(e) => ctx.set_error(builder.len(), e),
+            }
+            builder.commit_row();
+        }),
+    );
+
+    // geo_path_length([lon1, lon2, ...], [lat1, lat2, ...])
+    registry.register_passthrough_nullable_2_arg::<ArrayType<Float64Type>, ArrayType<Float64Type>, Float64Type, _, _>(
+        "geo_path_length",
+        |_, _, _| FunctionDomain::Full,
+        vectorize_with_builder_2_arg::<ArrayType<Float64Type>, ArrayType<Float64Type>, Float64Type>(
+            |lons, lats, builder, ctx| {
+                if lons.len() != lats.len() {
+                    ctx.set_error(
+                        builder.len(),
+                        format!(
+                            "the arrays of longitudes and latitudes must have the same length, but got {} and {}",
+                            lons.len(),
+                            lats.len()
+                        ),
+                    );
+                    builder.push(F64::from(0.0));
+                } else {
+                    let path = lons.iter().zip(lats.iter()).map(|(lon, lat)| (lon.0, lat.0)).collect::<Vec<_>>();
+                    builder.push(path_length(&path).into());
+                }
+            },
+        ),
+    );
+
+    // signed shortest difference from lon1 to lon2, positive eastward
+    registry.register_passthrough_nullable_2_arg::<Float64Type, Float64Type, Float64Type, _, _>(
+        "longitude_diff",
+        |_, _, _| FunctionDomain::Full,
+        vectorize_with_builder_2_arg::<Float64Type, Float64Type, Float64Type>(
+            |lon1, lon2, builder, _| builder.push(longitude_diff(lon1.0, lon2.0).into()),
+        ),
+    );
+
+    // compass_direction(bearing_deg[, points])
+    registry.register_passthrough_nullable_1_arg::<Float64Type, StringType, _, _>(
+        "compass_direction",
+        |_, _| FunctionDomain::Full,
+        vectorize_with_builder_1_arg::<Float64Type, StringType>(|bearing, builder, ctx| {
+            compass_direction_builder(bearing.0, 8, builder, ctx)
+        }),
+    );
+
+    registry.register_passthrough_nullable_2_arg::<Float64Type, UInt8Type, StringType, _, _>(
+        "compass_direction",
+        |_, _, _| FunctionDomain::Full,
+        vectorize_with_builder_2_arg::<Float64Type, UInt8Type, StringType>(
+            |bearing, points, builder, ctx| {
+                if points != 8 && points != 16 {
+                    ctx.set_error(
+                        builder.len(),
+                        format!("the number of compass points must be 8 or 16, but got {points}"),
+                    );
+                    builder.commit_row();
+                } else {
+                    compass_direction_builder(bearing.0, points as usize, builder, ctx)
+                }
+            },
+        ),
+    );
+
+    // the point diametrically opposite on the sphere
+    registry.register_passthrough_nullable_2_arg::<Float64Type, Float64Type, KvPair<Float64Type, Float64Type>, _, _>(
+        "geo_antipode",
+        |_, _, _| FunctionDomain::Full,
+        vectorize_with_builder_2_arg::<Float64Type, Float64Type, KvPair<Float64Type, Float64Type>>(
+            |lon, lat, builder, _| {
+                let (lon, lat) = antipode(lon.0, lat.0);
+                builder.push((lon.into(), lat.into()));
+            },
+        ),
+    );
+
+    // Web Mercator (EPSG:3857)
+    registry.register_passthrough_nullable_2_arg::<Float64Type, Float64Type, KvPair<Float64Type, Float64Type>, _, _>(
+        "lonlat_to_mercator",
+        |_, _, _| FunctionDomain::Full,
+        vectorize_with_builder_2_arg::<Float64Type, Float64Type, KvPair<Float64Type, Float64Type>>(
+            |lon, lat, builder, _| {
+                let (x, y) = lonlat_to_mercator(lon.0, lat.0);
+                builder.push((x.into(), y.into()))
+            },
+        ),
+    );
+
+    registry.register_passthrough_nullable_2_arg::<Float64Type, Float64Type, KvPair<Float64Type, Float64Type>, _, _>(
+        "mercator_to_lonlat",
+        |_, _, _| FunctionDomain::Full,
+        vectorize_with_builder_2_arg::<Float64Type, Float64Type, KvPair<Float64Type, Float64Type>>(
+            |x, y, builder, _| {
+                let (lon, lat) = mercator_to_lonlat(x.0, y.0);
+                builder.push((lon.into(), lat.into()))
+            },
+        ),
+    );
+
     registry.register_passthrough_nullable_2_arg::<Float64Type, Float64Type, StringType, _, _>(
         "geohash_encode",
         |_, _, _| FunctionDomain::Full,
@@ -193,6 +475,194 @@ pub fn register(registry: &mut FunctionRegistry) {
             ),
         );
 
+    // Morton (Z-order) key of (lon, lat)
+    registry.register_passthrough_nullable_2_arg::<Float64Type, Float64Type, UInt64Type, _, _>(
+        "geo_morton_encode",
+        |_, _, _| FunctionDomain::Full,
+        vectorize_with_builder_2_arg::<Float64Type, Float64Type, UInt64Type>(
+            |lon, lat, builder, ctx| {
+                if !(-180.0..=180.0).contains(&lon.0) || !(-90.0..=90.0).contains(&lat.0) {
+                    ctx.set_error(
+                        builder.len(),
+                        format!("longitude must be between -180 and 180 and latitude between -90 and 90, but got ({lon}, {lat})"),
+                    );
+                    builder.push(0);
+                    return;
+                }
+                builder.push(morton_encode(lon.0, lat.0));
+            },
+        ),
+    );
+
+    registry
+        .register_passthrough_nullable_1_arg::<UInt64Type, KvPair<Float64Type, Float64Type>, _, _>(
+            "geo_morton_decode",
+            |_, _| FunctionDomain::Full,
+            vectorize_with_builder_1_arg::<UInt64Type, KvPair<Float64Type, Float64Type>>(
+                |key, builder, _| {
+                    let (lon, lat) = morton_decode(key);
+                    builder.push((lon.into(), lat.into()));
+                },
+            ),
+        );
+
+    // is_simple_polygon([(x1, y1), (x2, y2), ...])
+    registry.register_passthrough_nullable_1_arg::<ArrayType<KvPair<Float64Type, Float64Type>>, BooleanType, _, _>(
+        "is_simple_polygon",
+        |_, _| FunctionDomain::Full,
+        vectorize_with_builder_1_arg::<ArrayType<KvPair<Float64Type, Float64Type>>, BooleanType>(
+            |ring, builder, _| {
+                let ring = ring.iter().map(|(x, y)| coord! { x: x.0, y: y.0 }).collect::<Vec<_>>();
+                builder.push(is_simple_ring(&ring));
+            },
+        ),
+    );
+
+    // geo_centroid([(lon1, lat1), (lon2, lat2), ...])
+    registry.register_combine_nullable_1_arg::<ArrayType<KvPair<Float64Type, Float64Type>>, KvPair<Float64Type, Float64Type>, _, _>(
+        "geo_centroid",
+        |_, _| FunctionDomain::Full,
+        vectorize_with_builder_1_arg::<ArrayType<KvPair<Float64Type, Float64Type>>, NullableType<KvPair<Float64Type, Float64Type>>>(
+            |ring, builder, _| {
+                let ring = ring.iter().map(|(lon, lat)| coord! { x: lon.0, y: lat.0 }).collect::<Vec<_>>();
+                match ring_centroid(&ring) {
+                    Some(c) => builder.push((c.x.into(), c.y.into())),
+                    None => builder.push_null(),
+                }
+            },
+        ),
+    );
+
+    // geo_round(lon, lat, decimals)
+    registry.register_passthrough_nullable_3_arg::<Float64Type, Float64Type, Int64Type, KvPair<Float64Type, Float64Type>, _, _>(
+        "geo_round",
+        |_, _, _, _| FunctionDomain::Full,
+        vectorize_with_builder_3_arg::<Float64Type, Float64Type, Int64Type, KvPair<Float64Type, Float64Type>>(
+            |lon, lat, decimals, builder, ctx| {
+                if !(0..=MAX_GEO_ROUND_DECIMALS).contains(&decimals) {
+                    ctx.set_error(builder.len(), format!("decimals must be between 0 and {MAX_GEO_ROUND_DECIMALS}, but got {decimals}"));
+                    builder.push((F64::from(0.0), F64::from(0.0)));
+                } else {
+                    let factor = 10f64.powi(decimals as i32);
+                    let lon = (lon.0 * factor).round() / factor;
+                    let lat = (lat.0 * factor).round() / factor;
+                    builder.push((lon.into(), lat.into()));
+                }
+            },
+        ),
+    );
+
+    // geo_rotate(lon, lat, center_lon, center_lat, angle_deg)
+    registry.register_passthrough_nullable_5_arg::<Float64Type, Float64Type, Float64Type, Float64Type, Float64Type, KvPair<Float64Type, Float64Type>, _, _>(
+        "geo_rotate",
+        |_, _, _, _, _, _| FunctionDomain::Full,
+        vectorize_with_builder_5_arg::<Float64Type, Float64Type, Float64Type, Float64Type, Float64Type, KvPair<Float64Type, Float64Type>>(
+            |lon, lat, center_lon, center_lat, angle, builder, ctx| {
+                if center_lat.0.abs() >= 90.0 {
+                    ctx.set_error(builder.len(), format!("the center latitude must be between -90 and 90 exclusive, but got {center_lat}"));
+                    builder.push((F64::from(0.0), F64::from(0.0)));
+                } else {
+                    let (lon, lat) = rotate_around(lon.0, lat.0, center_lon.0, center_lat.0, angle.0);
+                    builder.push((lon.into(), lat.into()));
+                }
+            },
+        ),
+    );
+
+    // geo_interpolate(lon1, lat1, lon2, lat2, n)
+    registry.register_passthrough_nullable_5_arg::<Float64Type, Float64Type, Float64Type, Float64Type, UInt64Type, ArrayType<KvPair<Float64Type, Float64Type>>, _, _>(
+        "geo_interpolate",
+        |_, _, _, _, _, _| FunctionDomain::Full,
+        vectorize_with_builder_5_arg::<Float64Type, Float64Type, Float64Type, Float64Type, UInt64Type, ArrayType<KvPair<Float64Type, Float64Type>>>(
+            |lon1, lat1, lon2, lat2, n, builder, ctx| {
+                if n < 2 {
+                    ctx.set_error(builder.len(), format!("the number of points must be at least 2, but got {n}"));
+                } else if n > MAX_GEO_PATH_POINTS as u64 {
+                    ctx.set_error(builder.len(), format!("the number of points must be at most {MAX_GEO_PATH_POINTS}, but got {n}"));
+                } else {
+                    for (lon, lat) in great_circle_points(lon1.0, lat1.0, lon2.0, lat2.0, n as usize) {
+                        builder.put_item((lon.into(), lat.into()));
+                    }
+                }
+                builder.commit_row();
+            },
+        ),
+    );
+
+    // geo_point_at_fraction(lon1, lat1, lon2, lat2, t)
+    registry.register_passthrough_nullable_5_arg::<Float64Type, Float64Type, Float64Type, Float64Type, Float64Type, KvPair<Float64Type, Float64Type>, _, _>(
+        "geo_point_at_fraction",
+        |_, _, _, _, _, _| FunctionDomain::Full,
+        vectorize_with_builder_5_arg::<Float64Type, Float64Type, Float64Type, Float64Type, Float64Type, KvPair<Float64Type, Float64Type>>(
+            |lon1, lat1, lon2, lat2, t, builder, _| {
+                let (lon, lat) = great_circle_fraction(lon1.0, lat1.0, lon2.0, lat2.0, t.0);
+                builder.push((lon.into(), lat.into()));
+            },
+        ),
+    );
+
+    // geo_simplify([(lon1, lat1), (lon2, lat2), ...], tolerance_m)
+    registry.register_passthrough_nullable_2_arg::<ArrayType<KvPair<Float64Type, Float64Type>>, Float64Type, ArrayType<KvPair<Float64Type, Float64Type>>, _, _>(
+        "geo_simplify",
+        |_, _, _| FunctionDomain::Full,
+        vectorize_with_builder_2_arg::<ArrayType<KvPair<Float64Type, Float64Type>>, Float64Type, ArrayType<KvPair<Float64Type, Float64Type>>>(
+            |ring, tolerance, builder, ctx| {
+                if tolerance.0 < 0.0 {
+                    ctx.set_error(builder.len(), format!("the tolerance must not be negative, but got {tolerance}"));
+                } else {
+                    let ring = ring.iter().map(|(lon, lat)| (lon.0, lat.0)).collect::<Vec<_>>();
+                    for (lon, lat) in simplify_path(&ring, tolerance.0) {
+                        builder.put_item((lon.into(), lat.into()));
+                    }
+                }
+                builder.commit_row();
+            },
+        ),
+    );
+
+    // geo_densify([(lon1, lat1), (lon2, lat2), ...], max_segment_m)
+    registry.register_passthrough_nullable_2_arg::<ArrayType<KvPair<Float64Type, Float64Type>>, Float64Type, ArrayType<KvPair<Float64Type, Float64Type>>, _, _>(
+        "geo_densify",
+        |_, _, _| FunctionDomain::Full,
+        vectorize_with_builder_2_arg::<ArrayType<KvPair<Float64Type, Float64Type>>, Float64Type, ArrayType<KvPair<Float64Type, Float64Type>>>(
+            |ring, max_segment, builder, ctx| {
+                if max_segment.0 > 0.0 {
+                    let ring = ring.iter().map(|(lon, lat)| (lon.0, lat.0)).collect::<Vec<_>>();
+                    match densify_path(&ring, max_segment.0) {
+                        Some(densified) => {
+                            for (lon, lat) in densified {
+                                builder.put_item((lon.into(), lat.into()));
+                            }
+                        }
+                        None => {
+                            ctx.set_error(builder.len(), format!("the densified path must have at most {MAX_GEO_PATH_POINTS} points, but the max segment length {max_segment} is too short"));
+                        }
+                    }
+                } else {
+                    ctx.set_error(builder.len(), format!("the max segment length must be positive, but got {max_segment}"));
+                }
+                builder.commit_row();
+            },
+        ),
+    );
+
+    // geo_distance_to_polygon(lon, lat, [(lon1, lat1), (lon2, lat2), ...])
+    registry.register_passthrough_nullable_3_arg::<Float64Type, Float64Type, ArrayType<KvPair<Float64Type, Float64Type>>, Float64Type, _, _>(
+        "geo_distance_to_polygon",
+        |_, _, _, _| FunctionDomain::Full,
+        vectorize_with_builder_3_arg::<Float64Type, Float64Type, ArrayType<KvPair<Float64Type, Float64Type>>, Float64Type>(
+            |lon, lat, ring, builder, ctx| {
+                if ring.len() == 0 {
+                    ctx.set_error(builder.len(), "the ring must not be empty");
+                    builder.push(F64::from(0.0));
+                } else {
+                    let ring = ring.iter().map(|(lon, lat)| coord! { x: lon.0, y: lat.0 }).collect::<Vec<_>>();
+                    builder.push(polygon_distance(lon.0, lat.0, &ring).into());
+                }
+            },
+        ),
+    );
+
     // point in ellipses
     registry.register_function_factory("point_in_ellipses", |_, args_type| {
         // The input parameters must be 2+4*n, where n is the number of ellipses.
@@ -212,6 +682,198 @@ pub fn register(registry: &mut FunctionRegistry) {
         }))
     });
 
+    // geo_triangle_area(lon1, lat1, lon2, lat2, lon3, lat3)
+    registry.register_function_factory("geo_triangle_area", |_, args_type| {
+        if args_type.len() != 6 {
+            return None;
+        }
+        let has_null = args_type.iter().any(|t| t.is_nullable_or_null());
+        let f = Function {
+            signature: FunctionSignature {
+                name: "geo_triangle_area".to_string(),
+                args_type: vec![DataType::Number(NumberDataType::Float64); 6],
+                return_type: DataType::Number(NumberDataType::Float64),
+            },
+            eval: FunctionEval::Scalar {
+                calc_domain: Box::new(|_, _| FunctionDomain::Full),
+                eval: Box::new(geo_triangle_area_fn),
+            },
+        };
+        if has_null {
+            Some(Arc::new(f.passthrough_nullable()))
+        } else {
+            Some(Arc::new(f))
+        }
+    });
+
+    // geo_cross_track_distance(lon, lat, path_lon1, path_lat1, path_lon2, path_lat2)
+    registry.register_function_factory("geo_cross_track_distance", |_, args_type| {
+        if args_type.len() != 6 {
+            return None;
+        }
+        let has_null = args_type.iter().any(|t| t.is_nullable_or_null());
+        let f = Function {
+            signature: FunctionSignature {
+                name: "geo_cross_track_distance".to_string(),
+                args_type: vec![DataType::Number(NumberDataType::Float64); 6],
+                return_type: DataType::Number(NumberDataType::Float64),
+            },
+            eval: FunctionEval::Scalar {
+                calc_domain: Box::new(|_, _| FunctionDomain::Full),
+                eval: Box::new(geo_cross_track_distance_fn),
+            },
+        };
+        if has_null {
+            Some(Arc::new(f.passthrough_nullable()))
+        } else {
+            Some(Arc::new(f))
+        }
+    });
+
+    // geo_on_path(lon, lat, path_lon1, path_lat1, path_lon2, path_lat2, tol_m)
+    registry.register_function_factory("geo_on_path", |_, args_type| {
+        if args_type.len() != 7 {
+            return None;
+        }
+        let has_null = args_type.iter().any(|t| t.is_nullable_or_null());
+        let f = Function {
+            signature: FunctionSignature {
+                name: "geo_on_path".to_string(),
+                args_type: vec![DataType::Number(NumberDataType::Float64); 7],
+                return_type: DataType::Boolean,
+            },
+            eval: FunctionEval::Scalar {
+                calc_domain: Box::new(|_, _| FunctionDomain::Full),
+                eval: Box::new(geo_on_path_fn),
+            },
+        };
+        if has_null {
+            Some(Arc::new(f.passthrough_nullable()))
+        } else {
+            Some(Arc::new(f))
+        }
+    });
+
+    // geo_along_track_fraction(path_lon1, path_lat1, path_lon2, path_lat2, lon, lat)
+    registry.register_function_factory("geo_along_track_fraction", |_, args_type| {
+        if args_type.len() != 6 {
+            return None;
+        }
+        let has_null = args_type.iter().any(|t| t.is_nullable_or_null());
+        let f = Function {
+            signature: FunctionSignature {
+                name: "geo_along_track_fraction".to_string(),
+                args_type: vec![DataType::Number(NumberDataType::Float64); 6],
+                return_type: DataType::Number(NumberDataType::Float64),
+            },
+            eval: FunctionEval::Scalar {
+                calc_domain: Box::new(|_, _| FunctionDomain::Full),
+                eval: Box::new(geo_along_track_fraction_fn),
+            },
+        };
+        if has_null {
+            Some(Arc::new(f.passthrough_nullable()))
+        } else {
+            Some(Arc::new(f))
+        }
+    });
+
+    // geo_turn_angle(lon1, lat1, lon2, lat2, lon3, lat3)
+    registry.register_function_factory("geo_turn_angle", |_, args_type| {
+        if args_type.len() != 6 {
+            return None;
+        }
+        let has_null = args_type.iter().any(|t| t.is_nullable_or_null());
+        let f = Function {
+            signature: FunctionSignature {
+                name: "geo_turn_angle".to_string(),
+                args_type: vec![DataType::Number(NumberDataType::Float64); 6],
+                return_type: DataType::Number(NumberDataType::Float64),
+            },
+            eval: FunctionEval::Scalar {
+                calc_domain: Box::new(|_, _| FunctionDomain::Full),
+                eval: Box::new(geo_turn_angle_fn),
+            },
+        };
+        if has_null {
+            Some(Arc::new(f.passthrough_nullable()))
+        } else {
+            Some(Arc::new(f))
+        }
+    });
+
+    // geo_boxes_intersect(min_lon1, min_lat1, max_lon1, max_lat1, min_lon2, ..., max_lat2)
+    registry.register_function_factory("geo_boxes_intersect", |_, args_type| {
+        if args_type.len() != 8 {
+            return None;
+        }
+        let has_null = args_type.iter().any(|t| t.is_nullable_or_null());
+        let f = Function {
+            signature: FunctionSignature {
+                name: "geo_boxes_intersect".to_string(),
+                args_type: vec![DataType::Number(NumberDataType::Float64); 8],
+                return_type: DataType::Boolean,
+            },
+            eval: FunctionEval::Scalar {
+                calc_domain: Box::new(|_, _| FunctionDomain::Full),
+                eval: Box::new(geo_boxes_intersect_fn),
+            },
+        };
+        if has_null {
+            Some(Arc::new(f.passthrough_nullable()))
+        } else {
+            Some(Arc::new(f))
+        }
+    });
+
+    // geo_box_intersection_area(min_lon1, min_lat1, max_lon1, max_lat1, min_lon2, ..., max_lat2)
+    registry.register_function_factory("geo_box_intersection_area", |_, args_type| {
+        if args_type.len() != 8 {
+            return None;
+        }
+        let has_null = args_type.iter().any(|t| t.is_nullable_or_null());
+        let f = Function {
+            signature: FunctionSignature {
+                name: "geo_box_intersection_area".to_string(),
+                args_type: vec![DataType::Number(NumberDataType::Float64); 8],
+                return_type: DataType::Number(NumberDataType::Float64),
+            },
+            eval: FunctionEval::Scalar {
+                calc_domain: Box::new(|_, _| FunctionDomain::Full),
+                eval: Box::new(geo_box_intersection_area_fn),
+            },
+        };
+        if has_null {
+            Some(Arc::new(f.passthrough_nullable()))
+        } else {
+            Some(Arc::new(f))
+        }
+    });
+
+    // geo_bbox_around(lon, lat, radius_m)
+    registry.register_function_factory("geo_bbox_around", |_, args_type| {
+        if args_type.len() != 3 {
+            return None;
+        }
+        let has_null = args_type.iter().any(|t| t.is_nullable_or_null());
+        let f = Function {
+            signature: FunctionSignature {
+                name: "geo_bbox_around".to_string(),
+                args_type: vec![DataType::Number(NumberDataType::Float64); 3],
+                return_type: DataType::Tuple(vec![DataType::Number(NumberDataType::Float64); 4]),
+            },
+            eval: FunctionEval::Scalar {
+                calc_domain: Box::new(|_, _| FunctionDomain::Full),
+                eval: Box::new(geo_bbox_around_fn),
+            },
+        };
+        if has_null {
+            Some(Arc::new(f.passthrough_nullable()))
+        } else {
+            Some(Arc::new(f))
+        }
+    });
+
     // simple polygon
     // point_in_polygon((x, y), [(x1, y1), (x2, y2), ...])
     registry.register_function_factory("point_in_polygon", |_, args_type| {
@@ -491,7 +1153,307 @@ fn point_in_polygon_fn(args: &[ValueRef<AnyType>], _: &mut EvalContext) -> Value
 
         let is_in = poly.contains(&point);
 
-        builder.push(NumberScalar::UInt8(u8::from(is_in)));
+        builder.push(NumberScalar::UInt8(u8::from(is_in)));
+    }
+
+    match len {
+        Some(_) => Value::Column(Column::Number(builder.build())),
+        _ => Value::Scalar(Scalar::Number(builder.build_scalar())),
+    }
+}
+
+fn point_in_ellipses_fn(args: &[ValueRef<AnyType>], _: &mut EvalContext) -> Value<AnyType> {
+    let len = args.iter().find_map(|arg| match arg {
+        ValueRef::Column(col) => Some(col.len()),
+        _ => None,
+    });
+    let args = args
+        .iter()
+        .map(|arg| arg.try_downcast::<Float64Type>().unwrap())
+        .collect::<Vec<_>>();
+
+    let input_rows = len.unwrap_or(1);
+
+    let ellipses_cnt = (args.len() - 2) / 4;
+    let mut ellipses: Vec<Ellipse> = Vec::with_capacity(ellipses_cnt);
+
+    for ellipse_idx in 0..ellipses_cnt {
+        let mut ellipse_data = [0.0; 4];
+        for (idx, e_data) in ellipse_data.iter_mut().enumerate() {
+            let arg_idx = 2 + 4 * ellipse_idx + idx;
+            *e_data = match args[arg_idx] {
+                ValueRef::Scalar(v) => *v,
+                _ => 0f64,
+            };
+        }
+        ellipses.push(Ellipse {
+            x: ellipse_data[0],
+            y: ellipse_data[1],
+            a: ellipse_data[2],
+            b: ellipse_data[3],
+        });
+    }
+
+    let mut start_index = 0;
+    let mut builder = NumberColumnBuilder::with_capacity(&NumberDataType::UInt8, input_rows);
+    for idx in 0..input_rows {
+        let col_x = match &args[0] {
+            ValueRef::Scalar(v) => *v,
+            ValueRef::Column(c) => unsafe { Float64Type::index_column_unchecked(c, idx) },
+        };
+        let col_y = match &args[1] {
+            ValueRef::Scalar(v) => *v,
+            ValueRef::Column(c) => unsafe { Float64Type::index_column_unchecked(c, idx) },
+        };
+
+        let r = u8::from(is_point_in_ellipses(
+            col_x.0,
+            col_y.0,
+            &ellipses,
+            ellipses_cnt,
+            &mut start_index,
+        ));
+        builder.push(NumberScalar::UInt8(r));
+    }
+
+    match len {
+        Some(_) => Value::Column(Column::Number(builder.build())),
+        _ => Value::Scalar(Scalar::Number(builder.build_scalar())),
+    }
+}
+
+fn geo_triangle_area_fn(args: &[ValueRef<AnyType>], _: &mut EvalContext) -> Value<AnyType> {
+    let len = args.iter().find_map(|arg| match arg {
+        ValueRef::Column(col) => Some(col.len()),
+        _ => None,
+    });
+    let args = args
+        .iter()
+        .map(|arg| arg.try_downcast::<Float64Type>().unwrap())
+        .collect::<Vec<_>>();
+
+    let input_rows = len.unwrap_or(1);
+    let mut builder = NumberColumnBuilder::with_capacity(&NumberDataType::Float64, input_rows);
+    for idx in 0..input_rows {
+        let mut coords = [0f64; 6];
+        for (arg, coord) in args.iter().zip(coords.iter_mut()) {
+            *coord = match arg {
+                ValueRef::Scalar(v) => v.0,
+                ValueRef::Column(c) => unsafe { Float64Type::index_column_unchecked(c, idx).0 },
+            };
+        }
+        let [lon1, lat1, lon2, lat2, lon3, lat3] = coords;
+        let area = spherical_triangle_area(lon1, lat1, lon2, lat2, lon3, lat3);
+        builder.push(NumberScalar::Float64(area.into()));
+    }
+
+    match len {
+        Some(_) => Value::Column(Column::Number(builder.build())),
+        _ => Value::Scalar(Scalar::Number(builder.build_scalar())),
+    }
+}
+
+fn geo_cross_track_distance_fn(args: &[ValueRef<AnyType>], _: &mut EvalContext) -> Value<AnyType> {
+    let len = args.iter().find_map(|arg| match arg {
+        ValueRef::Column(col) => Some(col.len()),
+        _ => None,
+    });
+    let args = args
+        .iter()
+        .map(|arg| arg.try_downcast::<Float64Type>().unwrap())
+        .collect::<Vec<_>>();
+
+    let input_rows = len.unwrap_or(1);
+    let mut builder = NumberColumnBuilder::with_capacity(&NumberDataType::Float64, input_rows);
+    for idx in 0..input_rows {
+        let mut coords = [0f64; 6];
+        for (arg, coord) in args.iter().zip(coords.iter_mut()) {
+            *coord = match arg {
+                ValueRef::Scalar(v) => v.0,
+                ValueRef::Column(c) => unsafe { Float64Type::index_column_unchecked(c, idx).0 },
+            };
+        }
+        let [lon, lat, lon1, lat1, lon2, lat2] = coords;
+        let distance = cross_track_distance(lon, lat, lon1, lat1, lon2, lat2);
+        builder.push(NumberScalar::Float64(distance.into()));
+    }
+
+    match len {
+        Some(_) => Value::Column(Column::Number(builder.build())),
+        _ => Value::Scalar(Scalar::Number(builder.build_scalar())),
+    }
+}
+
+fn geo_on_path_fn(args: &[ValueRef<AnyType>], _: &mut EvalContext) -> Value<AnyType> {
+    let len = args.iter().find_map(|arg| match arg {
+        ValueRef::Column(col) => Some(col.len()),
+        _ => None,
+    });
+    let args = args
+        .iter()
+        .map(|arg| arg.try_downcast::<Float64Type>().unwrap())
+        .collect::<Vec<_>>();
+
+    let input_rows = len.unwrap_or(1);
+    let mut builder = MutableBitmap::with_capacity(input_rows);
+    for idx in 0..input_rows {
+        let mut values = [0f64; 7];
+        for (arg, value) in args.iter().zip(values.iter_mut()) {
+            *value = match arg {
+                ValueRef::Scalar(v) => v.0,
+                ValueRef::Column(c) => unsafe { Float64Type::index_column_unchecked(c, idx).0 },
+            };
+        }
+        let [lon, lat, lon1, lat1, lon2, lat2, tolerance] = values;
+        builder.push(is_on_path(lon, lat, lon1, lat1, lon2, lat2, tolerance));
+    }
+
+    match len {
+        Some(_) => Value::Column(Column::Boolean(builder.into())),
+        _ => Value::Scalar(Scalar::Boolean(builder.get(0))),
+    }
+}
+
+fn geo_along_track_fraction_fn(args: &[ValueRef<AnyType>], _: &mut EvalContext) -> Value<AnyType> {
+    let len = args.iter().find_map(|arg| match arg {
+        ValueRef::Column(col) => Some(col.len()),
+        _ => None,
+    });
+    let args = args
+        .iter()
+        .map(|arg| arg.try_downcast::<Float64Type>().unwrap())
+        .collect::<Vec<_>>();
+
+    let input_rows = len.unwrap_or(1);
+    let mut builder = NumberColumnBuilder::with_capacity(&NumberDataType::Float64, input_rows);
+    for idx in 0..input_rows {
+        let mut coords = [0f64; 6];
+        for (arg, coord) in args.iter().zip(coords.iter_mut()) {
+            *coord = match arg {
+                ValueRef::Scalar(v) => v.0,
+                ValueRef::Column(c) => unsafe { Float64Type::index_column_unchecked(c, idx).0 },
+            };
+        }
+        let [lon1, lat1, lon2, lat2, lon, lat] = coords;
+        let fraction = along_track_fraction(lon, lat, lon1, lat1, lon2, lat2);
+        builder.push(NumberScalar::Float64(fraction.into()));
+    }
+
+    match len {
+        Some(_) => Value::Column(Column::Number(builder.build())),
+        _ => Value::Scalar(Scalar::Number(builder.build_scalar())),
+    }
+}
+
+fn geo_turn_angle_fn(args: &[ValueRef<AnyType>], _: &mut EvalContext) -> Value<AnyType> {
+    let len = args.iter().find_map(|arg| match arg {
+        ValueRef::Column(col) => Some(col.len()),
+        _ => None,
+    });
+    let args = args
+        .iter()
+        .map(|arg| arg.try_downcast::<Float64Type>().unwrap())
+        .collect::<Vec<_>>();
+
+    let input_rows = len.unwrap_or(1);
+    let mut builder = NumberColumnBuilder::with_capacity(&NumberDataType::Float64, input_rows);
+    for idx in 0..input_rows {
+        let mut coords = [0f64; 6];
+        for (arg, coord) in args.iter().zip(coords.iter_mut()) {
+            *coord = match arg {
+                ValueRef::Scalar(v) => v.0,
+                ValueRef::Column(c) => unsafe { Float64Type::index_column_unchecked(c, idx).0 },
+            };
+        }
+        let [lon1, lat1, lon2, lat2, lon3, lat3] = coords;
+        let angle = turn_angle(lon1, lat1, lon2, lat2, lon3, lat3);
+        builder.push(NumberScalar::Float64(angle.into()));
+    }
+
+    match len {
+        Some(_) => Value::Column(Column::Number(builder.build())),
+        _ => Value::Scalar(Scalar::Number(builder.build_scalar())),
+    }
+}
+
+fn geo_boxes_intersect_fn(args: &[ValueRef<AnyType>], _: &mut EvalContext) -> Value<AnyType> {
+    let len = args.iter().find_map(|arg| match arg {
+        ValueRef::Column(col) => Some(col.len()),
+        _ => None,
+    });
+    let args = args
+        .iter()
+        .map(|arg| arg.try_downcast::<Float64Type>().unwrap())
+        .collect::<Vec<_>>();
+
+    let input_rows = len.unwrap_or(1);
+    let mut builder = MutableBitmap::with_capacity(input_rows);
+    for idx in 0..input_rows {
+        let mut boxes = [[0f64; 4]; 2];
+        for (arg, coord) in args.iter().zip(boxes.iter_mut().flatten()) {
+            *coord = match arg {
+                ValueRef::Scalar(v) => v.0,
+                ValueRef::Column(c) => unsafe { Float64Type::index_column_unchecked(c, idx).0 },
+            };
+        }
+        builder.push(boxes_intersect(boxes[0], boxes[1]));
+    }
+
+    match len {
+        Some(_) => Value::Column(Column::Boolean(builder.into())),
+        _ => Value::Scalar(Scalar::Boolean(builder.get(0))),
+    }
+}
+
+/// Checks whether two bounding boxes `[min_lon, min_lat, max_lon, max_lat]` overlap, boxes
+/// touching at an edge or a corner overlap.
+///
+/// A box whose `min_lon` is greater than its `max_lon` crosses the antimeridian, it spans
+/// eastward from `min_lon` to 180 and on from -180 to `max_lon`. The longitudes -180 and 180
+/// are the same meridian, and a box from -180 to 180 covers all the longitudes.
+fn boxes_intersect(box1: [f64; 4], box2: [f64; 4]) -> bool {
+    let [min_lon1, min_lat1, max_lon1, max_lat1] = box1;
+    let [min_lon2, min_lat2, max_lon2, max_lat2] = box2;
+    if min_lat1 > max_lat2 || min_lat2 > max_lat1 {
+        return false;
+    }
+    // The longitudes are arcs going eastward, two arcs overlap if one of them starts
+    // within the other.
+    (min_lon2 - min_lon1).rem_euclid(360.0) <= box_width(min_lon1, max_lon1)
+        || (min_lon1 - min_lon2).rem_euclid(360.0) <= box_width(min_lon2, max_lon2)
+}
+
+/// The width in degrees of longitude of a bounding box, see [`boxes_intersect`].
+fn box_width(min_lon: f64, max_lon: f64) -> f64 {
+    if min_lon <= max_lon {
+        max_lon - min_lon
+    } else {
+        max_lon - min_lon + 360.0
+    }
+}
+
+fn geo_box_intersection_area_fn(args: &[ValueRef<AnyType>], _: &mut EvalContext) -> Value<AnyType> {
+    let len = args.iter().find_map(|arg| match arg {
+        ValueRef::Column(col) => Some(col.len()),
+        _ => None,
+    });
+    let args = args
+        .iter()
+        .map(|arg| arg.try_downcast::<Float64Type>().unwrap())
+        .collect::<Vec<_>>();
+
+    let input_rows = len.unwrap_or(1);
+    let mut builder = NumberColumnBuilder::with_capacity(&NumberDataType::Float64, input_rows);
+    for idx in 0..input_rows {
+        let mut boxes = [[0f64; 4]; 2];
+        for (arg, coord) in args.iter().zip(boxes.iter_mut().flatten()) {
+            *coord = match arg {
+                ValueRef::Scalar(v) => v.0,
+                ValueRef::Column(c) => unsafe { Float64Type::index_column_unchecked(c, idx).0 },
+            };
+        }
+        let area = box_intersection_area(boxes[0], boxes[1]);
+        builder.push(NumberScalar::Float64(area.into()));
     }
 
     match len {
@@ -500,7 +1462,56 @@ fn point_in_polygon_fn(args: &[ValueRef<AnyType>], _: &mut EvalContext) -> Value
     }
 }
 
-fn point_in_ellipses_fn(args: &[ValueRef<AnyType>], _: &mut EvalContext) -> Value<AnyType> {
+/// The area in square meters of the overlap of two bounding boxes `[min_lon, min_lat,
+/// max_lon, max_lat]`, 0 if they are disjoint or only touch at an edge or a corner.
+///
+/// The boxes cross the antimeridian when their `min_lon` is greater than their `max_lon`,
+/// as in [`boxes_intersect`], so the overlap of the longitudes may be in two parts, e.g.
+/// the boxes from 170 to -170 and from -175 to 175 overlap from 170 to 175 and from -175
+/// to -170. The latitudes are clamped at the poles.
+///
+/// The area of the WGS84 ellipsoid is the product of the meters per degree of latitude and
+/// of longitude of `geo_distance`, integrated over the latitudes with the trapezoidal rule
+/// in steps no longer than those of its lookup table.
+fn box_intersection_area(box1: [f64; 4], box2: [f64; 4]) -> f64 {
+    let [min_lon1, min_lat1, max_lon1, max_lat1] = box1;
+    let [min_lon2, min_lat2, max_lon2, max_lat2] = box2;
+    let min_lat = min_lat1.max(min_lat2).max(-90.0);
+    let max_lat = max_lat1.min(max_lat2).min(90.0);
+    let width = arcs_overlap(
+        min_lon1,
+        box_width(min_lon1, max_lon1),
+        min_lon2,
+        box_width(min_lon2, max_lon2),
+    );
+    if min_lat >= max_lat || width <= 0.0 {
+        return 0.0;
+    }
+
+    let square_meters_per_degree = |lat: f64| {
+        let (meters_per_lat, meters_per_lon) = wgs84_meters_per_degree(lat);
+        meters_per_lat * meters_per_lon
+    };
+    let steps = ((max_lat - min_lat) * METRIC_LUT_SIZE as f64 / 180.0).ceil() as usize;
+    let step = (max_lat - min_lat) / steps as f64;
+    let mut sum = (square_meters_per_degree(min_lat) + square_meters_per_degree(max_lat)) / 2.0;
+    for i in 1..steps {
+        sum += square_meters_per_degree(min_lat + i as f64 * step);
+    }
+    width * step * sum
+}
+
+/// The length in degrees of the overlap of two arcs of longitudes going eastward from
+/// `start1` and `start2`, of `width1` and `width2` degrees.
+fn arcs_overlap(start1: f64, width1: f64, start2: f64, width2: f64) -> f64 {
+    // With the first arc from 0 to `width1`, the second one starts in [0, 360) and may
+    // wrap around onto the start of the first one.
+    let start = (start2 - start1).rem_euclid(360.0);
+    let overlap = |start: f64| (width1.min(start + width2) - start.max(0.0)).max(0.0);
+    overlap(start) + overlap(start - 360.0)
+}
+
+fn geo_bbox_around_fn(args: &[ValueRef<AnyType>], ctx: &mut EvalContext) -> Value<AnyType> {
     let len = args.iter().find_map(|arg| match arg {
         ValueRef::Column(col) => Some(col.len()),
         _ => None,
@@ -511,53 +1522,705 @@ fn point_in_ellipses_fn(args: &[ValueRef<AnyType>], _: &mut EvalContext) -> Valu
         .collect::<Vec<_>>();
 
     let input_rows = len.unwrap_or(1);
+    let mut builders: [NumberColumnBuilder; 4] = std::array::from_fn(|_| {
+        NumberColumnBuilder::with_capacity(&NumberDataType::Float64, input_rows)
+    });
+    for idx in 0..input_rows {
+        let mut values = [0f64; 3];
+        for (arg, value) in args.iter().zip(values.iter_mut()) {
+            *value = match arg {
+                ValueRef::Scalar(v) => v.0,
+                ValueRef::Column(c) => unsafe { Float64Type::index_column_unchecked(c, idx).0 },
+            };
+        }
+        let [lon, lat, radius] = values;
+        let bbox = if !(-90.0..=90.0).contains(&lat) {
+            ctx.set_error(
+                idx,
+                format!("the latitude must be between -90 and 90, but got {lat}"),
+            );
+            [0f64; 4]
+        } else if radius < 0.0 {
+            ctx.set_error(
+                idx,
+                format!("the radius must not be negative, but got {radius}"),
+            );
+            [0f64; 4]
+        } else {
+            bbox_around(lon, lat, radius)
+        };
+        for (builder, value) in builders.iter_mut().zip(bbox) {
+            builder.push(NumberScalar::Float64(value.into()));
+        }
+    }
 
-    let ellipses_cnt = (args.len() - 2) / 4;
-    let mut ellipses: Vec<Ellipse> = Vec::with_capacity(ellipses_cnt);
+    match len {
+        Some(_) => Value::Column(Column::Tuple(
+            builders
+                .into_iter()
+                .map(|builder| Column::Number(builder.build()))
+                .collect(),
+        )),
+        _ => Value::Scalar(Scalar::Tuple(
+            builders
+                .into_iter()
+                .map(|builder| Scalar::Number(builder.build_scalar()))
+                .collect(),
+        )),
+    }
+}
 
-    for ellipse_idx in 0..ellipses_cnt {
-        let mut ellipse_data = [0.0; 4];
-        for (idx, e_data) in ellipse_data.iter_mut().enumerate() {
-            let arg_idx = 2 + 4 * ellipse_idx + idx;
-            *e_data = match args[arg_idx] {
-                ValueRef::Scalar(v) => *v,
-                _ => 0f64,
+/// The bounding box `[min_lon, min_lat, max_lon, max_lat]` enclosing the circle of
+/// `radius` meters around (lon, lat), meant to pre-filter the points before computing
+/// their exact distances.
+///
+/// The degrees of latitude and of longitude spanned by the radius come from the meters
+/// per degree of the WGS84 ellipsoid used by `geo_distance`. As a degree of longitude
+/// shrinks towards the poles, the half width of the box is taken at its edge closest to
+/// the pole, so that the box encloses the whole circle, a bit loosely at high latitudes.
+///
+/// The latitudes are clamped at the poles, and a box reaching a pole, or wider than the
+/// whole globe, covers all the longitudes from -180 to 180. Otherwise the longitudes are
+/// wrapped into [-180, 180], and a box crossing the antimeridian has a `min_lon` greater
+/// than its `max_lon`, as expected by `geo_boxes_intersect`.
+fn bbox_around(lon: f64, lat: f64, radius: f64) -> [f64; 4] {
+    let (meters_per_lat, _) = wgs84_meters_per_degree(lat);
+    let lat_radius = radius / meters_per_lat;
+    let min_lat = (lat - lat_radius).max(-90.0);
+    let max_lat = (lat + lat_radius).min(90.0);
+
+    let edge_lat = min_lat.abs().max(max_lat.abs());
+    let lon_radius = if edge_lat < 90.0 {
+        radius / wgs84_meters_per_degree(edge_lat).1
+    } else {
+        f64::INFINITY
+    };
+    if lon_radius >= 180.0 {
+        return [-180.0, min_lat, 180.0, max_lat];
+    }
+    [
+        longitude_diff(0.0, lon - lon_radius),
+        min_lat,
+        longitude_diff(0.0, lon + lon_radius),
+        max_lat,
+    ]
+}
+
+/// The meters per degree of latitude and of longitude at `lat` on the WGS84 ellipsoid,
+/// interpolated in the lookup table of `geo_distance`.
+fn wgs84_meters_per_degree(lat: f64) -> (f64, f64) {
+    let wgs84_metric_meters_lut = WGS84_METRIC_METERS_LUT.get().unwrap();
+    let position = (lat + 90.0) * METRIC_LUT_SIZE as f64 / 180.0;
+    let index = (position as usize).min(METRIC_LUT_SIZE - 1);
+    let fraction = position - index as f64;
+    let interpolate = |offset: usize| {
+        let low = wgs84_metric_meters_lut[index * 2 + offset] as f64;
+        let high = wgs84_metric_meters_lut[(index + 1) * 2 + offset] as f64;
+        (low + (high - low) * fraction).sqrt()
+    };
+    (interpolate(0), interpolate(1))
+}
+
+/// Central angle in radians between two points given in degrees, using the haversine formula.
+fn central_angle(lon1deg: f64, lat1deg: f64, lon2deg: f64, lat2deg: f64) -> f64 {
+    let lat1 = lat1deg.to_radians();
+    let lat2 = lat2deg.to_radians();
+    let sin_lat = ((lat2 - lat1) / 2.0).sin();
+    let sin_lon = ((lon2deg - lon1deg).to_radians() / 2.0).sin();
+    let h = sin_lat * sin_lat + lat1.cos() * lat2.cos() * (sin_lon * sin_lon);
+    2.0 * h.sqrt().min(1.0).asin()
+}
+
+/// Distance in meters with the equirectangular approximation: the longitude difference,
+/// across the antimeridian when shorter, is scaled by the cosine of the mean latitude and
+/// combined with the latitude difference as on a plane. It is within a millimeter of the
+/// great circle distance for points a few kilometers apart, closer than the f32 lookup
+/// tables of `great_circle_distance`, but the error grows quickly with the distance and
+/// towards the poles, e.g. 830 km too long for points 14000 km apart.
+fn equirect_distance(lon1: f64, lat1: f64, lon2: f64, lat2: f64) -> f64 {
+    let x = longitude_diff(lon1, lon2).to_radians() * ((lat1 + lat2) / 2.0).to_radians().cos();
+    let y = (lat2 - lat1).to_radians();
+    EARTH_RADIUS_F64 * (x * x + y * y).sqrt()
+}
+
+/// Distance in meters along the rhumb line (loxodrome) from the first point to the second,
+/// on the sphere of the mean earth radius. The rhumb line crosses all the meridians at the
+/// same angle, so it is a straight line on a Mercator map, and longer than the great
+/// circle except along the equator or a meridian. It goes across the antimeridian when
+/// that is shorter.
+fn rhumb_distance(lon1: f64, lat1: f64, lon2: f64, lat2: f64) -> f64 {
+    let (lat_diff, lon_diff, mercator_diff) = rhumb_diffs(lon1, lat1, lon2, lat2);
+    // The ratio of the latitude difference to the Mercator one is the cosine of the
+    // latitude when both points are on the same parallel, where it is 0 / 0.
+    let stretch = if mercator_diff.abs() > RHUMB_MIN_MERCATOR_DIFF {
+        lat_diff / mercator_diff
+    } else {
+        lat1.to_radians().cos()
+    };
+    EARTH_RADIUS_F64 * (lat_diff * lat_diff + stretch * stretch * lon_diff * lon_diff).sqrt()
+}
+
+/// The bearing in degrees in [0, 360) of the rhumb line from the first point to the
+/// second, clockwise from the north. Unlike the bearing along a great circle it is the
+/// same at every point of the line, 90 or 270 for two points on the same parallel.
+fn rhumb_bearing(lon1: f64, lat1: f64, lon2: f64, lat2: f64) -> f64 {
+    let (_, lon_diff, mercator_diff) = rhumb_diffs(lon1, lat1, lon2, lat2);
+    lon_diff.atan2(mercator_diff).to_degrees().rem_euclid(360.0)
+}
+
+/// The differences in radians of the latitudes, of the longitudes and of the latitudes
+/// stretched by the Mercator projection from the first point to the second.
+fn rhumb_diffs(lon1: f64, lat1: f64, lon2: f64, lat2: f64) -> (f64, f64, f64) {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let mercator_diff = ((PI / 4.0 + lat2 / 2.0).tan() / (PI / 4.0 + lat1 / 2.0).tan()).ln();
+    (
+        lat2 - lat1,
+        longitude_diff(lon1, lon2).to_radians(),
+        mercator_diff,
+    )
+}
+
+/// Distance in meters computed with `method`:
+/// - 'fast': the lookup tables of `geo_distance`, without its rounding to f32. Points less
+///   than 13 degrees of longitude apart are within 0.1% with the WGS84 meters per degree,
+///   e.g. 34 centimeters too long for points 55 km apart, others are on the sphere.
+/// - 'haversine': the great circle distance on the sphere of the mean earth radius, in
+///   f64. Up to 0.5% off, as the earth is flattened at the poles.
+/// - 'vincenty': the geodesic distance on the WGS84 ellipsoid with Vincenty's inverse
+///   formula, within a millimeter. It is an error for nearly antipodal points where the
+///   formula doesn't converge.
+fn distance_by_method(
+    lon1: f64,
+    lat1: f64,
+    lon2: f64,
+    lat2: f64,
+    method: &str,
+) -> Result<f64, String> {
+    match method {
+        "fast" => Ok(distance_f64(
+            lon1 as f32,
+            lat1 as f32,
+            lon2 as f32,
+            lat2 as f32,
+            GeoMethod::Wgs84Meters,
+        )),
+        "haversine" => Ok(EARTH_RADIUS_F64 * central_angle(lon1, lat1, lon2, lat2)),
+        "vincenty" => vincenty_distance(lon1, lat1, lon2, lat2).ok_or_else(|| {
+            format!(
+                "the vincenty distance doesn't converge for the nearly antipodal points ({lon1}, {lat1}) and ({lon2}, {lat2})"
+            )
+        }),
+        _ => Err(format!(
+            "the method must be 'fast', 'haversine' or 'vincenty', but got '{method}'"
+        )),
+    }
+}
+
+/// Geodesic distance in meters on the WGS84 ellipsoid with Vincenty's inverse formula,
+/// or None if the iteration on the longitude difference on the auxiliary sphere doesn't
+/// converge. See <https://en.wikipedia.org/wiki/Vincenty%27s_formulae>.
+fn vincenty_distance(lon1: f64, lat1: f64, lon2: f64, lat2: f64) -> Option<f64> {
+    let b = WGS84_A * (1.0 - WGS84_F);
+    let l = longitude_diff(lon1, lon2).to_radians();
+    // the reduced latitudes
+    let (sin_u1, cos_u1) = ((1.0 - WGS84_F) * lat1.to_radians().tan()).atan().sin_cos();
+    let (sin_u2, cos_u2) = ((1.0 - WGS84_F) * lat2.to_radians().tan()).atan().sin_cos();
+
+    let mut lambda = l;
+    for _ in 0..VINCENTY_MAX_ITERATIONS {
+        let (sin_lambda, cos_lambda) = lambda.sin_cos();
+        let sin_sigma = ((cos_u2 * sin_lambda).powi(2)
+            + (cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_lambda).powi(2))
+        .sqrt();
+        if sin_sigma == 0.0 {
+            // coincident points
+            return Some(0.0);
+        }
+        let cos_sigma = sin_u1 * sin_u2 + cos_u1 * cos_u2 * cos_lambda;
+        let sigma = sin_sigma.atan2(cos_sigma);
+        let sin_alpha = cos_u1 * cos_u2 * sin_lambda / sin_sigma;
+        let cos_sq_alpha = 1.0 - sin_alpha * sin_alpha;
+        // the geodesic is along the equator
+        let cos_2sigma_m = if cos_sq_alpha == 0.0 {
+            0.0
+        } else {
+            cos_sigma - 2.0 * sin_u1 * sin_u2 / cos_sq_alpha
+        };
+        let c = WGS84_F / 16.0 * cos_sq_alpha * (4.0 + WGS84_F * (4.0 - 3.0 * cos_sq_alpha));
+        let prev_lambda = lambda;
+        lambda = l
+            + (1.0 - c)
+                * WGS84_F
+                * sin_alpha
+                * (sigma
+                    + c * sin_sigma
+                        * (cos_2sigma_m + c * cos_sigma * (-1.0 + 2.0 * cos_2sigma_m.powi(2))));
+
+        if (lambda - prev_lambda).abs() < VINCENTY_EPSILON {
+            let u_sq = cos_sq_alpha * (WGS84_A * WGS84_A - b * b) / (b * b);
+            let big_a =
+                1.0 + u_sq / 16384.0 * (4096.0 + u_sq * (-768.0 + u_sq * (320.0 - 175.0 * u_sq)));
+            let big_b = u_sq / 1024.0 * (256.0 + u_sq * (-128.0 + u_sq * (74.0 - 47.0 * u_sq)));
+            let delta_sigma = big_b
+                * sin_sigma
+                * (cos_2sigma_m
+                    + big_b / 4.0
+                        * (cos_sigma * (-1.0 + 2.0 * cos_2sigma_m.powi(2))
+                            - big_b / 6.0
+                                * cos_2sigma_m
+                                * (-3.0 + 4.0 * sin_sigma.powi(2))
+                                * (-3.0 + 4.0 * cos_2sigma_m.powi(2))));
+            return Some(b * big_a * (sigma - delta_sigma));
+        }
+    }
+    None
+}
+
+/// Area in square meters of the spherical triangle, computed from the spherical excess
+/// with L'Huilier's theorem. Degenerate (collinear) triangles have an area of 0.
+pub(crate) fn spherical_triangle_area(
+    lon1: f64,
+    lat1: f64,
+    lon2: f64,
+    lat2: f64,
+    lon3: f64,
+    lat3: f64,
+) -> f64 {
+    let a = central_angle(lon2, lat2, lon3, lat3);
+    let b = central_angle(lon1, lat1, lon3, lat3);
+    let c = central_angle(lon1, lat1, lon2, lat2);
+    let s = (a + b + c) / 2.0;
+    let t = (s / 2.0).tan() * ((s - a) / 2.0).tan() * ((s - b) / 2.0).tan() * ((s - c) / 2.0).tan();
+    if t.is_nan() || t <= 0.0 {
+        return 0.0;
+    }
+    let excess = 4.0 * t.sqrt().atan();
+    excess * EARTH_RADIUS_F64 * EARTH_RADIUS_F64
+}
+
+/// Initial bearing in radians from the first point to the second, clockwise from the north.
+fn initial_bearing(lon1deg: f64, lat1deg: f64, lon2deg: f64, lat2deg: f64) -> f64 {
+    let lat1 = lat1deg.to_radians();
+    let lat2 = lat2deg.to_radians();
+    let dlon = (lon2deg - lon1deg).to_radians();
+    let y = dlon.sin() * lat2.cos();
+    let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * dlon.cos();
+    y.atan2(x)
+}
+
+/// Signed distance in meters from (lon, lat) to the great circle going from (lon1, lat1)
+/// through (lon2, lat2): positive on the right of the path, negative on its left.
+fn cross_track_distance(lon: f64, lat: f64, lon1: f64, lat1: f64, lon2: f64, lat2: f64) -> f64 {
+    let d13 = central_angle(lon1, lat1, lon, lat);
+    let theta13 = initial_bearing(lon1, lat1, lon, lat);
+    let theta12 = initial_bearing(lon1, lat1, lon2, lat2);
+    (d13.sin() * (theta13 - theta12).sin()).asin() * EARTH_RADIUS_F64
+}
+
+/// Signed distance in meters along the great circle going from (lon1, lat1) through
+/// (lon2, lat2), from (lon1, lat1) to the point of the circle closest to (lon, lat). It is
+/// negative when that point lies behind (lon1, lat1).
+fn along_track_distance(lon: f64, lat: f64, lon1: f64, lat1: f64, lon2: f64, lat2: f64) -> f64 {
+    let d13 = central_angle(lon1, lat1, lon, lat);
+    let delta = initial_bearing(lon1, lat1, lon, lat) - initial_bearing(lon1, lat1, lon2, lat2);
+    let dxt = cross_track_distance(lon, lat, lon1, lat1, lon2, lat2) / EARTH_RADIUS_F64;
+    let dat = (d13.cos() / dxt.cos()).clamp(-1.0, 1.0).acos();
+    dat.copysign(delta.cos()) * EARTH_RADIUS_F64
+}
+
+/// Fraction of the great circle arc from (lon1, lat1) to (lon2, lat2) passed at the projection
+/// of (lon, lat) on the arc, clamped to [0, 1]. A degenerate arc returns 0.
+fn along_track_fraction(lon: f64, lat: f64, lon1: f64, lat1: f64, lon2: f64, lat2: f64) -> f64 {
+    let d12 = central_angle(lon1, lat1, lon2, lat2) * EARTH_RADIUS_F64;
+    if d12 == 0.0 {
+        return 0.0;
+    }
+    let dat = along_track_distance(lon, lat, lon1, lat1, lon2, lat2);
+    (dat / d12).clamp(0.0, 1.0)
+}
+
+/// Signed angle in degrees in `(-180, 180]` to turn at (lon2, lat2) when going from
+/// (lon1, lat1) to (lon3, lat3): positive to the right, negative to the left, 0 straight on
+/// and 180 back. The incoming bearing is the bearing at the end of the first leg, opposite
+/// to the bearing back to (lon1, lat1). A leg of zero length has no bearing, the angle is
+/// then meaningless.
+fn turn_angle(lon1: f64, lat1: f64, lon2: f64, lat2: f64, lon3: f64, lat3: f64) -> f64 {
+    let incoming = initial_bearing(lon2, lat2, lon1, lat1).to_degrees() - 180.0;
+    let outgoing = initial_bearing(lon2, lat2, lon3, lat3).to_degrees();
+    longitude_diff(incoming, outgoing)
+}
+
+/// Checks whether (lon, lat) is within `tolerance` meters of the great circle arc between
+/// (lon1, lat1) and (lon2, lat2): either close to the circle with its projection between
+/// the endpoints, or close to one of the endpoints.
+fn is_on_path(
+    lon: f64,
+    lat: f64,
+    lon1: f64,
+    lat1: f64,
+    lon2: f64,
+    lat2: f64,
+    tolerance: f64,
+) -> bool {
+    if central_angle(lon1, lat1, lon, lat) * EARTH_RADIUS_F64 <= tolerance
+        || central_angle(lon2, lat2, lon, lat) * EARTH_RADIUS_F64 <= tolerance
+    {
+        return true;
+    }
+    if cross_track_distance(lon, lat, lon1, lat1, lon2, lat2).abs() > tolerance {
+        return false;
+    }
+    let d12 = central_angle(lon1, lat1, lon2, lat2) * EARTH_RADIUS_F64;
+    let dat = along_track_distance(lon, lat, lon1, lat1, lon2, lat2);
+    (0.0..=d12).contains(&dat)
+}
+
+/// Distance in meters from (lon, lat) to the great circle arc between (lon1, lat1) and
+/// (lon2, lat2). A point whose projection falls outside of the arc is measured to the
+/// nearest endpoint.
+fn arc_distance(lon: f64, lat: f64, lon1: f64, lat1: f64, lon2: f64, lat2: f64) -> f64 {
+    let d12 = central_angle(lon1, lat1, lon2, lat2);
+    let d13 = central_angle(lon1, lat1, lon, lat);
+    if d12 == 0.0 {
+        return d13 * EARTH_RADIUS_F64;
+    }
+    let delta = initial_bearing(lon1, lat1, lon, lat) - initial_bearing(lon1, lat1, lon2, lat2);
+    if delta.cos() < 0.0 {
+        return d13 * EARTH_RADIUS_F64;
+    }
+    let dxt = (d13.sin() * delta.sin()).asin();
+    let dat = (d13.cos() / dxt.cos()).clamp(-1.0, 1.0).acos();
+    if dat > d12 {
+        return central_angle(lon2, lat2, lon, lat) * EARTH_RADIUS_F64;
+    }
+    dxt.abs() * EARTH_RADIUS_F64
+}
+
+/// Distance in meters from (lon, lat) to the nearest edge of the ring, 0 inside the ring.
+/// The ring is closed implicitly. Like `point_in_polygon`, a point is inside when it is
+/// contained in the ring on the plane of the longitudes and latitudes, while the edges are
+/// measured as great circle arcs with [`arc_distance`].
+fn polygon_distance(lon: f64, lat: f64, ring: &[Coord]) -> f64 {
+    let polygon = Polygon::new(LineString::from(ring.to_vec()), vec![]);
+    if polygon.contains(&coord! { x: lon, y: lat }) {
+        return 0.0;
+    }
+    (0..ring.len())
+        .map(|i| {
+            let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+            arc_distance(lon, lat, a.x, a.y, b.x, b.y)
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// Simplifies the path with the Ramer-Douglas-Peucker algorithm: the vertex farthest from
+/// the arc between the endpoints of a section is kept if it is more than `tolerance`
+/// meters away, and both halves are simplified in turn. Otherwise the section is replaced
+/// by its endpoints. The first and the last points are always kept, so a closed ring stays
+/// closed, and a path with less than 3 points is returned unchanged.
+fn simplify_path(points: &[(f64, f64)], tolerance: f64) -> Vec<(f64, f64)> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+    let last = points.len() - 1;
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[last] = true;
+    let mut sections = vec![(0, last)];
+    while let Some((first, last)) = sections.pop() {
+        let (lon1, lat1) = points[first];
+        let (lon2, lat2) = points[last];
+        let mut farthest = None;
+        let mut max_distance = tolerance;
+        for (i, &(lon, lat)) in points.iter().enumerate().take(last).skip(first + 1) {
+            let distance = arc_distance(lon, lat, lon1, lat1, lon2, lat2);
+            if distance > max_distance {
+                farthest = Some(i);
+                max_distance = distance;
+            }
+        }
+        if let Some(i) = farthest {
+            keep[i] = true;
+            sections.push((first, i));
+            sections.push((i, last));
+        }
+    }
+    points
+        .iter()
+        .zip(keep)
+        .filter_map(|(point, keep)| keep.then_some(*point))
+        .collect()
+}
+
+/// Inserts points along the great circle arc of each segment of the path, evenly spaced
+/// so that no segment is longer than `max_segment` meters. A segment already short enough
+/// is kept as is, and the given points are returned unchanged. Like [`simplify_path`],
+/// the closing edge of a ring is only densified if the ring repeats its first point.
+/// Returns `None` if the path would have more than `MAX_GEO_PATH_POINTS` points.
+fn densify_path(points: &[(f64, f64)], max_segment: f64) -> Option<Vec<(f64, f64)>> {
+    let arcs = points
+        .windows(2)
+        .map(|edge| {
+            let (a, m, omega) = great_circle(edge[0].0, edge[0].1, edge[1].0, edge[1].1);
+            let pieces = (omega * EARTH_RADIUS_F64 / max_segment).ceil().max(1f64);
+            (a, m, omega, pieces)
+        })
+        .collect::<Vec<_>>();
+    let total = points.len() as f64 + arcs.iter().map(|arc| arc.3 - 1f64).sum::<f64>();
+    if total > MAX_GEO_PATH_POINTS as f64 {
+        return None;
+    }
+
+    let mut densified = Vec::with_capacity(total as usize);
+    densified.extend(points.first());
+    for (&(a, m, omega, pieces), &point) in arcs.iter().zip(points.iter().skip(1)) {
+        for j in 1..pieces as usize {
+            densified.push(great_circle_point(a, m, omega * j as f64 / pieces));
+        }
+        densified.push(point);
+    }
+    Some(densified)
+}
+
+type Vector3 = [f64; 3];
+
+fn to_unit_vector(lon: f64, lat: f64) -> Vector3 {
+    let (lon, lat) = (lon.to_radians(), lat.to_radians());
+    [lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin()]
+}
+
+fn dot(a: Vector3, b: Vector3) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: Vector3, b: Vector3) -> Vector3 {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+/// The component of `b` orthogonal to the unit vector `a`, normalized.
+fn orthogonal_unit(a: Vector3, b: Vector3) -> Vector3 {
+    let d = dot(a, b);
+    let v = [b[0] - d * a[0], b[1] - d * a[1], b[2] - d * a[2]];
+    let norm = dot(v, v).sqrt();
+    [v[0] / norm, v[1] / norm, v[2] / norm]
+}
+
+/// The great circle from (lon1, lat1) to (lon2, lat2) as the unit vector `a` of the start
+/// point, the unit vector `m` orthogonal to `a` towards the end point, and the angle between
+/// the points, so that the point at angle `theta` is `a * cos(theta) + m * sin(theta)`.
+///
+/// The great circle of two antipodal points is undefined, the path then goes through
+/// the north pole along the meridian of the start point, or along the prime meridian
+/// if the start point is a pole. The path of two equal points repeats that point.
+fn great_circle(lon1: f64, lat1: f64, lon2: f64, lat2: f64) -> (Vector3, Vector3, f64) {
+    const EPSILON: f64 = 1e-12;
+
+    let a = to_unit_vector(lon1, lat1);
+    let b = to_unit_vector(lon2, lat2);
+    let cos_omega = dot(a, b);
+    let c = cross(a, b);
+    let sin_omega = dot(c, c).sqrt();
+    let omega = sin_omega.atan2(cos_omega);
+
+    // `a` and `m` are an orthonormal basis of the plane of the great circle.
+    let m = if sin_omega > EPSILON {
+        orthogonal_unit(a, b)
+    } else if cos_omega > 0.0 {
+        [0.0; 3]
+    } else if a[2].abs() < 1.0 - EPSILON {
+        orthogonal_unit(a, [0.0, 0.0, 1.0])
+    } else {
+        orthogonal_unit(a, [1.0, 0.0, 0.0])
+    };
+    (a, m, omega)
+}
+
+/// The point at angle `theta` along the great circle of the basis `a` and `m`.
+fn great_circle_point(a: Vector3, m: Vector3, theta: f64) -> (f64, f64) {
+    let (sin, cos) = (theta.sin(), theta.cos());
+    let p = [
+        a[0] * cos + m[0] * sin,
+        a[1] * cos + m[1] * sin,
+        a[2] * cos + m[2] * sin,
+    ];
+    let lon = p[1].atan2(p[0]).to_degrees();
+    let lat = p[2].atan2((p[0] * p[0] + p[1] * p[1]).sqrt()).to_degrees();
+    (lon, lat)
+}
+
+/// `n` points evenly spaced along the great circle from (lon1, lat1) to (lon2, lat2),
+/// computed with the spherical linear interpolation (slerp) of the unit vectors.
+/// The endpoints are returned as given.
+fn great_circle_points(lon1: f64, lat1: f64, lon2: f64, lat2: f64, n: usize) -> Vec<(f64, f64)> {
+    let (a, m, omega) = great_circle(lon1, lat1, lon2, lat2);
+    let mut points = Vec::with_capacity(n);
+    points.push((lon1, lat1));
+    for i in 1..n - 1 {
+        points.push(great_circle_point(a, m, omega * i as f64 / (n - 1) as f64));
+    }
+    points.push((lon2, lat2));
+    points
+}
+
+/// The point at the fraction `t` of the great circle from (lon1, lat1) to (lon2, lat2),
+/// `t` is clamped to [0, 1] and the endpoints are returned as given.
+fn great_circle_fraction(lon1: f64, lat1: f64, lon2: f64, lat2: f64, t: f64) -> (f64, f64) {
+    let t = t.clamp(0.0, 1.0);
+    if t == 0.0 {
+        return (lon1, lat1);
+    }
+    if t == 1.0 {
+        return (lon2, lat2);
+    }
+    let (a, m, omega) = great_circle(lon1, lat1, lon2, lat2);
+    great_circle_point(a, m, omega * t)
+}
+
+/// Checks whether the ring has no self-intersections.
+///
+/// The ring is closed implicitly, a closing point equal to the first one is allowed.
+/// Consecutive duplicate points are collapsed into one. A ring with less than 3 distinct
+/// vertices is degenerate and never simple. Adjacent edges may only share their common
+/// vertex, so a collinear edge going back over the previous one is not simple. Other edges
+/// must not touch at all, including at a vertex or along a collinear overlap.
+fn is_simple_ring(ring: &[Coord]) -> bool {
+    let mut points: Vec<Coord> = Vec::with_capacity(ring.len());
+    for p in ring {
+        if points.last() != Some(p) {
+            points.push(*p);
+        }
+    }
+    if points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    let n = points.len();
+    if n < 3 {
+        return false;
+    }
+
+    let edges = (0..n)
+        .map(|i| (points[i], points[(i + 1) % n]))
+        .collect::<Vec<_>>();
+
+    // Sweep the edges by the minimal x, only the edges overlapping on the x axis
+    // are still active and need to be checked.
+    let mut order = (0..n).collect::<Vec<_>>();
+    order.sort_by(|a, b| {
+        let a = edges[*a].0.x.min(edges[*a].1.x);
+        let b = edges[*b].0.x.min(edges[*b].1.x);
+        a.total_cmp(&b)
+    });
+    let mut active: Vec<usize> = Vec::new();
+    for i in order {
+        let (p, q) = edges[i];
+        let min_x = p.x.min(q.x);
+        active.retain(|j| edges[*j].0.x.max(edges[*j].1.x) >= min_x);
+        for j in active.iter().copied() {
+            let (r, s) = edges[j];
+            let intersects = if (i + 1) % n == j {
+                // edge j starts where edge i ends
+                is_folded_back(p, q, s)
+            } else if (j + 1) % n == i {
+                is_folded_back(r, s, q)
+            } else {
+                segments_intersect(p, q, r, s)
             };
+            if intersects {
+                return false;
+            }
         }
-        ellipses.push(Ellipse {
-            x: ellipse_data[0],
-            y: ellipse_data[1],
-            a: ellipse_data[2],
-            b: ellipse_data[3],
-        });
+        active.push(i);
     }
+    true
+}
 
-    let mut start_index = 0;
-    let mut builder = NumberColumnBuilder::with_capacity(&NumberDataType::UInt8, input_rows);
-    for idx in 0..input_rows {
-        let col_x = match &args[0] {
-            ValueRef::Scalar(v) => *v,
-            ValueRef::Column(c) => unsafe { Float64Type::index_column_unchecked(c, idx) },
-        };
-        let col_y = match &args[1] {
-            ValueRef::Scalar(v) => *v,
-            ValueRef::Column(c) => unsafe { Float64Type::index_column_unchecked(c, idx) },
-        };
+fn orientation(a: Coord, b: Coord, c: Coord) -> f64 {
+    (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+}
 
-        let r = u8::from(is_point_in_ellipses(
-            col_x.0,
-            col_y.0,
-            &ellipses,
-            ellipses_cnt,
-            &mut start_index,
-        ));
-        builder.push(NumberScalar::UInt8(r));
+fn on_segment(a: Coord, b: Coord, p: Coord) -> bool {
+    p.x >= a.x.min(b.x) && p.x <= a.x.max(b.x) && p.y >= a.y.min(b.y) && p.y <= a.y.max(b.y)
+}
+
+// Whether the edges (a, b) and (b, c) overlap beyond their common vertex `b`.
+fn is_folded_back(a: Coord, b: Coord, c: Coord) -> bool {
+    orientation(a, b, c) == 0.0 && (a.x - b.x) * (c.x - b.x) + (a.y - b.y) * (c.y - b.y) > 0.0
+}
+
+fn segments_intersect(p1: Coord, p2: Coord, q1: Coord, q2: Coord) -> bool {
+    let d1 = orientation(q1, q2, p1);
+    let d2 = orientation(q1, q2, p2);
+    let d3 = orientation(p1, p2, q1);
+    let d4 = orientation(p1, p2, q2);
+    if ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0))
+        && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0))
+    {
+        return true;
     }
+    (d1 == 0.0 && on_segment(q1, q2, p1))
+        || (d2 == 0.0 && on_segment(q1, q2, p2))
+        || (d3 == 0.0 && on_segment(p1, p2, q1))
+        || (d4 == 0.0 && on_segment(p1, p2, q2))
+}
 
-    match len {
-        Some(_) => Value::Column(Column::Number(builder.build())),
-        _ => Value::Scalar(Scalar::Number(builder.build_scalar())),
+/// The area-weighted centroid of a polygon ring, on the plane of the longitudes and
+/// the latitudes.
+///
+/// The ring is closed implicitly like in [`is_simple_ring`]. The longitudes are unwrapped
+/// along the ring, so a ring crossing the antimeridian has its centroid near it rather than
+/// on the other side of the earth, and the longitude of the centroid is normalized into
+/// [-180, 180]. A ring with less than 3 distinct vertices or without an area is degenerate
+/// and has no centroid.
+fn ring_centroid(ring: &[Coord]) -> Option<Coord> {
+    let mut points: Vec<Coord> = Vec::with_capacity(ring.len());
+    for p in ring {
+        if points.last() != Some(p) {
+            points.push(*p);
+        }
+    }
+    if points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    if points.len() < 3 {
+        return None;
+    }
+
+    // Relative to the first vertex, which keeps the products small.
+    let origin = points[0];
+    let mut unwrapped = Vec::with_capacity(points.len());
+    let mut x = 0.0;
+    for (i, p) in points.iter().enumerate() {
+        if i > 0 {
+            x += longitude_diff(points[i - 1].x, p.x);
+        }
+        unwrapped.push(coord! { x: x, y: p.y - origin.y });
     }
+
+    let n = unwrapped.len();
+    let (mut area, mut cx, mut cy) = (0.0, 0.0, 0.0);
+    for i in 0..n {
+        let a = unwrapped[i];
+        let b = unwrapped[(i + 1) % n];
+        let cross = a.x * b.y - b.x * a.y;
+        area += cross;
+        cx += (a.x + b.x) * cross;
+        cy += (a.y + b.y) * cross;
+    }
+    if area.abs() < MIN_CENTROID_RING_AREA * 2.0 {
+        return None;
+    }
+
+    let mut lon = origin.x + cx / (3.0 * area);
+    if lon > 180.0 {
+        lon -= 360.0;
+    } else if lon < -180.0 {
+        lon += 360.0;
+    }
+    Some(coord! { x: lon, y: origin.y + cy / (3.0 * area) })
 }
 
 fn is_point_in_ellipses(
@@ -623,15 +2286,16 @@ pub fn geo_dist_init() {
             for i in 0..=METRIC_LUT_SIZE {
                 let latitude: f64 = i as f64 * (PI / METRIC_LUT_SIZE as f64) - PI * 0.5f64;
 
-                wgs84_metric_meters_lut[i].write(
+                // the squares of the meters per degree of latitude and of longitude
+                wgs84_metric_meters_lut[i * 2].write(
                     (111132.09f64 - 566.05f64 * (2f64 * latitude).cos()
                         + 1.20f64 * (4f64 * latitude).cos())
-                    .sqrt() as f32,
+                    .powi(2) as f32,
                 );
                 wgs84_metric_meters_lut[i * 2 + 1].write(
                     (111415.13f64 * latitude.cos() - 94.55f64 * (3f64 * latitude).cos()
                         + 0.12f64 * (5f64 * latitude).cos())
-                    .sqrt() as f32,
+                    .powi(2) as f32,
                 );
 
                 sphere_metric_meters_lut[i]
@@ -660,6 +2324,300 @@ pub fn geo_dist_init() {
     });
 }
 
+/// The sum of the great circle distances of the segments of a WKT LINESTRING, in meters.
+/// Each segment is measured like `great_circle_distance`, the sum is kept in f64.
+fn linestring_length(wkt: &str) -> Result<f64, String> {
+    let line = match Wkt(wkt).to_geo() {
+        Ok(geo::Geometry::LineString(line)) => line,
+        Ok(geometry) => {
+            return Err(format!(
+                "expected a LINESTRING, but got a {}",
+                wkt_type_name(&geometry)
+            ));
+        }
+        Err(_) => return Err("invalid WKT".to_string()),
+    };
+    Ok(line
+        .lines()
+        .map(|segment| {
+            let (start, end) = (segment.start, segment.end);
+            sphere_distance_meters(start.x as f32, start.y as f32, end.x as f32, end.y as f32)
+                as f64
+        })
+        .sum())
+}
+
+/// Parses a WKT POINT, LINESTRING or POLYGON, the geometries supported by the `st_*`
+/// functions on WKT strings.
+///
+/// Besides being syntactically valid, the coordinates must be finite, a LINESTRING must
+/// have at least 2 points and every ring of a POLYGON at least 4 points once closed.
+fn parse_wkt(wkt: &str) -> Result<geo::Geometry, String> {
+    let geometry = Wkt(wkt).to_geo().map_err(|_| "invalid WKT".to_string())?;
+    let coords: Vec<&Coord> = match &geometry {
+        geo::Geometry::Point(point) => vec![&point.0],
+        geo::Geometry::LineString(line) => {
+            if line.0.len() < 2 {
+                return Err(format!(
+                    "a LINESTRING must have at least 2 points, but got {}",
+                    line.0.len()
+                ));
+            }
+            line.0.iter().collect()
+        }
+        geo::Geometry::Polygon(polygon) => {
+            let rings = std::iter::once(polygon.exterior()).chain(polygon.interiors());
+            let mut coords = vec![];
+            for ring in rings {
+                if ring.0.len() < 4 {
+                    return Err(format!(
+                        "a POLYGON ring must have at least 4 points, but got {}",
+                        ring.0.len()
+                    ));
+                }
+                coords.extend(ring.0.iter());
+            }
+            coords
+        }
+        geometry => {
+            return Err(format!(
+                "expected a POINT, LINESTRING or POLYGON, but got a {}",
+                wkt_type_name(geometry)
+            ));
+        }
+    };
+    if coords.iter().any(|c| !c.x.is_finite() || !c.y.is_finite()) {
+        return Err("the coordinates must be finite".to_string());
+    }
+    Ok(geometry)
+}
+
+/// The canonical WKT of a geometry returned by `parse_wkt`, in the format of `st_aswkt`:
+/// no whitespace but a single space between the x and y of a point, coordinates printed
+/// in their shortest form, and -0 printed as 0.
+///
+/// The exterior ring of a POLYGON is wound counter-clockwise and its interior rings
+/// clockwise, and every ring starts at its smallest point, comparing x then y. The points
+/// of a LINESTRING are kept in their order, as its direction is meaningful.
+fn normalized_wkt(geometry: geo::Geometry) -> String {
+    fn write_coords(out: &mut String, coords: &[Coord]) {
+        out.push('(');
+        for (i, c) in coords.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            // adding 0.0 turns -0.0 into 0.0
+            out.push_str(&format!("{} {}", c.x + 0.0, c.y + 0.0));
+        }
+        out.push(')');
+    }
+
+    let mut out = String::new();
+    match geometry {
+        geo::Geometry::Point(point) => {
+            out.push_str("POINT");
+            write_coords(&mut out, &[point.0]);
+        }
+        geo::Geometry::LineString(line) => {
+            out.push_str("LINESTRING");
+            write_coords(&mut out, &line.0);
+        }
+        geo::Geometry::Polygon(polygon) => {
+            let (mut exterior, mut interiors) = polygon.into_inner();
+            exterior.make_ccw_winding();
+            interiors.iter_mut().for_each(|ring| ring.make_cw_winding());
+            out.push_str("POLYGON(");
+            for (i, ring) in std::iter::once(exterior).chain(interiors).enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_coords(&mut out, &rotate_ring_to_min(ring.0));
+            }
+            out.push(')');
+        }
+        _ => unreachable!(),
+    }
+    out
+}
+
+/// Rotates a closed ring so that it starts, and ends, at its smallest point.
+fn rotate_ring_to_min(mut ring: Vec<Coord>) -> Vec<Coord> {
+    ring.pop();
+    let start = (0..ring.len())
+        .min_by(|&i, &j| {
+            (ring[i].x, ring[i].y)
+                .partial_cmp(&(ring[j].x, ring[j].y))
+                .unwrap()
+        })
+        .unwrap_or(0);
+    ring.rotate_left(start);
+    ring.push(ring[0]);
+    ring
+}
+
+/// Total great circle length in meters of the path through `points`, the same as
+/// `st_length` of the LINESTRING of the points. A path of less than two points is 0.
+fn path_length(points: &[(f64, f64)]) -> f64 {
+    points
+        .windows(2)
+        .map(|segment| {
+            let ((lon1, lat1), (lon2, lat2)) = (segment[0], segment[1]);
+            sphere_distance_meters(lon1 as f32, lat1 as f32, lon2 as f32, lat2 as f32) as f64
+        })
+        .sum()
+}
+
+fn wkt_type_name(geometry: &geo::Geometry) -> &'static str {
+    match geometry {
+        geo::Geometry::Point(_) => "POINT",
+        geo::Geometry::Line(_) => "LINE",
+        geo::Geometry::LineString(_) => "LINESTRING",
+        geo::Geometry::Polygon(_) => "POLYGON",
+        geo::Geometry::MultiPoint(_) => "MULTIPOINT",
+        geo::Geometry::MultiLineString(_) => "MULTILINESTRING",
+        geo::Geometry::MultiPolygon(_) => "MULTIPOLYGON",
+        geo::Geometry::GeometryCollection(_) => "GEOMETRYCOLLECTION",
+        geo::Geometry::Rect(_) => "RECT",
+        geo::Geometry::Triangle(_) => "TRIANGLE",
+    }
+}
+
+/// Projects a point to Web Mercator (x, y) in meters.
+///
+/// The latitude is clamped to ±`WEB_MERCATOR_MAX_LATITUDE`, so the poles, which are at
+/// the infinity in Mercator, are projected to the top and bottom edges of the map.
+fn lonlat_to_mercator(lon: f64, lat: f64) -> (f64, f64) {
+    let lat = lat.clamp(-WEB_MERCATOR_MAX_LATITUDE, WEB_MERCATOR_MAX_LATITUDE);
+    let x = WEB_MERCATOR_RADIUS * lon.to_radians();
+    let y = WEB_MERCATOR_RADIUS * lat.to_radians().tan().asinh();
+    (x, y)
+}
+
+/// The inverse of [`lonlat_to_mercator`]. A `y` beyond the edges of the map gives
+/// a latitude beyond `WEB_MERCATOR_MAX_LATITUDE`, approaching the pole.
+fn mercator_to_lonlat(x: f64, y: f64) -> (f64, f64) {
+    let lon = (x / WEB_MERCATOR_RADIUS).to_degrees();
+    let lat = (y / WEB_MERCATOR_RADIUS).sinh().atan().to_degrees();
+    (lon, lat)
+}
+
+/// The signed shortest angular difference in degrees to go from `lon1` to `lon2`, in
+/// `(-180, 180]`. It is positive eastward and negative westward, crossing the antimeridian
+/// when it is shorter, e.g. 20 from 170 to -170. Points 180 degrees apart give 180.
+pub(crate) fn longitude_diff(lon1: f64, lon2: f64) -> f64 {
+    let diff = (lon2 - lon1).rem_euclid(360.0);
+    if diff > 180.0 { diff - 360.0 } else { diff }
+}
+
+fn compass_direction_builder(
+    bearing: f64,
+    points: usize,
+    builder: &mut StringColumnBuilder,
+    ctx: &mut EvalContext,
+) {
+    if !bearing.is_finite() {
+        ctx.set_error(
+            builder.len(),
+            format!("the bearing must be finite, but got {bearing}"),
+        );
+    } else {
+        builder.put_str(compass_direction(bearing, points));
+    }
+    builder.commit_row();
+}
+
+/// The label of the direction of a bearing in degrees clockwise from the north, on a
+/// compass rose of 8 or 16 points. The bearing is normalized into `[0, 360)` first.
+///
+/// Each point covers a sector centered on it, a bearing on the boundary of two sectors
+/// goes to the next point clockwise, e.g. 22.5 is NE and 337.5 is N on the 8-point rose.
+fn compass_direction(bearing: f64, points: usize) -> &'static str {
+    let sector = 360.0 / points as f64;
+    let index = ((bearing.rem_euclid(360.0) + sector / 2.0) / sector).floor() as usize % points;
+    COMPASS_POINTS[index * (COMPASS_POINTS.len() / points)]
+}
+
+/// The point diametrically opposite on the sphere, with the longitude in `(-180, 180]`.
+fn antipode(lon: f64, lat: f64) -> (f64, f64) {
+    // `0.0 - lat` keeps the equator at 0 rather than -0.
+    (longitude_diff(0.0, lon + 180.0), 0.0 - lat)
+}
+
+/// Rotates a point clockwise by `angle_deg` around a center, as seen on a map with the
+/// north up, i.e. 90 degrees turns a point north of the center to the east of it.
+///
+/// The rotation is done on the local tangent plane of the center, where a degree of
+/// longitude is shortened by the cosine of the center latitude. It is only accurate for
+/// points within a few hundred kilometers of the center and away from the poles, farther
+/// points drift off their true circle around the center as the plane departs from the sphere.
+fn rotate_around(
+    lon: f64,
+    lat: f64,
+    center_lon: f64,
+    center_lat: f64,
+    angle_deg: f64,
+) -> (f64, f64) {
+    let scale = center_lat.to_radians().cos();
+    let x = longitude_diff(center_lon, lon) * scale;
+    let y = lat - center_lat;
+    let (sin, cos) = angle_deg.to_radians().sin_cos();
+    let rotated_x = x * cos + y * sin;
+    let rotated_y = y * cos - x * sin;
+    (
+        longitude_diff(0.0, center_lon + rotated_x / scale),
+        center_lat + rotated_y,
+    )
+}
+
+/// Interleaves the quantized longitude and latitude into a Morton (Z-order) key, the
+/// longitude takes the even bits and the latitude the odd bits. Close points share a long
+/// prefix of their keys, so a range of keys scans a region of the map.
+///
+/// A cell is 360 / 2^32 (about 8.4e-8) degrees of longitude by 180 / 2^32 (about 4.2e-8)
+/// degrees of latitude, about 9.3 mm by 4.7 mm at the equator.
+fn morton_encode(lon: f64, lat: f64) -> u64 {
+    let lon = morton_quantize(lon + 180.0, 360.0);
+    let lat = morton_quantize(lat + 90.0, 180.0);
+    morton_spread(lon) | (morton_spread(lat) << 1)
+}
+
+/// The inverse of [`morton_encode`], the center of the cell of the key. It is within half
+/// a cell of the encoded point.
+fn morton_decode(key: u64) -> (f64, f64) {
+    let lon = morton_dequantize(morton_compact(key), 360.0) - 180.0;
+    let lat = morton_dequantize(morton_compact(key >> 1), 180.0) - 90.0;
+    (lon, lat)
+}
+
+fn morton_quantize(offset: f64, range: f64) -> u32 {
+    // The end of the range falls into the last cell.
+    (offset / range * MORTON_CELLS).min(u32::MAX as f64) as u32
+}
+
+fn morton_dequantize(cell: u32, range: f64) -> f64 {
+    (cell as f64 + 0.5) / MORTON_CELLS * range
+}
+
+/// Spreads the bits of `v` to the even bits of the result.
+fn morton_spread(v: u32) -> u64 {
+    let mut x = v as u64;
+    x = (x | (x << 16)) & 0x0000_FFFF_0000_FFFF;
+    x = (x | (x << 8)) & 0x00FF_00FF_00FF_00FF;
+    x = (x | (x << 4)) & 0x0F0F_0F0F_0F0F_0F0F;
+    x = (x | (x << 2)) & 0x3333_3333_3333_3333;
+    (x | (x << 1)) & 0x5555_5555_5555_5555
+}
+
+/// The inverse of [`morton_spread`], gathers the even bits of `x`.
+fn morton_compact(x: u64) -> u32 {
+    let mut x = x & 0x5555_5555_5555_5555;
+    x = (x | (x >> 1)) & 0x3333_3333_3333_3333;
+    x = (x | (x >> 2)) & 0x0F0F_0F0F_0F0F_0F0F;
+    x = (x | (x >> 4)) & 0x00FF_00FF_00FF_00FF;
+    x = (x | (x >> 8)) & 0x0000_FFFF_0000_FFFF;
+    ((x | (x >> 16)) & 0x0000_0000_FFFF_FFFF) as u32
+}
+
 #[inline(always)]
 fn geodist_deg_diff(mut f: f32) -> f32 {
     f = f.abs();
@@ -714,7 +2672,26 @@ fn float_to_index(x: f32) -> usize {
     x as usize
 }
 
+/// Distance in meters on the sphere, the same as `great_circle_distance`.
+/// The lookup tables must be initialized by [`geo_dist_init`] first.
+pub(crate) fn sphere_distance_meters(
+    lon1deg: f32,
+    lat1deg: f32,
+    lon2deg: f32,
+    lat2deg: f32,
+) -> f32 {
+    distance(lon1deg, lat1deg, lon2deg, lat2deg, GeoMethod::SphereMeters)
+}
+
 fn distance(lon1deg: f32, lat1deg: f32, lon2deg: f32, lat2deg: f32, method: GeoMethod) -> f32 {
+    // Rounding the f64 result gives the same as doing the last step in f32: a product of
+    // two f32 is exact in f64, and an f64 square root rounds to the f32 square root.
+    distance_f64(lon1deg, lat1deg, lon2deg, lat2deg, method) as f32
+}
+
+/// The same approximation as `distance`, with the last multiplication or square root done
+/// in f64, so the result is not rounded to f32.
+fn distance_f64(lon1deg: f32, lat1deg: f32, lon2deg: f32, lat2deg: f32, method: GeoMethod) -> f64 {
     let lat_diff = geodist_deg_diff(lat1deg - lat2deg);
     let lon_diff = geodist_deg_diff(lon1deg - lon2deg);
 
@@ -759,7 +2736,7 @@ fn distance(lon1deg: f32, lat1deg: f32, lon2deg: f32, lat2deg: f32, method: GeoM
             }
         };
 
-        (k_lat * lat_diff * lat_diff + k_lon * lon_diff * lon_diff).sqrt()
+        ((k_lat * lat_diff * lat_diff + k_lon * lon_diff * lon_diff) as f64).sqrt()
     } else {
         let a: f32 = (geodist_fast_sin(lat_diff * RAD_IN_DEG_HALF)).powi(2)
             + geodist_fast_cos(lat1deg * RAD_IN_DEG)
@@ -767,9 +2744,9 @@ fn distance(lon1deg: f32, lat1deg: f32, lon2deg: f32, lat2deg: f32, method: GeoM
                 * (geodist_fast_sin(lon_diff * RAD_IN_DEG_HALF)).powi(2);
 
         if method == GeoMethod::SphereDegrees {
-            return (360f32 / PI_F) * geodist_fast_asin_sqrt(a);
+            return (360f32 / PI_F) as f64 * geodist_fast_asin_sqrt(a) as f64;
         }
 
-        EARTH_DIAMETER * geodist_fast_asin_sqrt(a)
+        EARTH_DIAMETER as f64 * geodist_fast_asin_sqrt(a) as f64
     }
 }
@@ -499,6 +499,7 @@ pub fn register(registry: &mut FunctionRegistry) {
                 .and_then(|index| {
                     CellIndex::try_from(a_h3)
                         .map_err(|e| e.to_string())
+                        .and_then(|a_index| check_same_resolution(index, a_index).map(|_| a_index))
                         .map(|a_index| index.grid_distance(a_index).map_err(|e| e.to_string()))
                 }) {
                 Ok(Ok(dist)) => builder.push(dist),
@@ -510,6 +511,8 @@ pub fn register(registry: &mut FunctionRegistry) {
         ),
     );
 
+    registry.register_aliases("h3_distance", &["h3_grid_distance"]);
+
     registry
         .register_passthrough_nullable_2_arg::<UInt64Type, UInt32Type, ArrayType<UInt64Type>, _, _>(
             "h3_hex_ring",
@@ -683,3 +686,15 @@ pub fn register(registry: &mut FunctionRegistry) {
         }),
     );
 }
+
+/// The grid distance is only defined between cells of the same resolution.
+fn check_same_resolution(index: CellIndex, a_index: CellIndex) -> Result<(), String> {
+    if index.resolution() != a_index.resolution() {
+        return Err(format!(
+            "the resolutions of the two cells must be the same, but got {} and {}",
+            u8::from(index.resolution()),
+            u8::from(a_index.resolution())
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,183 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use databend_common_expression::types::map::KvPair;
+use databend_common_expression::types::Float64Type;
+use databend_common_expression::types::UInt64Type;
+use databend_common_expression::types::UInt8Type;
+use databend_common_expression::types::F64;
+use databend_common_expression::vectorize_with_builder_1_arg;
+use databend_common_expression::vectorize_with_builder_3_arg;
+use databend_common_expression::FunctionDomain;
+use databend_common_expression::FunctionRegistry;
+
+const S2_MAX_LEVEL: u8 = 30;
+
+/// The position along the Hilbert curve of the child cell `(i << 1) | j` for each
+/// orientation of the curve.
+const IJ_TO_POS: [[u64; 4]; 4] = [[0, 1, 3, 2], [0, 3, 1, 2], [2, 3, 1, 0], [2, 1, 3, 0]];
+
+/// The child cell `(i << 1) | j` at each position along the Hilbert curve, the inverse
+/// of [`IJ_TO_POS`].
+const POS_TO_IJ: [[u64; 4]; 4] = [[0, 1, 3, 2], [0, 2, 3, 1], [3, 2, 0, 1], [3, 1, 0, 2]];
+
+/// How the orientation of the curve changes in the child cell at each position: bit 0
+/// swaps the i and j axes, bit 1 inverts both of them.
+const POS_TO_ORIENTATION: [usize; 4] = [1, 0, 0, 3];
+
+pub fn register(registry: &mut FunctionRegistry) {
+    registry.register_passthrough_nullable_3_arg::<Float64Type, Float64Type, UInt8Type, UInt64Type, _, _>(
+        "geo_to_s2",
+        |_, _, _, _| FunctionDomain::Full,
+        vectorize_with_builder_3_arg::<Float64Type, Float64Type, UInt8Type, UInt64Type>(
+            |lon, lat, level, builder, ctx| {
+                if level > S2_MAX_LEVEL {
+                    ctx.set_error(
+                        builder.len(),
+                        format!("the level must be between 0 and {S2_MAX_LEVEL}, but got {level}"),
+                    );
+                    builder.push(0);
+                } else {
+                    builder.push(s2_cell_id(lon.0, lat.0, level));
+                }
+            },
+        ),
+    );
+
+    registry
+        .register_passthrough_nullable_1_arg::<UInt64Type, KvPair<Float64Type, Float64Type>, _, _>(
+            "s2_to_geo",
+            |_, _| FunctionDomain::Full,
+            vectorize_with_builder_1_arg::<UInt64Type, KvPair<Float64Type, Float64Type>>(
+                |id, builder, ctx| match s2_cell_center(id) {
+                    Some((lon, lat)) => builder.push((lon.into(), lat.into())),
+                    None => {
+                        ctx.set_error(builder.len(), format!("invalid S2 cell id {id}"));
+                        builder.push((F64::from(0.0), F64::from(0.0)));
+                    }
+                },
+            ),
+        );
+}
+
+/// The id of the S2 cell at `level` containing (lon, lat).
+///
+/// The point is projected on the face of the cube the axis of its largest coordinate
+/// points to, then the quadratic transform of S2 maps the face coordinates (u, v) to
+/// (s, t), which are quantized into the leaf cell (i, j) of 2^30 cells along each axis.
+///
+/// The id holds the face in its 3 highest bits, then 2 bits per level for the position
+/// of the cell along the Hilbert curve of the face, then a single set bit marking the
+/// level, followed by zeros. See <https://s2geometry.io/devguide/s2cell_hierarchy>.
+fn s2_cell_id(lon: f64, lat: f64, level: u8) -> u64 {
+    let (lon, lat) = (lon.to_radians(), lat.to_radians());
+    let p = [lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin()];
+    let (face, u, v) = xyz_to_face_uv(p);
+    let i = st_to_ij(uv_to_st(u));
+    let j = st_to_ij(uv_to_st(v));
+
+    let mut orientation = (face & 1) as usize;
+    let mut pos = 0;
+    for k in 0..level {
+        let bit = S2_MAX_LEVEL - 1 - k;
+        let ij = (((i >> bit) & 1) << 1) | ((j >> bit) & 1);
+        let child = IJ_TO_POS[orientation][ij as usize];
+        pos = (pos << 2) | child;
+        orientation ^= POS_TO_ORIENTATION[child as usize];
+    }
+    let level = level as u32;
+    (face << 61) | (pos << (61 - 2 * level)) | (1 << (60 - 2 * level))
+}
+
+/// The center (lon, lat) of the S2 cell, or None if `id` is not a valid cell id.
+fn s2_cell_center(id: u64) -> Option<(f64, f64)> {
+    let face = id >> 61;
+    // the level marker is the lowest set bit, on an even position
+    let lsb = id & id.wrapping_neg();
+    if face > 5 || lsb & 0x1555_5555_5555_5555 == 0 {
+        return None;
+    }
+    let level = S2_MAX_LEVEL as u32 - lsb.trailing_zeros() / 2;
+
+    let mut orientation = (face & 1) as usize;
+    let (mut i, mut j) = (0u64, 0u64);
+    for k in 0..level {
+        let child = (id >> (59 - 2 * k)) & 3;
+        let ij = POS_TO_IJ[orientation][child as usize];
+        i = (i << 1) | (ij >> 1);
+        j = (j << 1) | (ij & 1);
+        orientation ^= POS_TO_ORIENTATION[child as usize];
+    }
+    let cells = (1u64 << level) as f64;
+    let u = st_to_uv((i as f64 + 0.5) / cells);
+    let v = st_to_uv((j as f64 + 0.5) / cells);
+    let [x, y, z] = face_uv_to_xyz(face, u, v);
+    let lat = z.atan2((x * x + y * y).sqrt()).to_degrees();
+    let lon = y.atan2(x).to_degrees();
+    Some((lon, lat))
+}
+
+fn xyz_to_face_uv(p: [f64; 3]) -> (u64, f64, f64) {
+    let [x, y, z] = p;
+    let (ax, ay, az) = (x.abs(), y.abs(), z.abs());
+    let axis = if ax > ay {
+        if ax > az { 0 } else { 2 }
+    } else if ay > az {
+        1
+    } else {
+        2
+    };
+    let face = if p[axis] < 0.0 { axis + 3 } else { axis };
+    let (u, v) = match face {
+        0 => (y / x, z / x),
+        1 => (-x / y, z / y),
+        2 => (-x / z, -y / z),
+        3 => (z / x, y / x),
+        4 => (z / y, -x / y),
+        _ => (-y / z, -x / z),
+    };
+    (face as u64, u, v)
+}
+
+fn face_uv_to_xyz(face: u64, u: f64, v: f64) -> [f64; 3] {
+    match face {
+        0 => [1.0, u, v],
+        1 => [-u, 1.0, v],
+        2 => [-u, -v, 1.0],
+        3 => [-1.0, -v, -u],
+        4 => [v, -1.0, -u],
+        _ => [v, u, -1.0],
+    }
+}
+
+fn uv_to_st(u: f64) -> f64 {
+    if u >= 0.0 {
+        0.5 * (1.0 + 3.0 * u).sqrt()
+    } else {
+        1.0 - 0.5 * (1.0 - 3.0 * u).sqrt()
+    }
+}
+
+fn st_to_uv(s: f64) -> f64 {
+    if s >= 0.5 {
+        (1.0 / 3.0) * (4.0 * s * s - 1.0)
+    } else {
+        (1.0 / 3.0) * (1.0 - 4.0 * (1.0 - s) * (1.0 - s))
+    }
+}
+
+fn st_to_ij(s: f64) -> u64 {
+    let cells = (1u64 << S2_MAX_LEVEL) as f64;
+    (s * cells).floor().clamp(0.0, cells - 1.0) as u64
+}
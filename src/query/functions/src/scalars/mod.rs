@@ -26,6 +26,7 @@ mod datetime;
 mod decimal;
 mod geo;
 mod geo_h3;
+mod geo_s2;
 mod geography;
 mod geometry;
 mod hash;
@@ -33,6 +34,7 @@ mod hilbert;
 mod map;
 mod math;
 mod other;
+mod rank_digest;
 mod string;
 mod string_multi_args;
 mod tuple;
@@ -40,6 +42,10 @@ mod variant;
 mod vector;
 
 pub use comparison::ALL_COMP_FUNC_NAMES;
+pub(crate) use geo::geo_dist_init;
+pub(crate) use geo::longitude_diff;
+pub(crate) use geo::sphere_distance_meters;
+pub(crate) use geo::spherical_triangle_area;
 pub use string::ALL_STRING_FUNC_NAMES;
 
 pub fn register(registry: &mut FunctionRegistry) {
@@ -58,6 +64,7 @@ pub fn register(registry: &mut FunctionRegistry) {
     tuple::register(registry);
     geo::register(registry);
     geo_h3::register(registry);
+    geo_s2::register(registry);
     hash::register(registry);
     other::register(registry);
     decimal::register_to_decimal(registry);
@@ -66,4 +73,5 @@ pub fn register(registry: &mut FunctionRegistry) {
     geometry::register(registry);
     geography::register(registry);
     hilbert::register(registry);
+    rank_digest::register(registry);
 }
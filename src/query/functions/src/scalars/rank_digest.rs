@@ -0,0 +1,81 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use databend_common_expression::types::BinaryType;
+use databend_common_expression::types::Float64Type;
+use databend_common_expression::vectorize_with_builder_2_arg;
+use databend_common_expression::FunctionDomain;
+use databend_common_expression::FunctionRegistry;
+
+use crate::aggregates::QuantileTDigestState;
+
+pub fn register(registry: &mut FunctionRegistry) {
+    registry.register_passthrough_nullable_2_arg::<BinaryType, Float64Type, Float64Type, _, _>(
+        "rank_digest_quantile",
+        |_, _, _| FunctionDomain::MayThrow,
+        vectorize_with_builder_2_arg::<BinaryType, Float64Type, Float64Type>(
+            |digest, level, builder, ctx| {
+                if let Some(validity) = &ctx.validity {
+                    if !validity.get_bit(builder.len()) {
+                        builder.push(0f64.into());
+                        return;
+                    }
+                }
+                if !(0.0..=1.0).contains(&level.0) {
+                    ctx.set_error(
+                        builder.len(),
+                        format!("the level must be between 0 and 1, but got {level}"),
+                    );
+                    builder.push(0f64.into());
+                    return;
+                }
+                match deserialize_digest(digest) {
+                    Some(mut state) => builder.push(state.quantile(level.0).into()),
+                    None => {
+                        ctx.set_error(builder.len(), "invalid rank digest");
+                        builder.push(0f64.into());
+                    }
+                }
+            },
+        ),
+    );
+
+    registry.register_passthrough_nullable_2_arg::<BinaryType, Float64Type, Float64Type, _, _>(
+        "rank_digest_cdf",
+        |_, _, _| FunctionDomain::MayThrow,
+        vectorize_with_builder_2_arg::<BinaryType, Float64Type, Float64Type>(
+            |digest, value, builder, ctx| {
+                if let Some(validity) = &ctx.validity {
+                    if !validity.get_bit(builder.len()) {
+                        builder.push(0f64.into());
+                        return;
+                    }
+                }
+                match deserialize_digest(digest) {
+                    Some(mut state) => builder.push(state.cdf(value.0).into()),
+                    None => {
+                        ctx.set_error(builder.len(), "invalid rank digest");
+                        builder.push(0f64.into());
+                    }
+                }
+            },
+        ),
+    );
+}
+
+fn deserialize_digest(digest: &[u8]) -> Option<QuantileTDigestState> {
+    borsh::from_slice::<QuantileTDigestState>(digest)
+        .ok()
+        .filter(|state| state.is_valid_digest())
+}
@@ -15,15 +15,22 @@
 use std::io::Write;
 
 use databend_common_expression::types::decimal::Decimal128Type;
+use databend_common_expression::types::number::Float64Type;
 use databend_common_expression::types::number::Int64Type;
+use databend_common_expression::types::number::NumberScalar;
 use databend_common_expression::types::number::UInt64Type;
+use databend_common_expression::types::BinaryType;
 use databend_common_expression::types::BitmapType;
 use databend_common_expression::types::BooleanType;
+use databend_common_expression::types::DataType;
 use databend_common_expression::types::DecimalSize;
+use databend_common_expression::types::NumberDataType;
 use databend_common_expression::types::StringType;
 use databend_common_expression::types::TimestampType;
+use databend_common_expression::types::ValueType;
 use databend_common_expression::Column;
 use databend_common_expression::FromData;
+use databend_common_expression::Scalar;
 use databend_common_functions::aggregates::eval_aggr;
 use goldenfile::Mint;
 use itertools::Itertools;
@@ -31,6 +38,7 @@ use roaring::RoaringTreemap;
 
 use super::run_agg_ast;
 use super::simulate_two_groups_group_by;
+use super::simulate_two_states_merge;
 use super::AggregationSimulator;
 
 #[test]
@@ -52,6 +60,7 @@ fn test_agg() {
     test_agg_covar_samp(file, eval_aggr);
     test_agg_covar_pop(file, eval_aggr);
     test_agg_retention(file, eval_aggr);
+    test_agg_churn(file, eval_aggr);
     test_agg_stddev(file, eval_aggr);
     test_agg_kurtosis(file, eval_aggr);
     test_agg_skewness(file, eval_aggr);
@@ -73,6 +82,60 @@ fn test_agg() {
     test_agg_json_array_agg(file, eval_aggr);
     test_agg_json_object_agg(file, eval_aggr);
     test_agg_mode(file, eval_aggr);
+    test_agg_value_counts(file, eval_aggr);
+    test_agg_fill_rate(file, eval_aggr);
+    test_agg_mode_with_count(file, eval_aggr);
+    test_agg_sum_foreach(file, eval_aggr);
+    test_agg_histogram_quantile(file, eval_aggr);
+    test_agg_last_by(file, eval_aggr);
+    test_agg_coef_variation(file, eval_aggr);
+    test_agg_stderr(file, eval_aggr);
+    test_agg_funnel_time(file, eval_aggr);
+    test_agg_sum_count(file, eval_aggr);
+    test_agg_count_distinct_exact(file, eval_aggr);
+    test_agg_dedup_latest(file, eval_aggr);
+    test_agg_bool_runs(file, eval_aggr);
+    test_agg_min_max_k(file, eval_aggr);
+    test_agg_geo_dedup(file, eval_aggr);
+    test_agg_arg_min_max_n(file, eval_aggr);
+    test_agg_percent_rank_of(file, eval_aggr);
+    test_agg_cdf_at(file, eval_aggr);
+    test_agg_uniq_composite(file, eval_aggr);
+    test_agg_window_funnel_steps(file, eval_aggr);
+    test_agg_jaccard_approx(file, eval_aggr);
+    test_agg_is_monotonic(file, eval_aggr);
+    test_agg_distinct_time_buckets(file, eval_aggr);
+    test_agg_uniq_by_bucket(file, eval_aggr);
+    test_agg_ema(file, eval_aggr);
+    test_agg_autocorr(file, eval_aggr);
+    test_agg_time_slope(file, eval_aggr);
+    test_agg_r_squared(file, eval_aggr);
+    test_agg_mape(file, eval_aggr);
+    test_agg_theil_sen_slope(file, eval_aggr);
+    test_agg_longest_run_value(file, eval_aggr);
+    test_agg_sign_changes(file, eval_aggr);
+    test_agg_lis_length(file, eval_aggr);
+    test_agg_transition_count(file, eval_aggr);
+    test_agg_dot_product(file, eval_aggr);
+    test_agg_trimmed_mean(file, eval_aggr);
+    test_agg_mad_outlier_count(file, eval_aggr);
+    test_agg_anomaly_count(file, eval_aggr);
+    test_agg_ntile_counts(file, eval_aggr);
+    test_agg_max_time_gap(file, eval_aggr);
+    test_agg_group_uniq_array(file, eval_aggr);
+    test_agg_group_uniq_array_sorted(file, eval_aggr);
+    test_agg_track_endpoints(file, eval_aggr);
+    test_agg_avg_speed(file, eval_aggr);
+    test_agg_median_weighted(file, eval_aggr);
+    test_agg_weighted_sum(file, eval_aggr);
+    test_agg_geo_convex_hull_area(file, eval_aggr);
+    test_agg_geo_enclosing_circle(file, eval_aggr);
+    test_agg_geo_spherical_centroid(file, eval_aggr);
+    test_agg_count_within_distance(file, eval_aggr);
+    test_agg_sum_resample(file, eval_aggr);
+    test_agg_hhi(file, eval_aggr);
+    test_agg_rate_if(file, eval_aggr);
+    test_agg_rank_digest(file, eval_aggr);
 }
 
 #[test]
@@ -94,6 +157,7 @@ fn test_agg_group_by() {
     test_agg_covar_samp(file, simulate_two_groups_group_by);
     test_agg_covar_pop(file, simulate_two_groups_group_by);
     test_agg_retention(file, simulate_two_groups_group_by);
+    test_agg_churn(file, simulate_two_groups_group_by);
     test_agg_stddev(file, simulate_two_groups_group_by);
     test_agg_kurtosis(file, simulate_two_groups_group_by);
     test_agg_skewness(file, simulate_two_groups_group_by);
@@ -113,6 +177,146 @@ fn test_agg_group_by() {
     test_agg_json_array_agg(file, eval_aggr);
     test_agg_json_object_agg(file, eval_aggr);
     test_agg_mode(file, simulate_two_groups_group_by);
+    test_agg_value_counts(file, simulate_two_groups_group_by);
+    test_agg_fill_rate(file, simulate_two_groups_group_by);
+    test_agg_mode_with_count(file, simulate_two_groups_group_by);
+    test_agg_sum_foreach(file, simulate_two_groups_group_by);
+    test_agg_histogram_quantile(file, simulate_two_groups_group_by);
+    test_agg_last_by(file, simulate_two_groups_group_by);
+    test_agg_coef_variation(file, simulate_two_groups_group_by);
+    test_agg_stderr(file, simulate_two_groups_group_by);
+    test_agg_funnel_time(file, simulate_two_groups_group_by);
+    test_agg_sum_count(file, simulate_two_groups_group_by);
+    test_agg_count_distinct_exact(file, simulate_two_groups_group_by);
+    test_agg_dedup_latest(file, simulate_two_groups_group_by);
+    test_agg_bool_runs(file, simulate_two_groups_group_by);
+    test_agg_min_max_k(file, simulate_two_groups_group_by);
+    test_agg_geo_dedup(file, simulate_two_groups_group_by);
+    test_agg_arg_min_max_n(file, simulate_two_groups_group_by);
+    test_agg_percent_rank_of(file, simulate_two_groups_group_by);
+    test_agg_cdf_at(file, simulate_two_groups_group_by);
+    test_agg_uniq_composite(file, simulate_two_groups_group_by);
+    test_agg_window_funnel_steps(file, simulate_two_groups_group_by);
+    test_agg_jaccard_approx(file, simulate_two_groups_group_by);
+    test_agg_is_monotonic(file, simulate_two_groups_group_by);
+    test_agg_distinct_time_buckets(file, simulate_two_groups_group_by);
+    test_agg_uniq_by_bucket(file, simulate_two_groups_group_by);
+    test_agg_ema(file, simulate_two_groups_group_by);
+    test_agg_autocorr(file, simulate_two_groups_group_by);
+    test_agg_time_slope(file, simulate_two_groups_group_by);
+    test_agg_r_squared(file, simulate_two_groups_group_by);
+    test_agg_mape(file, simulate_two_groups_group_by);
+    test_agg_theil_sen_slope(file, simulate_two_groups_group_by);
+    test_agg_longest_run_value(file, simulate_two_groups_group_by);
+    test_agg_sign_changes(file, simulate_two_groups_group_by);
+    test_agg_lis_length(file, simulate_two_groups_group_by);
+    test_agg_transition_count(file, simulate_two_groups_group_by);
+    test_agg_dot_product(file, simulate_two_groups_group_by);
+    test_agg_trimmed_mean(file, simulate_two_groups_group_by);
+    test_agg_mad_outlier_count(file, simulate_two_groups_group_by);
+    test_agg_anomaly_count(file, simulate_two_groups_group_by);
+    test_agg_ntile_counts(file, simulate_two_groups_group_by);
+    test_agg_max_time_gap(file, simulate_two_groups_group_by);
+    test_agg_group_uniq_array(file, simulate_two_groups_group_by);
+    test_agg_group_uniq_array_sorted(file, simulate_two_groups_group_by);
+    test_agg_track_endpoints(file, simulate_two_groups_group_by);
+    test_agg_avg_speed(file, simulate_two_groups_group_by);
+    test_agg_median_weighted(file, simulate_two_groups_group_by);
+    test_agg_weighted_sum(file, simulate_two_groups_group_by);
+    test_agg_geo_convex_hull_area(file, simulate_two_groups_group_by);
+    test_agg_geo_enclosing_circle(file, simulate_two_groups_group_by);
+    test_agg_geo_spherical_centroid(file, simulate_two_groups_group_by);
+    test_agg_count_within_distance(file, simulate_two_groups_group_by);
+    test_agg_sum_resample(file, simulate_two_groups_group_by);
+    test_agg_hhi(file, simulate_two_groups_group_by);
+    test_agg_rate_if(file, simulate_two_groups_group_by);
+    test_agg_rank_digest(file, simulate_two_groups_group_by);
+}
+
+#[test]
+fn test_agg_approx_count_distinct_merge() {
+    let sketches = |params: Vec<Scalar>, values: Vec<u64>| {
+        let rows = values.len();
+        let column = UInt64Type::from_data(values);
+        eval_aggr("approx_count_distinct_state", params, &[column], rows)
+            .unwrap()
+            .0
+    };
+    let error_rate = vec![Scalar::Number(NumberScalar::Float64(0.1.into()))];
+
+    for params in [vec![], error_rate] {
+        // sketches of overlapping parts, including an empty one
+        let sketch_column = Column::concat_columns(
+            vec![
+                sketches(params.clone(), vec![1, 2, 3]),
+                sketches(params.clone(), vec![3, 4]),
+                sketches(params.clone(), vec![]),
+            ]
+            .into_iter(),
+        )
+        .unwrap();
+        let (merged, data_type) = eval_aggr(
+            "approx_count_distinct_merge",
+            params.clone(),
+            &[sketch_column],
+            3,
+        )
+        .unwrap();
+        let (expected, _) = eval_aggr(
+            "approx_count_distinct",
+            params,
+            &[UInt64Type::from_data(vec![1u64, 2, 3, 4])],
+            4,
+        )
+        .unwrap();
+        assert_eq!(data_type, DataType::Number(NumberDataType::UInt64));
+        assert_eq!(merged, expected);
+    }
+
+    let invalid = BinaryType::from_data(vec![b"not a sketch".as_slice()]);
+    let err = eval_aggr("approx_count_distinct_merge", vec![], &[invalid], 1).unwrap_err();
+    assert!(
+        err.message()
+            .starts_with("approx_count_distinct_merge expects sketches built by")
+    );
+
+    let err = eval_aggr(
+        "approx_count_distinct_merge",
+        vec![],
+        &[UInt64Type::from_data(vec![1u64])],
+        1,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.message(),
+        "approx_count_distinct_merge expects a Binary sketch, but got 'Number(UInt64)'"
+    );
+}
+
+#[test]
+fn test_agg_count_fast_path() {
+    let rows = 1000;
+    let values = (0..rows as u64).collect::<Vec<_>>();
+    let validity = (0..rows).map(|i| i % 3 != 0).collect::<Vec<_>>();
+    let column = UInt64Type::from_data(values.clone());
+    let nullable = UInt64Type::from_data_with_validity(values.clone(), validity);
+    let all_null = UInt64Type::from_data_with_validity(values, vec![false; rows]);
+    let cases = [
+        (vec![], rows),
+        (vec![column], rows),
+        (vec![nullable], 666),
+        (vec![all_null], 0),
+    ];
+
+    for (columns, expected) in cases {
+        // `accumulate` counts the block at once, `accumulate_keys` iterates the rows.
+        let (fast, _) = eval_aggr("count", vec![], &columns, rows).unwrap();
+        let (groups, _) = simulate_two_groups_group_by("count", vec![], &columns, rows).unwrap();
+        let fast = UInt64Type::try_downcast_column(&fast).unwrap();
+        let groups = UInt64Type::try_downcast_column(&groups).unwrap();
+        assert_eq!(fast.as_slice(), &[expected as u64]);
+        assert_eq!(groups.iter().sum::<u64>(), expected as u64);
+    }
 }
 
 fn gen_bitmap_data() -> Column {
@@ -191,6 +395,17 @@ fn get_example() -> Vec<(&'static str, Column)> {
                 },
             ),
         ),
+        (
+            "lon",
+            Float64Type::from_data(vec![116.4, 116.3, 116.5, 116.6]),
+        ),
+        ("lat", Float64Type::from_data(vec![39.9, 39.8, 40.0, 40.1])),
+        (
+            "lat_null",
+            Float64Type::from_data_with_validity(vec![39.9, 39.8, 40.0, 40.1], vec![
+                true, false, true, true,
+            ]),
+        ),
     ]
 }
 
@@ -207,6 +422,8 @@ fn test_sum(file: &mut impl Write, simulator: impl AggregationSimulator) {
     run_agg_ast(file, "sum(a)", get_example().as_slice(), simulator);
     run_agg_ast(file, "sum(x_null)", get_example().as_slice(), simulator);
     run_agg_ast(file, "sum(all_null)", get_example().as_slice(), simulator);
+    // there is no interval type for the total of timestamps
+    run_agg_ast(file, "sum(dt)", get_example().as_slice(), simulator);
 }
 
 fn test_avg(file: &mut impl Write, simulator: impl AggregationSimulator) {
@@ -215,6 +432,40 @@ fn test_avg(file: &mut impl Write, simulator: impl AggregationSimulator) {
     run_agg_ast(file, "avg(dec)", get_example().as_slice(), simulator);
     run_agg_ast(file, "avg(x_null)", get_example().as_slice(), simulator);
     run_agg_ast(file, "avg(all_null)", get_example().as_slice(), simulator);
+
+    // the mean of b is 2.5 of the last digit, rounded to 3 by half_up and to 2 by half_even
+    run_agg_ast(
+        file,
+        "avg('half_up')(CAST(b AS DECIMAL(5, 0)) * CAST('0.0001' AS DECIMAL(4, 4)))",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "avg('half_even')(CAST(b AS DECIMAL(5, 0)) * CAST('0.0001' AS DECIMAL(4, 4)))",
+        get_example().as_slice(),
+        simulator,
+    );
+    // the mean of c is 1.75 of the last digit, and 2.5 for the second group
+    run_agg_ast(
+        file,
+        "avg('half_up')(CAST(c AS DECIMAL(5, 0)) * CAST('0.0001' AS DECIMAL(4, 4)))",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "avg('half_even')(CAST(c AS DECIMAL(5, 0)) * CAST('0.0001' AS DECIMAL(4, 4)))",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "avg('truncate')(CAST(c AS DECIMAL(5, 0)) * CAST('0.0001' AS DECIMAL(4, 4)))",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(file, "avg('ceil')(a)", get_example().as_slice(), simulator);
 }
 
 fn test_uniq(file: &mut impl Write, simulator: impl AggregationSimulator) {
@@ -406,6 +657,34 @@ fn test_agg_retention(file: &mut impl Write, simulator: impl AggregationSimulato
     );
 }
 
+fn test_agg_churn(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    run_agg_ast(
+        file,
+        "churn(a > 1, b > 1)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "churn(a > 1, b > 1, x_null > 1)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "churn(a > 1, b > 1, x_null > 1, all_null > 1)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // the first condition is never met, no step has a churn
+    run_agg_ast(
+        file,
+        "churn(a > 10, b > 1)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
 fn test_agg_stddev(file: &mut impl Write, simulator: impl AggregationSimulator) {
     run_agg_ast(file, "stddev_pop(a)", get_example().as_slice(), simulator);
     run_agg_ast(file, "stddev(x_null)", get_example().as_slice(), simulator);
@@ -642,6 +921,25 @@ fn test_agg_quantile_tdigest(file: &mut impl Write, simulator: impl AggregationS
         get_example().as_slice(),
         simulator,
     );
+    run_agg_ast(
+        file,
+        "quantile_tdigest(0.2)(a)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+#[test]
+fn test_agg_quantile_tdigest_merge() {
+    let column = UInt64Type::from_data(vec![10u64, 40, 20, 50, 30]);
+    for level in [0f64, 0.5, 1f64] {
+        let params = vec![Scalar::Number(NumberScalar::Float64(level.into()))];
+        let (merged, _) =
+            simulate_two_states_merge("quantile_tdigest", params.clone(), &[column.clone()], 5)
+                .unwrap();
+        let (expected, _) = eval_aggr("quantile_tdigest", params, &[column.clone()], 5).unwrap();
+        assert_eq!(merged, expected);
+    }
 }
 
 fn test_agg_quantile_tdigest_weighted(file: &mut impl Write, simulator: impl AggregationSimulator) {
@@ -892,3 +1190,1733 @@ fn test_agg_mode(file: &mut impl Write, simulator: impl AggregationSimulator) {
     run_agg_ast(file, "mode(d)", get_example().as_slice(), simulator);
     run_agg_ast(file, "mode(all_null)", get_example().as_slice(), simulator);
 }
+
+fn test_agg_value_counts(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    run_agg_ast(file, "value_counts(c)", get_example().as_slice(), simulator);
+    run_agg_ast(
+        file,
+        "value_counts(x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "value_counts(all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "value_counts_with_nulls(x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "value_counts_with_nulls(all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_fill_rate(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    run_agg_ast(file, "fill_rate(a)", get_example().as_slice(), simulator);
+    run_agg_ast(
+        file,
+        "fill_rate(x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "fill_rate(all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_mode_with_count(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    run_agg_ast(
+        file,
+        "mode_with_count(c)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "mode_with_count(x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "mode_with_count(all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_sum_foreach(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    run_agg_ast(
+        file,
+        "sum_foreach([b, c])",
+        get_example().as_slice(),
+        simulator,
+    );
+    // ragged arrays
+    run_agg_ast(
+        file,
+        "sum_foreach(slice([b, c, d], b))",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "max_foreach([x_null, y_null])",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(file, "sum_foreach(b)", get_example().as_slice(), simulator);
+    run_agg_ast(
+        file,
+        "covar_pop_foreach([b, c], slice([b, c], b))",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_histogram_quantile(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // two series of the buckets (0.5, 1, 2, +Inf)
+    let buckets = [
+        (
+            "le",
+            Float64Type::from_data(vec![
+                0.5,
+                0.5,
+                1.0,
+                1.0,
+                2.0,
+                2.0,
+                f64::INFINITY,
+                f64::INFINITY,
+            ]),
+        ),
+        (
+            "cnt",
+            UInt64Type::from_data(vec![2u64, 1, 5, 4, 9, 6, 10, 8]),
+        ),
+    ];
+    run_agg_ast(
+        file,
+        "histogram_quantile(0.75)(le, cnt)",
+        &buckets,
+        simulator,
+    );
+    // falls into the +Inf bucket
+    run_agg_ast(
+        file,
+        "histogram_quantile(0.9)(le, cnt)",
+        &buckets,
+        simulator,
+    );
+    // the count of bucket 2 violates the monotonicity
+    let buckets = [
+        (
+            "le",
+            Float64Type::from_data(vec![0.5, 1.0, 2.0, 4.0, f64::INFINITY]),
+        ),
+        ("cnt", UInt64Type::from_data(vec![2u64, 6, 4, 9, 10])),
+    ];
+    run_agg_ast(
+        file,
+        "histogram_quantile(0.7)(le, cnt)",
+        &buckets,
+        simulator,
+    );
+    // without the +Inf bucket
+    run_agg_ast(
+        file,
+        "histogram_quantile(0.5)(a, b)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_last_by(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    run_agg_ast(file, "last_by(a, dt)", get_example().as_slice(), simulator);
+    run_agg_ast(file, "last_by(s, dt)", get_example().as_slice(), simulator);
+    // the NULL value of the latest row is kept
+    run_agg_ast(
+        file,
+        "last_by(x_null, dt)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // rows with NULL timestamp are ignored
+    run_agg_ast(
+        file,
+        "last_by(dt, x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "last_by(a, all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // the first row wins on ties
+    run_agg_ast(file, "last_by(a, d)", get_example().as_slice(), simulator);
+}
+
+fn test_agg_coef_variation(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // same as stddev_pop(a) / avg(a)
+    run_agg_ast(
+        file,
+        "coef_variation(a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "coef_variation_samp(a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "coef_variation(x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // zero mean
+    run_agg_ast(
+        file,
+        "coef_variation(z)",
+        &[("z", Int64Type::from_data(vec![1i64, -2, -1, 2]))],
+        simulator,
+    );
+}
+
+fn test_agg_stderr(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // same as stddev_samp(a) / sqrt(count(a))
+    run_agg_ast(file, "stderr(a)", get_example().as_slice(), simulator);
+    run_agg_ast(file, "stderr(dec)", get_example().as_slice(), simulator);
+    run_agg_ast(file, "stderr(x_null)", get_example().as_slice(), simulator);
+    run_agg_ast(
+        file,
+        "stderr(all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_funnel_time(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // not fully converted
+    run_agg_ast(
+        file,
+        "funnel_time(2)(dt, event1, event2, event3)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "funnel_time(2)(dt, a = 4, a = 2, a = 1)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // the last event is out of the window
+    run_agg_ast(
+        file,
+        "funnel_time(1)(dt, a = 4, a = 2, a = 1)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "funnel_time(2)(dt, event1)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_sum_count(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // sum / count of the output equals avg(a)
+    run_agg_ast(file, "sum_count(a)", get_example().as_slice(), simulator);
+    run_agg_ast(file, "avg(a)", get_example().as_slice(), simulator);
+    run_agg_ast(
+        file,
+        "sum_count(x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(file, "avg(x_null)", get_example().as_slice(), simulator);
+}
+
+fn test_agg_count_distinct_exact(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // the outputs must be the same as uniq(c) and uniq(x_null)
+    run_agg_ast(
+        file,
+        "count_distinct_exact(c)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "count_distinct_exact(x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "count_distinct_exact(s)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "count_distinct_exact(c, d)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "count_distinct_exact(all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_dedup_latest(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    run_agg_ast(
+        file,
+        "dedup_latest(c, a, b)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // equal versions, the greatest value wins
+    run_agg_ast(
+        file,
+        "dedup_latest(c, a, d)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // a NULL value of the latest version is kept
+    run_agg_ast(
+        file,
+        "dedup_latest(c, x_null, b)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // rows without version are ignored
+    run_agg_ast(
+        file,
+        "dedup_latest(c, a, y_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "dedup_latest(c, s, dt)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_bool_runs(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    run_agg_ast(
+        file,
+        "bool_runs(dt, event1)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "bool_runs(dt, a > 2)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // rows with a NULL flag are skipped
+    run_agg_ast(
+        file,
+        "bool_runs(dt, x_null = 1)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "bool_runs(dt, all_null = 1)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_min_max_k(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    run_agg_ast(file, "min_k(2)(a)", get_example().as_slice(), simulator);
+    run_agg_ast(file, "max_k(3)(b)", get_example().as_slice(), simulator);
+    // fewer values than k
+    run_agg_ast(
+        file,
+        "min_k(3)(x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_geo_dedup(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // (1, 4), (2, 3), (3, 2), (4, 1) are about 157 km apart from their neighbours
+    run_agg_ast(
+        file,
+        "geo_dedup(a, b, 1000)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // greedy: (1, 4) covers (2, 3), and (3, 2) covers (4, 1)
+    run_agg_ast(
+        file,
+        "geo_dedup(a, b, 200000)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "geo_dedup(a, b, 10000000)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // duplicated points
+    run_agg_ast(
+        file,
+        "geo_dedup(c, d, 1)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "geo_dedup(x_null, d, 1)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_arg_min_max_n(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    run_agg_ast(
+        file,
+        "arg_max_n(a, b, 2)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "arg_min_n(a, b, 2)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // ties of the order are sorted by value
+    run_agg_ast(
+        file,
+        "arg_max_n(a, c, 3)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // fewer rows than n
+    run_agg_ast(
+        file,
+        "arg_max_n(x_null, b, 3)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_percent_rank_of(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // the inverse of the quantile
+    run_agg_ast(
+        file,
+        "quantile_disc(0.5)(a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "percent_rank_of(2)(a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "percent_rank_of(1)(c)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "percent_rank_of(1.5)(x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "percent_rank_of(1)(all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_cdf_at(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    run_agg_ast(
+        file,
+        "cdf_at(1, 2.5, 4)(a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(file, "cdf_at(1, 2)(c)", get_example().as_slice(), simulator);
+    run_agg_ast(
+        file,
+        "cdf_at(1.5, 2)(x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "cdf_at(1)(all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // the thresholds are required
+    run_agg_ast(file, "cdf_at(a)", get_example().as_slice(), simulator);
+}
+
+fn test_agg_uniq_composite(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    run_agg_ast(
+        file,
+        "uniq_combined(a, c)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "uniq_combined(c, d)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // the rows with NULL components are counted
+    run_agg_ast(
+        file,
+        "uniq_combined(x_null, d)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // (2, NULL) and (NULL, 2) are different keys
+    run_agg_ast(
+        file,
+        "uniq_combined(x_null, y_null - 1)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // the rows where every component is NULL are skipped, the outputs must be the same
+    // as uniq(x_null) and uniq(all_null)
+    run_agg_ast(
+        file,
+        "uniq_combined(x_null, x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "uniq_combined(all_null, all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_window_funnel_steps(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // a uniform window is from the first event, the per-step windows are between the steps
+    run_agg_ast(
+        file,
+        "window_funnel(1)(dt, a = 4, a = 2, a = 1)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "window_funnel([1, 1])(dt, a = 4, a = 2, a = 1)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "window_funnel([1, 0])(dt, a = 4, a = 2, a = 1)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "funnel_time([1, 1])(dt, a = 4, a = 2, a = 1)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "window_funnel([1])(dt, a = 4, a = 2, a = 1)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_jaccard_approx(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // the estimates of these small sets are exact: 3 / 4, 1 / 3, 2 / 4 and 0
+    run_agg_ast(
+        file,
+        "jaccard_approx(b, c)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "jaccard_approx(c, d)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "jaccard_approx(b, x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "jaccard_approx(x_null, y_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "jaccard_approx(all_null, all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_is_monotonic(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    run_agg_ast(
+        file,
+        "is_monotonic(dt, a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "is_monotonic(dt, dt)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "is_monotonic(b, a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // rows of the same order key are not ordered between them
+    run_agg_ast(
+        file,
+        "is_monotonic(d, a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "is_monotonic(c, b)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // rows with a NULL value are skipped
+    run_agg_ast(
+        file,
+        "is_monotonic(dt, x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "is_monotonic(dt, all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_distinct_time_buckets(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    run_agg_ast(
+        file,
+        "distinct_time_buckets(dt, 'day')",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "distinct_time_buckets(dt, 'hour')",
+        get_example().as_slice(),
+        simulator,
+    );
+    // the timestamps are truncated to the bucket
+    run_agg_ast(
+        file,
+        "distinct_time_buckets(add_hours(dt, c), 'hour')",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "distinct_time_buckets(add_hours(dt, c), 'day')",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "distinct_time_buckets(add_minutes(dt, a), 'minute')",
+        get_example().as_slice(),
+        simulator,
+    );
+    // NULL timestamps are skipped
+    run_agg_ast(
+        file,
+        "distinct_time_buckets(add_hours(dt, x_null), 'hour')",
+        get_example().as_slice(),
+        simulator,
+    );
+    // unknown granularity
+    run_agg_ast(
+        file,
+        "distinct_time_buckets(dt, 'week')",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_uniq_by_bucket(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    run_agg_ast(
+        file,
+        "uniq_by_bucket(dt, 'day', c)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // the hours 1, 2, 1 and 3 hold the values 4, 3, 2 and 1
+    run_agg_ast(
+        file,
+        "uniq_by_bucket(add_hours(dt, c), 'hour', a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // rows with a NULL value are skipped
+    run_agg_ast(
+        file,
+        "uniq_by_bucket(dt, 'day', x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "uniq_by_bucket(dt, 'day', all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // unknown granularity
+    run_agg_ast(
+        file,
+        "uniq_by_bucket(dt, 'week', c)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_ema(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // sorted by `dt`, the values of `a` are 3, 4, 2, 1
+    run_agg_ast(file, "ema(0.5)(dt, a)", get_example().as_slice(), simulator);
+    run_agg_ast(file, "ema(0.2)(dt, a)", get_example().as_slice(), simulator);
+    run_agg_ast(file, "ema(1)(dt, a)", get_example().as_slice(), simulator);
+    run_agg_ast(
+        file,
+        "ema(0.5)(dt, x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "ema(0.5)(dt, all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(file, "ema(0)(dt, a)", get_example().as_slice(), simulator);
+    run_agg_ast(file, "ema(1.5)(dt, a)", get_example().as_slice(), simulator);
+}
+
+fn test_agg_autocorr(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // sorted by dt the values are 3, 4, 2, 1, around their mean 2.5 the lag 1 products
+    // sum to 0.75 and the squares to 5
+    run_agg_ast(
+        file,
+        "autocorr(1)(dt, a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "autocorr(2)(dt, a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // too short
+    run_agg_ast(
+        file,
+        "autocorr(4)(dt, a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // constant
+    run_agg_ast(
+        file,
+        "autocorr(1)(dt, d)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // rows with a NULL value are skipped
+    run_agg_ast(
+        file,
+        "autocorr(1)(dt, x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "autocorr(1)(dt, all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // invalid lag
+    run_agg_ast(
+        file,
+        "autocorr(0)(dt, a)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_time_slope(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // dt is in microseconds: regressed on [1, 0, 2, 3] around the mean 1.5, the values
+    // [4, 3, 2, 1] give the co-moments -4 and the squared deviations 5, a slope of -0.8
+    // per microsecond, i.e. -800000 per second
+    run_agg_ast(
+        file,
+        "time_slope(dt, a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // the time doesn't vary
+    run_agg_ast(
+        file,
+        "time_slope(d, a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "time_slope(dt, x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "time_slope(dt, all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_r_squared(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // the square of the correlation: b is a linear function of a, and c regressed on a
+    // has the co-moments -2.5 and the squared deviations 5 and 2.75, i.e. 6.25 / 13.75
+    run_agg_ast(file, "r_squared(b, a)", get_example().as_slice(), simulator);
+    run_agg_ast(file, "r_squared(c, a)", get_example().as_slice(), simulator);
+    // x or y doesn't vary
+    run_agg_ast(file, "r_squared(b, d)", get_example().as_slice(), simulator);
+    run_agg_ast(file, "r_squared(d, a)", get_example().as_slice(), simulator);
+    run_agg_ast(
+        file,
+        "r_squared(x_null, a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "r_squared(all_null, a)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_mape(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // the errors of b against a are 3 / 4, 1 / 3, 1 / 2 and 3 / 1, i.e. 55 / 12 in total
+    run_agg_ast(file, "mape(a, b)", get_example().as_slice(), simulator);
+    run_agg_ast(file, "mape(b, c)", get_example().as_slice(), simulator);
+    // the rows whose actual value is 0 are skipped: only 1 against 3 and 2 against 1
+    // are counted, the first group has no other row
+    run_agg_ast(file, "mape(c - 1, a)", get_example().as_slice(), simulator);
+    run_agg_ast(file, "mape(x_null, a)", get_example().as_slice(), simulator);
+    run_agg_ast(file, "mape(a, x_null)", get_example().as_slice(), simulator);
+    run_agg_ast(
+        file,
+        "mape(all_null, a)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_theil_sen_slope(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // b is a linear function of a, every pair has the slope -1
+    run_agg_ast(
+        file,
+        "theil_sen_slope(b, a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // the points (1, 1), (2, 2), (3, 1), (4, 3) have the pairwise slopes -1, 0, 1/2, 2/3,
+    // 1 and 2, the median is (1/2 + 2/3) / 2 where the least-squares slope is 1/2
+    run_agg_ast(
+        file,
+        "theil_sen_slope(c, b)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // x doesn't vary
+    run_agg_ast(
+        file,
+        "theil_sen_slope(b, d)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "theil_sen_slope(x_null, a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "theil_sen_slope(all_null, a)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_longest_run_value(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // sorted by dt, c is [2, 1, 1, 3]
+    run_agg_ast(
+        file,
+        "longest_run_value(dt, c)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "longest_run_value(dt, d)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // all the runs have a single row, the earliest one wins
+    run_agg_ast(
+        file,
+        "longest_run_value(dt, s)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "longest_run_value(dt, x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "longest_run_value(dt, all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_sign_changes(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // sorted by dt, the values are [1, -3, 1, -9]
+    run_agg_ast(
+        file,
+        "sign_changes(dt, (2 * a - 5) * (2 * c - 3))",
+        get_example().as_slice(),
+        simulator,
+    );
+    // sorted by dt, the values are [0, -1, -1, 1], the zero is skipped
+    run_agg_ast(
+        file,
+        "sign_changes(dt, c - 2)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "sign_changes(dt, x_null - 2)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "sign_changes(dt, all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_lis_length(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // sorted by dt, b is [2, 1, 3, 4], c is [2, 1, 1, 3] and a is [3, 4, 2, 1]
+    run_agg_ast(
+        file,
+        "lis_length(dt, b)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "lis_length(dt, c)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "lis_length(dt, a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // all the rows have the same order, only one of them is counted
+    run_agg_ast(
+        file,
+        "lis_length(d, b)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "lis_length(dt, x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "lis_length(dt, all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "lis_length(dt, s)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_transition_count(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // sorted by dt, c is [2, 1, 1, 3]
+    run_agg_ast(
+        file,
+        "transition_count(dt, c)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "transition_count(dt, d)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "transition_count(dt, x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "transition_count(dt, all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_dot_product(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    run_agg_ast(
+        file,
+        "dot_product(a, b)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "dot_product(a, x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "dot_product(a, all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_trimmed_mean(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // the percentiles of 1, 2, 3, 4 are 1.75 and 3.25, the mean of 2 and 3 is 2.5. Of the
+    // groups 2, 4 and 1, 3, no value is between the percentiles
+    run_agg_ast(
+        file,
+        "trimmed_mean(0.25, 0.75)(a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // the mean of all the values
+    run_agg_ast(
+        file,
+        "trimmed_mean(0, 1)(a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // the percentiles of 1, 1, 2, 3 are 1 and 1.5, the mean of 1 and 1
+    run_agg_ast(
+        file,
+        "trimmed_mean(0, 0.5)(c)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "trimmed_mean(0, 0.5)(x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "trimmed_mean(0, 1)(all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // invalid bounds
+    run_agg_ast(
+        file,
+        "trimmed_mean(0.5, 0.5)(a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "trimmed_mean(-0.1, 0.5)(a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "trimmed_mean(0.2, 1.5)(a)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_mad_outlier_count(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // 604 is far away from 1, 2 and 3, the MAD is 1
+    run_agg_ast(
+        file,
+        "mad_outlier_count(3)((b - 1) * (b - 2) * (b - 3) * 100 + b)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "mad_outlier_count(1)(a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "mad_outlier_count(0.5)(c)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // all the values are the median, the MAD is 0
+    run_agg_ast(
+        file,
+        "mad_outlier_count(1)(d)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // NULL values are ignored
+    run_agg_ast(
+        file,
+        "mad_outlier_count(1)(x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "mad_outlier_count(1)(all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // invalid threshold
+    run_agg_ast(
+        file,
+        "mad_outlier_count(0)(a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "mad_outlier_count(-1)(a)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_anomaly_count(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // the percentiles of 1, 2, 3, 4 are 1.3 and 3.7, 1 and 4 are outside them
+    run_agg_ast(
+        file,
+        "anomaly_count(0.1, 0.9)(a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // the percentiles of 1, 1, 2, 3 are 1 and 1.5, the values equal to them are inside
+    run_agg_ast(
+        file,
+        "anomaly_count(0, 0.5)(c)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "anomaly_count(0, 1)(a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // NULL values are ignored
+    run_agg_ast(
+        file,
+        "anomaly_count(0.1, 0.9)(x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "anomaly_count(0.1, 0.9)(all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // invalid bounds
+    run_agg_ast(
+        file,
+        "anomaly_count(0.9, 0.1)(a)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_ntile_counts(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    run_agg_ast(
+        file,
+        "ntile_counts(4)(a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "ntile_counts(2)(c)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // the values equal to an edge fall into the same bucket
+    run_agg_ast(
+        file,
+        "ntile_counts(3)(d)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "ntile_counts(2)(x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "ntile_counts(2)(all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "ntile_counts(0)(a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "ntile_counts(1000000000000)(a)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_max_time_gap(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    run_agg_ast(
+        file,
+        "max_time_gap(dt)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "max_time_gap(add_hours(dt, c))",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "max_time_gap(x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "max_time_gap(all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_group_uniq_array(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // the distinct values are sorted, the duplicated 1 is kept once
+    run_agg_ast(
+        file,
+        "group_uniq_array(c)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "group_uniq_array(x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "group_uniq_array(all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_group_uniq_array_sorted(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    run_agg_ast(
+        file,
+        "group_uniq_array_sorted(c)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "group_uniq_array_sorted(x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_track_endpoints(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    run_agg_ast(
+        file,
+        "track_endpoints(dt, lon, lat)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // the earliest point has a NULL latitude and is skipped
+    run_agg_ast(
+        file,
+        "track_endpoints(dt, lon, lat_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // all the points have the same ts, the smallest and the greatest (lon, lat) are taken
+    run_agg_ast(
+        file,
+        "track_endpoints(d, lon, lat)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "track_endpoints(dt, lon, all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_avg_speed(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // a fix every minute along the track
+    run_agg_ast(
+        file,
+        "avg_speed(b * 60, lon, lat)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // the timestamps are in the reverse order of the rows
+    run_agg_ast(
+        file,
+        "avg_speed(add_minutes(dt, a), lon, lat)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // no time elapsed
+    run_agg_ast(
+        file,
+        "avg_speed(d, lon, lat)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // the second group has a single point left
+    run_agg_ast(
+        file,
+        "avg_speed(b, lon, lat_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "avg_speed(b, lon, all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_median_weighted(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // 1, 2, 3, 4 weigh 4, 3, 2, 1, the cumulative weight 7 of 2 reaches half of 10
+    run_agg_ast(
+        file,
+        "median_weighted(a, b)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // with the same weights, the lower of the middle values
+    run_agg_ast(
+        file,
+        "median_weighted(b, d)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // 2 and 1 weigh 0 and -1 and are skipped, 3 and 4 weigh 1 and 2
+    run_agg_ast(
+        file,
+        "median_weighted(a, a - 2)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "median_weighted(a, a - 5)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "median_weighted(x_null, b)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "median_weighted(a, all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_weighted_sum(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    run_agg_ast(
+        file,
+        "weighted_sum(a, b)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // a float weight sums in Float64
+    run_agg_ast(
+        file,
+        "weighted_sum(a, b / 2)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "weighted_sum(x_null, a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "weighted_sum(all_null, a)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // far beyond the range of Int64 but within Decimal(38, 0), then beyond it
+    let big = [
+        ("big", Int64Type::from_data(vec![i64::MAX; 4])),
+        ("b", UInt64Type::from_data(vec![1u64, 2, 3, 4])),
+    ];
+    run_agg_ast(file, "weighted_sum(big, b)", &big, simulator);
+    run_agg_ast(file, "weighted_sum(big, big)", &big, simulator);
+}
+
+fn test_agg_geo_convex_hull_area(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // (2, 4) is inside the triangle of the other points
+    run_agg_ast(
+        file,
+        "geo_convex_hull_area(b, b * c)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // (1, 1) is inside the triangle of the other points
+    run_agg_ast(
+        file,
+        "geo_convex_hull_area(b % 3, c % 3)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // collinear points
+    run_agg_ast(
+        file,
+        "geo_convex_hull_area(b, b)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // rows with a NULL coordinate are skipped
+    run_agg_ast(
+        file,
+        "geo_convex_hull_area(x_null, b * c)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "geo_convex_hull_area(b, all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_geo_enclosing_circle(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // all the points are within the radius from the center, the farthest ones on it
+    run_agg_ast(
+        file,
+        "geo_enclosing_circle(lon, lat)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // the circle passes through 3 of the points, (1, 1) is inside it
+    run_agg_ast(
+        file,
+        "geo_enclosing_circle(b % 3, c % 3)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // a single point gives a circle of radius 0
+    run_agg_ast(
+        file,
+        "geo_enclosing_circle(d, d)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // rows with a NULL coordinate are skipped
+    run_agg_ast(
+        file,
+        "geo_enclosing_circle(lon, lat_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "geo_enclosing_circle(lon, all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_geo_spherical_centroid(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    run_agg_ast(
+        file,
+        "geo_spherical_centroid(lon, lat)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // the points straddle the antimeridian, the centroid is near it rather than near
+    // the prime meridian, where the mean of the longitudes is
+    run_agg_ast(
+        file,
+        "geo_spherical_centroid(lon, lat)",
+        &[
+            (
+                "lon",
+                Float64Type::from_data(vec![179.0, -177.0, 178.0, -176.0]),
+            ),
+            ("lat", Float64Type::from_data(vec![10.0, 20.0, 0.0, -10.0])),
+        ],
+        simulator,
+    );
+    // antipodal points cancel out
+    run_agg_ast(
+        file,
+        "geo_spherical_centroid(lon, lat)",
+        &[
+            ("lon", Float64Type::from_data(vec![0.0, 180.0, 0.0, 180.0])),
+            ("lat", Float64Type::from_data(vec![0.0, 0.0, 0.0, 0.0])),
+        ],
+        simulator,
+    );
+    // rows with a NULL coordinate are skipped
+    run_agg_ast(
+        file,
+        "geo_spherical_centroid(lon, lat_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "geo_spherical_centroid(lon, all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_count_within_distance(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // the points are 35864, 33904, 42590 and 52267 meters from (116, 40)
+    run_agg_ast(
+        file,
+        "count_within_distance(lon, lat, 116, 40, 40000)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "count_within_distance(lon, lat, 116, 40, 50000)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // the center and the radius are read on each row
+    run_agg_ast(
+        file,
+        "count_within_distance(lon, lat, lon, lat, 0)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // rows with a NULL coordinate are skipped
+    run_agg_ast(
+        file,
+        "count_within_distance(lon, lat_null, 116, 40, 50000)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "count_within_distance(all_null, lat, 116, 40, 50000)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_sum_resample(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    run_agg_ast(
+        file,
+        "sum_resample(0, 6, 2)(a, b)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // the last bucket [3, 5) is cut at the end 4
+    run_agg_ast(
+        file,
+        "sum_resample(1, 4, 2)(b, c)",
+        get_example().as_slice(),
+        simulator,
+    );
+    // the keys outside [start, end) are skipped
+    run_agg_ast(
+        file,
+        "sum_resample(2, 4, 1)(b, c)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "count_resample(0, 4, 2)(c)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "sum_resample(0, 4, 2)(b, x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "sum_resample(0, 4, 0)(b, c)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "sum_resample(4, 0, 1)(b, c)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
+
+fn test_agg_hhi(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    // the shares of 1, 2 and 3 are 2/4, 1/4 and 1/4: 4/16 + 1/16 + 1/16 = 0.375
+    run_agg_ast(file, "hhi(c)", get_example().as_slice(), simulator);
+    run_agg_ast(file, "hhi(b)", get_example().as_slice(), simulator);
+    run_agg_ast(file, "hhi(d)", get_example().as_slice(), simulator);
+    run_agg_ast(file, "hhi(x_null)", get_example().as_slice(), simulator);
+    run_agg_ast(file, "hhi(all_null)", get_example().as_slice(), simulator);
+}
+
+fn test_agg_rate_if(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    run_agg_ast(file, "rate_if(a > 2)", get_example().as_slice(), simulator);
+    run_agg_ast(file, "rate_if(b > 1)", get_example().as_slice(), simulator);
+    // the rows where `x_null` is NULL are counted but never match
+    run_agg_ast(
+        file,
+        "rate_if(x_null > 1)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "rate_if(all_null > 1)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(file, "rate_if(a)", get_example().as_slice(), simulator);
+}
+
+fn test_agg_rank_digest(file: &mut impl Write, simulator: impl AggregationSimulator) {
+    run_agg_ast(file, "rank_digest(a)", get_example().as_slice(), simulator);
+    run_agg_ast(
+        file,
+        "rank_digest(x_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+    run_agg_ast(
+        file,
+        "rank_digest(all_null)",
+        get_example().as_slice(),
+        simulator,
+    );
+}
@@ -0,0 +1,52 @@
+// Copyright 2022 Datafuse Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use databend_common_functions::aggregates::AggregateFunctionFactory;
+
+#[test]
+fn test_registered_signatures() {
+    let factory = AggregateFunctionFactory::instance();
+    let signatures = factory.registered_signatures();
+
+    let mut names = factory.registered_names();
+    names.sort();
+    assert_eq!(
+        signatures
+            .iter()
+            .map(|s| s.name.clone())
+            .collect::<Vec<_>>(),
+        names
+    );
+
+    let params_of = |name: &str| {
+        signatures
+            .iter()
+            .find(|s| s.name == name)
+            .map(|s| s.params)
+            .unwrap()
+    };
+    assert_eq!(params_of("sum"), (0, 0));
+    assert_eq!(params_of("window_funnel"), (1, 1));
+    assert_eq!(params_of("approx_count_distinct"), (0, 1));
+
+    let window_funnel = signatures
+        .iter()
+        .find(|s| s.name == "window_funnel")
+        .unwrap();
+    assert_eq!(window_funnel.arguments, vec![
+        "T: Integer | Date | Timestamp",
+        "Boolean..."
+    ]);
+    assert_eq!(window_funnel.return_type, "UInt8");
+}
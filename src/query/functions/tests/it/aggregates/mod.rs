@@ -13,6 +13,7 @@
 // limitations under the License.
 
 mod agg;
+mod agg_factory;
 mod agg_hashtable;
 
 use std::io::Write;
@@ -100,6 +101,17 @@ pub fn run_agg_ast(
                     params
                 };
 
+                // Convert the granularity of distinct_time_buckets and uniq_by_bucket to params
+                let params = if (name.eq_ignore_ascii_case("distinct_time_buckets")
+                    && args.len() == 2)
+                    || (name.eq_ignore_ascii_case("uniq_by_bucket") && args.len() == 3)
+                {
+                    let val = args[1].0.as_scalar().unwrap();
+                    vec![val.clone()]
+                } else {
+                    params
+                };
+
                 let arg_columns: Vec<Column> = args
                     .iter()
                     .map(|(arg, ty)| match arg {
@@ -217,3 +229,43 @@ pub fn simulate_two_groups_group_by(
 
     Ok((builder.build(), data_type))
 }
+
+/// Accumulate the even rows and the odd rows into two states, then merge the second
+/// state into the first one.
+pub fn simulate_two_states_merge(
+    name: &str,
+    params: Vec<Scalar>,
+    columns: &[Column],
+    rows: usize,
+) -> databend_common_exception::Result<(Column, DataType)> {
+    let factory = AggregateFunctionFactory::instance();
+    let arguments: Vec<DataType> = columns.iter().map(|c| c.data_type()).collect();
+
+    let func = factory.get(name, params, arguments)?;
+    let data_type = func.return_type()?;
+
+    let arena = Bump::new();
+
+    let addr1 = arena.alloc_layout(func.state_layout());
+    func.init_state(addr1.into());
+    let addr2 = arena.alloc_layout(func.state_layout());
+    func.init_state(addr2.into());
+
+    let places = (0..rows)
+        .map(|i| {
+            if i % 2 == 0 {
+                addr1.into()
+            } else {
+                addr2.into()
+            }
+        })
+        .collect::<Vec<_>>();
+
+    func.accumulate_keys(&places, 0, columns.into(), rows)?;
+    func.merge_states(addr1.into(), addr2.into())?;
+
+    let mut builder = ColumnBuilder::with_capacity(&data_type, 1024);
+    func.merge_result(addr1.into(), &mut builder)?;
+
+    Ok((builder.build(), data_type))
+}
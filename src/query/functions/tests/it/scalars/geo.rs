@@ -15,6 +15,7 @@
 use std::io::Write;
 
 use databend_common_expression::types::*;
+use databend_common_expression::Column;
 use databend_common_expression::FromData;
 use goldenfile::Mint;
 
@@ -27,12 +28,45 @@ fn test_geo() {
 
     test_geo_to_h3(file);
     test_great_circle_distance(file);
+    test_great_circle_distance_f64(file);
+    test_geo_in_circle(file);
+    test_equirect_distance(file);
     test_geo_distance(file);
+    test_geo_distance_method(file);
+    test_rhumb_line(file);
     test_great_circle_angle(file);
     test_point_in_ellipses(file);
     test_point_in_polygon(file);
     test_geohash_encode(file);
     test_geohash_decode(file);
+    test_geo_triangle_area(file);
+    test_is_simple_polygon(file);
+    test_geo_interpolate(file);
+    test_geo_point_at_fraction(file);
+    test_longitude_diff(file);
+    test_geo_cross_track_distance(file);
+    test_geo_on_path(file);
+    test_geo_along_track_fraction(file);
+    test_geo_turn_angle(file);
+    test_lonlat_to_mercator(file);
+    test_mercator_to_lonlat(file);
+    test_geo_round(file);
+    test_geo_rotate(file);
+    test_geo_centroid(file);
+    test_geo_antipode(file);
+    test_compass_direction(file);
+    test_geo_morton(file);
+    test_geo_boxes_intersect(file);
+    test_geo_box_intersection_area(file);
+    test_geo_bbox_around(file);
+    test_meters_per_degree(file);
+    test_st_length(file);
+    test_st_is_valid(file);
+    test_st_normalize(file);
+    test_geo_simplify(file);
+    test_geo_densify(file);
+    test_geo_distance_to_polygon(file);
+    test_geo_path_length(file);
 }
 
 fn test_geo_to_h3(file: &mut impl Write) {
@@ -79,6 +113,115 @@ fn test_great_circle_distance(file: &mut impl Write) {
         "great_circle_distance(lon1, lat1, lon2, lat2)",
         &table,
     );
+    // (lon, lat) points have the same result as the four-arg form
+    run_ast(
+        file,
+        "great_circle_distance((lon1, lat1), (lon2, lat2))",
+        &table,
+    );
+    let points = [
+        (
+            "p1",
+            Column::Tuple(vec![table[0].1.clone(), table[1].1.clone()]),
+        ),
+        (
+            "p2",
+            Column::Tuple(vec![table[2].1.clone(), table[3].1.clone()]),
+        ),
+    ];
+    run_ast(file, "great_circle_distance(p1, p2)", &points);
+}
+
+fn test_great_circle_distance_f64(file: &mut impl Write) {
+    run_ast(
+        file,
+        "great_circle_distance_f64(55.755831, 37.617673, -55.755831, -37.617673)",
+        &[],
+    );
+    // the first row is far enough to use the haversine path, the second uses the LUT
+    let table = [
+        ("lon1", Float64Type::from_data(vec![55.755831, 116.4])),
+        ("lat1", Float64Type::from_data(vec![37.617673, 39.9])),
+        ("lon2", Float64Type::from_data(vec![-55.755831, 116.3])),
+        ("lat2", Float64Type::from_data(vec![-37.617673, 39.8])),
+    ];
+    run_ast(
+        file,
+        "great_circle_distance_f64(lon1, lat1, lon2, lat2)",
+        &table,
+    );
+    run_ast(
+        file,
+        "great_circle_distance_f64((lon1, lat1), (lon2, lat2))",
+        &table,
+    );
+    // rounded to f32, the result is the same as `great_circle_distance`
+    run_ast(
+        file,
+        "to_float32(great_circle_distance_f64(lon1, lat1, lon2, lat2)) = great_circle_distance(lon1, lat1, lon2, lat2)",
+        &table,
+    );
+}
+
+fn test_geo_in_circle(file: &mut impl Write) {
+    // the points are 35864.45, 42590.16 and 52267.09 meters from the center, just inside
+    // and just outside the radius, the center itself is within a radius of 0
+    run_ast(
+        file,
+        "geo_in_circle(lon, lat, center_lon, center_lat, radius_m)",
+        &[
+            (
+                "lon",
+                Float64Type::from_data(vec![116.4, 116.4, 116.5, 116.5, 116.0, 116.6]),
+            ),
+            (
+                "lat",
+                Float64Type::from_data(vec![39.9, 39.9, 40.0, 40.0, 40.0, 40.1]),
+            ),
+            ("center_lon", Float64Type::from_data(vec![116.0; 6])),
+            ("center_lat", Float64Type::from_data(vec![40.0; 6])),
+            (
+                "radius_m",
+                Float64Type::from_data(vec![35865.0, 35864.0, 42591.0, 42590.0, 0.0, 50000.0]),
+            ),
+        ],
+    );
+}
+
+fn test_equirect_distance(file: &mut impl Write) {
+    // Points up to 14 kilometers apart, including across the antimeridian and along a
+    // meridian: the equirectangular approximation is within millimeters of the exact
+    // distance, the lookup tables of `great_circle_distance_f64` are off by up to a meter.
+    let table = [
+        (
+            "lon1",
+            Float64Type::from_data(vec![116.4, -73.9857, 179.9995, 0.0, 116.4]),
+        ),
+        (
+            "lat1",
+            Float64Type::from_data(vec![39.9, 40.7484, 0.0, 0.0, 39.9]),
+        ),
+        (
+            "lon2",
+            Float64Type::from_data(vec![116.401, -73.9785, -179.9995, 0.0, 116.3]),
+        ),
+        (
+            "lat2",
+            Float64Type::from_data(vec![39.901, 40.753, 0.0, 0.005, 39.8]),
+        ),
+    ];
+    run_ast(file, "equirect_distance(lon1, lat1, lon2, lat2)", &table);
+    run_ast(
+        file,
+        "great_circle_distance_f64(lon1, lat1, lon2, lat2)",
+        &table,
+    );
+    // far apart, the approximation diverges: `great_circle_distance` is 14128353
+    run_ast(
+        file,
+        "equirect_distance(55.755831, 37.617673, -55.755831, -37.617673)",
+        &[],
+    );
 }
 
 fn test_geo_distance(file: &mut impl Write) {
@@ -106,6 +249,134 @@ fn test_geo_distance(file: &mut impl Write) {
         ),
     ];
     run_ast(file, "geo_distance(lon1, lat1, lon2, lat2)", &table);
+
+    // points less than 13 degrees apart go through the WGS84 lookup table
+    let table = [
+        (
+            "lon1",
+            Float64Type::from_data(vec![144.42486788888889, 116.4, 0.0]),
+        ),
+        (
+            "lat1",
+            Float64Type::from_data(vec![-37.95103341666667, 39.9, 0.0]),
+        ),
+        (
+            "lon2",
+            Float64Type::from_data(vec![143.92649552777777, 116.3, 1.0]),
+        ),
+        (
+            "lat2",
+            Float64Type::from_data(vec![-37.65282113888889, 39.8, 0.0]),
+        ),
+    ];
+    run_ast(file, "geo_distance(lon1, lat1, lon2, lat2)", &table);
+}
+
+fn test_geo_distance_method(file: &mut impl Write) {
+    // From Flinders Peak to Buninyong, 54972.271 meters on the WGS84 ellipsoid: 'vincenty'
+    // is within a millimeter, 'fast' 34 centimeters too long and 'haversine' on the sphere
+    // 47 meters too short. A degree along the equator is 111319.491 meters.
+    let flinders_peak = (144.42486788888889, -37.95103341666667);
+    let buninyong = (143.92649552777777, -37.65282113888889);
+    run_ast(
+        file,
+        "geo_distance_method(lon1, lat1, lon2, lat2, method)",
+        &[
+            (
+                "lon1",
+                Float64Type::from_data(vec![
+                    flinders_peak.0,
+                    flinders_peak.0,
+                    flinders_peak.0,
+                    0.0,
+                    0.0,
+                    0.0,
+                    1.0,
+                ]),
+            ),
+            (
+                "lat1",
+                Float64Type::from_data(vec![
+                    flinders_peak.1,
+                    flinders_peak.1,
+                    flinders_peak.1,
+                    0.0,
+                    0.0,
+                    0.0,
+                    2.0,
+                ]),
+            ),
+            (
+                "lon2",
+                Float64Type::from_data(vec![
+                    buninyong.0,
+                    buninyong.0,
+                    buninyong.0,
+                    1.0,
+                    1.0,
+                    1.0,
+                    1.0,
+                ]),
+            ),
+            (
+                "lat2",
+                Float64Type::from_data(vec![
+                    buninyong.1,
+                    buninyong.1,
+                    buninyong.1,
+                    0.0,
+                    0.0,
+                    0.0,
+                    2.0,
+                ]),
+            ),
+            (
+                "method",
+                StringType::from_data(vec![
+                    "fast",
+                    "haversine",
+                    "vincenty",
+                    "fast",
+                    "haversine",
+                    "vincenty",
+                    "vincenty",
+                ]),
+            ),
+        ],
+    );
+    run_ast(file, "geo_distance_method(0, 0, 1, 0, 'exact')", &[]);
+    run_ast(
+        file,
+        "geo_distance_method(0, 0, 179.7, 0.5, 'vincenty')",
+        &[],
+    );
+}
+
+fn test_rhumb_line(file: &mut impl Write) {
+    // New York to London is 5796891 meters along the rhumb line but 5572811 along the
+    // great circle. From (0, -10) to (20, 10) the line passes through (10, 0), the
+    // bearing of both halves is the same as the whole. Points on the same parallel are
+    // due east or west, across the antimeridian when shorter.
+    let table = [
+        (
+            "lon1",
+            Float64Type::from_data(vec![-74.0, 0.0, 10.0, 0.0, 10.0, 179.0, 20.0, 116.4]),
+        ),
+        (
+            "lat1",
+            Float64Type::from_data(vec![40.7, -10.0, 0.0, -10.0, 45.0, 30.0, 45.0, 39.9]),
+        ),
+        (
+            "lon2",
+            Float64Type::from_data(vec![-0.1, 10.0, 20.0, 20.0, 20.0, -179.0, 10.0, 116.4]),
+        ),
+        (
+            "lat2",
+            Float64Type::from_data(vec![51.5, 0.0, 10.0, 10.0, 45.0, 30.0, 45.0, 39.9]),
+        ),
+    ];
+    run_ast(file, "rhumb_distance(lon1, lat1, lon2, lat2)", &table);
+    run_ast(file, "rhumb_bearing(lon1, lat1, lon2, lat2)", &table);
 }
 
 fn test_great_circle_angle(file: &mut impl Write) {
@@ -114,6 +385,23 @@ fn test_great_circle_angle(file: &mut impl Write) {
         "a",
         Float64Type::from_data(vec![45.0, 46.0, 47.0]),
     )]);
+    let points = [
+        (
+            "p1",
+            Column::Tuple(vec![
+                Float64Type::from_data(vec![0.0, 0.0, 0.0]),
+                Float64Type::from_data(vec![0.0, 0.0, 0.0]),
+            ]),
+        ),
+        (
+            "p2",
+            Column::Tuple(vec![
+                Float64Type::from_data(vec![45.0, 46.0, 47.0]),
+                Float64Type::from_data(vec![0.0, 0.0, 0.0]),
+            ]),
+        ),
+    ];
+    run_ast(file, "great_circle_angle(p1, p2)", &points);
 }
 
 fn test_point_in_ellipses(file: &mut impl Write) {
@@ -200,3 +488,629 @@ fn test_geohash_encode(file: &mut impl Write) {
 fn test_geohash_decode(file: &mut impl Write) {
     run_ast(file, "geohash_decode('ezs42')", &[]);
 }
+
+fn test_geo_triangle_area(file: &mut impl Write) {
+    // A small triangle near the equator, a degenerate triangle along the equator
+    // and an octant of the sphere.
+    run_ast(
+        file,
+        "geo_triangle_area(lon1, lat1, lon2, lat2, lon3, lat3)",
+        &[
+            ("lon1", Float64Type::from_data(vec![0.0, 0.0, 0.0])),
+            ("lat1", Float64Type::from_data(vec![0.0, 0.0, 0.0])),
+            ("lon2", Float64Type::from_data(vec![1.0, 1.0, 90.0])),
+            ("lat2", Float64Type::from_data(vec![0.0, 0.0, 0.0])),
+            ("lon3", Float64Type::from_data(vec![0.0, 2.0, 0.0])),
+            ("lat3", Float64Type::from_data(vec![1.0, 0.0, 90.0])),
+        ],
+    );
+}
+
+fn test_is_simple_polygon(file: &mut impl Write) {
+    run_ast(
+        file,
+        "is_simple_polygon([(0, 0), (4, 0), (4, 4), (0, 4)])",
+        &[],
+    );
+    // duplicate points and the closing point are ignored
+    run_ast(
+        file,
+        "is_simple_polygon([(0, 0), (4, 0), (4, 0), (4, 4), (0, 4), (0, 0)])",
+        &[],
+    );
+    // bowtie
+    run_ast(
+        file,
+        "is_simple_polygon([(0, 0), (4, 4), (4, 0), (0, 4)])",
+        &[],
+    );
+    // collinear
+    run_ast(file, "is_simple_polygon([(0, 0), (1, 0), (2, 0)])", &[]);
+    // degenerate
+    run_ast(file, "is_simple_polygon([(0, 0), (1, 1), (0, 0)])", &[]);
+}
+
+fn test_geo_interpolate(file: &mut impl Write) {
+    // along the equator and along a meridian
+    run_ast(file, "geo_interpolate(0, 0, 90, 0, 4)", &[]);
+    run_ast(file, "geo_interpolate(0, 0, 0, 90, 3)", &[]);
+    run_ast(file, "geo_interpolate(-10, 20, 50, 40, 5)", &[]);
+    // antipodal points go through the north pole, or along the prime meridian from a pole
+    run_ast(file, "geo_interpolate(0, 0, 180, 0, 3)", &[]);
+    run_ast(file, "geo_interpolate(0, 90, 0, -90, 3)", &[]);
+    run_ast(file, "geo_interpolate(0, 0, 90, 0, 1)", &[]);
+    run_ast(file, "geo_interpolate(0, 0, 90, 0, 1000001)", &[]);
+    run_ast(file, "geo_interpolate(lon1, lat1, lon2, lat2, 3)", &[
+        ("lon1", Float64Type::from_data(vec![0.0, -10.0])),
+        ("lat1", Float64Type::from_data(vec![0.0, 20.0])),
+        ("lon2", Float64Type::from_data(vec![90.0, 50.0])),
+        ("lat2", Float64Type::from_data(vec![0.0, 40.0])),
+    ]);
+}
+
+fn test_geo_point_at_fraction(file: &mut impl Write) {
+    // The endpoints at t = 0 and t = 1, the midpoint at t = 0.5 is the middle point of
+    // `geo_interpolate(-10, 20, 50, 40, 3)`, t out of [0, 1] is clamped, and antipodal
+    // points go through the north pole, or along the prime meridian from a pole.
+    run_ast(file, "geo_point_at_fraction(lon1, lat1, lon2, lat2, t)", &[
+        (
+            "lon1",
+            Float64Type::from_data(vec![-10.0, -10.0, -10.0, -10.0, -10.0, -10.0, 0.0, 0.0]),
+        ),
+        (
+            "lat1",
+            Float64Type::from_data(vec![20.0, 20.0, 20.0, 20.0, 20.0, 20.0, 0.0, 90.0]),
+        ),
+        (
+            "lon2",
+            Float64Type::from_data(vec![50.0, 50.0, 50.0, 50.0, 50.0, 50.0, 180.0, 0.0]),
+        ),
+        (
+            "lat2",
+            Float64Type::from_data(vec![40.0, 40.0, 40.0, 40.0, 40.0, 40.0, 0.0, -90.0]),
+        ),
+        (
+            "t",
+            Float64Type::from_data(vec![0.0, 1.0, 0.5, 0.25, -1.0, 2.0, 0.5, 0.5]),
+        ),
+    ]);
+}
+
+fn test_longitude_diff(file: &mut impl Write) {
+    run_ast(file, "longitude_diff(10, 30)", &[]);
+    // across the antimeridian, positive eastward
+    run_ast(file, "longitude_diff(170, -170)", &[]);
+    run_ast(file, "longitude_diff(-170, 170)", &[]);
+    // half a turn is always positive
+    run_ast(file, "longitude_diff(0, 180)", &[]);
+    run_ast(file, "longitude_diff(lon1, lon2)", &[
+        (
+            "lon1",
+            Float64Type::from_data(vec![179.5, -179.5, 90.0, -180.0]),
+        ),
+        (
+            "lon2",
+            Float64Type::from_data(vec![-179.5, 179.5, -90.0, 180.0]),
+        ),
+    ]);
+}
+
+fn test_geo_cross_track_distance(file: &mut impl Write) {
+    // A point on the equator path, one on each side of it, and one east of a meridian path.
+    run_ast(
+        file,
+        "geo_cross_track_distance(lon, lat, lon1, lat1, lon2, lat2)",
+        &[
+            ("lon", Float64Type::from_data(vec![5.0, 5.0, 5.0, 1.0])),
+            ("lat", Float64Type::from_data(vec![0.0, 1.0, -1.0, 5.0])),
+            ("lon1", Float64Type::from_data(vec![0.0, 0.0, 0.0, 0.0])),
+            ("lat1", Float64Type::from_data(vec![0.0, 0.0, 0.0, 0.0])),
+            ("lon2", Float64Type::from_data(vec![10.0, 10.0, 10.0, 0.0])),
+            ("lat2", Float64Type::from_data(vec![0.0, 0.0, 0.0, 10.0])),
+        ],
+    );
+}
+
+fn test_geo_on_path(file: &mut impl Write) {
+    // Along a 10 degrees path on the equator with a tolerance of 1000 meters: a point near
+    // the middle, one just outside of the tolerance, one just beyond the end but close to
+    // it, and two on the great circle but beyond the endpoints.
+    run_ast(
+        file,
+        "geo_on_path(lon, lat, lon1, lat1, lon2, lat2, tol)",
+        &[
+            (
+                "lon",
+                Float64Type::from_data(vec![5.0, 5.0, 10.005, 11.0, -5.0]),
+            ),
+            (
+                "lat",
+                Float64Type::from_data(vec![0.005, 0.0091, 0.0, 0.0, 0.0]),
+            ),
+            ("lon1", Float64Type::from_data(vec![0.0; 5])),
+            ("lat1", Float64Type::from_data(vec![0.0; 5])),
+            ("lon2", Float64Type::from_data(vec![10.0; 5])),
+            ("lat2", Float64Type::from_data(vec![0.0; 5])),
+            ("tol", Float64Type::from_data(vec![1000.0; 5])),
+        ],
+    );
+}
+
+fn test_geo_along_track_fraction(file: &mut impl Write) {
+    // Along a 10 degrees path on the equator: the start, the end, the midpoint, a point
+    // off the path, and two points beyond the endpoints clamped to the segment.
+    run_ast(
+        file,
+        "geo_along_track_fraction(lon1, lat1, lon2, lat2, lon, lat)",
+        &[
+            ("lon1", Float64Type::from_data(vec![0.0; 6])),
+            ("lat1", Float64Type::from_data(vec![0.0; 6])),
+            ("lon2", Float64Type::from_data(vec![10.0; 6])),
+            ("lat2", Float64Type::from_data(vec![0.0; 6])),
+            (
+                "lon",
+                Float64Type::from_data(vec![0.0, 10.0, 5.0, 2.5, 12.0, -3.0]),
+            ),
+            (
+                "lat",
+                Float64Type::from_data(vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0]),
+            ),
+        ],
+    );
+}
+
+fn test_geo_turn_angle(file: &mut impl Write) {
+    // Straight on along the equator, a right and a left turn after going east, straight on
+    // along a meridian, a left turn at a mid latitude, and a U-turn.
+    run_ast(
+        file,
+        "geo_turn_angle(lon1, lat1, lon2, lat2, lon3, lat3)",
+        &[
+            (
+                "lon1",
+                Float64Type::from_data(vec![0.0, 0.0, 0.0, 0.0, 10.0, 0.0]),
+            ),
+            (
+                "lat1",
+                Float64Type::from_data(vec![0.0, 0.0, 0.0, 0.0, 50.0, 0.0]),
+            ),
+            (
+                "lon2",
+                Float64Type::from_data(vec![5.0, 5.0, 5.0, 0.0, 11.0, 5.0]),
+            ),
+            (
+                "lat2",
+                Float64Type::from_data(vec![0.0, 0.0, 0.0, 5.0, 50.0, 0.0]),
+            ),
+            (
+                "lon3",
+                Float64Type::from_data(vec![10.0, 5.0, 5.0, 0.0, 11.5, 0.0]),
+            ),
+            (
+                "lat3",
+                Float64Type::from_data(vec![0.0, -5.0, 5.0, 10.0, 50.8, 0.0]),
+            ),
+        ],
+    );
+}
+
+fn test_lonlat_to_mercator(file: &mut impl Write) {
+    run_ast(file, "lonlat_to_mercator(0, 0)", &[]);
+    run_ast(file, "lonlat_to_mercator(10, -45)", &[]);
+    // the poles are clamped to the edges of the map
+    run_ast(file, "lonlat_to_mercator(180, 90)", &[]);
+    run_ast(file, "lonlat_to_mercator(-180, -90)", &[]);
+}
+
+fn test_mercator_to_lonlat(file: &mut impl Write) {
+    run_ast(file, "mercator_to_lonlat(0, 0)", &[]);
+    run_ast(file, "mercator_to_lonlat(1113195, -5621521)", &[]);
+    run_ast(file, "mercator_to_lonlat(20037508, 20037508)", &[]);
+    // beyond the edge of the map
+    run_ast(file, "mercator_to_lonlat(0, 40000000)", &[]);
+    // round trip
+    run_ast(
+        file,
+        "mercator_to_lonlat(lonlat_to_mercator(lon, lat).1, lonlat_to_mercator(lon, lat).2)",
+        &[
+            (
+                "lon",
+                Float64Type::from_data(vec![0.0, 10.0, -122.4194, 179.9, -45.5]),
+            ),
+            (
+                "lat",
+                Float64Type::from_data(vec![0.0, -45.0, 37.7749, 85.0, -60.25]),
+            ),
+        ],
+    );
+}
+
+fn test_geo_round(file: &mut impl Write) {
+    let columns = &[
+        (
+            "lon",
+            Float64Type::from_data(vec![116.3912757, -0.5275, 179.99999, -73.935242]),
+        ),
+        (
+            "lat",
+            Float64Type::from_data(vec![39.906217, 51.507222, -89.5, 40.73061]),
+        ),
+    ];
+    run_ast(file, "geo_round(lon, lat, 0)", columns);
+    run_ast(file, "geo_round(lon, lat, 2)", columns);
+    run_ast(file, "geo_round(lon, lat, 12)", columns);
+    run_ast(file, "geo_round(1, 2, 13)", &[]);
+    run_ast(file, "geo_round(1, 2, -1)", &[]);
+}
+
+fn test_geo_rotate(file: &mut impl Write) {
+    // A full turn returns the original point, a quarter turn clockwise moves a point
+    // north-east of the center to its south-east, and the center itself stays put.
+    run_ast(
+        file,
+        "geo_rotate(lon, lat, center_lon, center_lat, angle)",
+        &[
+            (
+                "lon",
+                Float64Type::from_data(vec![116.4, 116.4, 0.1, 0.0, 116.3, -179.9]),
+            ),
+            (
+                "lat",
+                Float64Type::from_data(vec![39.9, 39.9, 0.0, 0.1, 39.8, 10.0]),
+            ),
+            (
+                "center_lon",
+                Float64Type::from_data(vec![116.3, 116.3, 0.0, 0.0, 116.3, 179.9]),
+            ),
+            (
+                "center_lat",
+                Float64Type::from_data(vec![39.8, 39.8, 0.0, 0.0, 39.8, 10.0]),
+            ),
+            (
+                "angle",
+                Float64Type::from_data(vec![360.0, 90.0, 90.0, -90.0, 45.0, 180.0]),
+            ),
+        ],
+    );
+    run_ast(file, "geo_rotate(0, 89, 0, 90, 45)", &[]);
+}
+
+fn test_geo_centroid(file: &mut impl Write) {
+    // symmetric polygons have their centroid at the geometric center
+    run_ast(file, "geo_centroid([(0, 0), (4, 0), (4, 4), (0, 4)])", &[]);
+    run_ast(
+        file,
+        "geo_centroid([(10, 20), (14, 20), (14, 24), (10, 24), (10, 20)])",
+        &[],
+    );
+    // weighted by the area, not the average (2.667, 2.667) of the vertices
+    run_ast(
+        file,
+        "geo_centroid([(0, 0), (6, 0), (6, 2), (2, 2), (2, 6), (0, 6)])",
+        &[],
+    );
+    run_ast(
+        file,
+        "geo_centroid([(182, 0), (186, 0), (186, 2), (182, 2)])",
+        &[],
+    );
+    // degenerate rings
+    run_ast(file, "geo_centroid([(0, 0), (1, 1), (2, 2)])", &[]);
+    run_ast(file, "geo_centroid([(0, 0), (1, 0)])", &[]);
+}
+
+fn test_geo_antipode(file: &mut impl Write) {
+    run_ast(file, "geo_antipode(10, 20)", &[]);
+    run_ast(file, "geo_antipode(-10, -20)", &[]);
+    run_ast(file, "geo_antipode(0, 0)", &[]);
+    run_ast(file, "geo_antipode(180, 45)", &[]);
+    run_ast(file, "geo_antipode(-180, -90)", &[]);
+    // about half of the circumference, 20015109 meters, within the error of the fast
+    // `great_circle_distance`
+    run_ast(
+        file,
+        "great_circle_distance((lon, lat), geo_antipode(lon, lat))",
+        &[
+            ("lon", Float64Type::from_data(vec![10.0, -75.5, 120.0])),
+            ("lat", Float64Type::from_data(vec![20.0, 40.25, -33.0])),
+        ],
+    );
+}
+
+fn test_compass_direction(file: &mut impl Write) {
+    // a bearing on the boundary of two sectors goes to the next point clockwise
+    let columns = &[(
+        "bearing",
+        Float64Type::from_data(vec![
+            0.0, 11.25, 22.4, 22.5, 45.0, 337.5, 359.9, -90.0, 720.0,
+        ]),
+    )];
+    run_ast(file, "compass_direction(bearing)", columns);
+    run_ast(file, "compass_direction(bearing, 16)", columns);
+    run_ast(file, "compass_direction(10, 4)", &[]);
+}
+
+fn test_geo_morton(file: &mut impl Write) {
+    run_ast(file, "geo_morton_encode(0, 0)", &[]);
+    run_ast(file, "geo_morton_encode(-180, -90)", &[]);
+    run_ast(file, "geo_morton_encode(180, 90)", &[]);
+    run_ast(file, "geo_morton_encode(200, 0)", &[]);
+    run_ast(file, "geo_morton_decode(0)", &[]);
+    // neighboring cells have nearby keys
+    run_ast(file, "geo_morton_encode(lon, lat)", &[
+        (
+            "lon",
+            Float64Type::from_data(vec![0.0, 0.0000001, 0.0, 0.0000001]),
+        ),
+        (
+            "lat",
+            Float64Type::from_data(vec![0.0, 0.0, 0.0000001, 0.0000001]),
+        ),
+    ]);
+    // round trip, within half a cell
+    run_ast(file, "geo_morton_decode(geo_morton_encode(lon, lat))", &[
+        (
+            "lon",
+            Float64Type::from_data(vec![116.3912757, -0.5275, 179.99999, -73.935242]),
+        ),
+        (
+            "lat",
+            Float64Type::from_data(vec![39.906217, 51.507222, -89.5, 40.73061]),
+        ),
+    ]);
+}
+
+fn test_geo_boxes_intersect(file: &mut impl Write) {
+    // overlapping, touching at a corner, disjoint by the longitudes and by the latitudes,
+    // crossing the antimeridian and overlapping or not, touching at the antimeridian
+    run_ast(
+        file,
+        "geo_boxes_intersect(min_lon1, min_lat1, max_lon1, max_lat1, min_lon2, min_lat2, max_lon2, max_lat2)",
+        &[
+            (
+                "min_lon1",
+                Float64Type::from_data(vec![0.0, 0.0, 0.0, 0.0, 170.0, 170.0, 170.0]),
+            ),
+            (
+                "min_lat1",
+                Float64Type::from_data(vec![0.0, 0.0, 0.0, 0.0, -10.0, -10.0, 0.0]),
+            ),
+            (
+                "max_lon1",
+                Float64Type::from_data(vec![10.0, 10.0, 10.0, 10.0, -170.0, -170.0, 180.0]),
+            ),
+            (
+                "max_lat1",
+                Float64Type::from_data(vec![10.0, 10.0, 10.0, 10.0, 10.0, 10.0, 10.0]),
+            ),
+            (
+                "min_lon2",
+                Float64Type::from_data(vec![5.0, 10.0, 11.0, 0.0, -175.0, 0.0, -180.0]),
+            ),
+            (
+                "min_lat2",
+                Float64Type::from_data(vec![5.0, 10.0, 0.0, 11.0, 0.0, 0.0, 0.0]),
+            ),
+            (
+                "max_lon2",
+                Float64Type::from_data(vec![15.0, 20.0, 20.0, 10.0, -160.0, 10.0, -170.0]),
+            ),
+            (
+                "max_lat2",
+                Float64Type::from_data(vec![15.0, 20.0, 10.0, 20.0, 5.0, 5.0, 10.0]),
+            ),
+        ],
+    );
+}
+
+fn test_geo_box_intersection_area(file: &mut impl Write) {
+    // the same boxes, a box inside the other, a partial overlap, disjoint boxes and boxes
+    // touching at an edge, a degree square at 60 degrees north being half the size of
+    // one on the equator, boxes crossing the antimeridian and the whole globe
+    run_ast(
+        file,
+        "geo_box_intersection_area(min_lon1, min_lat1, max_lon1, max_lat1, min_lon2, min_lat2, max_lon2, max_lat2)",
+        &[
+            (
+                "min_lon1",
+                Float64Type::from_data(vec![0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 170.0, 170.0, -180.0]),
+            ),
+            (
+                "min_lat1",
+                Float64Type::from_data(vec![0.0, 0.0, 0.0, 0.0, 0.0, 60.0, -10.0, 0.0, -90.0]),
+            ),
+            (
+                "max_lon1",
+                Float64Type::from_data(vec![1.0, 10.0, 2.0, 1.0, 1.0, 1.0, -170.0, -170.0, 180.0]),
+            ),
+            (
+                "max_lat1",
+                Float64Type::from_data(vec![1.0, 10.0, 2.0, 1.0, 1.0, 61.0, 10.0, 1.0, 90.0]),
+            ),
+            (
+                "min_lon2",
+                Float64Type::from_data(vec![0.0, 2.0, 1.0, 2.0, 1.0, 0.0, -175.0, -175.0, -180.0]),
+            ),
+            (
+                "min_lat2",
+                Float64Type::from_data(vec![0.0, 2.0, 1.0, 2.0, 0.0, 60.0, 0.0, 0.0, -90.0]),
+            ),
+            (
+                "max_lon2",
+                Float64Type::from_data(vec![1.0, 3.0, 3.0, 3.0, 2.0, 1.0, -160.0, 175.0, 180.0]),
+            ),
+            (
+                "max_lat2",
+                Float64Type::from_data(vec![1.0, 3.0, 3.0, 3.0, 1.0, 61.0, 5.0, 1.0, 90.0]),
+            ),
+        ],
+    );
+}
+
+fn test_geo_bbox_around(file: &mut impl Write) {
+    // 100 km is about 0.9 degrees at the equator, the box is wider in longitude at 70
+    // degrees north, reaches the pole at 89.5 and crosses the antimeridian at 179.5.
+    run_ast(file, "geo_bbox_around(lon, lat, radius)", &[
+        (
+            "lon",
+            Float64Type::from_data(vec![0.0, 25.0, 0.0, 179.5, 116.4]),
+        ),
+        (
+            "lat",
+            Float64Type::from_data(vec![0.0, 70.0, 89.5, 10.0, 39.9]),
+        ),
+        (
+            "radius",
+            Float64Type::from_data(vec![100000.0, 100000.0, 100000.0, 100000.0, 0.0]),
+        ),
+    ]);
+    run_ast(file, "geo_bbox_around(0, 91, 1000)", &[]);
+    run_ast(file, "geo_bbox_around(0, 0, -1)", &[]);
+}
+
+fn test_meters_per_degree(file: &mut impl Write) {
+    // 111321 meters per degree of longitude and 110567 of latitude at the equator, 78849
+    // and 111131 at 45 degrees, the same in the south. Beyond the poles the latitude is
+    // clamped to 90 degrees, where a degree of longitude is a point.
+    let table = [(
+        "lat",
+        Float64Type::from_data(vec![0.0, 45.0, -45.0, 60.0, 89.5, 90.0, 100.0, -120.0]),
+    )];
+    run_ast(file, "meters_per_degree_lon(lat)", &table);
+    run_ast(file, "meters_per_degree_lat(lat)", &table);
+}
+
+fn test_st_length(file: &mut impl Write) {
+    // the same as the sum of the distances of the segments
+    run_ast(file, "st_length('LINESTRING(0 0, 1 0, 1 1)')", &[]);
+    run_ast(
+        file,
+        "great_circle_distance(0, 0, 1, 0) + great_circle_distance(1, 0, 1, 1)",
+        &[],
+    );
+    // crossing the antimeridian, and a segment of a single point
+    run_ast(file, "st_length(line)", &[(
+        "line",
+        StringType::from_data(vec![
+            "LINESTRING(0 0, 1 0, 1 1)",
+            "LINESTRING(-170 10, 170 10, 160 20)",
+            "LINESTRING(1 1, 1 1)",
+        ]),
+    )]);
+    run_ast(file, "st_length('LINESTRING(0 0, 1)')", &[]);
+    run_ast(file, "st_length('POINT(1 1)')", &[]);
+}
+
+fn test_st_is_valid(file: &mut impl Write) {
+    // a LINESTRING of a single point, a POLYGON ring of less than 4 points once closed,
+    // a point missing its y, an unsupported geometry type and a malformed string
+    run_ast(file, "st_is_valid(wkt)", &[(
+        "wkt",
+        StringType::from_data(vec![
+            "POINT(1 2)",
+            "LINESTRING(0 0, 1 1)",
+            "POLYGON((0 0, 1 0, 1 1, 0 0))",
+            "LINESTRING(0 0)",
+            "POLYGON((0 0, 1 1, 0 0))",
+            "POINT(1)",
+            "MULTIPOINT((0 0), (1 1))",
+            "not wkt",
+        ]),
+    )]);
+}
+
+fn test_st_normalize(file: &mut impl Write) {
+    run_ast(file, "st_normalize('point ( 1  2 )')", &[]);
+    // the exterior ring of the POLYGON is clockwise and its hole counter-clockwise, the
+    // ring of the last POLYGON is not closed
+    let wkt = StringType::from_data(vec![
+        "LINESTRING(1.50 -0, 3 4)",
+        "POLYGON((4 0, 0 0, 0 4, 4 4, 4 0), (1 1, 2 1, 2 2, 1 1))",
+        "POLYGON((1 1, 0 1, 0 0))",
+    ]);
+    run_ast(file, "st_normalize(wkt)", &[("wkt", wkt.clone())]);
+    // normalizing is idempotent
+    run_ast(
+        file,
+        "st_normalize(st_normalize(wkt)) = st_normalize(wkt)",
+        &[("wkt", wkt)],
+    );
+    run_ast(file, "st_normalize('MULTIPOINT((0 0), (1 1))')", &[]);
+    run_ast(file, "st_normalize('LINESTRING(0 0)')", &[]);
+}
+
+fn test_geo_simplify(file: &mut impl Write) {
+    // a densely sampled great circle path collapses to its endpoints
+    run_ast(
+        file,
+        "geo_simplify(geo_interpolate(-10, 20, 50, 40, 21), 1)",
+        &[],
+    );
+    // the vertex at (2, 1) is 111 km away from the equator, (1, 0) is 50 km away
+    // from the segment from (0, 0) to (2, 1)
+    run_ast(
+        file,
+        "geo_simplify([(0, 0), (1, 0), (2, 1), (3, 0), (4, 0)], 200000)",
+        &[],
+    );
+    run_ast(
+        file,
+        "geo_simplify([(0, 0), (1, 0), (2, 1), (3, 0), (4, 0)], 100000)",
+        &[],
+    );
+    // a closed ring stays closed
+    run_ast(
+        file,
+        "geo_simplify([(0, 0), (2, 0), (4, 0), (4, 4), (0, 4), (0, 0)], 1000)",
+        &[],
+    );
+    run_ast(file, "geo_simplify([(0, 0), (1, 1)], 1000000)", &[]);
+    run_ast(file, "geo_simplify([(0, 0), (1, 1), (2, 0)], -1)", &[]);
+}
+
+fn test_geo_densify(file: &mut impl Write) {
+    // the equatorial edge of 333 km is split in two, the edge of 111 km along the meridian
+    // is kept
+    run_ast(file, "geo_densify([(0, 0), (3, 0), (3, 1)], 200000)", &[]);
+    run_ast(file, "geo_densify([(0, 0), (3, 0)], 100000)", &[]);
+    // the points follow the great circle, north of the parallel
+    run_ast(file, "geo_densify([(10, 40), (70, 40)], 2000000)", &[]);
+    run_ast(file, "geo_densify([(0, 0), (1, 1)], 0)", &[]);
+    run_ast(file, "geo_densify([(0, 0), (1, 1)], 0.001)", &[]);
+}
+
+fn test_geo_distance_to_polygon(file: &mut impl Write) {
+    // inside, outside 0.01 degrees below the southern edge, near the north-east vertex,
+    // on the southern edge, and 2 degrees east of the eastern edge
+    run_ast(
+        file,
+        "geo_distance_to_polygon(lon, lat, [(0, 0), (4, 0), (4, 4), (0, 4)])",
+        &[
+            (
+                "lon",
+                Float64Type::from_data(vec![2.0, 2.0, 4.01, 2.0, 6.0]),
+            ),
+            (
+                "lat",
+                Float64Type::from_data(vec![2.0, -0.01, 4.01, 0.0, 2.0]),
+            ),
+        ],
+    );
+}
+
+fn test_geo_path_length(file: &mut impl Write) {
+    // the same as `st_length` of the LINESTRING, and the sum of the distances of the segments
+    run_ast(file, "geo_path_length([0, 1, 1], [0, 0, 1])", &[]);
+    run_ast(
+        file,
+        "geo_path_length([10, 11, 12, 12], [50, 50, 51, 52])",
+        &[],
+    );
+    run_ast(
+        file,
+        "great_circle_distance(10, 50, 11, 50) + great_circle_distance(11, 50, 12, 51) + great_circle_distance(12, 51, 12, 52)",
+        &[],
+    );
+    run_ast(file, "geo_path_length([1], [2])", &[]);
+    run_ast(file, "geo_path_length([0, 1, 2], [0, 1])", &[]);
+}
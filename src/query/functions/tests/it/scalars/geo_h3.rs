@@ -52,6 +52,7 @@ fn test_geo_h3() {
     test_h3_num_hexagons(file);
     test_h3_line(file);
     test_h3_distance(file);
+    test_h3_grid_distance(file);
     test_h3_hex_ring(file);
     test_h3_get_unidirectional_edge(file);
     test_h3_unidirectional_edge_is_valid(file);
@@ -483,6 +484,25 @@ fn test_h3_distance(file: &mut impl Write) {
     ]);
 }
 
+fn test_h3_grid_distance(file: &mut impl Write) {
+    run_ast(
+        file,
+        "h3_grid_distance(599119489002373119, 599119489002373119)",
+        &[],
+    );
+    run_ast(
+        file,
+        "h3_grid_distance(599119489002373119, 599119491149856767)",
+        &[],
+    );
+    // the cells are of resolution 5 and 15
+    run_ast(
+        file,
+        "h3_grid_distance(599119489002373119, 644325524701193897)",
+        &[],
+    );
+}
+
 fn test_h3_hex_ring(file: &mut impl Write) {
     run_ast(file, "h3_hex_ring(0, 0)", &[]);
     run_ast(file, "h3_hex_ring(599686042433355775, 0)", &[]);
@@ -0,0 +1,85 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Write;
+
+use databend_common_expression::types::*;
+use databend_common_expression::FromData;
+use goldenfile::Mint;
+
+use super::run_ast;
+
+#[test]
+fn test_geo_s2() {
+    let mut mint = Mint::new("tests/it/scalars/testdata");
+    let file = &mut mint.new_goldenfile("geo_s2.txt").unwrap();
+
+    test_geo_to_s2(file);
+    test_s2_to_geo(file);
+    test_s2_round_trip(file);
+}
+
+fn test_geo_to_s2(file: &mut impl Write) {
+    run_ast(file, "geo_to_s2(10, 20, 31)", &[]);
+    run_ast(file, "geo_to_s2(-122.4194, 37.7749, 30)", &[]);
+    // a point on each of the 6 faces of the cube
+    run_ast(file, "geo_to_s2(lon, lat, 10)", &[
+        (
+            "lon",
+            Float64Type::from_data(vec![0.0, 90.0, -122.4194, 179.9, -100.0, -45.5, 30.0]),
+        ),
+        (
+            "lat",
+            Float64Type::from_data(vec![0.0, 10.0, 37.7749, -5.0, -3.0, -60.25, 80.0]),
+        ),
+    ]);
+}
+
+fn test_s2_to_geo(file: &mut impl Write) {
+    run_ast(file, "s2_to_geo(0)", &[]);
+    // the level marker bit is on an odd position
+    run_ast(file, "s2_to_geo(2)", &[]);
+    // the level 0 cell of the face 0 is centered on (0, 0)
+    run_ast(file, "s2_to_geo(1152921504606846976)", &[]);
+    run_ast(file, "s2_to_geo(s2)", &[(
+        "s2",
+        UInt64Type::from_data(vec![
+            1152922604118474752,
+            3494660269932544000,
+            9260950045757276160,
+            8061451029574582272,
+            11157763734322020352,
+            13546558298781646848,
+            5004399686031769600,
+        ]),
+    )]);
+}
+
+fn test_s2_round_trip(file: &mut impl Write) {
+    // the center of the cell of a point lies in the same cell
+    run_ast(
+        file,
+        "geo_to_s2(s2_to_geo(geo_to_s2(lon, lat, 10)).1, s2_to_geo(geo_to_s2(lon, lat, 10)).2, 10) = geo_to_s2(lon, lat, 10)",
+        &[
+            (
+                "lon",
+                Float64Type::from_data(vec![0.0, 90.0, -122.4194, 179.9, -100.0, -45.5, 30.0]),
+            ),
+            (
+                "lat",
+                Float64Type::from_data(vec![0.0, 10.0, 37.7749, -5.0, -3.0, -60.25, 80.0]),
+            ),
+        ],
+    );
+}
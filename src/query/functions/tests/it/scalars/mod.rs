@@ -43,6 +43,7 @@ mod geo;
 // TODO: fix this in running on linux
 #[cfg(not(target_os = "macos"))]
 mod geo_h3;
+mod geo_s2;
 mod geography;
 mod geometry;
 mod hash;
@@ -51,6 +52,7 @@ mod math;
 mod misc;
 mod other;
 pub(crate) mod parser;
+mod rank_digest;
 mod regexp;
 mod string;
 mod tuple;
@@ -0,0 +1,122 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Write;
+
+use databend_common_expression::types::*;
+use databend_common_expression::FromData;
+use goldenfile::Mint;
+
+use super::run_ast;
+
+#[test]
+fn test_rank_digest() {
+    let mut mint = Mint::new("tests/it/scalars/testdata");
+    let file = &mut mint.new_goldenfile("rank_digest.txt").unwrap();
+
+    test_rank_digest_quantile(file);
+    test_rank_digest_cdf(file);
+}
+
+/// A digest in the format returned by `rank_digest`, built from its centroids.
+fn digest(weights: &[f64], means: &[f64], min: f64, max: f64) -> Vec<u8> {
+    let mut data = vec![];
+    data.extend_from_slice(&100u32.to_le_bytes());
+    data.extend_from_slice(&2048u64.to_le_bytes());
+    data.extend_from_slice(&weights.iter().sum::<f64>().to_le_bytes());
+    for values in [weights, means] {
+        data.extend_from_slice(&(values.len() as u32).to_le_bytes());
+        for value in values {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    data.extend_from_slice(&0f64.to_le_bytes());
+    data.extend_from_slice(&0u32.to_le_bytes());
+    data.extend_from_slice(&0u32.to_le_bytes());
+    data.extend_from_slice(&min.to_le_bytes());
+    data.extend_from_slice(&max.to_le_bytes());
+    data
+}
+
+/// The digest of [1, 2, 3, 4], as returned by `rank_digest(a)` in the aggregate tests,
+/// which keeps every value in its own centroid.
+fn exact_digest() -> Vec<u8> {
+    digest(&[1.0, 1.0, 1.0, 1.0], &[1.0, 2.0, 3.0, 4.0], 1.0, 4.0)
+}
+
+/// The same values with 2 and 3 merged into a single centroid.
+fn merged_digest() -> Vec<u8> {
+    digest(&[1.0, 2.0, 1.0], &[1.0, 2.5, 4.0], 1.0, 4.0)
+}
+
+fn test_rank_digest_quantile(file: &mut impl Write) {
+    // the value of rank `level * 4` rounded, the same as `quantile_tdigest(level)(a)`
+    run_ast(file, "rank_digest_quantile(d, level)", &[
+        ("d", BinaryType::from_data(vec![exact_digest(); 5])),
+        (
+            "level",
+            Float64Type::from_data(vec![0.0, 0.25, 0.5, 0.75, 1.0]),
+        ),
+    ]);
+    // interpolated between the means inside the merged centroid
+    run_ast(file, "rank_digest_quantile(d, level)", &[
+        ("d", BinaryType::from_data(vec![merged_digest(); 5])),
+        (
+            "level",
+            Float64Type::from_data(vec![0.0, 0.25, 0.5, 0.625, 1.0]),
+        ),
+    ]);
+    run_ast(file, "rank_digest_quantile(d, level)", &[
+        (
+            "d",
+            BinaryType::from_data_with_validity(vec![exact_digest(), vec![]], vec![true, false]),
+        ),
+        ("level", Float64Type::from_data(vec![0.5, 0.5])),
+    ]);
+    run_ast(file, "rank_digest_quantile(d, level)", &[
+        ("d", BinaryType::from_data(vec![exact_digest()])),
+        ("level", Float64Type::from_data(vec![1.5])),
+    ]);
+    run_ast(file, "rank_digest_quantile(d, level)", &[
+        ("d", BinaryType::from_data(vec![vec![1u8, 2]])),
+        ("level", Float64Type::from_data(vec![0.5])),
+    ]);
+}
+
+fn test_rank_digest_cdf(file: &mut impl Write) {
+    // the exact fraction of the values not greater than `value`
+    run_ast(file, "rank_digest_cdf(d, value)", &[
+        ("d", BinaryType::from_data(vec![exact_digest(); 5])),
+        (
+            "value",
+            Float64Type::from_data(vec![0.0, 1.0, 2.5, 4.0, 5.0]),
+        ),
+    ]);
+    // the weight of the merged centroid is spread evenly over [1.75, 3.25]
+    run_ast(file, "rank_digest_cdf(d, value)", &[
+        ("d", BinaryType::from_data(vec![merged_digest(); 6])),
+        (
+            "value",
+            Float64Type::from_data(vec![1.0, 1.75, 2.5, 3.0, 3.25, 4.0]),
+        ),
+    ]);
+    // a truncated digest
+    run_ast(file, "rank_digest_cdf(d, value)", &[
+        (
+            "d",
+            BinaryType::from_data(vec![exact_digest()[..20].to_vec()]),
+        ),
+        ("value", Float64Type::from_data(vec![1.0])),
+    ]);
+}
@@ -12,7 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::time::Duration;
@@ -32,6 +36,7 @@ use databend_common_meta_app::schema::CreateLockRevReq;
 use databend_common_meta_app::schema::DeleteLockRevReq;
 use databend_common_meta_app::schema::ExtendLockRevReq;
 use databend_common_meta_app::schema::ListLockRevReq;
+use databend_common_meta_app::schema::LockKey;
 use databend_common_meta_app::schema::TableLockIdent;
 use databend_common_meta_kvapi::kvapi::Key;
 use databend_common_meta_types::protobuf::watch_request::FilterType;
@@ -43,18 +48,157 @@ use databend_common_users::UserApiProvider;
 use futures::future::select;
 use futures::future::Either;
 use futures_util::StreamExt;
+use parking_lot::Mutex;
 use rand::thread_rng;
 use rand::Rng;
 
 use crate::sessions::SessionManager;
 
+/// The policy applied by the lock holder when the lock can not be extended anymore.
+///
+/// [`LockManager`](crate::locks::LockManager) always kills, the query executor doesn't wait
+/// on a paused holder. `Pause` is for the callers that create their own `LockHolder` and
+/// check `LockHolder::is_paused` between units of work.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OnExtendFailure {
+    /// Force kill the query that holds the lock.
+    #[default]
+    Kill,
+    /// Signal the query to pause, and retry acquiring a fresh lock before resuming.
+    /// The query is only killed if the fresh lock can not be acquired.
+    ///
+    /// This suits idempotent workloads that can tolerate a brief stall over an abort.
+    Pause,
+}
+
+/// The progress token of the query that holds the lock, the executor reports through it
+/// whenever the query makes progress, e.g. a block of data is processed.
+///
+/// The query is blocked while its locks are being acquired, so the progress is only checked
+/// once all of them are acquired. From then on, a lock holder with a progress token only
+/// extends the locks if progress was reported since the acquisition or the last heartbeat.
+/// The heartbeat fires every `ttl / 3` to `ttl * 2 / 3`, so a query that keeps the lock must
+/// report at least once within `ttl / 3`. Otherwise the query is treated as stalled (e.g.
+/// waiting on external IO): the lock is no longer extended and expires with its ttl, and the
+/// query is force killed.
+#[derive(Debug, Default)]
+pub struct LockProgress {
+    ticks: AtomicU64,
+}
+
+impl LockProgress {
+    /// Report that the query made progress.
+    pub fn report(&self) {
+        self.ticks.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn ticks(&self) -> u64 {
+        self.ticks.load(Ordering::SeqCst)
+    }
+}
+
+/// The number of lock revisions created by all the lock holders of the process and not deleted yet.
+static HELD_LOCKS: AtomicUsize = AtomicUsize::new(0);
+
+/// The lock contention of the tables in the process, keyed by table id.
+static TABLE_CONTENTION: Mutex<BTreeMap<u64, TableContention>> = Mutex::new(BTreeMap::new());
+
+/// The contention on the lock of a table, the scheduler reads it to hold back new work
+/// on a table whose lock is already queued by many queries.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LockContention {
+    /// The sessions that are acquiring the lock.
+    pub waiters: usize,
+    /// The lock revisions that are acquired and not released yet.
+    pub holders: usize,
+}
+
+impl LockContention {
+    /// The waiters per holder, the waiters are counted as is when the lock is not held.
+    pub fn ratio(&self) -> f64 {
+        self.waiters as f64 / self.holders.max(1) as f64
+    }
+}
+
+/// Get the lock contention of the table.
+pub fn table_lock_contention(table_id: u64) -> LockContention {
+    TABLE_CONTENTION
+        .lock()
+        .get(&table_id)
+        .map(|contention| LockContention {
+            waiters: contention.waiters,
+            holders: contention.holders.len(),
+        })
+        .unwrap_or_default()
+}
+
+#[derive(Default)]
+struct TableContention {
+    waiters: usize,
+    holders: BTreeSet<u64>,
+}
+
+impl TableContention {
+    fn update(table_id: u64, f: impl FnOnce(&mut TableContention)) {
+        let mut tables = TABLE_CONTENTION.lock();
+        let contention = tables.entry(table_id).or_default();
+        f(contention);
+        if contention.waiters == 0 && contention.holders.is_empty() {
+            tables.remove(&table_id);
+        }
+    }
+}
+
+/// Count the session as a waiter of the table lock until dropped.
+struct LockWaiter {
+    table_id: u64,
+}
+
+impl LockWaiter {
+    fn create(table_id: u64) -> Self {
+        TableContention::update(table_id, |contention| contention.waiters += 1);
+        LockWaiter { table_id }
+    }
+}
+
+impl Drop for LockWaiter {
+    fn drop(&mut self) {
+        TableContention::update(self.table_id, |contention| contention.waiters -= 1);
+    }
+}
+
 #[derive(Default)]
 pub struct LockHolder {
+    on_extend_failure: OnExtendFailure,
+    /// Only extend the locks while the query is progressing, see [`LockProgress`].
+    progress: Option<Arc<LockProgress>>,
+    /// The progress reported when the locks were acquired, none while acquiring them.
+    acquired_ticks: Mutex<Option<u64>>,
+    /// The held lock revisions, extended by one heartbeat.
+    locks: Mutex<Vec<(CreateLockRevReq, u64)>>,
+    paused: AtomicBool,
+    resume_notify: Notify,
     shutdown_flag: AtomicBool,
     shutdown_notify: Notify,
 }
 
 impl LockHolder {
+    pub fn create(on_extend_failure: OnExtendFailure) -> Self {
+        LockHolder {
+            on_extend_failure,
+            ..Default::default()
+        }
+    }
+
+    /// Renew the locks only while the query reports progress through the token.
+    ///
+    /// Not used by [`LockManager`](crate::locks::LockManager), the query executor doesn't
+    /// report progress, it is for the callers that create their own `LockHolder`.
+    pub fn with_progress(mut self, progress: Arc<LockProgress>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
     #[async_backtrace::framed]
     pub(crate) async fn try_acquire_lock(
         self: &Arc<Self>,
@@ -65,16 +209,40 @@ impl LockHolder {
     ) -> Result<u64> {
         let start = Instant::now();
 
+        let lock_key = req.lock_key.clone();
         let ttl = req.ttl;
 
-        let lock_key = req.lock_key.clone();
+        let _waiter = LockWaiter::create(lock_key.get_table_id());
+        let revision = self.start(catalog.clone(), req, acquire_timeout).await?;
+        Self::wait_lock_acquired(
+            catalog,
+            &lock_key,
+            revision,
+            ttl,
+            should_retry,
+            acquire_timeout,
+            start,
+        )
+        .await?;
+        self.mark_acquired();
+        Ok(revision)
+    }
+
+    /// Wait until the revision is the minimum of all revisions of the lock key.
+    #[async_backtrace::framed]
+    async fn wait_lock_acquired(
+        catalog: Arc<dyn Catalog>,
+        lock_key: &LockKey,
+        revision: u64,
+        ttl: Duration,
+        should_retry: bool,
+        acquire_timeout: Duration,
+        start: Instant,
+    ) -> Result<()> {
         let lock_type = lock_key.lock_type().to_string();
         let table_id = lock_key.get_table_id();
         let tenant = lock_key.get_tenant();
 
-        let revision = self.start(catalog.clone(), req).await?;
-
-        let meta_api = UserApiProvider::instance().get_meta_store_client();
         let list_table_lock_req = ListLockRevReq::new(lock_key.clone());
 
         loop {
@@ -102,6 +270,9 @@ impl LockHolder {
                     ExtendLockRevReq::new(lock_key.clone(), revision, ttl, true);
 
                 catalog.extend_lock_revision(extend_table_lock_req).await?;
+                TableContention::update(table_id, |contention| {
+                    contention.holders.insert(revision);
+                });
                 // metrics.
                 record_acquired_lock_nums(lock_type, table_id, 1);
                 break;
@@ -119,6 +290,7 @@ impl LockHolder {
                 )));
             }
 
+            let meta_api = UserApiProvider::instance().get_meta_store_client();
             let watch_delete_ident = TableLockIdent::new(tenant, table_id, prev_revision);
 
             // Get the previous revision, watch the delete event.
@@ -159,7 +331,79 @@ impl LockHolder {
             }
         }
 
-        Ok(revision)
+        Ok(())
+    }
+
+    /// Acquire the locks of a statement touching several tables, all the locks share one heartbeat.
+    ///
+    /// The locks are acquired in a canonical order (by table id) to avoid deadlock between
+    /// statements. Either all the revisions are returned in the order of `reqs`, or the locks
+    /// that were partially acquired are rolled back.
+    #[async_backtrace::framed]
+    pub(crate) async fn start_many(
+        self: &Arc<Self>,
+        catalog: Arc<dyn Catalog>,
+        reqs: Vec<CreateLockRevReq>,
+        should_retry: bool,
+        acquire_timeout: Duration,
+    ) -> Result<Vec<u64>> {
+        let start = Instant::now();
+        let Some(ttl) = reqs.iter().map(|req| req.ttl).min() else {
+            return Ok(vec![]);
+        };
+
+        let mut order = (0..reqs.len()).collect::<Vec<_>>();
+        order.sort_by_key(|idx| reqs[*idx].lock_key.get_table_id());
+
+        self.spawn_heartbeat(
+            catalog.clone(),
+            reqs[0].query_id.clone(),
+            ttl,
+            acquire_timeout,
+        );
+
+        let mut revisions = vec![0; reqs.len()];
+        for idx in order {
+            let req = reqs[idx].clone();
+            let lock_key = req.lock_key.clone();
+            let _waiter = LockWaiter::create(lock_key.get_table_id());
+            let res = match Self::create_revision(catalog.clone(), req.clone()).await {
+                Ok(revision) => {
+                    revisions[idx] = revision;
+                    self.locks.lock().push((req, revision));
+                    Self::wait_lock_acquired(
+                        catalog.clone(),
+                        &lock_key,
+                        revision,
+                        ttl,
+                        should_retry,
+                        acquire_timeout,
+                        start,
+                    )
+                    .await
+                }
+                Err(e) => Err(e),
+            };
+
+            if let Err(e) = res {
+                // Roll back the partially acquired locks.
+                let locks = std::mem::take(&mut *self.locks.lock());
+                for (req, revision) in locks {
+                    let delete_table_lock_req = DeleteLockRevReq::new(req.lock_key, revision);
+                    let _ = Self::try_delete_lock(
+                        catalog.clone(),
+                        delete_table_lock_req,
+                        Some(req.ttl),
+                    )
+                    .await;
+                }
+                self.shutdown();
+                return Err(e);
+            }
+        }
+
+        self.mark_acquired();
+        Ok(revisions)
     }
 
     #[async_backtrace::framed]
@@ -167,26 +411,34 @@ impl LockHolder {
         self: &Arc<Self>,
         catalog: Arc<dyn Catalog>,
         req: CreateLockRevReq,
+        acquire_timeout: Duration,
     ) -> Result<u64> {
-        let lock_key = req.lock_key.clone();
         let query_id = req.query_id.clone();
         let ttl = req.ttl;
-        let sleep_range = (ttl / 3)..=(ttl * 2 / 3);
 
         // get a new table lock revision.
-        let res = catalog.create_lock_revision(req).await?;
-        let revision = res.revision;
-        // metrics.
-        record_created_lock_nums(lock_key.lock_type().to_string(), lock_key.get_table_id(), 1);
-        log::debug!("create table lock success, revision={}", revision);
+        let revision = Self::create_revision(catalog.clone(), req.clone()).await?;
+        self.locks.lock().push((req, revision));
 
-        let delete_table_lock_req = DeleteLockRevReq::new(lock_key.clone(), revision);
-        let extend_table_lock_req = ExtendLockRevReq::new(lock_key.clone(), revision, ttl, false);
+        self.spawn_heartbeat(catalog, query_id, ttl, acquire_timeout);
+        Ok(revision)
+    }
+
+    /// Spawn the task that extends all the held locks, and deletes them when shutdown.
+    fn spawn_heartbeat(
+        self: &Arc<Self>,
+        catalog: Arc<dyn Catalog>,
+        query_id: String,
+        ttl: Duration,
+        acquire_timeout: Duration,
+    ) {
+        let sleep_range = (ttl / 3)..=(ttl * 2 / 3);
 
         GlobalIORuntime::instance().spawn({
             let self_clone = self.clone();
             async move {
                 let mut notified = Box::pin(self_clone.shutdown_notify.notified());
+                let mut last_ticks = None;
                 while !self_clone.shutdown_flag.load(Ordering::SeqCst) {
                     let rand_sleep_duration = {
                         let mut rng = thread_rng();
@@ -201,40 +453,233 @@ impl LockHolder {
                         }
                         Either::Right((_, new_notified)) => {
                             notified = new_notified;
-                            if let Err(e) = self_clone
-                                .try_extend_lock(
-                                    catalog.clone(),
-                                    extend_table_lock_req.clone(),
-                                    Some(ttl - rand_sleep_duration),
-                                )
-                                .await
+                            let acquired_ticks = *self_clone.acquired_ticks.lock();
+                            if let (Some(progress), Some(acquired_ticks)) =
+                                (&self_clone.progress, acquired_ticks)
                             {
-                                // Force kill the query if extend lock failure.
-                                if let Some(session) =
-                                    SessionManager::instance().get_session_by_id(&query_id)
+                                let ticks = progress.ticks();
+                                if last_ticks.unwrap_or(acquired_ticks) == ticks {
+                                    let e = ErrorCode::TableLockExpired(format!(
+                                        "the query {} made no progress since the last heartbeat, stop extending its table locks",
+                                        query_id
+                                    ));
+                                    log::warn!("{}", e.message());
+                                    self_clone.lapse_locks();
+                                    Self::force_kill_query(&query_id, e.clone());
+                                    return Err(e);
+                                }
+                                last_ticks = Some(ticks);
+                            }
+
+                            let locks = self_clone.locks.lock().clone();
+                            for (req, revision) in locks {
+                                let extend_table_lock_req = ExtendLockRevReq::new(
+                                    req.lock_key.clone(),
+                                    revision,
+                                    req.ttl,
+                                    false,
+                                );
+                                if let Err(e) = self_clone
+                                    .try_extend_lock(
+                                        catalog.clone(),
+                                        extend_table_lock_req,
+                                        Some(ttl - rand_sleep_duration),
+                                    )
+                                    .await
                                 {
-                                    session.force_kill_query(e.clone());
+                                    if self_clone.on_extend_failure == OnExtendFailure::Kill {
+                                        // Force kill the query if extend lock failure.
+                                        let _ = self_clone.release_locks(catalog.clone()).await;
+                                        Self::force_kill_query(&query_id, e.clone());
+                                        return Err(e);
+                                    }
+
+                                    log::warn!(
+                                        "failed to extend the lock revision {}, pause the query {} and retry to acquire a new lock. cause {:?}",
+                                        revision,
+                                        query_id,
+                                        e
+                                    );
+                                    self_clone.paused.store(true, Ordering::SeqCst);
+                                    if let Err(e) = self_clone
+                                        .try_reacquire_lock(
+                                            catalog.clone(),
+                                            req,
+                                            revision,
+                                            acquire_timeout,
+                                        )
+                                        .await
+                                    {
+                                        let _ = self_clone.release_locks(catalog.clone()).await;
+                                        Self::force_kill_query(&query_id, e.clone());
+                                        return Err(e);
+                                    }
+                                    // The query is blocked while paused, check its progress from now on.
+                                    last_ticks =
+                                        self_clone.progress.as_ref().map(|progress| progress.ticks());
+                                    self_clone.resume();
                                 }
-                                return Err(e);
                             }
                         }
                     }
                 }
 
-                Self::try_delete_lock(catalog, delete_table_lock_req, Some(ttl)).await
+                self_clone.release_locks(catalog).await
             }
         });
+    }
 
-        Ok(revision)
+    /// The revision of the first held lock, it changes after a fresh lock is acquired
+    /// under [`OnExtendFailure::Pause`].
+    pub fn revision(&self) -> u64 {
+        self.locks
+            .lock()
+            .first()
+            .map(|(_, revision)| *revision)
+            .unwrap_or_default()
+    }
+
+    /// Whether the query should pause because the lock is lost and a fresh lock is being acquired.
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Wait until the holder is not paused.
+    #[async_backtrace::framed]
+    pub async fn wait_resumed(&self) {
+        loop {
+            let notified = self.resume_notify.notified();
+            if !self.is_paused() || self.shutdown_flag.load(Ordering::SeqCst) {
+                return;
+            }
+            notified.await;
+        }
     }
 
     pub fn shutdown(&self) {
         self.shutdown_flag.store(true, Ordering::SeqCst);
         self.shutdown_notify.notify_one();
+        self.resume_notify.notify_waiters();
+    }
+
+    fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+        self.resume_notify.notify_waiters();
+    }
+
+    /// Whether any lock holder of the process still holds a lock revision,
+    /// e.g. graceful shutdown waits until the locks are released.
+    pub fn any_locks_held() -> bool {
+        HELD_LOCKS.load(Ordering::SeqCst) > 0
     }
 }
 
 impl LockHolder {
+    async fn create_revision(catalog: Arc<dyn Catalog>, req: CreateLockRevReq) -> Result<u64> {
+        let lock_key = req.lock_key.clone();
+        let res = catalog.create_lock_revision(req).await?;
+        let revision = res.revision;
+        HELD_LOCKS.fetch_add(1, Ordering::SeqCst);
+        // metrics.
+        record_created_lock_nums(lock_key.lock_type().to_string(), lock_key.get_table_id(), 1);
+        log::debug!("create table lock success, revision={}", revision);
+        Ok(revision)
+    }
+
+    /// Replace the expired revision with a fresh one, and wait until it is acquired.
+    async fn try_reacquire_lock(
+        &self,
+        catalog: Arc<dyn Catalog>,
+        req: CreateLockRevReq,
+        expired_revision: u64,
+        acquire_timeout: Duration,
+    ) -> Result<()> {
+        let start = Instant::now();
+        let lock_key = req.lock_key.clone();
+        let ttl = req.ttl;
+        let _waiter = LockWaiter::create(lock_key.get_table_id());
+
+        // The expired revision is useless, remove it.
+        let delete_table_lock_req = DeleteLockRevReq::new(lock_key.clone(), expired_revision);
+        let _ = Self::try_delete_lock(catalog.clone(), delete_table_lock_req, Some(ttl)).await;
+
+        let res = match Self::create_revision(catalog.clone(), req).await {
+            Ok(revision) => {
+                let res = Self::wait_lock_acquired(
+                    catalog.clone(),
+                    &lock_key,
+                    revision,
+                    ttl,
+                    true,
+                    acquire_timeout,
+                    start,
+                )
+                .await;
+                if res.is_err() {
+                    let delete_table_lock_req = DeleteLockRevReq::new(lock_key, revision);
+                    let _ = Self::try_delete_lock(catalog, delete_table_lock_req, Some(ttl)).await;
+                }
+                res.map(|_| revision)
+            }
+            Err(e) => Err(e),
+        };
+
+        let mut locks = self.locks.lock();
+        match res {
+            Ok(revision) => {
+                if let Some(lock) = locks.iter_mut().find(|(_, rev)| *rev == expired_revision) {
+                    lock.1 = revision;
+                }
+                log::info!("reacquire table lock success, revision={}", revision);
+                Ok(())
+            }
+            Err(e) => {
+                // The expired revision is already deleted, it is no longer held.
+                locks.retain(|(_, rev)| *rev != expired_revision);
+                Err(e)
+            }
+        }
+    }
+
+    /// Delete all the held locks, when shutdown or before the query is force killed.
+    async fn release_locks(&self, catalog: Arc<dyn Catalog>) -> Result<()> {
+        let locks = std::mem::take(&mut *self.locks.lock());
+        let mut res = Ok(());
+        for (req, revision) in locks {
+            let delete_table_lock_req = DeleteLockRevReq::new(req.lock_key, revision);
+            if let Err(e) =
+                Self::try_delete_lock(catalog.clone(), delete_table_lock_req, Some(req.ttl)).await
+            {
+                res = Err(e);
+            }
+        }
+        res
+    }
+
+    /// Give up the held locks without deleting them, they expire with their ttl.
+    fn lapse_locks(&self) {
+        let locks = std::mem::take(&mut *self.locks.lock());
+        for (req, revision) in locks {
+            TableContention::update(req.lock_key.get_table_id(), |contention| {
+                contention.holders.remove(&revision);
+            });
+            HELD_LOCKS.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Start checking the progress of the query, see [`LockProgress`].
+    fn mark_acquired(&self) {
+        if let Some(progress) = &self.progress {
+            *self.acquired_ticks.lock() = Some(progress.ticks());
+        }
+    }
+
+    fn force_kill_query(query_id: &str, cause: ErrorCode) {
+        if let Some(session) = SessionManager::instance().get_session_by_id(query_id) {
+            session.force_kill_query(cause);
+        }
+    }
+
     async fn try_extend_lock(
         self: &Arc<Self>,
         catalog: Arc<dyn Catalog>,
@@ -294,10 +739,16 @@ impl LockHolder {
         req: DeleteLockRevReq,
         max_retry_elapsed: Option<Duration>,
     ) -> Result<()> {
+        // The revision is released, even if deleting fails it no longer holds the lock.
+        TableContention::update(req.lock_key.get_table_id(), |contention| {
+            contention.holders.remove(&req.revision);
+        });
+
         let mut backoff = set_backoff(Some(Duration::from_millis(2)), None, max_retry_elapsed);
         loop {
             match catalog.delete_lock_revision(req.clone()).await {
                 Ok(_) => {
+                    HELD_LOCKS.fetch_sub(1, Ordering::SeqCst);
                     log::debug!("delete table lock success, revision={}", req.revision);
                     break;
                 }
@@ -320,6 +771,8 @@ impl LockHolder {
                             e,
                         );
                         log::error!("{}", error_info);
+                        // The revision is given up, it expires with its ttl.
+                        HELD_LOCKS.fetch_sub(1, Ordering::SeqCst);
                         return Err(ErrorCode::OCCRetryFailure(error_info));
                     }
                 },
@@ -0,0 +1,831 @@
+// Copyright 2021 Datafuse Labs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use databend_common_base::base::tokio;
+use databend_common_base::base::tokio::sync::watch;
+use databend_common_base::base::tokio::time::sleep;
+use databend_common_base::base::tokio::time::timeout;
+use databend_common_base::base::GlobalInstance;
+use databend_common_base::runtime::GlobalIORuntime;
+use databend_common_catalog::catalog::Catalog;
+use databend_common_catalog::database::Database;
+use databend_common_catalog::table::Table;
+use databend_common_exception::ErrorCode;
+use databend_common_exception::Result;
+use databend_common_meta_app::schema::database_name_ident::DatabaseNameIdent;
+use databend_common_meta_app::schema::dictionary_name_ident::DictionaryNameIdent;
+use databend_common_meta_app::schema::CatalogInfo;
+use databend_common_meta_app::schema::CommitTableMetaReply;
+use databend_common_meta_app::schema::CommitTableMetaReq;
+use databend_common_meta_app::schema::CreateDatabaseReply;
+use databend_common_meta_app::schema::CreateDatabaseReq;
+use databend_common_meta_app::schema::CreateDictionaryReply;
+use databend_common_meta_app::schema::CreateDictionaryReq;
+use databend_common_meta_app::schema::CreateIndexReply;
+use databend_common_meta_app::schema::CreateIndexReq;
+use databend_common_meta_app::schema::CreateLockRevReply;
+use databend_common_meta_app::schema::CreateLockRevReq;
+use databend_common_meta_app::schema::CreateSequenceReply;
+use databend_common_meta_app::schema::CreateSequenceReq;
+use databend_common_meta_app::schema::CreateTableIndexReq;
+use databend_common_meta_app::schema::CreateTableReply;
+use databend_common_meta_app::schema::CreateTableReq;
+use databend_common_meta_app::schema::CreateVirtualColumnReq;
+use databend_common_meta_app::schema::DeleteLockRevReq;
+use databend_common_meta_app::schema::DictionaryMeta;
+use databend_common_meta_app::schema::DropDatabaseReply;
+use databend_common_meta_app::schema::DropDatabaseReq;
+use databend_common_meta_app::schema::DropIndexReq;
+use databend_common_meta_app::schema::DropSequenceReply;
+use databend_common_meta_app::schema::DropSequenceReq;
+use databend_common_meta_app::schema::DropTableByIdReq;
+use databend_common_meta_app::schema::DropTableIndexReq;
+use databend_common_meta_app::schema::DropTableReply;
+use databend_common_meta_app::schema::DropVirtualColumnReq;
+use databend_common_meta_app::schema::ExtendLockRevReq;
+use databend_common_meta_app::schema::GetDictionaryReply;
+use databend_common_meta_app::schema::GetIndexReply;
+use databend_common_meta_app::schema::GetIndexReq;
+use databend_common_meta_app::schema::GetSequenceNextValueReply;
+use databend_common_meta_app::schema::GetSequenceNextValueReq;
+use databend_common_meta_app::schema::GetSequenceReply;
+use databend_common_meta_app::schema::GetSequenceReq;
+use databend_common_meta_app::schema::GetTableCopiedFileReply;
+use databend_common_meta_app::schema::GetTableCopiedFileReq;
+use databend_common_meta_app::schema::IndexMeta;
+use databend_common_meta_app::schema::ListDictionaryReq;
+use databend_common_meta_app::schema::ListIndexesByIdReq;
+use databend_common_meta_app::schema::ListIndexesReq;
+use databend_common_meta_app::schema::ListLockRevReq;
+use databend_common_meta_app::schema::ListLocksReq;
+use databend_common_meta_app::schema::ListVirtualColumnsReq;
+use databend_common_meta_app::schema::LockInfo;
+use databend_common_meta_app::schema::LockKey;
+use databend_common_meta_app::schema::LockMeta;
+use databend_common_meta_app::schema::RenameDatabaseReply;
+use databend_common_meta_app::schema::RenameDatabaseReq;
+use databend_common_meta_app::schema::RenameTableReply;
+use databend_common_meta_app::schema::RenameTableReq;
+use databend_common_meta_app::schema::SetTableColumnMaskPolicyReply;
+use databend_common_meta_app::schema::SetTableColumnMaskPolicyReq;
+use databend_common_meta_app::schema::TableInfo;
+use databend_common_meta_app::schema::TableMeta;
+use databend_common_meta_app::schema::TruncateTableReply;
+use databend_common_meta_app::schema::TruncateTableReq;
+use databend_common_meta_app::schema::UndropDatabaseReply;
+use databend_common_meta_app::schema::UndropDatabaseReq;
+use databend_common_meta_app::schema::UndropTableReq;
+use databend_common_meta_app::schema::UpdateDictionaryReply;
+use databend_common_meta_app::schema::UpdateDictionaryReq;
+use databend_common_meta_app::schema::UpdateIndexReply;
+use databend_common_meta_app::schema::UpdateIndexReq;
+use databend_common_meta_app::schema::UpdateVirtualColumnReq;
+use databend_common_meta_app::schema::UpsertTableOptionReply;
+use databend_common_meta_app::schema::UpsertTableOptionReq;
+use databend_common_meta_app::schema::VirtualColumnMeta;
+use databend_common_meta_app::tenant::Tenant;
+use databend_common_meta_types::seq_value::SeqV;
+use databend_common_meta_types::MetaId;
+use parking_lot::Mutex;
+
+use crate::locks::lock_holder::LockHolder;
+use crate::locks::table_lock_contention;
+use crate::locks::LockContention;
+use crate::locks::LockProgress;
+use crate::locks::OnExtendFailure;
+
+#[derive(Default)]
+struct MockLockState {
+    next_revision: u64,
+    revisions: BTreeMap<u64, (LockKey, LockMeta)>,
+    expired: HashSet<u64>,
+    heartbeats: HashMap<u64, usize>,
+    deleted: Vec<u64>,
+}
+
+/// A catalog that only keeps lock revisions in memory.
+struct MockLockCatalog {
+    info: Arc<CatalogInfo>,
+    state: Mutex<MockLockState>,
+    create_blocked: watch::Sender<bool>,
+    acquire_blocked: watch::Sender<bool>,
+}
+
+impl MockLockCatalog {
+    fn create() -> Arc<Self> {
+        Arc::new(MockLockCatalog {
+            info: Arc::new(CatalogInfo::default()),
+            state: Mutex::new(MockLockState::default()),
+            create_blocked: watch::Sender::new(false),
+            acquire_blocked: watch::Sender::new(false),
+        })
+    }
+
+    /// Make the revision expired, extending it fails afterwards.
+    fn expire(&self, revision: u64) {
+        let mut state = self.state.lock();
+        state.revisions.remove(&revision);
+        state.expired.insert(revision);
+    }
+
+    /// Block or unblock creating new lock revisions.
+    fn block_create(&self, blocked: bool) {
+        self.create_blocked.send_replace(blocked);
+    }
+
+    /// Block or unblock marking the lock revisions as acquired.
+    fn block_acquire(&self, blocked: bool) {
+        self.acquire_blocked.send_replace(blocked);
+    }
+
+    fn heartbeats(&self, revision: u64) -> usize {
+        let state = self.state.lock();
+        state.heartbeats.get(&revision).copied().unwrap_or_default()
+    }
+
+    fn revisions(&self, table_id: u64) -> Vec<u64> {
+        let state = self.state.lock();
+        state
+            .revisions
+            .iter()
+            .filter(|(_, (key, _))| key.get_table_id() == table_id)
+            .map(|(revision, _)| *revision)
+            .collect()
+    }
+
+    fn deleted(&self) -> Vec<u64> {
+        self.state.lock().deleted.clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl Catalog for MockLockCatalog {
+    fn name(&self) -> String {
+        "MockLockCatalog".to_string()
+    }
+
+    fn info(&self) -> Arc<CatalogInfo> {
+        self.info.clone()
+    }
+
+    async fn get_database(&self, _tenant: &Tenant, _db_name: &str) -> Result<Arc<dyn Database>> {
+        unimplemented!()
+    }
+
+    async fn list_databases(&self, _tenant: &Tenant) -> Result<Vec<Arc<dyn Database>>> {
+        unimplemented!()
+    }
+
+    async fn create_database(&self, _req: CreateDatabaseReq) -> Result<CreateDatabaseReply> {
+        unimplemented!()
+    }
+
+    async fn drop_database(&self, _req: DropDatabaseReq) -> Result<DropDatabaseReply> {
+        unimplemented!()
+    }
+
+    async fn undrop_database(&self, _req: UndropDatabaseReq) -> Result<UndropDatabaseReply> {
+        unimplemented!()
+    }
+
+    async fn rename_database(&self, _req: RenameDatabaseReq) -> Result<RenameDatabaseReply> {
+        unimplemented!()
+    }
+
+    fn get_table_by_info(&self, _table_info: &TableInfo) -> Result<Arc<dyn Table>> {
+        unimplemented!()
+    }
+
+    async fn mget_table_names_by_ids(
+        &self,
+        _tenant: &Tenant,
+        _table_ids: &[MetaId],
+    ) -> Result<Vec<Option<String>>> {
+        unimplemented!()
+    }
+
+    async fn get_db_name_by_id(&self, _db_id: MetaId) -> Result<String> {
+        unimplemented!()
+    }
+
+    async fn mget_databases(
+        &self,
+        _tenant: &Tenant,
+        _db_names: &[DatabaseNameIdent],
+    ) -> Result<Vec<Arc<dyn Database>>> {
+        unimplemented!()
+    }
+
+    async fn mget_database_names_by_ids(
+        &self,
+        _tenant: &Tenant,
+        _db_ids: &[MetaId],
+    ) -> Result<Vec<Option<String>>> {
+        unimplemented!()
+    }
+
+    async fn get_table_name_by_id(&self, _table_id: MetaId) -> Result<Option<String>> {
+        unimplemented!()
+    }
+
+    async fn get_table(
+        &self,
+        _tenant: &Tenant,
+        _db_name: &str,
+        _table_name: &str,
+    ) -> Result<Arc<dyn Table>> {
+        unimplemented!()
+    }
+
+    async fn get_table_history(
+        &self,
+        _tenant: &Tenant,
+        _db_name: &str,
+        _table_name: &str,
+    ) -> Result<Vec<Arc<dyn Table>>> {
+        unimplemented!()
+    }
+
+    async fn list_tables(&self, _tenant: &Tenant, _db_name: &str) -> Result<Vec<Arc<dyn Table>>> {
+        unimplemented!()
+    }
+
+    async fn list_tables_history(
+        &self,
+        _tenant: &Tenant,
+        _db_name: &str,
+    ) -> Result<Vec<Arc<dyn Table>>> {
+        unimplemented!()
+    }
+
+    async fn create_table(&self, _req: CreateTableReq) -> Result<CreateTableReply> {
+        unimplemented!()
+    }
+
+    async fn drop_table_by_id(&self, _req: DropTableByIdReq) -> Result<DropTableReply> {
+        unimplemented!()
+    }
+
+    async fn undrop_table(&self, _req: UndropTableReq) -> Result<()> {
+        unimplemented!()
+    }
+
+    async fn commit_table_meta(&self, _req: CommitTableMetaReq) -> Result<CommitTableMetaReply> {
+        unimplemented!()
+    }
+
+    async fn rename_table(&self, _req: RenameTableReq) -> Result<RenameTableReply> {
+        unimplemented!()
+    }
+
+    async fn upsert_table_option(
+        &self,
+        _tenant: &Tenant,
+        _db_name: &str,
+        _req: UpsertTableOptionReq,
+    ) -> Result<UpsertTableOptionReply> {
+        unimplemented!()
+    }
+
+    async fn set_table_column_mask_policy(
+        &self,
+        _req: SetTableColumnMaskPolicyReq,
+    ) -> Result<SetTableColumnMaskPolicyReply> {
+        unimplemented!()
+    }
+
+    #[async_backtrace::framed]
+    async fn create_table_index(&self, _req: CreateTableIndexReq) -> Result<()> {
+        unimplemented!()
+    }
+
+    #[async_backtrace::framed]
+    async fn drop_table_index(&self, _req: DropTableIndexReq) -> Result<()> {
+        unimplemented!()
+    }
+
+    async fn get_table_copied_file_info(
+        &self,
+        _tenant: &Tenant,
+        _db_name: &str,
+        _req: GetTableCopiedFileReq,
+    ) -> Result<GetTableCopiedFileReply> {
+        unimplemented!()
+    }
+
+    async fn truncate_table(
+        &self,
+        _table_info: &TableInfo,
+        _req: TruncateTableReq,
+    ) -> Result<TruncateTableReply> {
+        unimplemented!()
+    }
+
+    #[async_backtrace::framed]
+    async fn create_index(&self, _req: CreateIndexReq) -> Result<CreateIndexReply> {
+        unimplemented!()
+    }
+
+    #[async_backtrace::framed]
+    async fn drop_index(&self, _req: DropIndexReq) -> Result<()> {
+        unimplemented!()
+    }
+
+    #[async_backtrace::framed]
+    async fn get_index(&self, _req: GetIndexReq) -> Result<GetIndexReply> {
+        unimplemented!()
+    }
+
+    #[async_backtrace::framed]
+    async fn update_index(&self, _req: UpdateIndexReq) -> Result<UpdateIndexReply> {
+        unimplemented!()
+    }
+
+    #[async_backtrace::framed]
+    async fn list_indexes(&self, _req: ListIndexesReq) -> Result<Vec<(u64, String, IndexMeta)>> {
+        unimplemented!()
+    }
+
+    #[async_backtrace::framed]
+    async fn list_index_ids_by_table_id(&self, _req: ListIndexesByIdReq) -> Result<Vec<u64>> {
+        unimplemented!()
+    }
+
+    #[async_backtrace::framed]
+    async fn list_indexes_by_table_id(
+        &self,
+        _req: ListIndexesByIdReq,
+    ) -> Result<Vec<(u64, String, IndexMeta)>> {
+        unimplemented!()
+    }
+
+    #[async_backtrace::framed]
+    async fn create_virtual_column(&self, _req: CreateVirtualColumnReq) -> Result<()> {
+        unimplemented!()
+    }
+
+    #[async_backtrace::framed]
+    async fn update_virtual_column(&self, _req: UpdateVirtualColumnReq) -> Result<()> {
+        unimplemented!()
+    }
+
+    #[async_backtrace::framed]
+    async fn drop_virtual_column(&self, _req: DropVirtualColumnReq) -> Result<()> {
+        unimplemented!()
+    }
+
+    #[async_backtrace::framed]
+    async fn list_virtual_columns(
+        &self,
+        _req: ListVirtualColumnsReq,
+    ) -> Result<Vec<VirtualColumnMeta>> {
+        unimplemented!()
+    }
+
+    async fn list_locks(&self, _req: ListLocksReq) -> Result<Vec<LockInfo>> {
+        unimplemented!()
+    }
+
+    async fn create_sequence(&self, _req: CreateSequenceReq) -> Result<CreateSequenceReply> {
+        unimplemented!()
+    }
+
+    async fn get_sequence_next_value(
+        &self,
+        _req: GetSequenceNextValueReq,
+    ) -> Result<GetSequenceNextValueReply> {
+        unimplemented!()
+    }
+
+    async fn drop_sequence(&self, _req: DropSequenceReq) -> Result<DropSequenceReply> {
+        unimplemented!()
+    }
+
+    async fn get_table_meta_by_id(&self, _table_id: MetaId) -> Result<Option<SeqV<TableMeta>>> {
+        unimplemented!()
+    }
+
+    async fn create_dictionary(&self, _req: CreateDictionaryReq) -> Result<CreateDictionaryReply> {
+        unimplemented!()
+    }
+
+    async fn update_dictionary(&self, _req: UpdateDictionaryReq) -> Result<UpdateDictionaryReply> {
+        unimplemented!()
+    }
+
+    async fn drop_dictionary(
+        &self,
+        _dict_ident: DictionaryNameIdent,
+    ) -> Result<Option<SeqV<DictionaryMeta>>> {
+        unimplemented!()
+    }
+
+    async fn get_dictionary(
+        &self,
+        _req: DictionaryNameIdent,
+    ) -> Result<Option<GetDictionaryReply>> {
+        unimplemented!()
+    }
+
+    async fn list_dictionaries(
+        &self,
+        _req: ListDictionaryReq,
+    ) -> Result<Vec<(String, DictionaryMeta)>> {
+        unimplemented!()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    async fn list_lock_revisions(&self, req: ListLockRevReq) -> Result<Vec<(u64, LockMeta)>> {
+        let state = self.state.lock();
+        Ok(state
+            .revisions
+            .iter()
+            .filter(|(_, (key, _))| key == &req.lock_key)
+            .map(|(revision, (_, meta))| (*revision, meta.clone()))
+            .collect())
+    }
+
+    async fn create_lock_revision(&self, req: CreateLockRevReq) -> Result<CreateLockRevReply> {
+        let mut blocked = self.create_blocked.subscribe();
+        let _ = blocked.wait_for(|blocked| !*blocked).await;
+
+        let mut state = self.state.lock();
+        state.next_revision += 1;
+        let revision = state.next_revision;
+        let meta = LockMeta {
+            user: req.user,
+            node: req.node,
+            query_id: req.query_id,
+            created_on: Utc::now(),
+            acquired_on: None,
+            lock_type: req.lock_key.lock_type(),
+            extra_info: req.lock_key.get_extra_info(),
+        };
+        state.revisions.insert(revision, (req.lock_key, meta));
+        Ok(CreateLockRevReply { revision })
+    }
+
+    async fn extend_lock_revision(&self, req: ExtendLockRevReq) -> Result<()> {
+        if req.acquire_lock {
+            let mut blocked = self.acquire_blocked.subscribe();
+            let _ = blocked.wait_for(|blocked| !*blocked).await;
+        }
+
+        let mut state = self.state.lock();
+        if state.expired.contains(&req.revision) || !state.revisions.contains_key(&req.revision) {
+            return Err(ErrorCode::TableLockExpired(format!(
+                "the lock revision {} is expired",
+                req.revision
+            )));
+        }
+        if !req.acquire_lock {
+            *state.heartbeats.entry(req.revision).or_default() += 1;
+        }
+        Ok(())
+    }
+
+    async fn delete_lock_revision(&self, req: DeleteLockRevReq) -> Result<()> {
+        let mut state = self.state.lock();
+        state.revisions.remove(&req.revision);
+        state.deleted.push(req.revision);
+        Ok(())
+    }
+}
+
+/// The count of held locks and the table contention are process-wide, the tests hold
+/// this lock to run one at a time.
+static SERIAL_TESTS: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+async fn init_runtime() -> tokio::sync::MutexGuard<'static, ()> {
+    let guard = SERIAL_TESTS.lock().await;
+    let thread_name = std::thread::current().name().unwrap().to_string();
+    GlobalInstance::init_testing(&thread_name);
+    GlobalIORuntime::init(2).unwrap();
+    // The heartbeat of the previous test may still be releasing its locks.
+    assert!(wait_until(|| !LockHolder::any_locks_held()).await);
+    guard
+}
+
+fn lock_req(table_id: u64, ttl: Duration) -> CreateLockRevReq {
+    CreateLockRevReq::new(
+        LockKey::Table {
+            tenant: Tenant::new_literal("test"),
+            table_id,
+        },
+        "root".to_string(),
+        "node".to_string(),
+        "query".to_string(),
+        ttl,
+    )
+}
+
+async fn wait_until(f: impl Fn() -> bool) -> bool {
+    timeout(Duration::from_secs(10), async {
+        while !f() {
+            sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .is_ok()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_pause_on_extend_failure() -> Result<()> {
+    let _guard = init_runtime().await;
+
+    let catalog = MockLockCatalog::create();
+    let holder = Arc::new(LockHolder::create(OnExtendFailure::Pause));
+    let revision = holder
+        .try_acquire_lock(
+            catalog.clone(),
+            lock_req(1, Duration::from_millis(300)),
+            false,
+            Duration::from_secs(1),
+        )
+        .await?;
+    assert!(!holder.is_paused());
+
+    // Lose the lock, and hold the holder in the pause state.
+    catalog.block_create(true);
+    catalog.expire(revision);
+    assert!(wait_until(|| holder.is_paused()).await);
+
+    catalog.block_create(false);
+    timeout(Duration::from_secs(10), holder.wait_resumed())
+        .await
+        .unwrap();
+    let new_revision = holder.revision();
+    assert_ne!(new_revision, revision);
+    assert!(catalog.deleted().contains(&revision));
+
+    // The query is not killed, the heartbeat goes on extending the fresh lock.
+    assert!(wait_until(|| catalog.heartbeats(new_revision) > 0).await);
+    assert!(!holder.is_paused());
+
+    holder.shutdown();
+    assert!(wait_until(|| catalog.deleted().contains(&new_revision)).await);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_start_many_rollback() -> Result<()> {
+    let _guard = init_runtime().await;
+
+    let catalog = MockLockCatalog::create();
+    let ttl = Duration::from_secs(3);
+    // Table 2 is locked by another statement.
+    let other = catalog
+        .create_lock_revision(lock_req(2, ttl))
+        .await?
+        .revision;
+
+    let holder = Arc::new(LockHolder::default());
+    let res = holder
+        .start_many(
+            catalog.clone(),
+            vec![lock_req(2, ttl), lock_req(1, ttl)],
+            false,
+            Duration::from_secs(1),
+        )
+        .await;
+    assert_eq!(res.unwrap_err().code(), ErrorCode::TABLE_ALREADY_LOCKED);
+
+    // The lock of table 1 is acquired first, and rolled back.
+    assert!(catalog.revisions(1).is_empty());
+    assert_eq!(catalog.revisions(2), vec![other]);
+    assert_eq!(catalog.deleted().len(), 2);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_any_locks_held() -> Result<()> {
+    let _guard = init_runtime().await;
+
+    let catalog = MockLockCatalog::create();
+    let ttl = Duration::from_secs(3);
+    let mut holders = vec![];
+    for table_id in [3, 4] {
+        let holder = Arc::new(LockHolder::default());
+        holder
+            .try_acquire_lock(
+                catalog.clone(),
+                lock_req(table_id, ttl),
+                false,
+                Duration::from_secs(1),
+            )
+            .await?;
+        holders.push(holder);
+    }
+    assert!(LockHolder::any_locks_held());
+
+    for holder in holders.iter() {
+        holder.shutdown();
+    }
+    assert!(wait_until(|| !LockHolder::any_locks_held()).await);
+    assert_eq!(catalog.deleted().len(), 2);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_kill_on_extend_failure_releases_locks() -> Result<()> {
+    let _guard = init_runtime().await;
+
+    let catalog = MockLockCatalog::create();
+    let holder = Arc::new(LockHolder::default());
+    let revision = holder
+        .try_acquire_lock(
+            catalog.clone(),
+            lock_req(7, Duration::from_millis(300)),
+            false,
+            Duration::from_secs(1),
+        )
+        .await?;
+    assert!(LockHolder::any_locks_held());
+
+    // Lose the lock, the query is killed and the lock is released.
+    catalog.expire(revision);
+    assert!(wait_until(|| catalog.deleted().contains(&revision)).await);
+    assert_eq!(holder.revision(), 0);
+    assert!(wait_until(|| !LockHolder::any_locks_held()).await);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_table_lock_contention() -> Result<()> {
+    let _guard = init_runtime().await;
+
+    let catalog = MockLockCatalog::create();
+    let ttl = Duration::from_secs(3);
+    let holder = Arc::new(LockHolder::default());
+    holder
+        .try_acquire_lock(
+            catalog.clone(),
+            lock_req(5, ttl),
+            false,
+            Duration::from_secs(1),
+        )
+        .await?;
+    assert_eq!(table_lock_contention(5), LockContention {
+        waiters: 0,
+        holders: 1,
+    });
+
+    // Hold the other sessions in acquiring the lock.
+    catalog.block_create(true);
+    let mut waiters = vec![];
+    for _ in 0..3 {
+        let catalog = catalog.clone();
+        waiters.push(tokio::spawn(async move {
+            let waiter = Arc::new(LockHolder::default());
+            let res = waiter
+                .try_acquire_lock(catalog, lock_req(5, ttl), false, Duration::from_secs(1))
+                .await;
+            waiter.shutdown();
+            res
+        }));
+    }
+    let contention = LockContention {
+        waiters: 3,
+        holders: 1,
+    };
+    assert!(wait_until(|| table_lock_contention(5) == contention).await);
+    assert_eq!(contention.ratio(), 3.0);
+
+    // The table is still locked, the waiters give up.
+    catalog.block_create(false);
+    for waiter in waiters {
+        let res = waiter.await.unwrap();
+        assert_eq!(res.unwrap_err().code(), ErrorCode::TABLE_ALREADY_LOCKED);
+    }
+    assert_eq!(table_lock_contention(5), LockContention {
+        waiters: 0,
+        holders: 1,
+    });
+
+    holder.shutdown();
+    assert!(wait_until(|| table_lock_contention(5) == LockContention::default()).await);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_table_lock_contention_after_kill() -> Result<()> {
+    let _guard = init_runtime().await;
+
+    let catalog = MockLockCatalog::create();
+    let ttl = Duration::from_millis(300);
+    for (table_id, on_extend_failure) in [(8, OnExtendFailure::Kill), (9, OnExtendFailure::Pause)] {
+        let holder = Arc::new(LockHolder::create(on_extend_failure));
+        let revision = holder
+            .try_acquire_lock(
+                catalog.clone(),
+                lock_req(table_id, ttl),
+                false,
+                Duration::ZERO,
+            )
+            .await?;
+        assert_eq!(table_lock_contention(table_id).holders, 1);
+
+        // Lose the lock while another session queues behind it, a fresh lock can't be
+        // acquired either and the query is killed.
+        catalog
+            .create_lock_revision(lock_req(table_id, ttl))
+            .await?;
+        catalog.expire(revision);
+        assert!(wait_until(|| catalog.deleted().contains(&revision)).await);
+        assert!(wait_until(|| table_lock_contention(table_id) == LockContention::default()).await);
+        assert_eq!(holder.revision(), 0);
+    }
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_lock_lapses_without_progress() -> Result<()> {
+    let _guard = init_runtime().await;
+
+    let catalog = MockLockCatalog::create();
+    let progress = Arc::new(LockProgress::default());
+    let holder = Arc::new(LockHolder::default().with_progress(progress.clone()));
+    let revision = holder
+        .try_acquire_lock(
+            catalog.clone(),
+            lock_req(6, Duration::from_millis(300)),
+            false,
+            Duration::from_secs(1),
+        )
+        .await?;
+    assert_eq!(table_lock_contention(6).holders, 1);
+
+    // No progress is reported, the lock is given up at the first heartbeat.
+    assert!(wait_until(|| table_lock_contention(6) == LockContention::default()).await);
+    assert_eq!(catalog.heartbeats(revision), 0);
+    assert_eq!(holder.revision(), 0);
+
+    // The revision is not deleted, it lapses with its ttl.
+    assert!(!catalog.deleted().contains(&revision));
+    assert_eq!(catalog.revisions(6), vec![revision]);
+
+    holder.shutdown();
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_lock_progress_checked_after_acquired() -> Result<()> {
+    let _guard = init_runtime().await;
+
+    let catalog = MockLockCatalog::create();
+    let progress = Arc::new(LockProgress::default());
+    let holder = Arc::new(LockHolder::default().with_progress(progress.clone()));
+
+    // Acquiring the lock takes several heartbeats, the query can't progress meanwhile.
+    catalog.block_acquire(true);
+    let acquire = tokio::spawn({
+        let catalog = catalog.clone();
+        let holder = holder.clone();
+        async move {
+            holder
+                .try_acquire_lock(
+                    catalog,
+                    lock_req(10, Duration::from_millis(300)),
+                    false,
+                    Duration::from_secs(1),
+                )
+                .await
+        }
+    });
+    assert!(wait_until(|| !catalog.revisions(10).is_empty()).await);
+    let revision = catalog.revisions(10)[0];
+    assert!(wait_until(|| catalog.heartbeats(revision) >= 3).await);
+
+    catalog.block_acquire(false);
+    assert_eq!(acquire.await.unwrap()?, revision);
+    assert_eq!(table_lock_contention(10).holders, 1);
+
+    // No progress is reported since the lock is acquired, it is given up.
+    assert!(wait_until(|| table_lock_contention(10) == LockContention::default()).await);
+    assert_eq!(holder.revision(), 0);
+
+    holder.shutdown();
+    Ok(())
+}
@@ -96,6 +96,8 @@ impl LockManager {
 
         let catalog = ctx.get_catalog(catalog_name).await?;
 
+        // The executor neither reports progress nor waits on a paused holder, so the lock
+        // is renewed unconditionally and the query is killed if it is lost.
         let lock_holder = Arc::new(LockHolder::default());
         match lock_holder
             .try_acquire_lock(catalog, req, should_retry, acquire_timeout)
@@ -13,7 +13,13 @@
 // limitations under the License.
 
 mod lock_holder;
+#[cfg(test)]
+mod lock_holder_test;
 mod lock_manager;
 mod table_lock;
 
+pub use lock_holder::table_lock_contention;
+pub use lock_holder::LockContention;
+pub use lock_holder::LockProgress;
+pub use lock_holder::OnExtendFailure;
 pub use lock_manager::LockManager;
@@ -1732,6 +1732,24 @@ impl<'a> TypeChecker<'a> {
             params
         };
 
+        // Convert the granularity of distinct_time_buckets and uniq_by_bucket to params
+        let params = if ((func_name.eq_ignore_ascii_case("distinct_time_buckets")
+            && arguments.len() == 2)
+            || (func_name.eq_ignore_ascii_case("uniq_by_bucket") && arguments.len() == 3))
+            && params.is_empty()
+        {
+            let granularity = ConstantExpr::try_from(arguments[1].clone());
+            if arg_types[1] != DataType::String || granularity.is_err() {
+                return Err(ErrorCode::SemanticError(format!(
+                    "The granularity of `{}` must be a constant string",
+                    func_name.to_lowercase()
+                )));
+            }
+            vec![granularity.unwrap().value]
+        } else {
+            params
+        };
+
         // Rewrite `xxx(distinct)` to `xxx_distinct(...)`
         let (func_name, distinct) = if func_name.eq_ignore_ascii_case("count") && distinct {
             ("count_distinct", false)